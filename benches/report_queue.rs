@@ -0,0 +1,91 @@
+//! Throughput of a raw interface streaming 64 byte reports to a simulated host
+//!
+//! The host reads the IN endpoint once per poll, every 1ms frame at full speed or every 125µs
+//! microframe at high speed. The device produces reports in bursts of two every other poll, as a
+//! data logger does when its sampling loop is late by a poll. The number of reports the host
+//! received is printed for each benchmark, so queued and unqueued interfaces can be compared. The
+//! time measured covers a whole stream, including creating the device, and divided by the number
+//! of polls must stay well below the poll interval.
+//!
+//! Run with `cargo bench --bench report_queue`
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use fugit::{MicrosDurationU32, MillisDurationU32};
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::prelude::*;
+use usbd_human_interface_device::interface::{
+    InBytes64, Interface, InterfaceBuilder, OutNone, ReportSingle,
+};
+use usbd_human_interface_device::test_util::{TestUsbBus, UsbTestManager};
+use usbd_human_interface_device::usb_class::UsbHidClassBuilder;
+
+const POLLS: usize = 1000;
+
+#[derive(Clone, Copy, Debug)]
+enum Speed {
+    Full,
+    High,
+}
+
+/// Stream reports for [`POLLS`] polls, returning the number of reports the host received
+fn stream(speed: Speed, queue: bool) -> usize {
+    let manager = UsbTestManager::default();
+    let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+    let builder = InterfaceBuilder::<InBytes64, OutNone, ReportSingle>::new(&[]).unwrap();
+    let builder = match speed {
+        Speed::Full => builder.in_endpoint(MillisDurationU32::millis(1)),
+        Speed::High => builder.in_endpoint_high_speed(MicrosDurationU32::micros(125)),
+    }
+    .unwrap();
+    let builder = if queue {
+        builder.queue_reports()
+    } else {
+        builder
+    };
+    let mut hid = UsbHidClassBuilder::new()
+        .add_device(builder.build())
+        .build(&usb_alloc);
+
+    let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001)).build();
+    manager.enumerate(&mut usb_dev, &mut [&mut hid]);
+
+    let mut received = 0;
+    let mut report = [0_u8; 64];
+    for poll in 0..POLLS {
+        if poll % 2 == 0 {
+            for _ in 0..2 {
+                report[0] = report[0].wrapping_add(1);
+                let _ = hid
+                    .device::<Interface<_, InBytes64, OutNone, ReportSingle>, _>()
+                    .write_report(black_box(&report));
+            }
+        }
+        if !manager.host_read_in().is_empty() {
+            received += 1;
+        }
+        usb_dev.poll(&mut [&mut hid]);
+    }
+    received
+}
+
+fn report_queue(c: &mut Criterion) {
+    let mut group = c.benchmark_group("stream_64_byte_reports");
+    for speed in [Speed::Full, Speed::High] {
+        for queue in [false, true] {
+            let name = if queue { "queued" } else { "unqueued" };
+            let received = stream(speed, queue);
+            println!("{speed:?} speed, {name}: host received {received} of {POLLS} reports");
+            group.throughput(Throughput::Bytes(64 * received as u64));
+            group.bench_with_input(
+                BenchmarkId::new(name, format!("{speed:?}")),
+                &(speed, queue),
+                |b, &(speed, queue)| b.iter(|| stream(speed, queue)),
+            );
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, report_queue);
+criterion_main!(benches);
@@ -95,7 +95,7 @@ fn main() -> ! {
             let report = get_report(&input_pins);
             if report != last {
                 match consumer.device().write_report(&report) {
-                    Err(UsbError::WouldBlock) => {}
+                    Err(UsbHidError::WouldBlock) => {}
                     Ok(_) => {
                         last = report;
                     }
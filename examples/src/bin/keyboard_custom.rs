@@ -171,7 +171,7 @@ fn main() -> ! {
                     .device()
                     .write_report(&BootKeyboardReport::new(keys).pack().unwrap())
                 {
-                    Err(UsbError::WouldBlock) => {}
+                    Err(UsbHidError::WouldBlock) => {}
                     Ok(_) => {
                         last_keys = Some(keys);
                         idle_count_down = reset_idle(&timer, keyboard.device().global_idle());
@@ -0,0 +1,166 @@
+#![no_std]
+#![no_main]
+
+use core::convert::Infallible;
+
+use bsp::entry;
+use bsp::hal;
+use cortex_m::prelude::*;
+use defmt::*;
+use defmt_rtt as _;
+use embedded_hal::digital::v2::*;
+use fugit::ExtU32;
+use hal::pac;
+use panic_probe as _;
+#[allow(clippy::wildcard_imports)]
+use usb_device::class_prelude::*;
+use usb_device::prelude::*;
+use usbd_human_interface_device::page::Keyboard;
+use usbd_human_interface_device::prelude::*;
+use usbd_serial::SerialPort;
+
+use rp_pico as bsp;
+
+#[entry]
+fn main() -> ! {
+    let mut pac = pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+    let clocks = hal::clocks::init_clocks_and_plls(
+        bsp::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .ok()
+    .unwrap();
+
+    let timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS);
+
+    let sio = hal::Sio::new(pac.SIO);
+    let pins = hal::gpio::Pins::new(
+        pac.IO_BANK0,
+        pac.PADS_BANK0,
+        sio.gpio_bank0,
+        &mut pac.RESETS,
+    );
+
+    info!("Starting");
+
+    //USB
+    let usb_bus = UsbBusAllocator::new(hal::usb::UsbBus::new(
+        pac.USBCTRL_REGS,
+        pac.USBCTRL_DPRAM,
+        clocks.usb_clock,
+        true,
+        &mut pac.RESETS,
+    ));
+
+    //Interfaces, endpoints and strings are allocated from the shared allocator, so the classes
+    //don't collide. The serial port's two interfaces are grouped by an IAD
+    let mut serial = SerialPort::new(&usb_bus);
+
+    let mut keyboard = UsbHidClassBuilder::new()
+        .add_device(usbd_human_interface_device::device::keyboard::BootKeyboardConfig::default())
+        .build(&usb_bus);
+
+    //https://pid.codes
+    let mut usb_dev = UsbDeviceBuilder::new(&usb_bus, UsbVidPid(0x1209, 0x0001))
+        .manufacturer("usbd-human-interface-device")
+        .product("Keyboard & Serial")
+        .serial_number("TEST")
+        .composite_with_iads()
+        .build();
+
+    //GPIO pins
+    let mut led_pin = pins.gpio13.into_push_pull_output();
+
+    let keys: &[&dyn InputPin<Error = core::convert::Infallible>] = &[
+        &pins.gpio1.into_pull_up_input(),
+        &pins.gpio2.into_pull_up_input(),
+        &pins.gpio3.into_pull_up_input(),
+    ];
+
+    let mut input_count_down = timer.count_down();
+    input_count_down.start(10.millis());
+
+    let mut tick_count_down = timer.count_down();
+    tick_count_down.start(1.millis());
+
+    loop {
+        //Poll the keys every 10ms
+        if input_count_down.wait().is_ok() {
+            let keys = get_keys(keys);
+
+            match keyboard.device().write_report(keys) {
+                Err(UsbHidError::WouldBlock) => {}
+                Err(UsbHidError::Duplicate) => {}
+                Ok(_) => {}
+                Err(e) => {
+                    core::panic!("Failed to write keyboard report: {:?}", e)
+                }
+            };
+        }
+
+        //Tick once per ms
+        if tick_count_down.wait().is_ok() {
+            match keyboard.tick() {
+                Err(UsbHidError::WouldBlock) => {}
+                Ok(_) => {}
+                Err(e) => {
+                    core::panic!("Failed to process keyboard tick: {:?}", e)
+                }
+            };
+        }
+
+        //Both classes are polled together, in the order they were allocated
+        if usb_dev.poll(&mut [&mut serial, &mut keyboard]) {
+            match keyboard.device().read_report() {
+                Err(UsbError::WouldBlock) => {
+                    //do nothing
+                }
+                Err(e) => {
+                    core::panic!("Failed to read keyboard report: {:?}", e)
+                }
+                Ok(leds) => {
+                    led_pin.set_state(PinState::from(leds.num_lock)).ok();
+                }
+            }
+
+            //Echo anything received on the serial port
+            let mut buf = [0; 64];
+            match serial.read(&mut buf) {
+                Err(UsbError::WouldBlock) => {}
+                Err(e) => {
+                    core::panic!("Failed to read serial data: {:?}", e)
+                }
+                Ok(count) => {
+                    serial.write(&buf[..count]).ok();
+                }
+            }
+        }
+    }
+}
+
+fn get_keys(keys: &[&dyn InputPin<Error = Infallible>]) -> [Keyboard; 3] {
+    [
+        if keys[0].is_low().unwrap() {
+            Keyboard::A
+        } else {
+            Keyboard::NoEventIndicated
+        }, //A
+        if keys[1].is_low().unwrap() {
+            Keyboard::B
+        } else {
+            Keyboard::NoEventIndicated
+        }, //B
+        if keys[2].is_low().unwrap() {
+            Keyboard::C
+        } else {
+            Keyboard::NoEventIndicated
+        }, //C
+    ]
+}
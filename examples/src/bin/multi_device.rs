@@ -178,7 +178,7 @@ fn main() -> ! {
             if last_consumer_report != consumer_report {
                 let consumer = multi_device.device::<ConsumerControl<'_, _>, _>();
                 match consumer.write_report(&consumer_report) {
-                    Err(UsbError::WouldBlock) => {}
+                    Err(UsbHidError::WouldBlock) => {}
                     Ok(_) => {
                         last_consumer_report = consumer_report;
                     }
@@ -0,0 +1,113 @@
+#![no_std]
+#![no_main]
+
+use bsp::entry;
+use bsp::hal;
+use defmt::*;
+use defmt_rtt as _;
+use fugit::ExtU32;
+use hal::pac;
+use panic_probe as _;
+#[allow(clippy::wildcard_imports)]
+use usb_device::class_prelude::*;
+use usb_device::prelude::*;
+use usbd_human_interface_device::interface::{InBytes64, OutNone, ReportSingle};
+use usbd_human_interface_device::usb_class::prelude::*;
+
+use rp_pico as bsp;
+
+// Vendor defined 64 byte input report
+#[rustfmt::skip]
+pub const LOGGER_REPORT_DESCRIPTOR: &[u8] = &[
+    0x06, 0x00, 0xFF, // Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x01,       // Usage (0x01)
+    0xA1, 0x01,       // Collection (Application)
+    0x15, 0x00,       //   Logical Minimum (0)
+    0x26, 0xFF, 0x00, //   Logical Maximum (255)
+    0x75, 0x08,       //   Report Size (8)
+    0x95, 0x40,       //   Report Count (64)
+    0x09, 0x01,       //   Usage (0x01)
+    0x81, 0x02,       //   Input (Data,Var,Abs)
+    0xC0,             // End Collection
+];
+
+#[entry]
+fn main() -> ! {
+    let mut pac = pac::Peripherals::take().unwrap();
+
+    let mut watchdog = hal::Watchdog::new(pac.WATCHDOG);
+    let clocks = hal::clocks::init_clocks_and_plls(
+        bsp::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .ok()
+    .unwrap();
+
+    let timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS);
+
+    info!("Starting");
+
+    //USB
+    let usb_bus = UsbBusAllocator::new(hal::usb::UsbBus::new(
+        pac.USBCTRL_REGS,
+        pac.USBCTRL_DPRAM,
+        clocks.usb_clock,
+        true,
+        &mut pac.RESETS,
+    ));
+
+    // Queue reports so one is ready for every 1ms poll of the endpoint
+    let mut logger = UsbHidClassBuilder::new()
+        .add_device(
+            InterfaceBuilder::<InBytes64, OutNone, ReportSingle>::new(LOGGER_REPORT_DESCRIPTOR)
+                .unwrap()
+                .description("Raw Logger")
+                .in_endpoint(1.millis())
+                .unwrap()
+                .without_out_endpoint()
+                .send_unchanged_reports()
+                .queue_reports()
+                .build(),
+        )
+        .build(&usb_bus);
+
+    //https://pid.codes
+    let mut usb_dev = UsbDeviceBuilder::new(&usb_bus, UsbVidPid(0x1209, 0x0001))
+        .manufacturer("usbd-human-interface-device")
+        .product("Raw Logger")
+        .serial_number("TEST")
+        .build();
+
+    let mut sequence: u32 = 0;
+    let mut report = [0u8; 64];
+    let mut ready = false;
+
+    loop {
+        if !ready {
+            // Sequence number followed by timestamped sample data
+            report[..4].copy_from_slice(&sequence.to_le_bytes());
+            report[4..12].copy_from_slice(&timer.get_counter().ticks().to_le_bytes());
+            ready = true;
+        }
+
+        if usb_dev.state() == UsbDeviceState::Configured {
+            match logger.device().write_report(&report) {
+                Err(UsbHidError::WouldBlock | UsbHidError::QueueFull) => {}
+                Ok(_) => {
+                    sequence = sequence.wrapping_add(1);
+                    ready = false;
+                }
+                Err(e) => {
+                    core::panic!("Failed to write logger report: {:?}", e)
+                }
+            }
+        }
+
+        if usb_dev.poll(&mut [&mut logger]) {}
+    }
+}
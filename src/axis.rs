@@ -0,0 +1,217 @@
+//! Scaling raw analog readings, e.g. from an ADC, to report axis values
+//!
+//! An [`AxisCalibration`] maps a centered input, such as a thumb stick, to a signed axis and a
+//! [`TriggerCalibration`] maps an input with a released position, such as a trigger or pedal, to
+//! an unsigned axis. Both apply a deadzone and scale each side of the input's travel separately,
+//! so an input whose center isn't midway between its ends still reaches both ends of the axis.
+//!
+//! ```
+//! # use usbd_human_interface_device::axis::{AxisCalibration, TriggerCalibration};
+//! # use usbd_human_interface_device::device::gamepad::GamepadReport;
+//! // 12 bit stick resting at 2000, trigger reading 3900 when released
+//! const STICK: AxisCalibration = AxisCalibration::new(50, 2000, 4040).deadzone(40);
+//! const TRIGGER: TriggerCalibration = TriggerCalibration::new(3900, 200).deadzone(30);
+//!
+//! let report = GamepadReport {
+//!     left_x: STICK.to_i16(4040),
+//!     left_y: STICK.to_i16(2030),
+//!     left_trigger: TRIGGER.to_u16(100),
+//!     ..Default::default()
+//! };
+//! assert_eq!(report.left_x, i16::MAX);
+//! assert_eq!(report.left_y, 0);
+//! assert_eq!(report.left_trigger, u16::MAX);
+//! ```
+
+/// Calibration of a centered analog input, such as a thumb stick axis
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AxisCalibration {
+    min: i32,
+    center: i32,
+    max: i32,
+    deadzone: i32,
+    inverted: bool,
+}
+
+impl AxisCalibration {
+    /// An input reading `min` and `max` at the ends of its travel and `center` at rest
+    #[must_use]
+    pub const fn new(min: i32, center: i32, max: i32) -> Self {
+        Self {
+            min,
+            center,
+            max,
+            deadzone: 0,
+            inverted: false,
+        }
+    }
+
+    /// Report readings within `deadzone` of the center as centered. The rest of the travel is
+    /// scaled to the whole axis, so there is no jump at the edge of the deadzone
+    #[must_use]
+    pub const fn deadzone(mut self, deadzone: i32) -> Self {
+        self.deadzone = if deadzone < 0 { 0 } else { deadzone };
+        self
+    }
+
+    /// Report `min` as the positive end of the axis, e.g. for a Y axis where the input reads
+    /// higher when pushed down
+    #[must_use]
+    pub const fn inverted(mut self) -> Self {
+        self.inverted = !self.inverted;
+        self
+    }
+
+    /// Scale `raw` to an axis from `-logical_max` to `logical_max`, readings beyond the
+    /// calibrated travel are clamped
+    #[must_use]
+    pub fn scale(&self, raw: i32, logical_max: i32) -> i32 {
+        let offset = i64::from(raw) - i64::from(self.center);
+        let end = if offset < 0 { self.min } else { self.max };
+        let value = scale_travel(
+            offset.abs(),
+            (i64::from(end) - i64::from(self.center)).abs(),
+            i64::from(self.deadzone),
+            i64::from(logical_max),
+        );
+        let value = if (offset < 0) == self.inverted {
+            value
+        } else {
+            -value
+        };
+        i32::try_from(value).unwrap_or_default()
+    }
+
+    /// Scale `raw` to an 8 bit axis, from -127 to 127
+    #[must_use]
+    pub fn to_i8(&self, raw: i32) -> i8 {
+        i8::try_from(self.scale(raw, i32::from(i8::MAX))).unwrap_or_default()
+    }
+
+    /// Scale `raw` to a 16 bit axis, from -32767 to 32767
+    #[must_use]
+    pub fn to_i16(&self, raw: i32) -> i16 {
+        i16::try_from(self.scale(raw, i32::from(i16::MAX))).unwrap_or_default()
+    }
+}
+
+/// Calibration of an analog input travelling from a released position, such as a trigger or
+/// pedal
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TriggerCalibration {
+    released: i32,
+    pressed: i32,
+    deadzone: i32,
+}
+
+impl TriggerCalibration {
+    /// An input reading `released` at rest and `pressed` when fully pressed, `pressed` may be
+    /// less than `released`
+    #[must_use]
+    pub const fn new(released: i32, pressed: i32) -> Self {
+        Self {
+            released,
+            pressed,
+            deadzone: 0,
+        }
+    }
+
+    /// Report readings within `deadzone` of the released position as released
+    #[must_use]
+    pub const fn deadzone(mut self, deadzone: i32) -> Self {
+        self.deadzone = if deadzone < 0 { 0 } else { deadzone };
+        self
+    }
+
+    /// Scale `raw` to an axis from 0 to `logical_max`, readings beyond the calibrated travel are
+    /// clamped
+    #[must_use]
+    pub fn scale(&self, raw: i32, logical_max: i32) -> i32 {
+        let mut offset = i64::from(raw) - i64::from(self.released);
+        let mut travel = i64::from(self.pressed) - i64::from(self.released);
+        if travel < 0 {
+            offset = -offset;
+            travel = -travel;
+        }
+        if offset <= 0 {
+            return 0;
+        }
+        let value = scale_travel(
+            offset,
+            travel,
+            i64::from(self.deadzone),
+            i64::from(logical_max),
+        );
+        i32::try_from(value).unwrap_or_default()
+    }
+
+    /// Scale `raw` to an 8 bit axis, from 0 to 255
+    #[must_use]
+    pub fn to_u8(&self, raw: i32) -> u8 {
+        u8::try_from(self.scale(raw, i32::from(u8::MAX))).unwrap_or_default()
+    }
+
+    /// Scale `raw` to a 16 bit axis, from 0 to 65535
+    #[must_use]
+    pub fn to_u16(&self, raw: i32) -> u16 {
+        u16::try_from(self.scale(raw, i32::from(u16::MAX))).unwrap_or_default()
+    }
+}
+
+/// Scale `offset` of `travel` beyond `deadzone` to 0 to `logical_max`, rounding to nearest
+fn scale_travel(offset: i64, travel: i64, deadzone: i64, logical_max: i64) -> i64 {
+    let span = travel - deadzone;
+    if offset <= deadzone || span <= 0 || logical_max <= 0 {
+        return 0;
+    }
+    let offset = (offset - deadzone).min(span);
+    (offset * logical_max + span / 2) / span
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn off_center_axis() {
+        let axis = AxisCalibration::new(0, 1000, 4000);
+        assert_eq!(axis.to_i16(1000), 0);
+        assert_eq!(axis.to_i16(0), -32767);
+        assert_eq!(axis.to_i16(4000), 32767);
+        assert_eq!(axis.to_i16(500), -16384);
+        assert_eq!(axis.to_i16(2500), 16384);
+        assert_eq!(axis.to_i8(-100), -127);
+        assert_eq!(axis.to_i8(9000), 127);
+        assert_eq!(axis.inverted().to_i8(0), 127);
+        assert_eq!(axis.scale(2500, 1023), 512);
+    }
+
+    #[test]
+    fn axis_deadzone() {
+        let axis = AxisCalibration::new(0, 2048, 4096).deadzone(48);
+        assert_eq!(axis.to_i8(2000), 0);
+        assert_eq!(axis.to_i8(2096), 0);
+        assert_eq!(axis.to_i8(2097), 0);
+        assert_eq!(axis.to_i8(3096), 64);
+        assert_eq!(axis.to_i8(0), -127);
+        assert_eq!(axis.to_i8(4096), 127);
+        assert_eq!(AxisCalibration::new(0, 10, 20).deadzone(10).to_i8(20), 0);
+    }
+
+    #[test]
+    fn trigger() {
+        let trigger = TriggerCalibration::new(100, 900).deadzone(20);
+        assert_eq!(trigger.to_u8(50), 0);
+        assert_eq!(trigger.to_u8(120), 0);
+        assert_eq!(trigger.to_u8(510), 128);
+        assert_eq!(trigger.to_u8(900), 255);
+        assert_eq!(trigger.to_u16(1000), 65535);
+
+        let reversed = TriggerCalibration::new(900, 100);
+        assert_eq!(reversed.to_u8(900), 0);
+        assert_eq!(reversed.to_u8(500), 128);
+        assert_eq!(reversed.to_u16(0), 65535);
+    }
+}
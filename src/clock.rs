@@ -0,0 +1,62 @@
+//! Time sources used to drive [`UsbHidClass::tick`](crate::usb_class::UsbHidClass::tick)
+//!
+//! Devices track time in 1ms ticks. Rather than arranging for a 1kHz timer, a [`HidClock`] can
+//! be passed to [`UsbHidClass::tick_clock`](crate::usb_class::UsbHidClass::tick_clock) from the
+//! main loop, which calls `tick` once for each millisecond elapsed since the previous call.
+
+/// The longest period that will be caught up on in a single call to `tick_clock`
+///
+/// This is the longest HID idle period (255 * 4ms). Any time elapsed beyond this is discarded.
+pub const MAX_CATCH_UP_MS: u32 = 1020;
+
+/// Source of the current time in milliseconds
+pub trait HidClock {
+    /// Milliseconds since an arbitrary epoch. Expected to wrap on overflow
+    fn now_millis(&mut self) -> u32;
+}
+
+/// [`HidClock`] backed by a function returning a plain millisecond count, e.g. a `SysTick`
+/// counter maintained by an interrupt handler
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MillisClock<F>(pub F);
+
+impl<F: FnMut() -> u32> HidClock for MillisClock<F> {
+    fn now_millis(&mut self) -> u32 {
+        (self.0)()
+    }
+}
+
+/// [`HidClock`] backed by a function returning a 64 bit [`fugit::Instant`], e.g. a HAL's
+/// free running timer counter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FugitClock<F>(pub F);
+
+impl<F, const NOM: u32, const DENOM: u32> HidClock for FugitClock<F>
+where
+    F: FnMut() -> fugit::Instant<u64, NOM, DENOM>,
+{
+    fn now_millis(&mut self) -> u32 {
+        // Truncation gives the expected wrapping behaviour
+        #[allow(clippy::cast_possible_truncation)]
+        let ms = (self.0)().duration_since_epoch().to_millis() as u32;
+        ms
+    }
+}
+
+/// Converts successive clock readings into a number of elapsed 1ms ticks
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ElapsedTicks {
+    last_ms: Option<u32>,
+}
+
+impl ElapsedTicks {
+    /// Milliseconds elapsed since the previous call, limited to [`MAX_CATCH_UP_MS`]. The first
+    /// call returns 0
+    pub fn update(&mut self, now_ms: u32) -> u32 {
+        let elapsed = self
+            .last_ms
+            .map_or(0, |last| now_ms.wrapping_sub(last).min(MAX_CATCH_UP_MS));
+        self.last_ms = Some(now_ms);
+        elapsed
+    }
+}
@@ -1,10 +1,14 @@
 //! HID descriptor constants and enumerations
+
+#[cfg(feature = "std")]
+pub mod inspect;
+pub mod item;
+
 use num_enum::{IntoPrimitive, TryFromPrimitive};
 use packed_struct::prelude::*;
 
 pub(crate) const USB_CLASS_HID: u8 = 0x03;
 pub(crate) const SPEC_VERSION_1_11: u16 = 0x0111; //1.11 in BCD
-pub(crate) const COUNTRY_CODE_NOT_SUPPORTED: u8 = 0x0;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Ord, PartialOrd, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
@@ -14,6 +18,54 @@ pub enum InterfaceProtocol {
     Mouse = 0x02,
 }
 
+/// Country code of localized hardware, as reported in the HID descriptor
+///
+/// Defined in section 6.2.1 of [Device Class Definition for Human Interface Devices (Hid)
+/// Version 1.11](<https://www.usb.org/sites/default/files/hid1_11.pdf>)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum HidCountryCode {
+    #[default]
+    NotSupported = 0,
+    Arabic = 1,
+    Belgian = 2,
+    CanadianBilingual = 3,
+    CanadianFrench = 4,
+    CzechRepublic = 5,
+    Danish = 6,
+    Finnish = 7,
+    French = 8,
+    German = 9,
+    Greek = 10,
+    Hebrew = 11,
+    Hungary = 12,
+    International = 13,
+    Italian = 14,
+    JapanKatakana = 15,
+    Korean = 16,
+    LatinAmerican = 17,
+    NetherlandsDutch = 18,
+    Norwegian = 19,
+    PersianFarsi = 20,
+    Poland = 21,
+    Portuguese = 22,
+    Russia = 23,
+    Slovakia = 24,
+    Spanish = 25,
+    Swedish = 26,
+    SwissFrench = 27,
+    SwissGerman = 28,
+    Switzerland = 29,
+    Taiwan = 30,
+    TurkishQ = 31,
+    UK = 32,
+    US = 33,
+    Yugoslavia = 34,
+    TurkishF = 35,
+    //36-255 Reserved
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PrimitiveEnum, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub(crate) enum DescriptorType {
@@ -21,9 +73,10 @@ pub(crate) enum DescriptorType {
     Report = 0x22,
 }
 
+/// `bInterfaceSubClass` of a HID interface, whether it supports the boot protocol
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
-pub(crate) enum InterfaceSubClass {
+pub enum InterfaceSubClass {
     None = 0x00,
     Boot = 0x01,
 }
@@ -46,6 +99,16 @@ pub enum HidProtocol {
     Report = 0x01,
 }
 
+/// Report type of `GET_REPORT` and `SET_REPORT` requests - HID spec 7.2.1
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum ReportType {
+    Input = 0x01,
+    Output = 0x02,
+    Feature = 0x03,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub(crate) enum HidRequest {
@@ -0,0 +1,582 @@
+//! Pretty printing and diffing report descriptors
+//!
+//! Requires the `std` feature. [`DescriptorTree`] parses a report descriptor into its items, with
+//! the items of each collection nested under it, and displays them one per line in the notation
+//! of the HID specification. Descriptors can then be asserted against a golden representation in
+//! tests, with a failing assertion showing the lines that differ rather than two byte arrays:
+//!
+//! ```
+//! # use usbd_human_interface_device::descriptor::inspect::assert_descriptor_matches;
+//! # use usbd_human_interface_device::device::camera::CAMERA_CONTROL_REPORT_DESCRIPTOR;
+//! assert_descriptor_matches(
+//!     CAMERA_CONTROL_REPORT_DESCRIPTOR,
+//!     "
+//!     Usage Page (Consumer)
+//!     Usage (0x01)
+//!     Collection (Application)
+//!       Logical Minimum (0)
+//!       Logical Maximum (1)
+//!       Report Size (1)
+//!       Report Count (2)
+//!       Usage Page (Camera Control)
+//!       Usage (0x20)
+//!       Usage (0x21)
+//!       Input (Data, Variable, Absolute)
+//!       Usage Page (Consumer)
+//!       Usage (0x022D)
+//!       Usage (0x022E)
+//!       Input (Data, Variable, Absolute)
+//!       Report Count (4)
+//!       Input (Constant, Variable, Absolute)
+//!     End Collection
+//!     ",
+//! );
+//! ```
+//!
+//! [`diff`] compares two descriptors in the same way.
+
+use core::fmt::{Display, Formatter};
+use std::format;
+use std::string::String;
+use std::vec::Vec;
+
+const LONG_ITEM: u8 = 0xFE;
+
+/// Error parsing a report descriptor, giving the byte offset of the item at fault
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseError {
+    /// The descriptor ends part way through an item
+    TruncatedItem { offset: usize },
+    /// An End Collection item without a matching Collection item
+    UnmatchedEndCollection { offset: usize },
+    /// A Collection item without a matching End Collection item
+    UnclosedCollection { offset: usize },
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::TruncatedItem { offset } => write!(f, "Truncated item at offset {offset}"),
+            Self::UnmatchedEndCollection { offset } => {
+                write!(f, "End collection without a collection at offset {offset}")
+            }
+            Self::UnclosedCollection { offset } => {
+                write!(f, "Collection without an end collection at offset {offset}")
+            }
+        }
+    }
+}
+
+/// Type of a report descriptor item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ItemType {
+    Main,
+    Global,
+    Local,
+    Reserved,
+    /// Long item, with its tag in the first data byte
+    Long,
+}
+
+/// A single item of a report descriptor
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorItem {
+    /// Byte offset of the item in the descriptor
+    pub offset: usize,
+    /// The item's prefix byte
+    pub prefix: u8,
+    /// The item's data, for long items starting with the long item tag
+    pub data: Vec<u8>,
+}
+
+impl DescriptorItem {
+    #[must_use]
+    pub fn item_type(&self) -> ItemType {
+        if self.prefix == LONG_ITEM {
+            return ItemType::Long;
+        }
+        match (self.prefix >> 2) & 0x3 {
+            0 => ItemType::Main,
+            1 => ItemType::Global,
+            2 => ItemType::Local,
+            _ => ItemType::Reserved,
+        }
+    }
+
+    /// The item's tag, for long items the long item tag
+    #[must_use]
+    pub fn tag(&self) -> u8 {
+        if self.prefix == LONG_ITEM {
+            self.data.first().copied().unwrap_or_default()
+        } else {
+            self.prefix >> 4
+        }
+    }
+
+    /// The item's data as a little endian unsigned value
+    #[must_use]
+    pub fn value(&self) -> u32 {
+        self.data
+            .iter()
+            .take(4)
+            .rev()
+            .fold(0, |acc, &b| (acc << 8) | u32::from(b))
+    }
+
+    /// The item's data as a little endian two's complement value
+    #[must_use]
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn signed_value(&self) -> i32 {
+        let bits = 8 * self.data.len().min(4);
+        if bits == 0 {
+            return 0;
+        }
+        let shift = 32 - bits;
+        ((self.value() << shift) as i32) >> shift
+    }
+
+    fn is_collection(&self) -> bool {
+        self.item_type() == ItemType::Main && self.tag() == 0xA
+    }
+
+    fn is_end_collection(&self) -> bool {
+        self.item_type() == ItemType::Main && self.tag() == 0xC
+    }
+
+    /// Value in hex, zero padded to the item's data size
+    fn hex(&self) -> String {
+        let width = 2 + 2 * self.data.len().min(4);
+        format!("{:#0width$X}", self.value())
+    }
+}
+
+impl Display for DescriptorItem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        let value = self.value();
+        let signed = self.signed_value();
+        match (self.item_type(), self.tag()) {
+            (ItemType::Main, 0x8) => write!(f, "Input ({})", main_flags(value, false)),
+            (ItemType::Main, 0x9) => write!(f, "Output ({})", main_flags(value, true)),
+            (ItemType::Main, 0xB) => write!(f, "Feature ({})", main_flags(value, true)),
+            (ItemType::Main, 0xA) => write!(f, "Collection ({})", collection_type(value)),
+            (ItemType::Main, 0xC) => write!(f, "End Collection"),
+            (ItemType::Global, 0x0) => write!(f, "Usage Page ({})", usage_page(value)),
+            (ItemType::Global, 0x1) => write!(f, "Logical Minimum ({signed})"),
+            (ItemType::Global, 0x2) => write!(f, "Logical Maximum ({signed})"),
+            (ItemType::Global, 0x3) => write!(f, "Physical Minimum ({signed})"),
+            (ItemType::Global, 0x4) => write!(f, "Physical Maximum ({signed})"),
+            (ItemType::Global, 0x5) => {
+                //Only the low nibble is used, as a 4 bit signed value
+                let exponent = ((value & 0xF) as i8) << 4 >> 4;
+                write!(f, "Unit Exponent ({exponent})")
+            }
+            (ItemType::Global, 0x6) => write!(f, "Unit ({})", self.hex()),
+            (ItemType::Global, 0x7) => write!(f, "Report Size ({value})"),
+            (ItemType::Global, 0x8) => write!(f, "Report ID ({value})"),
+            (ItemType::Global, 0x9) => write!(f, "Report Count ({value})"),
+            (ItemType::Global, 0xA) => write!(f, "Push"),
+            (ItemType::Global, 0xB) => write!(f, "Pop"),
+            (ItemType::Local, 0x0) => write!(f, "Usage ({})", self.hex()),
+            (ItemType::Local, 0x1) => write!(f, "Usage Minimum ({})", self.hex()),
+            (ItemType::Local, 0x2) => write!(f, "Usage Maximum ({})", self.hex()),
+            (ItemType::Local, 0x3) => write!(f, "Designator Index ({value})"),
+            (ItemType::Local, 0x4) => write!(f, "Designator Minimum ({value})"),
+            (ItemType::Local, 0x5) => write!(f, "Designator Maximum ({value})"),
+            (ItemType::Local, 0x7) => write!(f, "String Index ({value})"),
+            (ItemType::Local, 0x8) => write!(f, "String Minimum ({value})"),
+            (ItemType::Local, 0x9) => write!(f, "String Maximum ({value})"),
+            (ItemType::Local, 0xA) if value == 1 => write!(f, "Delimiter (Open)"),
+            (ItemType::Local, 0xA) => write!(f, "Delimiter (Close)"),
+            (ItemType::Long, tag) => write!(
+                f,
+                "Long Item ({tag:#04X}, {} bytes)",
+                self.data.len().saturating_sub(1)
+            ),
+            _ => write!(f, "Reserved ({:#04X}, {})", self.prefix, self.hex()),
+        }
+    }
+}
+
+fn main_flags(value: u32, volatile: bool) -> String {
+    let mut flags = Vec::new();
+    flags.push(if value & 0x1 == 0 { "Data" } else { "Constant" });
+    flags.push(if value & 0x2 == 0 {
+        "Array"
+    } else {
+        "Variable"
+    });
+    flags.push(if value & 0x4 == 0 {
+        "Absolute"
+    } else {
+        "Relative"
+    });
+    for (bit, flag) in [
+        (0x8, "Wrap"),
+        (0x10, "Non Linear"),
+        (0x20, "No Preferred State"),
+        (0x40, "Null State"),
+    ] {
+        if value & bit != 0 {
+            flags.push(flag);
+        }
+    }
+    if volatile && value & 0x80 != 0 {
+        flags.push("Volatile");
+    }
+    if value & 0x100 != 0 {
+        flags.push("Buffered Bytes");
+    }
+    flags.join(", ")
+}
+
+fn collection_type(value: u32) -> String {
+    match value {
+        0x00 => "Physical".into(),
+        0x01 => "Application".into(),
+        0x02 => "Logical".into(),
+        0x03 => "Report".into(),
+        0x04 => "Named Array".into(),
+        0x05 => "Usage Switch".into(),
+        0x06 => "Usage Modifier".into(),
+        0x80..=0xFF => format!("Vendor Defined {value:#04X}"),
+        _ => format!("Reserved {value:#04X}"),
+    }
+}
+
+fn usage_page(value: u32) -> String {
+    let name = match value {
+        0x01 => "Generic Desktop",
+        0x02 => "Simulation",
+        0x03 => "VR",
+        0x04 => "Sport",
+        0x05 => "Game",
+        0x06 => "Generic Device",
+        0x07 => "Keyboard",
+        0x08 => "LED",
+        0x09 => "Button",
+        0x0A => "Ordinal",
+        0x0B => "Telephony",
+        0x0C => "Consumer",
+        0x0D => "Digitizer",
+        0x0E => "Haptics",
+        0x0F => "Physical Input Device",
+        0x14 => "Auxiliary Display",
+        0x20 => "Sensors",
+        0x59 => "Lighting and Illumination",
+        0x84 => "Power",
+        0x85 => "Battery System",
+        0x8C => "Bar Code Scanner",
+        0x8D => "Scale",
+        0x8E => "Magnetic Stripe Reader",
+        0x90 => "Camera Control",
+        0xF1D0 => "FIDO Alliance",
+        0xFF00..=0xFFFF => return format!("Vendor Defined {value:#06X}"),
+        _ => return format!("{value:#06X}"),
+    };
+    name.into()
+}
+
+/// An item of a [`DescriptorTree`], collections holding the items up to their End Collection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Node {
+    Item(DescriptorItem),
+    Collection {
+        collection: DescriptorItem,
+        children: Vec<Self>,
+        end: DescriptorItem,
+    },
+}
+
+/// A parsed report descriptor, displayed as one indented item per line
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DescriptorTree {
+    nodes: Vec<Node>,
+}
+
+impl DescriptorTree {
+    /// Parse `descriptor`
+    ///
+    /// # Errors
+    ///
+    /// If an item is truncated or the collections aren't balanced
+    pub fn parse(descriptor: &[u8]) -> Result<Self, ParseError> {
+        let mut open: Vec<(DescriptorItem, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+
+        let mut offset = 0;
+        while offset < descriptor.len() {
+            let prefix = descriptor[offset];
+            let len = if prefix == LONG_ITEM {
+                //bDataSize, bLongItemTag, data
+                let size = descriptor
+                    .get(offset + 1)
+                    .ok_or(ParseError::TruncatedItem { offset })?;
+                2 + usize::from(*size)
+            } else {
+                match prefix & 0x3 {
+                    3 => 4,
+                    n => usize::from(n),
+                }
+            };
+            let data = descriptor
+                .get(offset + 1..offset + 1 + len)
+                .ok_or(ParseError::TruncatedItem { offset })?;
+            let item = DescriptorItem {
+                offset,
+                prefix,
+                //Long item data starts after bDataSize
+                data: if prefix == LONG_ITEM {
+                    data[1..].to_vec()
+                } else {
+                    data.to_vec()
+                },
+            };
+            offset += 1 + len;
+
+            if item.is_collection() {
+                open.push((item, Vec::new()));
+            } else if item.is_end_collection() {
+                let (collection, children) =
+                    open.pop().ok_or(ParseError::UnmatchedEndCollection {
+                        offset: item.offset,
+                    })?;
+                let node = Node::Collection {
+                    collection,
+                    children,
+                    end: item,
+                };
+                match open.last_mut() {
+                    Some((_, siblings)) => siblings.push(node),
+                    None => nodes.push(node),
+                }
+            } else {
+                match open.last_mut() {
+                    Some((_, siblings)) => siblings.push(Node::Item(item)),
+                    None => nodes.push(Node::Item(item)),
+                }
+            }
+        }
+
+        if let Some((collection, _)) = open.first() {
+            return Err(ParseError::UnclosedCollection {
+                offset: collection.offset,
+            });
+        }
+        Ok(Self { nodes })
+    }
+
+    /// The top level items of the descriptor
+    #[must_use]
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+}
+
+impl Display for DescriptorTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        fn write_nodes(f: &mut Formatter<'_>, nodes: &[Node], depth: usize) -> core::fmt::Result {
+            let indent = 2 * depth;
+            for node in nodes {
+                match node {
+                    Node::Item(item) => writeln!(f, "{:indent$}{item}", "")?,
+                    Node::Collection {
+                        collection,
+                        children,
+                        end,
+                    } => {
+                        writeln!(f, "{:indent$}{collection}", "")?;
+                        write_nodes(f, children, depth + 1)?;
+                        writeln!(f, "{:indent$}{end}", "")?;
+                    }
+                }
+            }
+            Ok(())
+        }
+        write_nodes(f, &self.nodes, 0)
+    }
+}
+
+/// Compare two descriptors item by item, returning `None` if they are identical, otherwise a line
+/// by line diff of their [`DescriptorTree`]s, removed lines prefixed with `-` and added lines with
+/// `+`
+///
+/// # Errors
+///
+/// If either descriptor can't be parsed
+pub fn diff(expected: &[u8], actual: &[u8]) -> Result<Option<String>, ParseError> {
+    let expected = format!("{}", DescriptorTree::parse(expected)?);
+    let actual = format!("{}", DescriptorTree::parse(actual)?);
+    Ok(diff_lines(
+        &expected.lines().collect::<Vec<_>>(),
+        &actual.lines().collect::<Vec<_>>(),
+    ))
+}
+
+/// Assert that `descriptor` is displayed as `golden`
+///
+/// Indentation common to all lines of `golden`, and leading and trailing blank lines, are
+/// ignored, so golden representations can be indented along with the test.
+///
+/// # Panics
+///
+/// If the descriptor can't be parsed or doesn't match, showing the diff
+pub fn assert_descriptor_matches(descriptor: &[u8], golden: &str) {
+    let tree = DescriptorTree::parse(descriptor)
+        .unwrap_or_else(|e| ::core::panic!("Invalid report descriptor: {e}"));
+    let actual = format!("{tree}");
+    let expected = dedent(golden);
+    if let Some(diff) = diff_lines(&expected, &actual.lines().collect::<Vec<_>>()) {
+        ::core::panic!("Report descriptor doesn't match (-golden +actual):\n{diff}");
+    }
+}
+
+fn dedent(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.lines().map(str::trim_end).collect();
+    while lines.first().is_some_and(|l| l.is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    let indent = lines
+        .iter()
+        .filter(|l| !l.is_empty())
+        .map(|l| l.len() - l.trim_start().len())
+        .min()
+        .unwrap_or_default();
+    lines
+        .into_iter()
+        .map(|l| l.get(indent..).unwrap_or_default())
+        .collect()
+}
+
+/// Longest common subsequence diff of two lists of lines
+fn diff_lines(expected: &[&str], actual: &[&str]) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+
+    //common[i][j] is the length of the longest common subsequence of expected[i..] and actual[j..]
+    let mut common = std::vec![std::vec![0usize; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            common[i][j] = if expected[i] == actual[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let mut out = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            out.push(format!("  {}", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < expected.len() && (j == actual.len() || common[i + 1][j] >= common[i][j + 1])
+        {
+            out.push(format!("- {}", expected[i]));
+            i += 1;
+        } else {
+            out.push(format!("+ {}", actual[j]));
+            j += 1;
+        }
+    }
+    out.push(String::new());
+    Some(out.join("\n"))
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::device::encoder::ENCODERS_REPORT_DESCRIPTOR;
+
+    #[test]
+    fn encoders_descriptor() {
+        assert_descriptor_matches(
+            ENCODERS_REPORT_DESCRIPTOR,
+            "
+            Usage Page (Generic Desktop)
+            Usage (0x08)
+            Collection (Application)
+              Usage Page (Button)
+              Usage Minimum (0x01)
+              Usage Maximum (0x08)
+              Logical Minimum (0)
+              Logical Maximum (1)
+              Report Size (1)
+              Report Count (8)
+              Input (Data, Variable, Absolute)
+              Usage Page (Generic Desktop)
+              Usage (0x37)
+              Usage (0x38)
+              Logical Minimum (-127)
+              Logical Maximum (127)
+              Report Size (8)
+              Report Count (2)
+              Input (Data, Variable, Relative)
+              Usage Page (Simulation)
+              Usage (0xB1)
+              Usage (0xB9)
+              Input (Data, Variable, Relative)
+            End Collection
+            ",
+        );
+    }
+
+    #[test]
+    fn diff_shows_changed_items() {
+        let expected = [0x05, 0x01, 0xA1, 0x01, 0x75, 0x08, 0x81, 0x02, 0xC0];
+        let actual = [0x05, 0x01, 0xA1, 0x01, 0x75, 0x10, 0x81, 0x06, 0xC0];
+
+        assert_eq!(diff(&expected, &expected).unwrap(), None);
+        assert_eq!(
+            diff(&expected, &actual).unwrap().unwrap(),
+            "  Usage Page (Generic Desktop)\n\
+             \x20 Collection (Application)\n\
+             -   Report Size (8)\n\
+             -   Input (Data, Variable, Absolute)\n\
+             +   Report Size (16)\n\
+             +   Input (Data, Variable, Relative)\n\
+             \x20 End Collection\n"
+        );
+    }
+
+    #[test]
+    fn item_values() {
+        let tree = DescriptorTree::parse(&[
+            0x06, 0x00, 0xFF, 0x16, 0x00, 0x80, 0x55, 0x0E, 0x67, 0x01, 0x10, 0x00, 0x00, 0xFE,
+            0x02, 0x10, 0xAA, 0xBB, 0xB1, 0xC3,
+        ])
+        .unwrap();
+        assert_eq!(
+            format!("{tree}"),
+            "Usage Page (Vendor Defined 0xFF00)\n\
+             Logical Minimum (-32768)\n\
+             Unit Exponent (-2)\n\
+             Unit (0x00001001)\n\
+             Long Item (0x10, 2 bytes)\n\
+             Feature (Constant, Variable, Absolute, Null State, Volatile)\n"
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            DescriptorTree::parse(&[0x05, 0x01, 0x26, 0xFF]),
+            Err(ParseError::TruncatedItem { offset: 2 })
+        );
+        assert_eq!(
+            DescriptorTree::parse(&[0xA1, 0x01, 0xA1, 0x00, 0xC0]),
+            Err(ParseError::UnclosedCollection { offset: 0 })
+        );
+        assert_eq!(
+            DescriptorTree::parse(&[0x09, 0x01, 0xC0]),
+            Err(ParseError::UnmatchedEndCollection { offset: 2 })
+        );
+    }
+}
@@ -0,0 +1,654 @@
+//! Report descriptor items
+//!
+//! Typed encoding of the global items giving the physical range and units of report fields.
+//! Items are written to a [`ReportDescriptor`] in a `const` or `static`, so invalid values are
+//! rejected at compile time:
+//!
+//! ```
+//! # use usbd_human_interface_device::descriptor::item::{Item, ReportDescriptor, Unit};
+//! // Hat switch reporting 0-7 as 0-315 degrees
+//! static HAT_SWITCH: ReportDescriptor<32> = ReportDescriptor::new()
+//!     .bytes(&[0x09, 0x39]) // Usage (Hat switch)
+//!     .item(Item::logical_minimum(0))
+//!     .item(Item::logical_maximum(7))
+//!     .item(Item::physical_minimum(0))
+//!     .item(Item::physical_maximum(315))
+//!     .item(Item::unit(Unit::DEGREE))
+//!     .item(Item::unit_exponent(0))
+//!     .bytes(&[0x75, 0x04, 0x95, 0x01, 0x81, 0x42]) // Report Size (4), Count (1), Input
+//!     .item(Item::unit(Unit::NONE));
+//!
+//! assert_eq!(
+//!     HAT_SWITCH.as_bytes(),
+//!     [
+//!         0x09, 0x39, 0x15, 0x00, 0x25, 0x07, 0x35, 0x00, 0x46, 0x3B, 0x01, 0x65, 0x14, 0x55,
+//!         0x00, 0x75, 0x04, 0x95, 0x01, 0x81, 0x42, 0x65, 0x00
+//!     ]
+//! );
+//! ```
+//!
+//! [`ReportDescriptor::validate`] and [`check_report_descriptor`] check a finished descriptor is
+//! well formed, again at compile time when used in a `const`.
+//!
+//! [`report_length`] goes the other way, finding the length of reports declared by an existing
+//! descriptor.
+//!
+//! Defined in section 6.2.2.7 of [Device Class Definition for Human Interface Devices (Hid)
+//! Version 1.11](<https://www.usb.org/sites/default/files/hid1_11.pdf>)
+
+use crate::descriptor::ReportType;
+
+const USAGE_PAGE: u8 = 0x04;
+const USAGE: u8 = 0x08;
+const LOGICAL_MINIMUM: u8 = 0x14;
+const LOGICAL_MAXIMUM: u8 = 0x24;
+const PHYSICAL_MINIMUM: u8 = 0x34;
+const PHYSICAL_MAXIMUM: u8 = 0x44;
+const UNIT_EXPONENT: u8 = 0x54;
+const UNIT: u8 = 0x64;
+
+/// Measurement system of a [`Unit`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum UnitSystem {
+    None = 0x0,
+    SiLinear = 0x1,
+    SiRotation = 0x2,
+    EnglishLinear = 0x3,
+    EnglishRotation = 0x4,
+}
+
+/// Unit of a report field, the system and the exponent of each base unit
+///
+/// The base units of length, mass, time, temperature, current and luminous intensity depend on
+/// the system, e.g. centimeters or inches for length, radians or degrees for rotation
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Unit(u32);
+
+impl Unit {
+    pub const NONE: Self = Self(0);
+    pub const CENTIMETER: Self = Self::new(UnitSystem::SiLinear, [1, 0, 0, 0, 0, 0]);
+    pub const INCH: Self = Self::new(UnitSystem::EnglishLinear, [1, 0, 0, 0, 0, 0]);
+    pub const RADIAN: Self = Self::new(UnitSystem::SiRotation, [1, 0, 0, 0, 0, 0]);
+    pub const DEGREE: Self = Self::new(UnitSystem::EnglishRotation, [1, 0, 0, 0, 0, 0]);
+    pub const GRAM: Self = Self::new(UnitSystem::SiLinear, [0, 1, 0, 0, 0, 0]);
+    pub const SECOND: Self = Self::new(UnitSystem::SiLinear, [0, 0, 1, 0, 0, 0]);
+    pub const KELVIN: Self = Self::new(UnitSystem::SiLinear, [0, 0, 0, 1, 0, 0]);
+    pub const FAHRENHEIT: Self = Self::new(UnitSystem::EnglishLinear, [0, 0, 0, 1, 0, 0]);
+    pub const AMPERE: Self = Self::new(UnitSystem::SiLinear, [0, 0, 0, 0, 1, 0]);
+    pub const CANDELA: Self = Self::new(UnitSystem::SiLinear, [0, 0, 0, 0, 0, 1]);
+    pub const CENTIMETER_PER_SECOND: Self = Self::new(UnitSystem::SiLinear, [1, 0, -1, 0, 0, 0]);
+    pub const CENTIMETER_PER_SECOND_SQUARED: Self =
+        Self::new(UnitSystem::SiLinear, [1, 0, -2, 0, 0, 0]);
+    pub const RADIAN_PER_SECOND: Self = Self::new(UnitSystem::SiRotation, [1, 0, -1, 0, 0, 0]);
+    pub const DEGREE_PER_SECOND: Self = Self::new(UnitSystem::EnglishRotation, [1, 0, -1, 0, 0, 0]);
+
+    /// `exponents` of length, mass, time, temperature, current and luminous intensity
+    ///
+    /// # Panics
+    ///
+    /// If an exponent is outside of -8 to 7
+    #[must_use]
+    pub const fn new(system: UnitSystem, exponents: [i8; 6]) -> Self {
+        let mut value = system as u32;
+        let mut i = 0;
+        while i < exponents.len() {
+            value |= nibble(exponents[i]) << (4 * (i + 1));
+            i += 1;
+        }
+        Self(value)
+    }
+
+    #[must_use]
+    pub const fn value(self) -> u32 {
+        self.0
+    }
+}
+
+#[allow(clippy::cast_sign_loss)]
+const fn nibble(value: i8) -> u32 {
+    ::core::assert!(
+        value >= -8 && value <= 7,
+        "exponent out of range, must be -8 to 7"
+    );
+    (value as u8 & 0xF) as u32
+}
+
+/// A single short item
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Item {
+    bytes: [u8; 5],
+    len: usize,
+}
+
+impl Item {
+    #[must_use]
+    pub const fn usage_page(page: u16) -> Self {
+        Self::unsigned(USAGE_PAGE, page as u32)
+    }
+
+    /// Usage on the current usage page, such as [`Simulation::id`](crate::page::Simulation::id)
+    #[must_use]
+    pub const fn usage(usage: u16) -> Self {
+        Self::unsigned(USAGE, usage as u32)
+    }
+
+    #[must_use]
+    pub const fn logical_minimum(value: i32) -> Self {
+        Self::signed(LOGICAL_MINIMUM, value)
+    }
+
+    #[must_use]
+    pub const fn logical_maximum(value: i32) -> Self {
+        Self::signed(LOGICAL_MAXIMUM, value)
+    }
+
+    #[must_use]
+    pub const fn physical_minimum(value: i32) -> Self {
+        Self::signed(PHYSICAL_MINIMUM, value)
+    }
+
+    #[must_use]
+    pub const fn physical_maximum(value: i32) -> Self {
+        Self::signed(PHYSICAL_MAXIMUM, value)
+    }
+
+    #[must_use]
+    pub const fn unit(unit: Unit) -> Self {
+        Self::unsigned(UNIT, unit.value())
+    }
+
+    /// Base 10 exponent applied to the unit of following fields
+    ///
+    /// # Panics
+    ///
+    /// If `exponent` is outside of -8 to 7
+    #[must_use]
+    pub const fn unit_exponent(exponent: i8) -> Self {
+        Self::sized(UNIT_EXPONENT, nibble(exponent), 1)
+    }
+
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+
+    /// Signed data in the fewest bytes
+    #[allow(clippy::cast_sign_loss)]
+    const fn signed(tag: u8, value: i32) -> Self {
+        let size = if value >= i8::MIN as i32 && value <= i8::MAX as i32 {
+            1
+        } else if value >= i16::MIN as i32 && value <= i16::MAX as i32 {
+            2
+        } else {
+            4
+        };
+        Self::sized(tag, value as u32, size)
+    }
+
+    /// Unsigned data in the fewest bytes
+    const fn unsigned(tag: u8, value: u32) -> Self {
+        let size = if value <= 0xFF {
+            1
+        } else if value <= 0xFFFF {
+            2
+        } else {
+            4
+        };
+        Self::sized(tag, value, size)
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn sized(tag: u8, value: u32, size: usize) -> Self {
+        let data = value.to_le_bytes();
+        let size_code = if size == 4 { 3 } else { size as u8 };
+        let mut bytes = [tag | size_code, 0, 0, 0, 0];
+        let mut i = 0;
+        while i < size {
+            bytes[i + 1] = data[i];
+            i += 1;
+        }
+        Self {
+            bytes,
+            len: size + 1,
+        }
+    }
+}
+
+/// Report descriptor of up to `N` bytes, built from [`Item`]s and raw bytes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReportDescriptor<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Default for ReportDescriptor<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> ReportDescriptor<N> {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    /// # Panics
+    ///
+    /// If the descriptor is longer than `N` bytes
+    #[must_use]
+    pub const fn item(self, item: Item) -> Self {
+        self.push(&item.bytes, item.len)
+    }
+
+    /// Append encoded items, such as usages and main items
+    ///
+    /// # Panics
+    ///
+    /// If the descriptor is longer than `N` bytes
+    #[must_use]
+    pub const fn bytes(self, bytes: &[u8]) -> Self {
+        self.push(bytes, bytes.len())
+    }
+
+    const fn push(mut self, bytes: &[u8], len: usize) -> Self {
+        ::core::assert!(self.len + len <= N, "report descriptor too long");
+        let mut i = 0;
+        while i < len {
+            self.bytes[self.len + i] = bytes[i];
+            i += 1;
+        }
+        self.len += len;
+        self
+    }
+
+    /// Check the descriptor is well formed, see [`check_report_descriptor`]
+    ///
+    /// # Panics
+    ///
+    /// If the descriptor isn't well formed
+    #[must_use]
+    pub const fn validate(self) -> Self {
+        check_report_descriptor(self.as_bytes());
+        self
+    }
+
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8] {
+        self.bytes.split_at(self.len).0
+    }
+}
+
+const LONG_ITEM: u8 = 0xFE;
+const MAX_PUSH_DEPTH: usize = 4;
+
+#[derive(Clone, Copy, Default)]
+struct ReportGlobals {
+    size: u32,
+    count: u32,
+    id: u8,
+}
+
+/// Length in bytes of the `report_type` report with `report_id` declared by `descriptor`,
+/// including the report ID prefix if the descriptor uses report IDs
+///
+/// Use a `report_id` of 0 for descriptors without report IDs. Returns `None` if the descriptor
+/// doesn't declare the report, or is malformed.
+#[must_use]
+pub fn report_length(descriptor: &[u8], report_type: ReportType, report_id: u8) -> Option<usize> {
+    let main_tag = match report_type {
+        ReportType::Input => 0x8,
+        ReportType::Output => 0x9,
+        ReportType::Feature => 0xB,
+    };
+
+    let mut globals = ReportGlobals::default();
+    let mut stack = [ReportGlobals::default(); MAX_PUSH_DEPTH];
+    let mut depth = 0;
+    let mut uses_ids = false;
+    let mut bits: Option<u32> = None;
+
+    let mut rest = descriptor;
+    while let Some((&prefix, tail)) = rest.split_first() {
+        if prefix == LONG_ITEM {
+            let (&len, _) = tail.split_first()?;
+            rest = tail.get(2 + usize::from(len)..)?;
+            continue;
+        }
+
+        let len = match prefix & 0x3 {
+            3 => 4,
+            n => usize::from(n),
+        };
+        let data = tail.get(..len)?;
+        rest = &tail[len..];
+        let value = data
+            .iter()
+            .rev()
+            .fold(0, |acc, &b| (acc << 8) | u32::from(b));
+
+        match (prefix >> 2) & 0x3 {
+            //Main
+            0 if prefix >> 4 == main_tag && globals.id == report_id => {
+                let total = bits.unwrap_or_default();
+                bits = Some(total.checked_add(globals.size.checked_mul(globals.count)?)?);
+            }
+            //Global
+            1 => match prefix >> 4 {
+                0x7 => globals.size = value,
+                0x8 => {
+                    globals.id = u8::try_from(value).ok()?;
+                    uses_ids = true;
+                }
+                0x9 => globals.count = value,
+                0xA => {
+                    *stack.get_mut(depth)? = globals;
+                    depth += 1;
+                }
+                0xB => {
+                    depth = depth.checked_sub(1)?;
+                    globals = stack[depth];
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    let bytes = usize::try_from(bits?.div_ceil(8)).ok()?;
+    Some(bytes + usize::from(uses_ids))
+}
+
+/// Check that `descriptor` is well formed, panicking if it isn't
+///
+/// Intended for use in a `const`, such as through [`ReportDescriptor::validate`], so mistakes in
+/// a descriptor fail the build rather than enumeration. The checks are that every item is
+/// complete, collections are closed, report IDs aren't 0, and every data main item has a report
+/// size, a report count and a usage.
+///
+/// # Panics
+///
+/// If any check fails
+pub const fn check_report_descriptor(descriptor: &[u8]) {
+    let mut globals = [0; 2];
+    let mut stack = [[0; 2]; MAX_PUSH_DEPTH];
+    let mut depth = 0;
+    let mut collections = 0;
+    let mut has_usage = false;
+
+    let mut i = 0;
+    while i < descriptor.len() {
+        let prefix = descriptor[i];
+        if prefix == LONG_ITEM {
+            ::core::assert!(i + 1 < descriptor.len(), "truncated long item");
+            i += 3 + descriptor[i + 1] as usize;
+            ::core::assert!(i <= descriptor.len(), "truncated long item");
+            continue;
+        }
+
+        let len = match prefix & 0x3 {
+            3 => 4,
+            n => n as usize,
+        };
+        ::core::assert!(i + len < descriptor.len(), "truncated item");
+        let value = item_value(descriptor, i + 1, len);
+
+        match (prefix >> 2) & 0x3 {
+            //Main
+            0 => {
+                match prefix >> 4 {
+                    //Input, Output, Feature
+                    0x8 | 0x9 | 0xB => {
+                        ::core::assert!(
+                            globals[0] != 0 && globals[1] != 0,
+                            "main item without a report size and report count"
+                        );
+                        //Constant items are padding
+                        ::core::assert!(value & 0x1 != 0 || has_usage, "data item without a usage");
+                    }
+                    0xA => collections += 1,
+                    0xC => {
+                        ::core::assert!(collections > 0, "end collection without a collection");
+                        collections -= 1;
+                    }
+                    _ => {}
+                }
+                has_usage = false;
+            }
+            //Global
+            1 => match prefix >> 4 {
+                0x7 => globals[0] = value,
+                0x8 => ::core::assert!(value != 0 && value <= 0xFF, "report ID out of range"),
+                0x9 => globals[1] = value,
+                0xA => {
+                    ::core::assert!(depth < MAX_PUSH_DEPTH, "too many pushes");
+                    stack[depth] = globals;
+                    depth += 1;
+                }
+                0xB => {
+                    ::core::assert!(depth > 0, "pop without a push");
+                    depth -= 1;
+                    globals = stack[depth];
+                }
+                _ => {}
+            },
+            //Local Usage, Usage Minimum
+            2 if prefix >> 4 <= 0x1 => has_usage = true,
+            _ => {}
+        }
+        i += 1 + len;
+    }
+    ::core::assert!(collections == 0, "collection without an end collection");
+}
+
+/// Length in bytes of the longest `report_type` report declared by `descriptor`, including the
+/// report ID prefix if the descriptor uses report IDs, or 0 if there are no such reports
+///
+/// Unlike [`report_length`] this can be used in a `const`, the descriptor should be checked with
+/// [`check_report_descriptor`] first.
+#[must_use]
+pub const fn max_report_length(descriptor: &[u8], report_type: ReportType) -> usize {
+    let main_tag = match report_type {
+        ReportType::Input => 0x8,
+        ReportType::Output => 0x9,
+        ReportType::Feature => 0xB,
+    };
+
+    let mut bits = [0; 256];
+    let mut globals = [0; 3];
+    let mut stack = [[0; 3]; MAX_PUSH_DEPTH];
+    let mut depth = 0;
+    let mut uses_ids = false;
+
+    let mut i = 0;
+    while i < descriptor.len() {
+        let prefix = descriptor[i];
+        if prefix == LONG_ITEM {
+            i += 3 + descriptor[i + 1] as usize;
+            continue;
+        }
+
+        let len = match prefix & 0x3 {
+            3 => 4,
+            n => n as usize,
+        };
+        let value = item_value(descriptor, i + 1, len);
+
+        match (prefix >> 2) & 0x3 {
+            0 if prefix >> 4 == main_tag => {
+                bits[globals[2] as usize & 0xFF] += globals[0] as usize * globals[1] as usize;
+            }
+            1 => match prefix >> 4 {
+                0x7 => globals[0] = value,
+                0x8 => {
+                    globals[2] = value;
+                    uses_ids = true;
+                }
+                0x9 => globals[1] = value,
+                0xA if depth < MAX_PUSH_DEPTH => {
+                    stack[depth] = globals;
+                    depth += 1;
+                }
+                0xB if depth > 0 => {
+                    depth -= 1;
+                    globals = stack[depth];
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+        i += 1 + len;
+    }
+
+    let mut longest = 0;
+    let mut id = 0;
+    while id < bits.len() {
+        if bits[id] > longest {
+            longest = bits[id];
+        }
+        id += 1;
+    }
+    if longest == 0 {
+        0
+    } else {
+        longest.div_ceil(8) + uses_ids as usize
+    }
+}
+
+/// Little endian value of the `len` bytes of item data at `start`
+const fn item_value(descriptor: &[u8], start: usize, len: usize) -> u32 {
+    let mut value = 0;
+    let mut i = len;
+    while i > 0 {
+        i -= 1;
+        value = (value << 8) | descriptor[start + i] as u32;
+    }
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn items_use_smallest_encoding() {
+        assert_eq!(Item::physical_minimum(-127).as_bytes(), [0x35, 0x81]);
+        assert_eq!(Item::physical_maximum(32767).as_bytes(), [0x46, 0xFF, 0x7F]);
+        assert_eq!(Item::logical_minimum(-32768).as_bytes(), [0x16, 0x00, 0x80]);
+        assert_eq!(
+            Item::logical_maximum(65535).as_bytes(),
+            [0x27, 0xFF, 0xFF, 0x00, 0x00]
+        );
+        assert_eq!(Item::unit_exponent(-2).as_bytes(), [0x55, 0x0E]);
+        assert_eq!(
+            Item::unit(Unit::CENTIMETER_PER_SECOND_SQUARED).as_bytes(),
+            [0x66, 0x11, 0xE0]
+        );
+        assert_eq!(Item::unit(Unit::NONE).as_bytes(), [0x65, 0x00]);
+        assert_eq!(Item::usage_page(0x02).as_bytes(), [0x05, 0x02]);
+        assert_eq!(Item::usage(0xBB).as_bytes(), [0x09, 0xBB]);
+        assert_eq!(Item::usage_page(0xFF00).as_bytes(), [0x06, 0x00, 0xFF]);
+    }
+
+    #[test]
+    fn report_length_of_descriptors() {
+        use crate::device::consumer::MULTIPLE_CODE_REPORT_DESCRIPTOR;
+        use crate::device::keyboard::BOOT_KEYBOARD_REPORT_DESCRIPTOR;
+
+        static WITH_IDS: ReportDescriptor<32> = ReportDescriptor::new()
+            .bytes(&[0x85, 0x01, 0x75, 0x08, 0x95, 0x03, 0x81, 0x02]) // ID 1, 3 bytes input
+            .bytes(&[0xA4, 0x75, 0x01, 0x95, 0x04, 0x91, 0x02, 0xB4]) // Push, 4 bits output, Pop
+            .bytes(&[0x85, 0x02, 0x95, 0x02, 0x81, 0x02]); // ID 2, 2 bytes input
+
+        let keyboard = BOOT_KEYBOARD_REPORT_DESCRIPTOR;
+        assert_eq!(report_length(keyboard, ReportType::Input, 0), Some(8));
+        assert_eq!(report_length(keyboard, ReportType::Output, 0), Some(1));
+        assert_eq!(report_length(keyboard, ReportType::Feature, 0), None);
+        assert_eq!(report_length(keyboard, ReportType::Input, 1), None);
+        assert_eq!(
+            report_length(MULTIPLE_CODE_REPORT_DESCRIPTOR, ReportType::Input, 0),
+            Some(8)
+        );
+
+        let with_ids = WITH_IDS.as_bytes();
+        assert_eq!(report_length(with_ids, ReportType::Input, 1), Some(4));
+        assert_eq!(report_length(with_ids, ReportType::Output, 1), Some(2));
+        assert_eq!(report_length(with_ids, ReportType::Input, 2), Some(3));
+        assert_eq!(report_length(&[0x75], ReportType::Input, 0), None);
+
+        assert_eq!(max_report_length(keyboard, ReportType::Input), 8);
+        assert_eq!(max_report_length(keyboard, ReportType::Feature), 0);
+        assert_eq!(max_report_length(with_ids, ReportType::Input), 4);
+        assert_eq!(max_report_length(with_ids, ReportType::Output), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "data item without a usage")]
+    fn data_item_needs_usage() {
+        check_report_descriptor(&[0x09, 0x01, 0x75, 0x08, 0x95, 0x01, 0x81, 0x02, 0x81, 0x02]);
+    }
+
+    #[test]
+    #[should_panic(expected = "main item without a report size and report count")]
+    fn main_item_needs_report_size() {
+        check_report_descriptor(&[0x95, 0x01, 0x81, 0x01]);
+    }
+
+    #[test]
+    #[should_panic(expected = "collection without an end collection")]
+    fn collections_are_closed() {
+        check_report_descriptor(&[0x09, 0x01, 0xA1, 0x01]);
+    }
+
+    #[test]
+    #[should_panic(expected = "truncated item")]
+    fn items_are_complete() {
+        check_report_descriptor(&[0x09, 0x01, 0x26, 0xFF]);
+    }
+
+    #[test]
+    fn device_descriptors_are_well_formed() {
+        use crate::device::joystick::CustomJoystickReport;
+        use crate::device::{
+            barcode, camera, card_reader, consumer, encoder, fido, flight, gamepad, haptics,
+            joystick, keyboard, mouse, scale, touchpad, vr, wireless_radio,
+        };
+
+        for descriptor in [
+            barcode::BARCODE_SCANNER_REPORT_DESCRIPTOR,
+            camera::CAMERA_CONTROL_REPORT_DESCRIPTOR,
+            card_reader::CARD_READER_REPORT_DESCRIPTOR,
+            consumer::MULTIPLE_CODE_REPORT_DESCRIPTOR,
+            consumer::FIXED_FUNCTION_REPORT_DESCRIPTOR,
+            consumer::CAPTURE_CONTROL_REPORT_DESCRIPTOR,
+            encoder::ENCODERS_REPORT_DESCRIPTOR,
+            fido::FIDO_REPORT_DESCRIPTOR,
+            flight::FLIGHT_CONTROLS_REPORT_DESCRIPTOR,
+            gamepad::GAMEPAD_REPORT_DESCRIPTOR,
+            haptics::HAPTIC_CONTROLLER_REPORT_DESCRIPTOR,
+            joystick::JOYSTICK_DESCRIPTOR,
+            CustomJoystickReport::<4, 12>::REPORT_DESCRIPTOR,
+            keyboard::BOOT_KEYBOARD_REPORT_DESCRIPTOR,
+            keyboard::NKRO_BOOT_KEYBOARD_REPORT_DESCRIPTOR,
+            keyboard::DUAL_MODE_KEYBOARD_REPORT_DESCRIPTOR,
+            keyboard::APPLE_KEYBOARD_REPORT_DESCRIPTOR,
+            keyboard::NKRO_COMPACT_KEYBOARD_REPORT_DESCRIPTOR,
+            mouse::BOOT_MOUSE_REPORT_DESCRIPTOR,
+            mouse::WHEEL_MOUSE_REPORT_DESCRIPTOR,
+            mouse::ABSOLUTE_WHEEL_MOUSE_REPORT_DESCRIPTOR,
+            mouse::HYBRID_MOUSE_REPORT_DESCRIPTOR,
+            scale::SCALE_REPORT_DESCRIPTOR,
+            touchpad::PRECISION_TOUCHPAD_REPORT_DESCRIPTOR,
+            vr::VR_CONTROLLER_REPORT_DESCRIPTOR,
+            wireless_radio::WIRELESS_RADIO_CONTROLS_REPORT_DESCRIPTOR,
+        ] {
+            check_report_descriptor(descriptor);
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//!HID Point of Sale bar code scanner
+//!
+//! Reports decoded bar codes to the host as HID POS scanned data reports, rather than typing them
+//! as a keyboard. Bar codes longer than a single report are split across reports by
+//! [`UsbHidClass::tick()`].
+//!
+//! Defined by the Bar Code Scanner page (0x8C) of the [HID Point of Sale Usage Tables](<https://www.usb.org/sites/default/files/pos1_02.pdf>)
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use heapless::Vec;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Most bytes of decoded data in a single [`ScannedDataReport`]
+pub const DECODED_DATA_LEN: usize = 56;
+
+/// Longest bar code that can be passed to [`BarcodeScanner::scan`]
+pub const MAX_BARCODE_LEN: usize = 256;
+
+const SCANNED_DATA_REPORT_ID: u8 = 0x01;
+const TRIGGER_REPORT_ID: u8 = 0x02;
+const STATUS_REPORT_ID: u8 = 0x03;
+
+/// Bar code scanner with a scanned data input report, a trigger feature report written by the
+/// host and a status feature report read by the host
+#[rustfmt::skip]
+pub const BARCODE_SCANNER_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x8C,                   // Usage Page (Bar Code Scanner)
+    0x09, 0x02,                   // Usage (Bar Code Scanner)
+    0xA1, 0x01,                   // Collection (Application)
+    0x09, 0x12,                   //   Usage (Scanned Data Report)
+    0xA1, 0x02,                   //   Collection (Logical)
+    0x85, SCANNED_DATA_REPORT_ID, //     Report ID
+    0x15, 0x00,                   //     Logical Minimum (0)
+    0x26, 0xFF, 0x00,             //     Logical Maximum (255)
+    0x75, 0x08,                   //     Report Size (8)
+    0x95, 0x01,                   //     Report Count (1)
+    0x09, 0xFB,                   //     Usage (Symbology Identifier 1)
+    0x81, 0x02,                   //     Input (Data, Variable, Absolute)
+    0x09, 0xFC,                   //     Usage (Symbology Identifier 2)
+    0x81, 0x02,                   //     Input (Data, Variable, Absolute)
+    0x09, 0xFD,                   //     Usage (Symbology Identifier 3)
+    0x81, 0x02,                   //     Input (Data, Variable, Absolute)
+    0x95, 0x38,                   //     Report Count (56)
+    0x09, 0xFE,                   //     Usage (Decoded Data)
+    0x82, 0x02, 0x01,             //     Input (Data, Variable, Absolute, Buffered Bytes)
+    0x25, 0x01,                   //     Logical Maximum (1)
+    0x75, 0x01,                   //     Report Size (1)
+    0x95, 0x01,                   //     Report Count (1)
+    0x09, 0xFF,                   //     Usage (Decode Data Continued)
+    0x81, 0x02,                   //     Input (Data, Variable, Absolute)
+    0x95, 0x07,                   //     Report Count (7)
+    0x81, 0x03,                   //     Input (Constant)
+    0xC0,                         //   End Collection
+    0x09, 0x14,                   //   Usage (Trigger Report)
+    0xA1, 0x02,                   //   Collection (Logical)
+    0x85, TRIGGER_REPORT_ID,      //     Report ID
+    0x95, 0x01,                   //     Report Count (1)
+    0x09, 0x60,                   //     Usage (Initiate Barcode Read)
+    0xB1, 0x02,                   //     Feature (Data, Variable, Absolute)
+    0x09, 0x5F,                   //     Usage (Prevent Read of Barcodes)
+    0xB1, 0x02,                   //     Feature (Data, Variable, Absolute)
+    0x95, 0x06,                   //     Report Count (6)
+    0xB1, 0x03,                   //     Feature (Constant)
+    0xC0,                         //   End Collection
+    0x09, 0x15,                   //   Usage (Status Report)
+    0xA1, 0x02,                   //   Collection (Logical)
+    0x85, STATUS_REPORT_ID,       //     Report ID
+    0x95, 0x01,                   //     Report Count (1)
+    0x09, 0x61,                   //     Usage (Trigger State)
+    0xB1, 0x02,                   //     Feature (Data, Variable, Absolute)
+    0x09, 0x57,                   //     Usage (Bar Code Present)
+    0xB1, 0x02,                   //     Feature (Data, Variable, Absolute)
+    0x95, 0x06,                   //     Report Count (6)
+    0xB1, 0x03,                   //     Feature (Constant)
+    0xC0,                         //   End Collection
+    0xC0,                         // End Collection
+];
+
+/// A part of a decoded bar code
+///
+/// The unused bytes of [`Self::decoded_data`] are 0
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "msb0", size_bytes = "61")]
+pub struct ScannedDataReport {
+    #[packed_field(bytes = "0")]
+    pub report_id: u8,
+    /// AIM symbology identifier of the bar code, such as `]E0` for EAN-13
+    #[packed_field(bytes = "1..=3")]
+    pub symbology: [u8; 3],
+    #[packed_field(bytes = "4..=59")]
+    pub decoded_data: [u8; 56],
+    /// Set if the bar code continues in the next report
+    #[packed_field(bits = "487")]
+    pub continued: bool,
+}
+
+impl Default for ScannedDataReport {
+    fn default() -> Self {
+        Self {
+            report_id: SCANNED_DATA_REPORT_ID,
+            symbology: [0; 3],
+            decoded_data: [0; DECODED_DATA_LEN],
+            continued: false,
+        }
+    }
+}
+
+/// Trigger report written by the host
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "lsb0", size_bytes = "1")]
+pub struct BarcodeTriggerReport {
+    /// The host has requested a scan
+    #[packed_field(bits = "0")]
+    pub initiate_read: bool,
+    /// The host has disabled scanning
+    #[packed_field(bits = "1")]
+    pub prevent_read: bool,
+}
+
+/// Status report read by the host
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "lsb0", size_bytes = "1")]
+pub struct BarcodeStatusReport {
+    #[packed_field(bits = "0")]
+    pub trigger_pulled: bool,
+    /// A bar code is in the scanner's field of view
+    #[packed_field(bits = "1")]
+    pub bar_code_present: bool,
+}
+
+/// Interface implementing a HID POS bar code scanner
+///
+/// **Note:** Bar codes are sent by [`UsbHidClass::tick()`] which must be called regularly.
+pub struct BarcodeScanner<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes64, OutBytes8, Reports8>,
+    trigger: BarcodeTriggerReport,
+    trigger_changed: bool,
+    status: BarcodeStatusReport,
+    symbology: [u8; 3],
+    pending: Vec<u8, MAX_BARCODE_LEN>,
+    sent: usize,
+}
+
+impl<B: UsbBus> BarcodeScanner<'_, B> {
+    /// Send a decoded bar code, with the AIM `symbology` identifier and `data` decoded from it
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::WouldBlock`] if a bar code is still being sent, or
+    /// [`UsbHidError::SerializationError`] if `data` is longer than [`MAX_BARCODE_LEN`]
+    pub fn scan(&mut self, symbology: [u8; 3], data: &[u8]) -> Result<(), UsbHidError> {
+        if self.is_sending() {
+            return Err(UsbHidError::WouldBlock);
+        }
+        self.pending = Vec::from_slice(data).map_err(|()| {
+            error!("Bar code too long");
+            UsbHidError::SerializationError
+        })?;
+        self.symbology = symbology;
+        self.sent = 0;
+        self.send_pending()
+    }
+
+    /// True while a bar code is being sent
+    #[must_use]
+    pub fn is_sending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// The trigger report last written by the host
+    #[must_use]
+    pub fn trigger(&self) -> BarcodeTriggerReport {
+        self.trigger
+    }
+
+    /// Returns the trigger report if the host has written it since this was last called
+    pub fn trigger_changed(&mut self) -> Option<BarcodeTriggerReport> {
+        core::mem::take(&mut self.trigger_changed).then_some(self.trigger)
+    }
+
+    /// Update the status report returned to the host
+    ///
+    /// # Errors
+    ///
+    /// Any [`UsbError`] caching the report
+    pub fn set_status(&mut self, status: BarcodeStatusReport) -> Result<(), UsbHidError> {
+        self.status = status;
+        self.set_feature_reports()
+    }
+
+    fn set_feature_reports(&mut self) -> Result<(), UsbHidError> {
+        let trigger = self
+            .trigger
+            .pack()
+            .map_err(|_| UsbHidError::SerializationError)?;
+        let status = self
+            .status
+            .pack()
+            .map_err(|_| UsbHidError::SerializationError)?;
+        self.interface
+            .set_feature_report(&[TRIGGER_REPORT_ID, trigger[0]])?;
+        self.interface
+            .set_feature_report(&[STATUS_REPORT_ID, status[0]])?;
+        Ok(())
+    }
+
+    fn send_pending(&mut self) -> Result<(), UsbHidError> {
+        let remaining = &self.pending[self.sent..];
+        let len = remaining.len().min(DECODED_DATA_LEN);
+        let mut report = ScannedDataReport {
+            symbology: self.symbology,
+            continued: remaining.len() > DECODED_DATA_LEN,
+            ..Default::default()
+        };
+        report.decoded_data[..len].copy_from_slice(&remaining[..len]);
+        let data = report.pack().map_err(|_| {
+            error!("Error packing ScannedDataReport");
+            UsbHidError::SerializationError
+        })?;
+        match self.interface.write_report(&data) {
+            Ok(_) => {
+                self.sent += len;
+                if self.sent >= self.pending.len() {
+                    self.pending.clear();
+                    self.sent = 0;
+                }
+                Ok(())
+            }
+            Err(UsbHidError::WouldBlock) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for BarcodeScanner<'a, B> {
+    type I = Interface<'a, B, InBytes64, OutBytes8, Reports8>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.trigger = BarcodeTriggerReport::default();
+        self.trigger_changed = false;
+        self.pending.clear();
+        self.sent = 0;
+        if self.set_feature_reports().is_err() {
+            error!("Failed to set bar code scanner feature reports");
+        }
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        let mut data = [0; 8];
+        match self.interface.read_report(&mut data) {
+            Ok(n) => match data[..n] {
+                [TRIGGER_REPORT_ID, value, ..] => {
+                    self.trigger = BarcodeTriggerReport::unpack(&[value]).map_err(|_| {
+                        error!("Error unpacking BarcodeTriggerReport");
+                        UsbHidError::SerializationError
+                    })?;
+                    self.trigger_changed = true;
+                }
+                _ => warn!("Unexpected bar code scanner report"),
+            },
+            Err(UsbError::WouldBlock) => {}
+            Err(e) => return Err(UsbHidError::from(e)),
+        }
+
+        if self.is_sending() {
+            self.send_pending()?;
+        }
+        Ok(())
+    }
+}
+
+pub struct BarcodeScannerConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes64, OutBytes8, Reports8>,
+}
+
+impl Default for BarcodeScannerConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(BARCODE_SCANNER_REPORT_DESCRIPTOR))
+                    .description("Bar Code Scanner")
+                    .in_endpoint(1.millis())
+            )
+            .without_out_endpoint()
+            .send_unchanged_reports()
+            .build(),
+        )
+    }
+}
+
+impl<'a> BarcodeScannerConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes64, OutBytes8, Reports8>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for BarcodeScannerConfig<'a> {
+    type Allocated = BarcodeScanner<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        let mut scanner = Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            trigger: BarcodeTriggerReport::default(),
+            trigger_changed: false,
+            status: BarcodeStatusReport::default(),
+            symbology: [0; 3],
+            pending: Vec::new(),
+            sent: 0,
+        };
+        if scanner.set_feature_reports().is_err() {
+            error!("Failed to set bar code scanner feature reports");
+        }
+        scanner
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn reports_match_descriptor() {
+        let descriptor = BARCODE_SCANNER_REPORT_DESCRIPTOR;
+        assert_eq!(
+            report_length(descriptor, ReportType::Input, SCANNED_DATA_REPORT_ID),
+            Some(61)
+        );
+        assert_eq!(
+            report_length(descriptor, ReportType::Feature, TRIGGER_REPORT_ID),
+            Some(2)
+        );
+        assert_eq!(
+            report_length(descriptor, ReportType::Feature, STATUS_REPORT_ID),
+            Some(2)
+        );
+
+        let report = ScannedDataReport {
+            symbology: *b"]E0",
+            continued: true,
+            ..Default::default()
+        };
+        let packed = report.pack().unwrap();
+        assert_eq!(packed[..4], [SCANNED_DATA_REPORT_ID, b']', b'E', b'0']);
+        assert_eq!(packed[60], 0x01);
+    }
+}
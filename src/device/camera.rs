@@ -0,0 +1,158 @@
+//!HID camera controls, such as a remote shutter release
+//!
+//! Reports the shutter and auto-focus buttons of the Camera Control page, and the zoom in and out
+//! buttons of the Consumer page, so hosts handle them as camera controls rather than keyboard
+//! shortcuts.
+//!
+//! Defined by the Camera Control page (0x90), see [`Camera`]
+use crate::descriptor::item::{Item, ReportDescriptor};
+use crate::page::Camera;
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+#[rustfmt::skip]
+const DESCRIPTOR: &ReportDescriptor<37> = &ReportDescriptor::new()
+    .bytes(&[
+        0x05, 0x0C, // Usage Page (Consumer)
+        0x09, 0x01, // Usage (Consumer Control)
+        0xA1, 0x01, // Collection (Application)
+        0x15, 0x00, //   Logical Minimum (0)
+        0x25, 0x01, //   Logical Maximum (1)
+        0x75, 0x01, //   Report Size (1)
+        0x95, 0x02, //   Report Count (2)
+    ])
+    .item(Item::usage_page(Camera::PAGE))
+    .item(Item::usage(Camera::CameraAutoFocus.id()))
+    .item(Item::usage(Camera::CameraShutter.id()))
+    .bytes(&[
+        0x81, 0x02,       //   Input (Data, Variable, Absolute)
+        0x05, 0x0C,       //   Usage Page (Consumer)
+        0x0A, 0x2D, 0x02, //   Usage (AC Zoom In)
+        0x0A, 0x2E, 0x02, //   Usage (AC Zoom Out)
+        0x81, 0x02,       //   Input (Data, Variable, Absolute)
+        0x95, 0x04,       //   Report Count (4)
+        0x81, 0x03,       //   Input (Constant)
+        0xC0,             // End Collection
+    ])
+    .validate();
+
+/// Camera auto-focus and shutter buttons, and zoom in and out buttons, in a single bit packed `u8`
+/// report
+pub const CAMERA_CONTROL_REPORT_DESCRIPTOR: &[u8] = DESCRIPTOR.as_bytes();
+
+/// Report for [`CameraControl`]
+///
+/// Cameras usually focus while the shutter is half pressed, so a shutter release should report
+/// [`Self::auto_focus`] before also setting [`Self::shutter`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "lsb0", size_bytes = "1")]
+pub struct CameraControlReport {
+    #[packed_field(bits = "0")]
+    pub auto_focus: bool,
+    #[packed_field(bits = "1")]
+    pub shutter: bool,
+    #[packed_field(bits = "2")]
+    pub zoom_in: bool,
+    #[packed_field(bits = "3")]
+    pub zoom_out: bool,
+}
+
+pub struct CameraControl<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<B: UsbBus> CameraControl<'_, B> {
+    pub fn write_report(&mut self, report: &CameraControlReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing CameraControlReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+}
+
+impl<B: UsbBus> ReportWriter<CameraControlReport> for CameraControl<'_, B> {
+    fn write_report(&mut self, report: &CameraControlReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for CameraControl<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct CameraControlConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl Default for CameraControlConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(CAMERA_CONTROL_REPORT_DESCRIPTOR))
+                    .description("Camera Control")
+                    .in_endpoint(10.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> CameraControlConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for CameraControlConfig<'a> {
+    type Allocated = CameraControl<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn report_matches_descriptor() {
+        assert_eq!(
+            report_length(CAMERA_CONTROL_REPORT_DESCRIPTOR, ReportType::Input, 0),
+            Some(1)
+        );
+        assert_eq!(
+            CAMERA_CONTROL_REPORT_DESCRIPTOR[14..20],
+            [0x05, 0x90, 0x09, 0x20, 0x09, 0x21]
+        );
+        let report = CameraControlReport {
+            shutter: true,
+            zoom_out: true,
+            ..Default::default()
+        };
+        assert_eq!(report.pack().unwrap(), [0b1010]);
+    }
+}
@@ -0,0 +1,245 @@
+//!HID Point of Sale magnetic stripe card reader
+//!
+//! Reports the decoded tracks of a swiped card to the host as HID POS track data reports, rather
+//! than typing them as a keyboard, avoiding problems with the focused window and keyboard layout.
+//!
+//! Each track is sent in its own report, tracks longer than [`TRACK_DATA_LEN`] are split across
+//! consecutive reports for that track. The track length of every report is the length of the
+//! whole track, so the host can concatenate the data of a track's reports until it has received
+//! that many bytes.
+//!
+//! Defined by the Magnetic Stripe Reader page (0x8E) of the [HID Point of Sale Usage Tables](<https://www.usb.org/sites/default/files/pos1_02.pdf>)
+use crate::descriptor::item::ReportDescriptor;
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use heapless::Vec;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+/// Number of tracks read from a card
+pub const TRACK_COUNT: usize = 3;
+
+/// Most bytes of track data in a single [`TrackDataReport`]
+pub const TRACK_DATA_LEN: usize = 62;
+
+/// Longest track that can be passed to [`CardReader::swipe`], longer than the 107 characters of
+/// an ISO/IEC 7811 track 3
+pub const MAX_TRACK_LEN: usize = 128;
+
+#[rustfmt::skip]
+const fn track_items(report_id: u8, length_usage: u8, data_usage: u8) -> [u8; 22] {
+    [
+        0x85, report_id,    //   Report ID
+        0x15, 0x00,         //   Logical Minimum (0)
+        0x26, 0xFF, 0x00,   //   Logical Maximum (255)
+        0x75, 0x08,         //   Report Size (8)
+        0x95, 0x01,         //   Report Count (1)
+        0x09, length_usage, //   Usage (Track Length)
+        0x81, 0x02,         //   Input (Data, Variable, Absolute)
+        0x95, 0x3E,         //   Report Count (62)
+        0x09, data_usage,   //   Usage (Track Data)
+        0x82, 0x02, 0x01,   //   Input (Data, Variable, Absolute, Buffered Bytes)
+    ]
+}
+
+const DESCRIPTOR: &ReportDescriptor<73> = &ReportDescriptor::new()
+    .bytes(&[
+        0x05, 0x8E, // Usage Page (Magnetic Stripe Reader)
+        0x09, 0x01, // Usage (MSR Device Read-Only)
+        0xA1, 0x01, // Collection (Application)
+    ])
+    .bytes(&track_items(0x01, 0x11, 0x21)) // Track 1 Length, Track 1 Data
+    .bytes(&track_items(0x02, 0x12, 0x22)) // Track 2 Length, Track 2 Data
+    .bytes(&track_items(0x03, 0x13, 0x23)) // Track 3 Length, Track 3 Data
+    .bytes(&[0xC0]) // End Collection
+    .validate();
+
+/// Read only magnetic stripe reader, with a report for each of three tracks
+///
+/// Report `n` holds the length and data of track `n`
+pub const CARD_READER_REPORT_DESCRIPTOR: &[u8] = DESCRIPTOR.as_bytes();
+
+/// A part of a track read from a card
+///
+/// The unused bytes of [`Self::data`] are 0
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "64")]
+pub struct TrackDataReport {
+    /// The track number, from 1
+    #[packed_field]
+    pub report_id: u8,
+    /// Length of the whole track
+    #[packed_field]
+    pub length: u8,
+    #[packed_field]
+    pub data: [u8; 62],
+}
+
+impl Default for TrackDataReport {
+    fn default() -> Self {
+        Self {
+            report_id: 1,
+            length: 0,
+            data: [0; TRACK_DATA_LEN],
+        }
+    }
+}
+
+/// Interface implementing a HID POS magnetic stripe reader
+///
+/// **Note:** Tracks are sent by [`UsbHidClass::tick()`] which must be called regularly.
+pub struct CardReader<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes64, OutNone, Reports8>,
+    tracks: [Vec<u8, MAX_TRACK_LEN>; TRACK_COUNT],
+    track: usize,
+    sent: usize,
+}
+
+impl<B: UsbBus> CardReader<'_, B> {
+    /// Send the tracks read from a swiped card, empty tracks aren't sent
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::WouldBlock`] if a card is still being sent, or
+    /// [`UsbHidError::SerializationError`] if a track is longer than [`MAX_TRACK_LEN`]
+    pub fn swipe(&mut self, tracks: [&[u8]; TRACK_COUNT]) -> Result<(), UsbHidError> {
+        if self.is_sending() {
+            return Err(UsbHidError::WouldBlock);
+        }
+        for (pending, track) in self.tracks.iter_mut().zip(tracks) {
+            *pending = Vec::from_slice(track).map_err(|()| {
+                error!("Track too long");
+                UsbHidError::SerializationError
+            })?;
+        }
+        self.track = 0;
+        self.sent = 0;
+        self.send_pending()
+    }
+
+    /// True while a card is being sent
+    #[must_use]
+    pub fn is_sending(&self) -> bool {
+        self.tracks.iter().any(|t| !t.is_empty())
+    }
+
+    fn send_pending(&mut self) -> Result<(), UsbHidError> {
+        while self.tracks[self.track].is_empty() {
+            self.track += 1;
+            if self.track >= TRACK_COUNT {
+                self.track = 0;
+                return Ok(());
+            }
+        }
+
+        let track = &self.tracks[self.track];
+        let remaining = &track[self.sent..];
+        let len = remaining.len().min(TRACK_DATA_LEN);
+        let mut report = TrackDataReport {
+            #[allow(clippy::cast_possible_truncation)]
+            report_id: self.track as u8 + 1,
+            length: u8::try_from(track.len()).unwrap_or(u8::MAX),
+            ..Default::default()
+        };
+        report.data[..len].copy_from_slice(&remaining[..len]);
+        let data = report.pack().map_err(|_| {
+            error!("Error packing TrackDataReport");
+            UsbHidError::SerializationError
+        })?;
+        match self.interface.write_report(&data) {
+            Ok(_) => {
+                self.sent += len;
+                if self.sent >= track.len() {
+                    self.tracks[self.track].clear();
+                    self.sent = 0;
+                }
+                Ok(())
+            }
+            Err(UsbHidError::WouldBlock) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for CardReader<'a, B> {
+    type I = Interface<'a, B, InBytes64, OutNone, Reports8>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        for track in &mut self.tracks {
+            track.clear();
+        }
+        self.track = 0;
+        self.sent = 0;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        if self.is_sending() {
+            self.send_pending()?;
+        }
+        Ok(())
+    }
+}
+
+pub struct CardReaderConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes64, OutNone, Reports8>,
+}
+
+impl Default for CardReaderConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(CARD_READER_REPORT_DESCRIPTOR))
+                    .description("Card Reader")
+                    .in_endpoint(1.millis())
+            )
+            .without_out_endpoint()
+            .send_unchanged_reports()
+            .build(),
+        )
+    }
+}
+
+impl<'a> CardReaderConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes64, OutNone, Reports8>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for CardReaderConfig<'a> {
+    type Allocated = CardReader<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            tracks: Default::default(),
+            track: 0,
+            sent: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn reports_match_descriptor() {
+        for report_id in 1..=3 {
+            assert_eq!(
+                report_length(CARD_READER_REPORT_DESCRIPTOR, ReportType::Input, report_id),
+                Some(64)
+            );
+        }
+        assert_eq!(&CARD_READER_REPORT_DESCRIPTOR[6..8], [0x85, 0x01]);
+        assert_eq!(&CARD_READER_REPORT_DESCRIPTOR[28..30], [0x85, 0x02]);
+    }
+}
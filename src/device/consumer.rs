@@ -17,9 +17,9 @@ pub const MULTIPLE_CODE_REPORT_DESCRIPTOR: &[u8] = &[
     0x75, 0x10, //     Report Size(16)
     0x95, 0x04, //     Report Count(4)
     0x15, 0x00, //     Logical Minimum(0)
-    0x26, 0x9C, 0x02, //     Logical Maximum(0x029C)
+    0x26, 0xD4, 0x02, //     Logical Maximum(0x02D4)
     0x19, 0x00, //     Usage Minimum(0)
-    0x2A, 0x9C, 0x02, //     Usage Maximum(0x029C)
+    0x2A, 0xD4, 0x02, //     Usage Maximum(0x02D4)
     0x81, 0x00, //     Input (Array, Data, Variable)
     0xC0, // End Collection
 ];
@@ -31,6 +31,36 @@ pub struct MultipleConsumerReport {
     pub codes: [Consumer; 4],
 }
 
+impl MultipleConsumerReport {
+    /// Report containing up to the first 4 `codes`, further codes are ignored
+    ///
+    /// ```
+    /// # use usbd_human_interface_device::device::consumer::MultipleConsumerReport;
+    /// # use usbd_human_interface_device::page::Consumer;
+    /// let report = MultipleConsumerReport::new([Consumer::VolumeIncrement, Consumer::ALCalculator]);
+    /// assert_eq!(
+    ///     report.codes,
+    ///     [
+    ///         Consumer::VolumeIncrement,
+    ///         Consumer::ALCalculator,
+    ///         Consumer::Unassigned,
+    ///         Consumer::Unassigned
+    ///     ]
+    /// );
+    /// ```
+    pub fn new<C: IntoIterator<Item = Consumer>>(codes: C) -> Self {
+        let mut report = Self::default();
+        for (slot, code) in report
+            .codes
+            .iter_mut()
+            .zip(codes.into_iter().filter(|&c| c != Consumer::Unassigned))
+        {
+            *slot = code;
+        }
+        report
+    }
+}
+
 #[allow(clippy::doc_markdown)]
 ///Fixed functionality consumer control report descriptor
 /// 
@@ -92,16 +122,22 @@ pub struct ConsumerControl<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
 }
 
-impl<'a, B: UsbBus> ConsumerControl<'a, B> {
-    pub fn write_report(&mut self, report: &MultipleConsumerReport) -> usb_device::Result<usize> {
+impl<B: UsbBus> ConsumerControl<'_, B> {
+    pub fn write_report(&mut self, report: &MultipleConsumerReport) -> Result<usize, UsbHidError> {
         let data = report.pack().map_err(|_| {
             error!("Error packing MultipleConsumerReport");
-            UsbError::ParseError
+            UsbHidError::SerializationError
         })?;
         self.interface.write_report(&data)
     }
 }
 
+impl<B: UsbBus> ReportWriter<MultipleConsumerReport> for ConsumerControl<'_, B> {
+    fn write_report(&mut self, report: &MultipleConsumerReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report).map(|_| ())
+    }
+}
+
 impl<'a, B: UsbBus> DeviceClass<'a> for ConsumerControl<'a, B> {
     type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
 
@@ -127,8 +163,7 @@ impl<'a> ConsumerControlConfig<'a> {
     }
 }
 
-impl<'a> Default for ConsumerControlConfig<'a> {
-    #[must_use]
+impl Default for ConsumerControlConfig<'_> {
     fn default() -> Self {
         Self::new(
             unwrap!(
@@ -156,16 +191,22 @@ pub struct ConsumerControlFixed<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
 }
 
-impl<'a, B: UsbBus> ConsumerControlFixed<'a, B> {
-    pub fn write_report(&mut self, report: &FixedFunctionReport) -> usb_device::Result<usize> {
+impl<B: UsbBus> ConsumerControlFixed<'_, B> {
+    pub fn write_report(&mut self, report: &FixedFunctionReport) -> Result<usize, UsbHidError> {
         let data = report.pack().map_err(|_| {
             error!("Error packing MultipleConsumerReport");
-            UsbError::ParseError
+            UsbHidError::SerializationError
         })?;
         self.interface.write_report(&data)
     }
 }
 
+impl<B: UsbBus> ReportWriter<FixedFunctionReport> for ConsumerControlFixed<'_, B> {
+    fn write_report(&mut self, report: &FixedFunctionReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report).map(|_| ())
+    }
+}
+
 impl<'a, B: UsbBus> DeviceClass<'a> for ConsumerControlFixed<'a, B> {
     type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
 
@@ -190,8 +231,7 @@ impl<'a> ConsumerControlFixedConfig<'a> {
     }
 }
 
-impl<'a> Default for ConsumerControlFixedConfig<'a> {
-    #[must_use]
+impl Default for ConsumerControlFixedConfig<'_> {
     fn default() -> Self {
         Self::new(
             unwrap!(
@@ -214,3 +254,187 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for ConsumerControlFixedConfig<'a
         }
     }
 }
+
+/// The consumer usages reported by [`CaptureControlReport`], in report bit order
+///
+/// These are the screen capture, recording and broadcast usages handled by the Windows Game Bar
+pub const CAPTURE_CONTROL_USAGES: [Consumer; 8] = [
+    Consumer::InvokeCaptureInterface,
+    Consumer::StartOrStopGameRecording,
+    Consumer::HistoricalGameCapture,
+    Consumer::CaptureGameScreenshot,
+    Consumer::ShowOrHideRecordingIndicator,
+    Consumer::StartOrStopMicrophoneCapture,
+    Consumer::StartOrStopCameraCapture,
+    Consumer::StartOrStopGameBroadcast,
+];
+
+///Screen capture and game recording consumer control report descriptor
+///
+/// Single bit packed `u8` report, with the usages of [`CAPTURE_CONTROL_USAGES`]
+#[rustfmt::skip]
+pub const CAPTURE_CONTROL_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x0C, // Usage Page (Consumer)
+    0x09, 0x01, // Usage (Consumer Control)
+    0xA1, 0x01, // Collection (Application)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x08, //   Report Count (8)
+    0x19, 0xD0, //   Usage Minimum (Invoke Capture Interface)
+    0x29, 0xD7, //   Usage Maximum (Start or Stop Game Broadcast)
+    0x81, 0x02, //   Input (Data, Variable, Absolute)
+    0xC0,       // End Collection
+];
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "lsb0", size_bytes = "1")]
+pub struct CaptureControlReport {
+    /// Open the Game Bar
+    #[packed_field(bits = "0")]
+    pub invoke_capture_interface: bool,
+    #[packed_field(bits = "1")]
+    pub start_or_stop_game_recording: bool,
+    /// Save the last few seconds of gameplay
+    #[packed_field(bits = "2")]
+    pub historical_game_capture: bool,
+    /// Save a screenshot
+    #[packed_field(bits = "3")]
+    pub capture_game_screenshot: bool,
+    #[packed_field(bits = "4")]
+    pub show_or_hide_recording_indicator: bool,
+    #[packed_field(bits = "5")]
+    pub start_or_stop_microphone_capture: bool,
+    #[packed_field(bits = "6")]
+    pub start_or_stop_camera_capture: bool,
+    #[packed_field(bits = "7")]
+    pub start_or_stop_game_broadcast: bool,
+}
+
+impl CaptureControlReport {
+    /// Report with the bits for `codes` set, codes not in [`CAPTURE_CONTROL_USAGES`] are ignored
+    ///
+    /// ```
+    /// # use usbd_human_interface_device::device::consumer::CaptureControlReport;
+    /// # use usbd_human_interface_device::page::Consumer;
+    /// let report = CaptureControlReport::new([Consumer::CaptureGameScreenshot, Consumer::Mute]);
+    /// assert_eq!(
+    ///     report,
+    ///     CaptureControlReport {
+    ///         capture_game_screenshot: true,
+    ///         ..Default::default()
+    ///     }
+    /// );
+    /// ```
+    pub fn new<C: IntoIterator<Item = Consumer>>(codes: C) -> Self {
+        let mut report = Self::default();
+        for code in codes {
+            match code {
+                Consumer::InvokeCaptureInterface => report.invoke_capture_interface = true,
+                Consumer::StartOrStopGameRecording => report.start_or_stop_game_recording = true,
+                Consumer::HistoricalGameCapture => report.historical_game_capture = true,
+                Consumer::CaptureGameScreenshot => report.capture_game_screenshot = true,
+                Consumer::ShowOrHideRecordingIndicator => {
+                    report.show_or_hide_recording_indicator = true;
+                }
+                Consumer::StartOrStopMicrophoneCapture => {
+                    report.start_or_stop_microphone_capture = true;
+                }
+                Consumer::StartOrStopCameraCapture => report.start_or_stop_camera_capture = true,
+                Consumer::StartOrStopGameBroadcast => report.start_or_stop_game_broadcast = true,
+                _ => {}
+            }
+        }
+        report
+    }
+}
+
+/// Consumer control device for stream deck style peripherals, reporting the screen capture and
+/// game recording usages of [`CAPTURE_CONTROL_USAGES`]
+pub struct ConsumerControlCapture<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<B: UsbBus> ConsumerControlCapture<'_, B> {
+    pub fn write_report(&mut self, report: &CaptureControlReport) -> Result<usize, UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing CaptureControlReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data)
+    }
+}
+
+impl<B: UsbBus> ReportWriter<CaptureControlReport> for ConsumerControlCapture<'_, B> {
+    fn write_report(&mut self, report: &CaptureControlReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report).map(|_| ())
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for ConsumerControlCapture<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), crate::UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct ConsumerControlCaptureConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<'a> ConsumerControlCaptureConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl Default for ConsumerControlCaptureConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(CAPTURE_CONTROL_REPORT_DESCRIPTOR))
+                    .description("Capture Control")
+                    .in_endpoint(50.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for ConsumerControlCaptureConfig<'a> {
+    type Allocated = ConsumerControlCapture<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+
+    #[test]
+    fn capture_control_report_bit_order() {
+        for (i, &usage) in CAPTURE_CONTROL_USAGES.iter().enumerate() {
+            assert_eq!(
+                CaptureControlReport::new([usage]).pack().unwrap(),
+                [1 << i],
+                "{usage:?}"
+            );
+            assert_eq!(u16::from(usage), 0xD0 + u16::try_from(i).unwrap());
+        }
+    }
+}
@@ -0,0 +1,198 @@
+//! Runtime composition of HID devices
+//!
+//! Requires the `alloc` feature. Devices are heap allocated and registered with a
+//! [`DynamicUsbHidClassBuilder`], allowing firmware to choose its HID personality at boot (e.g.
+//! a configuration mode vs. a normal keyboard mode) without a compile-time fixed [`frunk::HList`]
+//! of devices.
+
+use crate::device::{DeviceClass, DeviceHList};
+use crate::interface::{InterfaceClass, InterfaceEvent, UsbAllocatable};
+use crate::usb_class::UsbHidClass;
+use crate::UsbHidError;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use frunk::ToMut;
+#[allow(clippy::wildcard_imports)]
+use usb_device::class_prelude::*;
+
+/// Object safe wrapper around [`DeviceClass`]
+///
+/// Implemented for all [`DeviceClass`] types.
+pub trait DynamicDevice<'a> {
+    /// The HID interface of this device
+    ///
+    /// Reports can be written and read as raw bytes using [`InterfaceClass::write_report`] and
+    /// [`InterfaceClass::read_report`].
+    fn interface(&mut self) -> &mut dyn InterfaceClass<'a>;
+    /// Called if the USB Device is reset
+    fn reset(&mut self);
+    /// Called every 1ms
+    fn tick(&mut self) -> Result<(), UsbHidError>;
+}
+
+impl<'a, T: DeviceClass<'a>> DynamicDevice<'a> for T {
+    fn interface(&mut self) -> &mut dyn InterfaceClass<'a> {
+        DeviceClass::interface(self)
+    }
+
+    fn reset(&mut self) {
+        DeviceClass::reset(self);
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        DeviceClass::tick(self)
+    }
+}
+
+/// List of heap allocated devices registered at runtime
+#[derive(Default)]
+pub struct DynamicDevices<'a> {
+    devices: Vec<Box<dyn DynamicDevice<'a> + 'a>>,
+}
+
+impl<'a> DynamicDevices<'a> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            devices: Vec::new(),
+        }
+    }
+
+    /// Append a device, returning its index
+    pub fn push(&mut self, device: Box<dyn DynamicDevice<'a> + 'a>) -> usize {
+        self.devices.push(device);
+        self.devices.len() - 1
+    }
+
+    /// Borrow the device at `index`, in registration order
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut (dyn DynamicDevice<'a> + 'a)> {
+        self.devices.get_mut(index).map(|d| &mut **d)
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+}
+
+impl<'a, 'b: 'a> ToMut<'a> for DynamicDevices<'b> {
+    type Output = &'a mut [Box<dyn DynamicDevice<'b> + 'b>];
+
+    fn to_mut(&'a mut self) -> Self::Output {
+        &mut self.devices
+    }
+}
+
+impl<'a> DeviceHList<'a> for DynamicDevices<'a> {
+    fn get(&mut self, id: u8) -> Option<&mut dyn InterfaceClass<'a>> {
+        self.devices
+            .iter_mut()
+            .map(|d| d.interface())
+            .find(|i| u8::from(i.id()) == id)
+    }
+
+    fn reset(&mut self) {
+        for d in &mut self.devices {
+            d.interface().reset();
+            d.reset();
+        }
+    }
+
+    fn write_descriptors(&mut self, writer: &mut DescriptorWriter) -> usb_device::Result<()> {
+        for d in &mut self.devices {
+            d.interface().write_descriptors(writer)?;
+        }
+        Ok(())
+    }
+
+    fn get_string(&mut self, index: StringIndex, lang_id: LangID) -> Option<&'a str> {
+        self.devices
+            .iter_mut()
+            .find_map(|d| d.interface().get_string(index, lang_id))
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        for d in &mut self.devices {
+            d.interface().tick_rate_limit();
+            d.tick()?;
+        }
+        Ok(())
+    }
+
+    fn take_event(&mut self) -> Option<(u8, InterfaceEvent)> {
+        self.devices.iter_mut().find_map(|d| {
+            let interface = d.interface();
+            interface
+                .take_event()
+                .map(|event| (u8::from(interface.id()), event))
+        })
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        for d in &mut self.devices {
+            d.interface().endpoint_in_complete(addr);
+        }
+    }
+
+    fn endpoint_halt(&mut self, addr: EndpointAddress, halted: bool) {
+        for d in &mut self.devices {
+            d.interface().endpoint_halt(addr, halted);
+        }
+    }
+
+    fn set_suspended(&mut self, suspended: bool, remote_wakeup_enabled: bool) -> bool {
+        let mut wakeup = false;
+        for d in &mut self.devices {
+            let interface = d.interface();
+            interface.set_suspended(suspended, remote_wakeup_enabled);
+            wakeup |= interface.take_wakeup_request();
+        }
+        wakeup
+    }
+}
+
+/// Builder for a [`UsbHidClass`] with devices registered at runtime
+///
+/// Devices allocate their interfaces and endpoints as they are added, so all devices must be
+/// added before the `UsbDevice` is built.
+#[must_use = "this `DynamicUsbHidClassBuilder` must be assigned or consumed by `::build()`"]
+pub struct DynamicUsbHidClassBuilder<'a, B: UsbBus> {
+    usb_alloc: &'a UsbBusAllocator<B>,
+    devices: DynamicDevices<'a>,
+}
+
+impl<'a, B: UsbBus + 'a> DynamicUsbHidClassBuilder<'a, B> {
+    pub fn new(usb_alloc: &'a UsbBusAllocator<B>) -> Self {
+        Self {
+            usb_alloc,
+            devices: DynamicDevices::new(),
+        }
+    }
+
+    /// Allocate and register a device
+    pub fn add_device<Config>(mut self, config: Config) -> Self
+    where
+        Config: UsbAllocatable<'a, B>,
+        Config::Allocated: DeviceClass<'a> + 'a,
+    {
+        self.devices.push(Box::new(config.allocate(self.usb_alloc)));
+        self
+    }
+
+    #[must_use]
+    pub fn build(self) -> UsbHidClass<'a, B, DynamicDevices<'a>> {
+        UsbHidClass::new(self.devices)
+    }
+}
+
+impl<'a, B> UsbHidClass<'a, B, DynamicDevices<'a>> {
+    /// Borrow the device at `index`, in the order devices were added
+    pub fn dynamic_device(&mut self, index: usize) -> Option<&mut (dyn DynamicDevice<'a> + 'a)> {
+        self.devices_mut().get_mut(index)
+    }
+}
@@ -0,0 +1,218 @@
+//!HID relative rotary encoders, such as audio controller knobs and simulator trim wheels
+//!
+//! Reports four encoder channels as relative Dial, Wheel, Aileron Trim and Elevator Trim axes,
+//! and eight push buttons, so hosts receive encoder movement as axis motion rather than as key
+//! presses.
+use crate::descriptor::item::{Item, ReportDescriptor};
+use crate::page::Simulation;
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Number of encoder channels reported by an [`Encoders`] device
+pub const ENCODER_CHANNELS: usize = 4;
+
+#[rustfmt::skip]
+const DESCRIPTOR: &ReportDescriptor<47> = &ReportDescriptor::new()
+    .bytes(&[
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x08, // Usage (Multi-axis Controller)
+        0xA1, 0x01, // Collection (Application)
+        0x05, 0x09, //   Usage Page (Button)
+        0x19, 0x01, //   Usage Minimum (1)
+        0x29, 0x08, //   Usage Maximum (8)
+        0x15, 0x00, //   Logical Minimum (0)
+        0x25, 0x01, //   Logical Maximum (1)
+        0x75, 0x01, //   Report Size (1)
+        0x95, 0x08, //   Report Count (8)
+        0x81, 0x02, //   Input (Data, Variable, Absolute)
+        0x05, 0x01, //   Usage Page (Generic Desktop)
+        0x09, 0x37, //   Usage (Dial)
+        0x09, 0x38, //   Usage (Wheel)
+        0x15, 0x81, //   Logical Minimum (-127)
+        0x25, 0x7F, //   Logical Maximum (127)
+        0x75, 0x08, //   Report Size (8)
+        0x95, 0x02, //   Report Count (2)
+        0x81, 0x06, //   Input (Data, Variable, Relative)
+    ])
+    .item(Item::usage_page(Simulation::PAGE))
+    .item(Item::usage(Simulation::AileronTrim.id()))
+    .item(Item::usage(Simulation::ElevatorTrim.id()))
+    .bytes(&[
+        0x81, 0x06, //   Input (Data, Variable, Relative)
+        0xC0,       // End Collection
+    ])
+    .validate();
+
+/// Eight buttons and four relative 8 bit encoder channels, reported as Dial, Wheel, Aileron Trim
+/// and Elevator Trim
+pub const ENCODERS_REPORT_DESCRIPTOR: &[u8] = DESCRIPTOR.as_bytes();
+
+/// Report for [`Encoders`]
+///
+/// Channels are the movement since the previous report, positive for clockwise
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "5")]
+pub struct EncodersReport {
+    /// Bit 0 is button 1
+    #[packed_field]
+    pub buttons: u8,
+    #[packed_field]
+    pub channels: [i8; 4],
+}
+
+/// Relative rotary encoders with push buttons
+///
+/// Reports can be written directly, or encoder steps accumulated with [`Self::rotate`] and
+/// written by [`UsbHidClass::tick`], so steps aren't lost while the endpoint is busy.
+pub struct Encoders<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+    steps: [i32; ENCODER_CHANNELS],
+    buttons: u8,
+    buttons_changed: bool,
+}
+
+impl<B: UsbBus> Encoders<'_, B> {
+    pub fn write_report(&mut self, report: &EncodersReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing EncodersReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+
+    /// Add `steps` of movement on `channel`, to be reported by the next tick. Steps beyond the
+    /// range of a single report are carried over to following reports
+    pub fn rotate(&mut self, channel: usize, steps: i32) {
+        if let Some(pending) = self.steps.get_mut(channel) {
+            *pending = pending.saturating_add(steps);
+        }
+    }
+
+    /// Set the pressed buttons, to be reported by the next tick. Bit 0 is button 1
+    pub fn set_buttons(&mut self, buttons: u8) {
+        if buttons != self.buttons {
+            self.buttons = buttons;
+            self.buttons_changed = true;
+        }
+    }
+
+    /// Steps added with [`Self::rotate`] that haven't been reported yet
+    #[must_use]
+    pub fn pending_steps(&self) -> [i32; ENCODER_CHANNELS] {
+        self.steps
+    }
+}
+
+impl<B: UsbBus> ReportWriter<EncodersReport> for Encoders<'_, B> {
+    fn write_report(&mut self, report: &EncodersReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for Encoders<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.steps = [0; ENCODER_CHANNELS];
+        self.buttons_changed = self.buttons != 0;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        if !self.buttons_changed && self.steps.iter().all(|&s| s == 0) {
+            return Ok(());
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let channels = self
+            .steps
+            .map(|s| s.clamp(-i32::from(i8::MAX), i32::from(i8::MAX)) as i8);
+        let report = EncodersReport {
+            buttons: self.buttons,
+            channels,
+        };
+        match self.write_report(&report) {
+            Ok(()) => {
+                for (pending, sent) in self.steps.iter_mut().zip(channels) {
+                    *pending -= i32::from(sent);
+                }
+                self.buttons_changed = false;
+                Ok(())
+            }
+            Err(UsbHidError::WouldBlock | UsbHidError::UsbError(UsbError::WouldBlock)) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+pub struct EncodersConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl Default for EncodersConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(ENCODERS_REPORT_DESCRIPTOR))
+                .description("Encoders")
+                .in_endpoint(1.millis()))
+            .without_out_endpoint()
+            //Relative movement repeats, identical reports are not duplicates
+            .send_unchanged_reports()
+            .build(),
+        )
+    }
+}
+
+impl<'a> EncodersConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for EncodersConfig<'a> {
+    type Allocated = Encoders<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            steps: [0; ENCODER_CHANNELS],
+            buttons: 0,
+            buttons_changed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn report_matches_descriptor() {
+        assert_eq!(
+            report_length(ENCODERS_REPORT_DESCRIPTOR, ReportType::Input, 0),
+            Some(5)
+        );
+        assert_eq!(
+            ENCODERS_REPORT_DESCRIPTOR[38..44],
+            [0x05, 0x02, 0x09, 0xB1, 0x09, 0xB9]
+        );
+        let report = EncodersReport {
+            buttons: 0x81,
+            channels: [1, -1, 127, -127],
+        };
+        assert_eq!(report.pack().unwrap(), [0x81, 0x01, 0xFF, 0x7F, 0x81]);
+    }
+}
@@ -43,12 +43,15 @@ pub struct RawFido<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes64, OutBytes64, ReportSingle>,
 }
 
-impl<'a, B: UsbBus> RawFido<'a, B> {
+impl ReportBytes for RawFidoReport {
+    fn report_bytes(&self) -> &[u8] {
+        &self.packet
+    }
+}
+
+impl<B: UsbBus> RawFido<'_, B> {
     pub fn write_report(&mut self, report: &RawFidoReport) -> Result<(), UsbHidError> {
-        self.interface
-            .write_report(&report.packet)
-            .map(|_| ())
-            .map_err(UsbHidError::from)
+        self.interface.write_report_bytes(report).map(|_| ())
     }
     pub fn read_report(&mut self) -> usb_device::Result<RawFidoReport> {
         let mut report = RawFidoReport::default();
@@ -59,6 +62,12 @@ impl<'a, B: UsbBus> RawFido<'a, B> {
     }
 }
 
+impl<B: UsbBus> ReportWriter<RawFidoReport> for RawFido<'_, B> {
+    fn write_report(&mut self, report: &RawFidoReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
 impl<'a, B: UsbBus> DeviceClass<'a> for RawFido<'a, B> {
     type I = Interface<'a, B, InBytes64, OutBytes64, ReportSingle>;
 
@@ -77,8 +86,7 @@ pub struct RawFidoConfig<'a> {
     interface: InterfaceConfig<'a, InBytes64, OutBytes64, ReportSingle>,
 }
 
-impl<'a> Default for RawFidoConfig<'a> {
-    #[must_use]
+impl Default for RawFidoConfig<'_> {
     fn default() -> Self {
         Self::new(
             unwrap!(
@@ -87,6 +95,7 @@ impl<'a> Default for RawFidoConfig<'a> {
                     .in_endpoint(5.millis()))
                 .with_out_endpoint(5.millis())
             )
+            .send_unchanged_reports()
             .build(),
         )
     }
@@ -0,0 +1,177 @@
+//!HID flight controls using the Simulation Controls usage page
+//!
+//! Reports aileron, elevator and rudder axes, throttle and brake levers and 16 buttons, with the
+//! Simulation Controls usages for each axis so flight simulators can map them without manual
+//! configuration.
+use crate::descriptor::item::{Item, ReportDescriptor};
+use crate::page::Simulation;
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+const DESCRIPTOR: &ReportDescriptor<80> = &ReportDescriptor::new()
+    .bytes(&[
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x04, // Usage (Joystick)
+        0xA1, 0x01, // Collection (Application)
+        0x05, 0x09, //   Usage Page (Button)
+        0x19, 0x01, //   Usage Minimum (1)
+        0x29, 0x10, //   Usage Maximum (16)
+        0x15, 0x00, //   Logical Minimum (0)
+        0x25, 0x01, //   Logical Maximum (1)
+        0x75, 0x01, //   Report Size (1)
+        0x95, 0x10, //   Report Count (16)
+        0x81, 0x02, //   Input (Data, Variable, Absolute)
+    ])
+    .item(Item::usage_page(Simulation::PAGE))
+    .item(Item::usage(Simulation::Aileron.id()))
+    .item(Item::usage(Simulation::Elevator.id()))
+    .item(Item::usage(Simulation::Rudder.id()))
+    .item(Item::logical_minimum(-32767))
+    .item(Item::logical_maximum(32767))
+    .bytes(&[
+        0x75, 0x10, //   Report Size (16)
+        0x95, 0x03, //   Report Count (3)
+        0x81, 0x02, //   Input (Data, Variable, Absolute)
+    ])
+    .item(Item::usage(Simulation::Throttle.id()))
+    .item(Item::usage(Simulation::Brake.id()))
+    .item(Item::logical_minimum(0))
+    .item(Item::logical_maximum(65535))
+    .bytes(&[
+        0x95, 0x02, //   Report Count (2)
+        0x81, 0x02, //   Input (Data, Variable, Absolute)
+        0xC0, // End Collection
+    ])
+    .validate();
+
+/// Flight controls with 16 buttons, signed 16 bit aileron, elevator and rudder axes, and
+/// unsigned 16 bit throttle and brake axes
+///
+/// The application collection is a Generic Desktop joystick, which hosts require to treat the
+/// device as a game controller
+pub const FLIGHT_CONTROLS_REPORT_DESCRIPTOR: &[u8] = DESCRIPTOR.as_bytes();
+
+/// Report for [`FlightControls`]
+///
+/// Control surface axes are centered on 0, throttle and brake are 0 when closed or released
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "12")]
+pub struct FlightControlsReport {
+    /// Bit 0 is button 1
+    #[packed_field]
+    pub buttons: u16,
+    #[packed_field]
+    pub aileron: i16,
+    #[packed_field]
+    pub elevator: i16,
+    #[packed_field]
+    pub rudder: i16,
+    #[packed_field]
+    pub throttle: u16,
+    #[packed_field]
+    pub brake: u16,
+}
+
+pub struct FlightControls<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes16, OutNone, ReportSingle>,
+}
+
+impl<B: UsbBus> FlightControls<'_, B> {
+    pub fn write_report(&mut self, report: &FlightControlsReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing FlightControlsReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+}
+
+impl<B: UsbBus> ReportWriter<FlightControlsReport> for FlightControls<'_, B> {
+    fn write_report(&mut self, report: &FlightControlsReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for FlightControls<'a, B> {
+    type I = Interface<'a, B, InBytes16, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct FlightControlsConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>,
+}
+
+impl Default for FlightControlsConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(FLIGHT_CONTROLS_REPORT_DESCRIPTOR))
+                    .description("Flight Controls")
+                    .in_endpoint(1.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> FlightControlsConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for FlightControlsConfig<'a> {
+    type Allocated = FlightControls<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn report_matches_descriptor() {
+        assert_eq!(
+            report_length(FLIGHT_CONTROLS_REPORT_DESCRIPTOR, ReportType::Input, 0),
+            Some(12)
+        );
+        assert_eq!(
+            FLIGHT_CONTROLS_REPORT_DESCRIPTOR[22..30],
+            [0x05, 0x02, 0x09, 0xB0, 0x09, 0xB8, 0x09, 0xBA]
+        );
+        let report = FlightControlsReport {
+            buttons: 0x8001,
+            rudder: -2,
+            brake: 0x1234,
+            ..Default::default()
+        };
+        assert_eq!(
+            report.pack().unwrap(),
+            [0x01, 0x80, 0, 0, 0, 0, 0xFE, 0xFF, 0, 0, 0x34, 0x12]
+        );
+    }
+}
@@ -0,0 +1,197 @@
+//!HID gamepad with high resolution analog sticks and triggers
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+/// Gamepad with 32 buttons, two 16 bit analog sticks and two 16 bit analog triggers
+#[rustfmt::skip]
+pub const GAMEPAD_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,                   // Usage Page (Generic Desktop)
+    0x09, 0x05,                   // Usage (Gamepad)
+    0xA1, 0x01,                   // Collection (Application)
+    0x05, 0x09,                   //   Usage Page (Button)
+    0x19, 0x01,                   //   Usage Minimum (1)
+    0x29, 0x20,                   //   Usage Maximum (32)
+    0x15, 0x00,                   //   Logical Minimum (0)
+    0x25, 0x01,                   //   Logical Maximum (1)
+    0x75, 0x01,                   //   Report Size (1)
+    0x95, 0x20,                   //   Report Count (32)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x05, 0x01,                   //   Usage Page (Generic Desktop)
+    0x09, 0x30,                   //   Usage (X)
+    0x09, 0x31,                   //   Usage (Y)
+    0x09, 0x32,                   //   Usage (Z)
+    0x09, 0x35,                   //   Usage (Rz)
+    0x16, 0x01, 0x80,             //   Logical Minimum (-32767)
+    0x26, 0xFF, 0x7F,             //   Logical Maximum (32767)
+    0x75, 0x10,                   //   Report Size (16)
+    0x95, 0x04,                   //   Report Count (4)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x09, 0x33,                   //   Usage (Rx)
+    0x09, 0x34,                   //   Usage (Ry)
+    0x15, 0x00,                   //   Logical Minimum (0)
+    0x27, 0xFF, 0xFF, 0x00, 0x00, //   Logical Maximum (65535)
+    0x75, 0x10,                   //   Report Size (16)
+    0x95, 0x02,                   //   Report Count (2)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0xC0,                         // End Collection
+];
+
+/// Report for a [`Gamepad`]
+///
+/// Stick axes are centered on 0, triggers are 0 when released. Values from lower resolution
+/// inputs can be converted with [`GamepadReport::axis_from_centered`] and
+/// [`GamepadReport::trigger_from_10_bit`], or with an
+/// [`AxisCalibration`](crate::axis::AxisCalibration) to apply a deadzone.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "16")]
+pub struct GamepadReport {
+    /// Bit 0 is button 1
+    #[packed_field]
+    pub buttons: u32,
+    #[packed_field]
+    pub left_x: i16,
+    #[packed_field]
+    pub left_y: i16,
+    #[packed_field]
+    pub right_x: i16,
+    #[packed_field]
+    pub right_y: i16,
+    #[packed_field]
+    pub left_trigger: u16,
+    #[packed_field]
+    pub right_trigger: u16,
+}
+
+impl GamepadReport {
+    /// Scale `value`, read from an input centered on `center`, to an axis value
+    ///
+    /// `half_range` is the distance from `center` to either end of the input's travel. Values
+    /// beyond the travel are clamped to the ends of the axis.
+    #[must_use]
+    pub fn axis_from_centered(value: i32, center: i32, half_range: i32) -> i16 {
+        if half_range <= 0 {
+            return 0;
+        }
+        let offset = i64::from(value) - i64::from(center);
+        let scaled = offset * i64::from(i16::MAX) / i64::from(half_range);
+        let clamped = scaled.clamp(-i64::from(i16::MAX), i64::from(i16::MAX));
+        i16::try_from(clamped).unwrap_or_default()
+    }
+
+    /// Scale a value from a 10 bit trigger to the full 16 bit trigger range
+    #[must_use]
+    pub fn trigger_from_10_bit(value: u16) -> u16 {
+        let value = value.min(0x3FF);
+        (value << 6) | (value >> 4)
+    }
+}
+
+pub struct Gamepad<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes16, OutNone, ReportSingle>,
+}
+
+impl<B: UsbBus> Gamepad<'_, B> {
+    pub fn write_report(&mut self, report: &GamepadReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing GamepadReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+}
+
+impl<B: UsbBus> ReportWriter<GamepadReport> for Gamepad<'_, B> {
+    fn write_report(&mut self, report: &GamepadReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for Gamepad<'a, B> {
+    type I = Interface<'a, B, InBytes16, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct GamepadConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>,
+}
+
+impl Default for GamepadConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(GAMEPAD_REPORT_DESCRIPTOR))
+                .description("Gamepad")
+                .in_endpoint(1.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> GamepadConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes16, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for GamepadConfig<'a> {
+    type Allocated = Gamepad<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn report_matches_descriptor() {
+        assert_eq!(
+            report_length(GAMEPAD_REPORT_DESCRIPTOR, ReportType::Input, 0),
+            Some(16)
+        );
+        let report = GamepadReport {
+            buttons: 0x8000_0001,
+            left_x: -1,
+            right_trigger: 0xABCD,
+            ..Default::default()
+        };
+        assert_eq!(
+            report.pack().unwrap(),
+            [0x01, 0, 0, 0x80, 0xFF, 0xFF, 0, 0, 0, 0, 0, 0, 0, 0, 0xCD, 0xAB]
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(GamepadReport::axis_from_centered(2048, 2048, 2048), 0);
+        assert_eq!(GamepadReport::axis_from_centered(4096, 2048, 2048), 32767);
+        assert_eq!(GamepadReport::axis_from_centered(0, 2048, 2048), -32767);
+        assert_eq!(GamepadReport::axis_from_centered(-5000, 0, 100), -32767);
+        assert_eq!(GamepadReport::axis_from_centered(-64, 0, 127), -16512);
+        assert_eq!(GamepadReport::trigger_from_10_bit(0), 0);
+        assert_eq!(GamepadReport::trigger_from_10_bit(0x3FF), 0xFFFF);
+        assert_eq!(GamepadReport::trigger_from_10_bit(0x200), 0x8020);
+    }
+}
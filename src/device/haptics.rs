@@ -0,0 +1,393 @@
+//!HID simple haptic controller
+//!
+//! Receives standardized haptic commands, such as those sent by the Windows haptics driver, rather
+//! than a vendor specific protocol. The controller declares a list of [`WAVEFORM_COUNT`] waveforms
+//! it can play, the host then plays them with a manual trigger output report, or selects the
+//! waveform played automatically when the associated control, such as a touchpad button, is
+//! pressed.
+//!
+//! The controller doesn't drive an actuator itself, firmware reads each [`HapticCommand`] from
+//! [`HapticController::take_command`] and plays it.
+//!
+//! Defined by the Haptics page (0x0E), see [`Haptics`]
+use crate::descriptor::item::{Item, ReportDescriptor, Unit};
+use crate::page::Haptics;
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Number of waveforms in the waveform list of a [`HapticController`]
+pub const WAVEFORM_COUNT: usize = 5;
+
+const WAVEFORM_LIST_REPORT_ID: u8 = 0x01;
+const AUTO_TRIGGER_REPORT_ID: u8 = 0x02;
+const MANUAL_TRIGGER_REPORT_ID: u8 = 0x03;
+
+const ORDINAL_PAGE: u16 = 0x0A;
+
+// Ordinals 1 and 2 are always Waveform None and Waveform Stop, the waveform list starts at 3
+const FIRST_WAVEFORM_ORDINAL: u16 = 3;
+#[allow(clippy::cast_possible_truncation)]
+const LAST_WAVEFORM_ORDINAL: u16 = FIRST_WAVEFORM_ORDINAL + WAVEFORM_COUNT as u16 - 1;
+
+const MAX_INTENSITY: u8 = 100;
+
+#[rustfmt::skip]
+const DESCRIPTOR: &ReportDescriptor<137> = &ReportDescriptor::new()
+    .item(Item::usage_page(Haptics::PAGE))
+    .item(Item::usage(Haptics::SimpleHapticController.id()))
+    .bytes(&[
+        0xA1, 0x01,                    // Collection (Application)
+        0x85, WAVEFORM_LIST_REPORT_ID, //   Report ID
+    ])
+    .item(Item::usage(Haptics::WaveformList.id()))
+    .bytes(&[0xA1, 0x02]) //   Collection (Logical)
+    .item(Item::usage_page(ORDINAL_PAGE))
+    .bytes(&[
+        0x19, 0x03,   //     Usage Minimum (Ordinal 3)
+        0x29, 0x07,   //     Usage Maximum (Ordinal 7)
+        0x75, 0x10,   //     Report Size (16)
+        0x95, 0x05,   //     Report Count (5)
+    ])
+    .item(Item::logical_minimum(0))
+    .item(Item::logical_maximum(0xFFFF))
+    .bytes(&[
+        0xB1, 0x03,   //     Feature (Constant, Variable, Absolute)
+        0xC0,         //   End Collection
+    ])
+    .item(Item::usage_page(Haptics::PAGE))
+    .item(Item::usage(Haptics::DurationList.id()))
+    .bytes(&[0xA1, 0x02]) //   Collection (Logical)
+    .item(Item::usage_page(ORDINAL_PAGE))
+    .bytes(&[
+        0x19, 0x03,   //     Usage Minimum (Ordinal 3)
+        0x29, 0x07,   //     Usage Maximum (Ordinal 7)
+    ])
+    .item(Item::unit(Unit::SECOND))
+    .item(Item::unit_exponent(-3))
+    .bytes(&[
+        0xB1, 0x03,   //     Feature (Constant, Variable, Absolute)
+        0xC0,         //   End Collection
+    ])
+    .item(Item::usage_page(Haptics::PAGE))
+    .bytes(&[0x85, AUTO_TRIGGER_REPORT_ID]) //   Report ID
+    .item(Item::usage(Haptics::AutoTrigger.id()))
+    .item(Item::unit(Unit::NONE))
+    .item(Item::unit_exponent(0))
+    .item(Item::logical_minimum(1))
+    .item(Item::logical_maximum(LAST_WAVEFORM_ORDINAL as i32))
+    .bytes(&[
+        0x95, 0x01,   //   Report Count (1)
+        0xB1, 0x02,   //   Feature (Data, Variable, Absolute)
+    ])
+    .item(Item::usage(Haptics::AutoTriggerAssociatedControl.id()))
+    .item(Item::logical_minimum(0))
+    .item(Item::logical_maximum(i32::MAX))
+    .bytes(&[
+        0x75, 0x20,   //   Report Size (32)
+        0xB1, 0x03,   //   Feature (Constant, Variable, Absolute)
+    ])
+    .item(Item::usage(Haptics::Intensity.id()))
+    .item(Item::logical_maximum(MAX_INTENSITY as i32))
+    .bytes(&[
+        0x75, 0x08,   //   Report Size (8)
+        0xB1, 0x02,   //   Feature (Data, Variable, Absolute)
+        0x85, MANUAL_TRIGGER_REPORT_ID, //   Report ID
+    ])
+    .item(Item::usage(Haptics::ManualTrigger.id()))
+    .item(Item::logical_minimum(1))
+    .item(Item::logical_maximum(LAST_WAVEFORM_ORDINAL as i32))
+    .bytes(&[
+        0x75, 0x10,   //   Report Size (16)
+        0x91, 0x02,   //   Output (Data, Variable, Absolute)
+    ])
+    .item(Item::usage(Haptics::Intensity.id()))
+    .item(Item::logical_minimum(0))
+    .item(Item::logical_maximum(MAX_INTENSITY as i32))
+    .bytes(&[
+        0x75, 0x08,   //   Report Size (8)
+        0x91, 0x02,   //   Output (Data, Variable, Absolute)
+    ])
+    .item(Item::usage(Haptics::RepeatCount.id()))
+    .item(Item::logical_maximum(0xFF))
+    .bytes(&[0x91, 0x02]) //   Output (Data, Variable, Absolute)
+    .item(Item::usage(Haptics::RetriggerPeriod.id()))
+    .item(Item::logical_maximum(0xFFFF))
+    .item(Item::unit(Unit::SECOND))
+    .item(Item::unit_exponent(-3))
+    .bytes(&[
+        0x75, 0x10,   //   Report Size (16)
+        0x91, 0x02,   //   Output (Data, Variable, Absolute)
+        0xC0,         // End Collection
+    ])
+    .validate();
+
+/// Simple haptic controller with a waveform and duration list feature report, an auto trigger
+/// feature report, and a manual trigger output report with intensity, repeat count and retrigger
+/// period
+pub const HAPTIC_CONTROLLER_REPORT_DESCRIPTOR: &[u8] = DESCRIPTOR.as_bytes();
+
+/// A waveform in the waveform list of a [`HapticController`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HapticWaveform {
+    /// Haptics page usage of the waveform, such as [`Haptics::WaveformClick`], or a vendor
+    /// waveform from 0x2000 to 0x2FFF
+    pub waveform: u16,
+    /// Duration in milliseconds, 0 for continuous waveforms
+    pub duration_ms: u16,
+}
+
+impl HapticWaveform {
+    #[must_use]
+    pub const fn new(waveform: u16, duration_ms: u16) -> Self {
+        Self {
+            waveform,
+            duration_ms,
+        }
+    }
+}
+
+/// Waveform to play, sent by the host with a manual trigger
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HapticCommand {
+    /// Haptics page usage of the waveform, [`Haptics::WaveformStop`] stops the current waveform
+    pub waveform: u16,
+    /// Percentage of full intensity, from 0 to 100
+    pub intensity: u8,
+    /// Number of times the waveform is repeated after it has first played
+    pub repeat_count: u8,
+    /// Milliseconds from the start of one play of the waveform to the start of the next
+    pub retrigger_period: u16,
+}
+
+/// Interface implementing a HID simple haptic controller
+pub struct HapticController<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes32, OutBytes8, Reports8>,
+    waveforms: [HapticWaveform; WAVEFORM_COUNT],
+    auto_trigger_control: u32,
+    auto_trigger: u16,
+    auto_trigger_intensity: u8,
+    command: Option<HapticCommand>,
+}
+
+impl<B: UsbBus> HapticController<'_, B> {
+    /// Take the last waveform triggered by the host, if any
+    pub fn take_command(&mut self) -> Option<HapticCommand> {
+        self.command.take()
+    }
+
+    /// Haptics page usage of the waveform to play when the associated control is pressed, set by
+    /// the host
+    #[must_use]
+    pub fn auto_trigger(&self) -> u16 {
+        self.waveform(self.auto_trigger)
+            .unwrap_or(Haptics::WaveformNone.id())
+    }
+
+    /// Percentage of full intensity of the auto trigger waveform, set by the host
+    #[must_use]
+    pub fn auto_trigger_intensity(&self) -> u8 {
+        self.auto_trigger_intensity
+    }
+
+    fn waveform(&self, ordinal: u16) -> Option<u16> {
+        match ordinal {
+            1 => Some(Haptics::WaveformNone.id()),
+            2 => Some(Haptics::WaveformStop.id()),
+            FIRST_WAVEFORM_ORDINAL..=LAST_WAVEFORM_ORDINAL => {
+                Some(self.waveforms[usize::from(ordinal - FIRST_WAVEFORM_ORDINAL)].waveform)
+            }
+            _ => None,
+        }
+    }
+
+    fn set_feature_reports(&mut self) -> usb_device::Result<()> {
+        let mut waveform_list = [0; 1 + 4 * WAVEFORM_COUNT];
+        waveform_list[0] = WAVEFORM_LIST_REPORT_ID;
+        let (waveforms, durations) = waveform_list[1..].split_at_mut(2 * WAVEFORM_COUNT);
+        for (i, waveform) in self.waveforms.iter().enumerate() {
+            waveforms[2 * i..2 * i + 2].copy_from_slice(&waveform.waveform.to_le_bytes());
+            durations[2 * i..2 * i + 2].copy_from_slice(&waveform.duration_ms.to_le_bytes());
+        }
+        self.interface.set_feature_report(&waveform_list)?;
+
+        let [trigger_lo, trigger_hi] = self.auto_trigger.to_le_bytes();
+        let [c0, c1, c2, c3] = self.auto_trigger_control.to_le_bytes();
+        self.interface.set_feature_report(&[
+            AUTO_TRIGGER_REPORT_ID,
+            trigger_lo,
+            trigger_hi,
+            c0,
+            c1,
+            c2,
+            c3,
+            self.auto_trigger_intensity,
+        ])
+    }
+
+    fn report_set(&mut self, data: &[u8]) {
+        match *data {
+            [AUTO_TRIGGER_REPORT_ID, trigger_lo, trigger_hi, _, _, _, _, intensity, ..] => {
+                let ordinal = u16::from_le_bytes([trigger_lo, trigger_hi]);
+                if self.waveform(ordinal).is_some() {
+                    self.auto_trigger = ordinal;
+                } else {
+                    warn!("Unexpected auto trigger waveform {}", ordinal);
+                }
+                self.auto_trigger_intensity = intensity.min(MAX_INTENSITY);
+                info!(
+                    "Haptic auto trigger {:X}, intensity {}",
+                    self.auto_trigger(),
+                    self.auto_trigger_intensity
+                );
+            }
+            [MANUAL_TRIGGER_REPORT_ID, trigger_lo, trigger_hi, intensity, repeat_count, period_lo, period_hi, ..] =>
+            {
+                let ordinal = u16::from_le_bytes([trigger_lo, trigger_hi]);
+                if let Some(waveform) = self.waveform(ordinal) {
+                    self.command = Some(HapticCommand {
+                        waveform,
+                        intensity: intensity.min(MAX_INTENSITY),
+                        repeat_count,
+                        retrigger_period: u16::from_le_bytes([period_lo, period_hi]),
+                    });
+                } else {
+                    warn!("Unexpected manual trigger waveform {}", ordinal);
+                }
+            }
+            _ => warn!("Unexpected haptic controller report"),
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for HapticController<'a, B> {
+    type I = Interface<'a, B, InBytes32, OutBytes8, Reports8>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.auto_trigger = FIRST_WAVEFORM_ORDINAL;
+        self.auto_trigger_intensity = MAX_INTENSITY;
+        self.command = None;
+        if self.set_feature_reports().is_err() {
+            error!("Failed to set haptic controller feature reports");
+        }
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        let mut data = [0; 8];
+        match self.interface.read_report(&mut data) {
+            Ok(n) => self.report_set(&data[..n]),
+            Err(UsbError::WouldBlock) => {}
+            Err(e) => return Err(UsbHidError::from(e)),
+        }
+        Ok(())
+    }
+}
+
+pub struct HapticControllerConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes32, OutBytes8, Reports8>,
+    waveforms: [HapticWaveform; WAVEFORM_COUNT],
+    auto_trigger_control: u32,
+}
+
+impl Default for HapticControllerConfig<'_> {
+    /// The standard click, buzz, rumble, press and release waveforms, auto triggered by button 1
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(HAPTIC_CONTROLLER_REPORT_DESCRIPTOR))
+                    .description("Haptic Controller")
+                    .in_endpoint(10.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+            [
+                HapticWaveform::new(Haptics::WaveformClick.id(), 10),
+                HapticWaveform::new(Haptics::WaveformBuzzContinuous.id(), 0),
+                HapticWaveform::new(Haptics::WaveformRumbleContinuous.id(), 0),
+                HapticWaveform::new(Haptics::WaveformPress.id(), 10),
+                HapticWaveform::new(Haptics::WaveformRelease.id(), 10),
+            ],
+            0x0009_0001,
+        )
+    }
+}
+
+impl<'a> HapticControllerConfig<'a> {
+    /// `auto_trigger_control` is the extended usage, the usage page in the upper 16 bits and the
+    /// usage in the lower 16 bits, of the control that plays the auto trigger waveform
+    ///
+    /// The auto trigger is initially the first of the `waveforms`
+    #[must_use]
+    pub fn new(
+        interface: InterfaceConfig<'a, InBytes32, OutBytes8, Reports8>,
+        waveforms: [HapticWaveform; WAVEFORM_COUNT],
+        auto_trigger_control: u32,
+    ) -> Self {
+        Self {
+            interface,
+            waveforms,
+            auto_trigger_control,
+        }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for HapticControllerConfig<'a> {
+    type Allocated = HapticController<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        let mut controller = Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            waveforms: self.waveforms,
+            auto_trigger_control: self.auto_trigger_control,
+            auto_trigger: FIRST_WAVEFORM_ORDINAL,
+            auto_trigger_intensity: MAX_INTENSITY,
+            command: None,
+        };
+        if controller.set_feature_reports().is_err() {
+            error!("Failed to set haptic controller feature reports");
+        }
+        controller
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn reports_match_descriptor() {
+        let descriptor = HAPTIC_CONTROLLER_REPORT_DESCRIPTOR;
+        let lengths = [
+            (
+                ReportType::Feature,
+                WAVEFORM_LIST_REPORT_ID,
+                1 + 4 * WAVEFORM_COUNT,
+            ),
+            (ReportType::Feature, AUTO_TRIGGER_REPORT_ID, 8),
+            (ReportType::Output, MANUAL_TRIGGER_REPORT_ID, 7),
+        ];
+        for (report_type, report_id, len) in lengths {
+            assert_eq!(
+                report_length(descriptor, report_type, report_id),
+                Some(len),
+                "{report_type:?} report {report_id}"
+            );
+        }
+        assert_eq!(
+            descriptor[..6],
+            [0x05, 0x0E, 0x09, 0x01, 0xA1, 0x01],
+            "Simple Haptic Controller application collection"
+        );
+    }
+}
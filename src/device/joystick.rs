@@ -1,4 +1,5 @@
 //!HID joystick
+use crate::descriptor::item::{Item, ReportDescriptor};
 use crate::usb_class::prelude::*;
 use core::default::Default;
 use fugit::ExtU32;
@@ -47,16 +48,19 @@ pub struct Joystick<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
 }
 
-impl<'a, B: UsbBus> Joystick<'a, B> {
+impl<B: UsbBus> Joystick<'_, B> {
     pub fn write_report(&mut self, report: &JoystickReport) -> Result<(), UsbHidError> {
         let data = report.pack().map_err(|_| {
             error!("Error packing JoystickReport");
             UsbHidError::SerializationError
         })?;
-        self.interface
-            .write_report(&data)
-            .map(|_| ())
-            .map_err(UsbHidError::from)
+        self.interface.write_report(&data).map(|_| ())
+    }
+}
+
+impl<B: UsbBus> ReportWriter<JoystickReport> for Joystick<'_, B> {
+    fn write_report(&mut self, report: &JoystickReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
     }
 }
 
@@ -78,8 +82,7 @@ pub struct JoystickConfig<'a> {
     interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
 }
 
-impl<'a> Default for JoystickConfig<'a> {
-    #[must_use]
+impl Default for JoystickConfig<'_> {
     fn default() -> Self {
         Self::new(
             unwrap!(unwrap!(InterfaceBuilder::new(JOYSTICK_DESCRIPTOR))
@@ -108,3 +111,236 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for JoystickConfig<'a> {
         }
     }
 }
+
+/// Most axes supported by a [`CustomJoystick`]
+pub const MAX_CUSTOM_AXES: usize = 8;
+/// Most buttons supported by a [`CustomJoystick`]
+pub const MAX_CUSTOM_BUTTONS: usize = 128;
+
+/// Report for a [`CustomJoystick`] with `AXES` 16 bit axes and `BUTTONS` buttons
+///
+/// Axes are reported as X, Y, Z, Rx, Ry, Rz, Slider and Dial, in that order
+///
+/// ```
+/// # use usbd_human_interface_device::device::joystick::CustomJoystickReport;
+/// let mut report = CustomJoystickReport::<2, 12>::default();
+/// report.axes[0] = -1;
+/// report.set_button(11, true);
+///
+/// let mut buf = [0; 32];
+/// let len = report.pack_to(&mut buf);
+/// assert_eq!(&buf[..len], [0x00, 0x08, 0xFF, 0xFF, 0x00, 0x00]);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CustomJoystickReport<const AXES: usize, const BUTTONS: usize> {
+    /// From -32767 to 32767
+    pub axes: [i16; AXES],
+    /// Bit 0 is button 1
+    pub buttons: u128,
+}
+
+impl<const AXES: usize, const BUTTONS: usize> Default for CustomJoystickReport<AXES, BUTTONS> {
+    fn default() -> Self {
+        Self {
+            axes: [0; AXES],
+            buttons: 0,
+        }
+    }
+}
+
+impl<const AXES: usize, const BUTTONS: usize> CustomJoystickReport<AXES, BUTTONS> {
+    const BUTTON_BYTES: usize = BUTTONS.div_ceil(8);
+
+    /// Length of the packed report in bytes
+    pub const LEN: usize = Self::BUTTON_BYTES + 2 * AXES;
+
+    /// Report descriptor generated for `AXES` and `BUTTONS`
+    ///
+    /// Fails to compile if there are more than [`MAX_CUSTOM_AXES`] or [`MAX_CUSTOM_BUTTONS`]
+    pub const REPORT_DESCRIPTOR: &'static [u8] = Self::DESCRIPTOR.as_bytes();
+
+    const DESCRIPTOR: &'static ReportDescriptor<64> =
+        &custom_joystick_descriptor(AXES, BUTTONS).validate();
+
+    /// Set button `n`, numbered from 0. Buttons beyond `BUTTONS` are ignored
+    pub fn set_button(&mut self, n: usize, pressed: bool) {
+        if n < BUTTONS {
+            if pressed {
+                self.buttons |= 1 << n;
+            } else {
+                self.buttons &= !(1 << n);
+            }
+        }
+    }
+
+    /// Buttons followed by little endian axes, returns the length written to `buf`
+    ///
+    /// # Panics
+    ///
+    /// If `buf` is shorter than [`Self::LEN`]
+    pub fn pack_to(&self, buf: &mut [u8]) -> usize {
+        let (buttons, axes) = buf[..Self::LEN].split_at_mut(Self::BUTTON_BYTES);
+        buttons.copy_from_slice(&self.buttons.to_le_bytes()[..Self::BUTTON_BYTES]);
+        for (bytes, axis) in axes.chunks_exact_mut(2).zip(self.axes) {
+            bytes.copy_from_slice(&axis.to_le_bytes());
+        }
+        Self::LEN
+    }
+}
+
+const fn custom_joystick_descriptor(axes: usize, buttons: usize) -> ReportDescriptor<64> {
+    const AXIS_USAGES: [u8; MAX_CUSTOM_AXES] = [0x30, 0x31, 0x32, 0x33, 0x34, 0x35, 0x36, 0x37];
+
+    ::core::assert!(axes <= MAX_CUSTOM_AXES, "too many joystick axes");
+    ::core::assert!(buttons <= MAX_CUSTOM_BUTTONS, "too many joystick buttons");
+
+    #[allow(clippy::cast_possible_truncation)]
+    let (axes_count, buttons_count) = (axes as u8, buttons as u8);
+
+    let mut descriptor = ReportDescriptor::new().bytes(&[
+        0x05, 0x01, // Usage Page (Generic Desktop)
+        0x09, 0x04, // Usage (Joystick)
+        0xA1, 0x01, // Collection (Application)
+    ]);
+
+    if buttons > 0 {
+        descriptor = descriptor
+            .bytes(&[0x05, 0x09, 0x19, 0x01]) // Usage Page (Button), Usage Minimum (1)
+            .bytes(&[0x29])
+            .bytes(&[buttons_count]) // Usage Maximum (BUTTONS)
+            .item(Item::logical_minimum(0))
+            .item(Item::logical_maximum(1))
+            .bytes(&[0x75, 0x01, 0x95]) // Report Size (1), Report Count
+            .bytes(&[buttons_count])
+            .bytes(&[0x81, 0x02]); // Input (Data, Variable, Absolute)
+        if !buttons_count.is_multiple_of(8) {
+            descriptor = descriptor
+                .bytes(&[0x75])
+                .bytes(&[8 - buttons_count % 8]) // Report Size (padding)
+                .bytes(&[0x95, 0x01, 0x81, 0x01]); // Report Count (1), Input (Constant)
+        }
+    }
+
+    if axes > 0 {
+        descriptor = descriptor.bytes(&[0x05, 0x01]); // Usage Page (Generic Desktop)
+        let mut i = 0;
+        while i < axes {
+            descriptor = descriptor.bytes(&[0x09, AXIS_USAGES[i]]); // Usage (axis)
+            i += 1;
+        }
+        descriptor = descriptor
+            .item(Item::logical_minimum(-32767))
+            .item(Item::logical_maximum(32767))
+            .bytes(&[0x75, 0x10, 0x95]) // Report Size (16), Report Count
+            .bytes(&[axes_count])
+            .bytes(&[0x81, 0x02]); // Input (Data, Variable, Absolute)
+    }
+
+    descriptor.bytes(&[0xC0]) // End Collection
+}
+
+/// Joystick with `AXES` 16 bit axes and `BUTTONS` buttons, for button boxes and control panels
+pub struct CustomJoystick<'a, B: UsbBus, const AXES: usize, const BUTTONS: usize> {
+    interface: Interface<'a, B, InBytes32, OutNone, ReportSingle>,
+}
+
+impl<B: UsbBus, const AXES: usize, const BUTTONS: usize> CustomJoystick<'_, B, AXES, BUTTONS> {
+    pub fn write_report(
+        &mut self,
+        report: &CustomJoystickReport<AXES, BUTTONS>,
+    ) -> Result<(), UsbHidError> {
+        let mut data = [0; 32];
+        let len = report.pack_to(&mut data);
+        self.interface.write_report(&data[..len]).map(|_| ())
+    }
+}
+
+impl<B: UsbBus, const AXES: usize, const BUTTONS: usize>
+    ReportWriter<CustomJoystickReport<AXES, BUTTONS>> for CustomJoystick<'_, B, AXES, BUTTONS>
+{
+    fn write_report(
+        &mut self,
+        report: &CustomJoystickReport<AXES, BUTTONS>,
+    ) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus, const AXES: usize, const BUTTONS: usize> DeviceClass<'a>
+    for CustomJoystick<'a, B, AXES, BUTTONS>
+{
+    type I = Interface<'a, B, InBytes32, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct CustomJoystickConfig<'a, const AXES: usize, const BUTTONS: usize> {
+    interface: InterfaceConfig<'a, InBytes32, OutNone, ReportSingle>,
+}
+
+impl<const AXES: usize, const BUTTONS: usize> Default for CustomJoystickConfig<'_, AXES, BUTTONS> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(
+                CustomJoystickReport::<AXES, BUTTONS>::REPORT_DESCRIPTOR
+            ))
+            .description("Joystick")
+            .in_endpoint(10.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a, const AXES: usize, const BUTTONS: usize> CustomJoystickConfig<'a, AXES, BUTTONS> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes32, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a, const AXES: usize, const BUTTONS: usize> UsbAllocatable<'a, B>
+    for CustomJoystickConfig<'a, AXES, BUTTONS>
+{
+    type Allocated = CustomJoystick<'a, B, AXES, BUTTONS>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn custom_joystick_descriptor_matches_report() {
+        fn check<const AXES: usize, const BUTTONS: usize>() {
+            assert_eq!(
+                report_length(
+                    CustomJoystickReport::<AXES, BUTTONS>::REPORT_DESCRIPTOR,
+                    ReportType::Input,
+                    0
+                ),
+                Some(CustomJoystickReport::<AXES, BUTTONS>::LEN)
+            );
+        }
+        check::<0, 1>();
+        check::<2, 8>();
+        check::<3, 12>();
+        check::<8, 0>();
+        check::<8, 128>();
+    }
+}
@@ -1,5 +1,7 @@
 //!HID keyboards
 
+use crate::interface::{IdleManager, InSize, OutSize};
+use crate::page::layout::{KeyStroke, Layout, TypeStr};
 use crate::page::Keyboard;
 use crate::usb_class::prelude::*;
 use fugit::ExtU32;
@@ -10,9 +12,11 @@ use usb_device::UsbError;
 
 /// Interface implementing the HID boot keyboard specification
 ///
-/// **Note:** This is a managed interfaces that support HID idle, [`UsbHidClass::tick()`] must be called every 1ms.
+/// **Note:** This is a managed interfaces that support HID idle, [`UsbHidClass::tick()`] must be called every 1ms, or
+/// [`UsbHidClass::tick_clock()`] called regularly.
 pub struct BootKeyboard<'a, B: UsbBus> {
-    interface: ManagedIdleInterface<'a, B, BootKeyboardReport, InBytes8, OutBytes8>,
+    keyboard:
+        TypingKeyboard<'a, ManagedIdleInterface<'a, B, BootKeyboardReport, InBytes8, OutBytes8>>,
 }
 
 impl<'a, B> BootKeyboard<'a, B>
@@ -23,20 +27,52 @@ where
         &mut self,
         keys: K,
     ) -> Result<(), UsbHidError> {
-        self.interface
+        self.keyboard
+            .interface
             .write_report(&BootKeyboardReport::new(keys))
-            .map(|_| ())
+    }
+
+    /// Type `text` as it would be entered on a keyboard with the host's `layout`, replacing any
+    /// text still being typed
+    ///
+    /// Keys are pressed and released by [`UsbHidClass::tick()`], so reports should not be written
+    /// while [`Self::is_typing`]
+    pub fn type_str(&mut self, layout: Layout, text: &'a str) {
+        self.keyboard.type_str(layout, text);
+    }
+
+    #[must_use]
+    pub fn is_typing(&self) -> bool {
+        self.keyboard.is_typing()
+    }
+
+    /// Stop typing, releasing any pressed key
+    pub fn stop_typing(&mut self) {
+        self.keyboard.stop_typing();
     }
 
     pub fn read_report(&mut self) -> usb_device::Result<KeyboardLedsReport> {
-        let data = &mut [0];
-        match self.interface.read_report(data) {
-            Err(e) => Err(e),
-            Ok(_) => match KeyboardLedsReport::unpack(data) {
-                Ok(r) => Ok(r),
-                Err(_) => Err(UsbError::ParseError),
-            },
-        }
+        self.keyboard.read_report()
+    }
+
+    /// The LEDs last set by the host, over either the out endpoint or the control pipe
+    #[must_use]
+    pub fn leds(&self) -> KeyboardLedsReport {
+        self.keyboard.leds()
+    }
+
+    /// Returns the LED state if the host has changed it since this was last called
+    ///
+    /// LED reports are received by [`UsbHidClass::tick()`], so this can be polled instead of
+    /// [`Self::read_report`] by firmware that only mirrors the lock LEDs
+    pub fn leds_changed(&mut self) -> Option<KeyboardLedsReport> {
+        self.keyboard.leds_changed()
+    }
+}
+
+impl<B: UsbBus> ReportWriter<BootKeyboardReport> for BootKeyboard<'_, B> {
+    fn write_report(&mut self, report: &BootKeyboardReport) -> Result<(), UsbHidError> {
+        self.keyboard.interface.write_report(report)
     }
 }
 
@@ -47,15 +83,15 @@ where
     type I = Interface<'a, B, InBytes8, OutBytes8, ReportSingle>;
 
     fn interface(&mut self) -> &mut Self::I {
-        self.interface.interface()
+        self.keyboard.interface.interface()
     }
 
     fn reset(&mut self) {
-        self.interface.reset();
+        self.keyboard.reset();
     }
 
     fn tick(&mut self) -> Result<(), UsbHidError> {
-        self.interface.tick()
+        self.keyboard.tick()
     }
 }
 
@@ -63,8 +99,7 @@ pub struct BootKeyboardConfig<'a> {
     interface: ManagedIdleInterfaceConfig<'a, BootKeyboardReport, InBytes8, OutBytes8>,
 }
 
-impl<'a> Default for BootKeyboardConfig<'a> {
-    #[must_use]
+impl Default for BootKeyboardConfig<'_> {
     fn default() -> Self {
         Self::new(ManagedIdleInterfaceConfig::new(
             unwrap!(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
@@ -96,7 +131,7 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for BootKeyboardConfig<'a> {
 
     fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
         Self::Allocated {
-            interface: self.interface.allocate(usb_alloc),
+            keyboard: TypingKeyboard::new(self.interface.allocate(usb_alloc)),
         }
     }
 }
@@ -117,6 +152,65 @@ pub struct KeyboardLedsReport {
     pub kana: bool,
 }
 
+/// LED state received from the host by a keyboard
+#[derive(Debug, Default)]
+struct LedState {
+    current: KeyboardLedsReport,
+    changed: bool,
+    /// Received by a tick but not yet returned by `read_report`
+    unread: Option<KeyboardLedsReport>,
+}
+
+impl LedState {
+    fn read<F>(mut read: F) -> usb_device::Result<KeyboardLedsReport>
+    where
+        F: FnMut(&mut [u8]) -> usb_device::Result<usize>,
+    {
+        let data = &mut [0];
+        read(data)?;
+        KeyboardLedsReport::unpack(data).map_err(|_| UsbError::ParseError)
+    }
+
+    fn update(&mut self, leds: KeyboardLedsReport) {
+        if leds != self.current {
+            self.current = leds;
+            self.changed = true;
+        }
+    }
+
+    fn receive<F>(&mut self, read: F)
+    where
+        F: FnMut(&mut [u8]) -> usb_device::Result<usize>,
+    {
+        match Self::read(read) {
+            Ok(leds) => {
+                self.update(leds);
+                self.unread = Some(leds);
+            }
+            Err(UsbError::WouldBlock) => {}
+            Err(e) => warn!("Failed to read keyboard LEDs: {:?}", e),
+        }
+    }
+
+    fn read_report<F>(&mut self, read: F) -> usb_device::Result<KeyboardLedsReport>
+    where
+        F: FnMut(&mut [u8]) -> usb_device::Result<usize>,
+    {
+        if let Some(leds) = self.unread.take() {
+            return Ok(leds);
+        }
+        let leds = Self::read(read)?;
+        self.update(leds);
+        Ok(leds)
+    }
+
+    fn reset(&mut self) {
+        //The host sets the LEDs again after enumerating
+        self.update(KeyboardLedsReport::default());
+        self.unread = None;
+    }
+}
+
 /// Report implementing the HID boot keyboard specification
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
 #[packed_struct(endian = "lsb", bit_numbering = "msb0", size_bytes = "8")]
@@ -394,9 +488,13 @@ impl NKROBootKeyboardReport {
 
 /// Interface implementing a NKRO keyboard compatible with the HID boot keyboard specification
 ///
-/// **Note:** This is a managed interfaces that support HID idle, [`UsbHidClass::tick()`] must be called every 1ms/ at 1kHz.
+/// **Note:** This is a managed interfaces that support HID idle, [`UsbHidClass::tick()`] must be called every 1ms/ at 1kHz, or
+/// [`UsbHidClass::tick_clock()`] called regularly.
 pub struct NKROBootKeyboard<'a, B: UsbBus> {
-    interface: ManagedIdleInterface<'a, B, NKROBootKeyboardReport, InBytes32, OutBytes8>,
+    keyboard: TypingKeyboard<
+        'a,
+        ManagedIdleInterface<'a, B, NKROBootKeyboardReport, InBytes32, OutBytes8>,
+    >,
 }
 
 impl<'a, B> NKROBootKeyboard<'a, B>
@@ -407,20 +505,46 @@ where
         &mut self,
         keys: K,
     ) -> Result<(), UsbHidError> {
-        self.interface
+        self.keyboard
+            .interface
             .write_report(&NKROBootKeyboardReport::new(keys))
-            .map(|_| ())
+    }
+
+    /// Type `text` as it would be entered on a keyboard with the host's `layout`, replacing any
+    /// text still being typed
+    ///
+    /// Keys are pressed and released by [`UsbHidClass::tick()`], so reports should not be written
+    /// while [`Self::is_typing`]
+    pub fn type_str(&mut self, layout: Layout, text: &'a str) {
+        self.keyboard.type_str(layout, text);
+    }
+
+    #[must_use]
+    pub fn is_typing(&self) -> bool {
+        self.keyboard.is_typing()
+    }
+
+    /// Stop typing, releasing any pressed key
+    pub fn stop_typing(&mut self) {
+        self.keyboard.stop_typing();
     }
 
     pub fn read_report(&mut self) -> usb_device::Result<KeyboardLedsReport> {
-        let data = &mut [0];
-        match self.interface.read_report(data) {
-            Err(e) => Err(e),
-            Ok(_) => match KeyboardLedsReport::unpack(data) {
-                Ok(r) => Ok(r),
-                Err(_) => Err(UsbError::ParseError),
-            },
-        }
+        self.keyboard.read_report()
+    }
+
+    /// The LEDs last set by the host, over either the out endpoint or the control pipe
+    #[must_use]
+    pub fn leds(&self) -> KeyboardLedsReport {
+        self.keyboard.leds()
+    }
+
+    /// Returns the LED state if the host has changed it since this was last called
+    ///
+    /// LED reports are received by [`UsbHidClass::tick()`], so this can be polled instead of
+    /// [`Self::read_report`] by firmware that only mirrors the lock LEDs
+    pub fn leds_changed(&mut self) -> Option<KeyboardLedsReport> {
+        self.keyboard.leds_changed()
     }
 }
 
@@ -428,8 +552,7 @@ pub struct NKROBootKeyboardConfig<'a> {
     interface: ManagedIdleInterfaceConfig<'a, NKROBootKeyboardReport, InBytes32, OutBytes8>,
 }
 
-impl<'a> Default for NKROBootKeyboardConfig<'a> {
-    #[must_use]
+impl Default for NKROBootKeyboardConfig<'_> {
     fn default() -> Self {
         Self::new(ManagedIdleInterfaceConfig::new(
             unwrap!(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
@@ -459,11 +582,17 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for NKROBootKeyboardConfig<'a> {
 
     fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
         Self::Allocated {
-            interface: self.interface.allocate(usb_alloc),
+            keyboard: TypingKeyboard::new(self.interface.allocate(usb_alloc)),
         }
     }
 }
 
+impl<B: UsbBus> ReportWriter<NKROBootKeyboardReport> for NKROBootKeyboard<'_, B> {
+    fn write_report(&mut self, report: &NKROBootKeyboardReport) -> Result<(), UsbHidError> {
+        self.keyboard.interface.write_report(report)
+    }
+}
+
 impl<'a, B> DeviceClass<'a> for NKROBootKeyboard<'a, B>
 where
     B: UsbBus,
@@ -471,29 +600,34 @@ where
     type I = Interface<'a, B, InBytes32, OutBytes8, ReportSingle>;
 
     fn interface(&mut self) -> &mut Self::I {
-        self.interface.interface()
+        self.keyboard.interface.interface()
     }
 
     fn reset(&mut self) {
-        self.interface.reset();
+        self.keyboard.reset();
     }
 
-    fn tick(&mut self) -> core::result::Result<(), UsbHidError> {
-        self.interface.tick()
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        self.keyboard.tick()
     }
 }
 
-/// HID Keyboard report descriptor implementing an NKRO keyboard as a bitmap.
+const SIX_KEY_REPORT_ID: u8 = 0x01;
+const NKRO_REPORT_ID: u8 = 0x02;
+
+/// HID Keyboard report descriptor with both a 6KRO and an NKRO input report, for
+/// [`DualModeKeyboard`]
 ///
-/// N.B. This is not compatible with the HID boot specification
-//18 bytes - derived from https://learn.adafruit.com/custom-hid-devices-in-circuitpython/n-key-rollover-nkro-hid-device
-//First byte modifiers, 17 byte key bit array
+/// Report 1 has the layout of the boot keyboard report and holds the LED output report. Report 2
+/// holds the modifiers followed by a bitmap of the pressed keys.
+//Report 1 - 9 bytes: report ID, modifiers, reserved byte, 6 byte key array
+//Report 2 - 19 bytes: report ID, modifiers, 17 byte key bitmap
 #[rustfmt::skip]
-pub const NKRO_COMPACT_KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
+pub const DUAL_MODE_KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
     0x05, 0x01,                     // Usage Page (Generic Desktop),
     0x09, 0x06,                     // Usage (Keyboard),
     0xA1, 0x01,                     // Collection (Application),
-    // bitmap of modifiers
+    0x85, SIX_KEY_REPORT_ID,        //   Report ID (1),
     0x75, 0x01,                     //   Report Size (1),
     0x95, 0x08,                     //   Report Count (8),
     0x05, 0x07,                     //   Usage Page (Key Codes),
@@ -502,165 +636,1244 @@ pub const NKRO_COMPACT_KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
     0x15, 0x00,                     //   Logical Minimum (0),
     0x25, 0x01,                     //   Logical Maximum (1),
     0x81, 0x02,                     //   Input (Data, Variable, Absolute), ;Modifier byte
-    // LED output report
+    0x95, 0x01,                     //   Report Count (1),
+    0x75, 0x08,                     //   Report Size (8),
+    0x81, 0x01,                     //   Input (Constant), ;Reserved byte
     0x95, 0x05,                     //   Report Count (5),
     0x75, 0x01,                     //   Report Size (1),
     0x05, 0x08,                     //   Usage Page (LEDs),
     0x19, 0x01,                     //   Usage Minimum (1),
     0x29, 0x05,                     //   Usage Maximum (5),
-    0x91, 0x02,                     //   Output (Data, Variable, Absolute),
+    0x91, 0x02,                     //   Output (Data, Variable, Absolute), ;LED report
     0x95, 0x01,                     //   Report Count (1),
     0x75, 0x03,                     //   Report Size (3),
-    0x91, 0x03,                     //   Output (Constant),
-    // bitmap of keys
-    0x95, 0x88,                     //   Report Count () - (REPORT_BYTES-1)*8
-    0x75, 0x01,                     //   Report Size (1),
+    0x91, 0x01,                     //   Output (Constant), ;LED report padding
+    0x95, 0x06,                     //   Report Count (6),
+    0x75, 0x08,                     //   Report Size (8),
     0x15, 0x00,                     //   Logical Minimum (0),
-    0x25, 0x01,                     //   Logical Maximum(1),
+    0x26, 0xFF, 0x00,               //   Logical Maximum(255),
     0x05, 0x07,                     //   Usage Page (Key Codes),
     0x19, 0x00,                     //   Usage Minimum (0),
-    0x29, 0x87,                     //   Usage Maximum (), - (REPORT_BYTES-1)*8-1
-    0x81, 0x02,                     //   Input (Data, Variable, Absolute),
-    0xc0                            // End Collection
+    0x2A, 0xFF, 0x00,               //   Usage Maximum (255),
+    0x81, 0x00,                     //   Input (Data, Array),
+    0x85, NKRO_REPORT_ID,           //   Report ID (2),
+    0x75, 0x01,                     //   Report Size (1),
+    0x95, 0x08,                     //   Report Count (8),
+    0x05, 0x07,                     //   Usage Page (Key Codes),
+    0x19, 0xE0,                     //   Usage Minimum (224),
+    0x29, 0xE7,                     //   Usage Maximum (231),
+    0x15, 0x00,                     //   Logical Minimum (0),
+    0x25, 0x01,                     //   Logical Maximum (1),
+    0x81, 0x02,                     //   Input (Data, Variable, Absolute), ;Modifier byte
+    0x95, 0x88,                     //   Report Count (136),
+    0x19, 0x00,                     //   Usage Minimum (0),
+    0x29, 0x87,                     //   Usage Maximum (135),
+    0x81, 0x02,                     //   Input (Data, Variable, Absolute), ;Key bitmap
+    0xC0,                           // End Collection
 ];
 
-#[cfg(test)]
-mod test {
-    #![allow(clippy::unwrap_used)]
-    #![allow(clippy::expect_used)]
+/// Input report sent by a [`DualModeKeyboard`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum DualModeReport {
+    /// Boot keyboard report without a report ID
+    Boot,
+    SixKey,
+    NKey,
+}
 
-    use packed_struct::prelude::*;
+impl DualModeReport {
+    fn report_id(self) -> u8 {
+        match self {
+            Self::Boot => 0,
+            Self::SixKey => SIX_KEY_REPORT_ID,
+            Self::NKey => NKRO_REPORT_ID,
+        }
+    }
 
-    use crate::device::keyboard::{BootKeyboardReport, KeyboardLedsReport};
-    use crate::page::Keyboard;
+    /// Write `report` to `data` in this format, returning the report length
+    fn pack(self, report: &NKROBootKeyboardReport, data: &mut [u8]) -> Result<usize, UsbHidError> {
+        let packed = report.pack().map_err(|_| {
+            error!("Error packing NKROBootKeyboardReport");
+            UsbHidError::SerializationError
+        })?;
+        let len = match self {
+            Self::Boot => 8,
+            Self::SixKey => 9,
+            Self::NKey => 19,
+        };
+        let data = data.get_mut(..len).ok_or(UsbHidError::SerializationError)?;
+        match self {
+            Self::Boot => data.copy_from_slice(&packed[..8]),
+            Self::SixKey => {
+                data[0] = SIX_KEY_REPORT_ID;
+                data[1..].copy_from_slice(&packed[..8]);
+            }
+            Self::NKey => {
+                data[0] = NKRO_REPORT_ID;
+                data[1] = packed[0];
+                data[2..].copy_from_slice(&packed[8..]);
+            }
+        }
+        Ok(len)
+    }
+}
 
-    #[test]
-    fn leds_num_lock() {
-        assert_eq!(
-            KeyboardLedsReport::unpack(&[1]),
-            Ok(KeyboardLedsReport {
-                num_lock: true,
-                caps_lock: false,
-                scroll_lock: false,
-                compose: false,
-                kana: false,
-            })
-        );
+/// Interface implementing a keyboard with a 6KRO and an NKRO report, switching between them with
+/// the protocol selected by the host
+///
+/// Hosts using the boot protocol, such as a BIOS, receive boot keyboard reports. Hosts using the
+/// report protocol receive the NKRO report, or the 6KRO report if [`Self::set_rollover`] selects
+/// [`Rollover::SixKey`]. The last report is sent again in the new format when the host changes
+/// the protocol.
+///
+/// **Note:** This is a managed interface that supports HID idle, [`UsbHidClass::tick()`] must be
+/// called every 1ms, or [`UsbHidClass::tick_clock()`] called regularly.
+pub struct DualModeKeyboard<'a, B: UsbBus> {
+    keyboard: TypingKeyboard<'a, DualModeInterface<'a, B>>,
+}
+
+impl<'a, B> DualModeKeyboard<'a, B>
+where
+    B: UsbBus,
+{
+    pub fn write_report<K: IntoIterator<Item = Keyboard>>(
+        &mut self,
+        keys: K,
+    ) -> Result<(), UsbHidError> {
+        self.keyboard
+            .interface
+            .write_report(&NKROBootKeyboardReport::new(keys))
     }
 
-    #[test]
-    fn leds_caps_lock() {
-        assert_eq!(
-            KeyboardLedsReport::unpack(&[2]),
-            Ok(KeyboardLedsReport {
-                num_lock: false,
-                caps_lock: true,
-                scroll_lock: false,
-                compose: false,
-                kana: false,
-            })
-        );
+    /// The report sent to hosts using the report protocol
+    #[must_use]
+    pub fn rollover(&self) -> Rollover {
+        self.keyboard.interface.rollover
+    }
 
-        assert_eq!(
-            KeyboardLedsReport {
-                num_lock: false,
-                caps_lock: true,
-                scroll_lock: false,
-                compose: false,
-                kana: false,
-            }
-            .pack(),
-            Ok([2])
-        );
+    /// Select the report sent to hosts using the report protocol, the 6KRO report can be used
+    /// for hosts that don't handle the NKRO report
+    ///
+    /// The last report is sent again in the new format by [`UsbHidClass::tick()`]
+    pub fn set_rollover(&mut self, rollover: Rollover) {
+        self.keyboard.interface.rollover = rollover;
     }
 
-    #[test]
-    fn boot_keyboard_report_mixed() {
-        let bytes = BootKeyboardReport::new([
-            Keyboard::LeftAlt,
-            Keyboard::A,
-            Keyboard::B,
-            Keyboard::C,
-            Keyboard::RightGUI,
-        ])
-        .pack()
-        .unwrap();
+    /// Type `text` as it would be entered on a keyboard with the host's `layout`, replacing any
+    /// text still being typed
+    ///
+    /// Keys are pressed and released by [`UsbHidClass::tick()`], so reports should not be written
+    /// while [`Self::is_typing`]
+    pub fn type_str(&mut self, layout: Layout, text: &'a str) {
+        self.keyboard.type_str(layout, text);
+    }
 
-        let key_mod: u8 = 0x1_u8 << (u8::from(Keyboard::LeftAlt) - u8::from(Keyboard::LeftControl))
-            | 0x1_u8 << (u8::from(Keyboard::RightGUI) - u8::from(Keyboard::LeftControl));
+    #[must_use]
+    pub fn is_typing(&self) -> bool {
+        self.keyboard.is_typing()
+    }
 
-        assert_eq!(
-            bytes,
-            [
-                key_mod,
-                0,
-                Keyboard::A.into(),
-                Keyboard::B.into(),
-                Keyboard::C.into(),
-                0,
-                0,
-                0
-            ]
-        );
+    /// Stop typing, releasing any pressed key
+    pub fn stop_typing(&mut self) {
+        self.keyboard.stop_typing();
     }
 
-    #[test]
-    fn boot_keyboard_report_keys() {
-        let bytes = BootKeyboardReport::new([
-            Keyboard::A,
-            Keyboard::B,
-            Keyboard::C,
-            Keyboard::D,
-            Keyboard::E,
-            Keyboard::F,
-        ])
-        .pack()
-        .unwrap();
+    pub fn read_report(&mut self) -> usb_device::Result<KeyboardLedsReport> {
+        self.keyboard.read_report()
+    }
 
-        assert_eq!(
-            bytes,
-            [
-                0,
-                0,
-                Keyboard::A.into(),
-                Keyboard::B.into(),
-                Keyboard::C.into(),
-                Keyboard::D.into(),
-                Keyboard::E.into(),
-                Keyboard::F.into()
-            ]
-        );
+    /// The LEDs last set by the host, over either the out endpoint or the control pipe
+    #[must_use]
+    pub fn leds(&self) -> KeyboardLedsReport {
+        self.keyboard.leds()
     }
 
-    #[test]
-    fn boot_keyboard_report_rollover() {
-        let bytes = BootKeyboardReport::new([
-            Keyboard::LeftAlt,
-            Keyboard::A,
-            Keyboard::B,
-            Keyboard::C,
-            Keyboard::D,
-            Keyboard::E,
-            Keyboard::F,
-            Keyboard::G,
-            Keyboard::RightGUI,
-        ])
-        .pack()
-        .unwrap();
+    /// Returns the LED state if the host has changed it since this was last called
+    ///
+    /// LED reports are received by [`UsbHidClass::tick()`], so this can be polled instead of
+    /// [`Self::read_report`] by firmware that only mirrors the lock LEDs
+    pub fn leds_changed(&mut self) -> Option<KeyboardLedsReport> {
+        self.keyboard.leds_changed()
+    }
+}
 
-        let key_mod: u8 = 0x1_u8 << (u8::from(Keyboard::LeftAlt) - u8::from(Keyboard::LeftControl))
-            | 0x1_u8 << (u8::from(Keyboard::RightGUI) - u8::from(Keyboard::LeftControl));
-        assert_eq!(
-            bytes,
-            [
-                key_mod,
-                0,
-                Keyboard::ErrorRollOver.into(),
-                Keyboard::ErrorRollOver.into(),
-                Keyboard::ErrorRollOver.into(),
-                Keyboard::ErrorRollOver.into(),
-                Keyboard::ErrorRollOver.into(),
-                Keyboard::ErrorRollOver.into(),
+/// Interface of a [`DualModeKeyboard`], packing its reports in the format selected by the
+/// protocol and rollover
+struct DualModeInterface<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes32, OutBytes8, Reports8>,
+    idle_manager: IdleManager<NKROBootKeyboardReport>,
+    rollover: Rollover,
+    /// Format the last report was sent in
+    written: Option<DualModeReport>,
+}
+
+impl<B: UsbBus> DualModeInterface<'_, B> {
+    fn write_report(&mut self, report: &NKROBootKeyboardReport) -> Result<(), UsbHidError> {
+        if self.written == Some(self.format()) && self.idle_manager.is_duplicate(report) {
+            Err(UsbHidError::Duplicate)
+        } else {
+            self.send(report)
+        }
+    }
+
+    fn send(&mut self, report: &NKROBootKeyboardReport) -> Result<(), UsbHidError> {
+        let format = self.format();
+        let mut data = [0; 19];
+        let len = format.pack(report, &mut data)?;
+        self.interface.send_report(&data[..len])?;
+        self.idle_manager.report_written(*report);
+        self.written = Some(format);
+        Ok(())
+    }
+
+    fn format(&self) -> DualModeReport {
+        match (self.interface.protocol(), self.rollover) {
+            (HidProtocol::Boot, _) => DualModeReport::Boot,
+            (HidProtocol::Report, Rollover::SixKey) => DualModeReport::SixKey,
+            (HidProtocol::Report, Rollover::NKey) => DualModeReport::NKey,
+        }
+    }
+}
+
+impl<'a, B: UsbBus> KeyboardInterface<'a> for DualModeInterface<'a, B> {
+    fn write_keys<K: IntoIterator<Item = Keyboard>>(&mut self, keys: K) -> Result<(), UsbHidError> {
+        self.write_report(&NKROBootKeyboardReport::new(keys))
+    }
+
+    fn read_leds(&mut self, data: &mut [u8]) -> usb_device::Result<usize> {
+        let mut report = [0; 2];
+        let len = self.interface.read_report(&mut report)?;
+        //The LED report has a report ID, except in the boot protocol
+        match report[..len] {
+            [SIX_KEY_REPORT_ID, leds] | [leds] => {
+                data[0] = leds;
+                Ok(1)
+            }
+            _ => Err(UsbError::ParseError),
+        }
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for DualModeInterface<'a, B> {
+    type I = Interface<'a, B, InBytes32, OutBytes8, Reports8>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.idle_manager = IdleManager::default();
+        self.written = None;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        let format = self.format();
+        //IdleManager treats a zero timeout as indefinite
+        let idle = self
+            .interface
+            .get_idle_duration(format.report_id())
+            .unwrap_or(0.millis());
+        let repeat = self.idle_manager.tick(idle);
+        match self.idle_manager.last_report() {
+            //The host changed the protocol, or the rollover changed, since the last report
+            Some(report) if self.written != Some(format) => match self.send(&report) {
+                Err(UsbHidError::WouldBlock) => Ok(()),
+                r => r,
+            },
+            Some(report) if repeat => self.send(&report),
+            _ => Ok(()),
+        }
+    }
+}
+
+pub struct DualModeKeyboardConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes32, OutBytes8, Reports8>,
+}
+
+impl Default for DualModeKeyboardConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+                DUAL_MODE_KEYBOARD_REPORT_DESCRIPTOR
+            ))
+            .description("NKRO Keyboard")
+            .boot_device(InterfaceProtocol::Keyboard)
+            .idle_default(500.millis()))
+            .in_endpoint(10.millis()))
+            .with_out_endpoint(100.millis()))
+            .build(),
+        )
+    }
+}
+
+impl<'a> DualModeKeyboardConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes32, OutBytes8, Reports8>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for DualModeKeyboardConfig<'a> {
+    type Allocated = DualModeKeyboard<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            keyboard: TypingKeyboard::new(DualModeInterface {
+                interface: Interface::new(usb_alloc, self.interface),
+                idle_manager: IdleManager::default(),
+                rollover: Rollover::NKey,
+                written: None,
+            }),
+        }
+    }
+}
+
+impl<B: UsbBus> ReportWriter<NKROBootKeyboardReport> for DualModeKeyboard<'_, B> {
+    fn write_report(&mut self, report: &NKROBootKeyboardReport) -> Result<(), UsbHidError> {
+        self.keyboard.interface.write_report(report)
+    }
+}
+
+impl<'a, B> DeviceClass<'a> for DualModeKeyboard<'a, B>
+where
+    B: UsbBus,
+{
+    type I = Interface<'a, B, InBytes32, OutBytes8, Reports8>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.keyboard.interface.interface
+    }
+
+    fn reset(&mut self) {
+        self.keyboard.reset();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        self.keyboard.tick()
+    }
+}
+
+/// HID Keyboard report descriptor for a keyboard with an Apple Fn/Globe key
+///
+/// Follows the boot keyboard report with a byte holding the Fn key, using the vendor usage
+/// (page 0x00FF, usage 0x0003) Apple keyboards use for it. The first 8 bytes of each report are
+/// compatible with the HID boot specification.
+#[rustfmt::skip]
+pub const APPLE_KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,                     // Usage Page (Generic Desktop),
+    0x09, 0x06,                     // Usage (Keyboard),
+    0xA1, 0x01,                     // Collection (Application),
+    0x75, 0x01,                     //   Report Size (1),
+    0x95, 0x08,                     //   Report Count (8),
+    0x05, 0x07,                     //   Usage Page (Key Codes),
+    0x19, 0xE0,                     //   Usage Minimum (224),
+    0x29, 0xE7,                     //   Usage Maximum (231),
+    0x15, 0x00,                     //   Logical Minimum (0),
+    0x25, 0x01,                     //   Logical Maximum (1),
+    0x81, 0x02,                     //   Input (Data, Variable, Absolute), ;Modifier byte
+    0x95, 0x01,                     //   Report Count (1),
+    0x75, 0x08,                     //   Report Size (8),
+    0x81, 0x01,                     //   Input (Constant), ;Reserved byte
+    0x95, 0x05,                     //   Report Count (5),
+    0x75, 0x01,                     //   Report Size (1),
+    0x05, 0x08,                     //   Usage Page (LEDs),
+    0x19, 0x01,                     //   Usage Minimum (1),
+    0x29, 0x05,                     //   Usage Maximum (5),
+    0x91, 0x02,                     //   Output (Data, Variable, Absolute), ;LED report
+    0x95, 0x01,                     //   Report Count (1),
+    0x75, 0x03,                     //   Report Size (3),
+    0x91, 0x01,                     //   Output (Constant), ;LED report padding
+    0x95, 0x06,                     //   Report Count (6),
+    0x75, 0x08,                     //   Report Size (8),
+    0x15, 0x00,                     //   Logical Minimum (0),
+    0x26, 0xFF, 0x00,               //   Logical Maximum(255),
+    0x05, 0x07,                     //   Usage Page (Key Codes),
+    0x19, 0x00,                     //   Usage Minimum (0),
+    0x2A, 0xFF, 0x00,               //   Usage Maximum (255),
+    0x81, 0x00,                     //   Input (Data, Array),
+    0x05, 0xFF,                     //   Usage Page (Apple Vendor Top Case),
+    0x09, 0x03,                     //   Usage (Keyboard Fn),
+    0x75, 0x01,                     //   Report Size (1),
+    0x95, 0x01,                     //   Report Count (1),
+    0x25, 0x01,                     //   Logical Maximum (1),
+    0x81, 0x02,                     //   Input (Data, Variable, Absolute), ;Fn key
+    0x95, 0x07,                     //   Report Count (7),
+    0x81, 0x01,                     //   Input (Constant), ;Fn key padding
+    0xC0,                           // End Collection
+];
+
+/// Report for an [`AppleKeyboard`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "msb0", size_bytes = "9")]
+pub struct AppleKeyboardReport {
+    #[packed_field(bytes = "0..=7")]
+    pub keyboard: BootKeyboardReport,
+    /// The Fn/Globe key
+    #[packed_field(bits = "71")]
+    pub fn_key: bool,
+}
+
+impl AppleKeyboardReport {
+    pub fn new<K: IntoIterator<Item = Keyboard>>(keys: K, fn_key: bool) -> Self {
+        Self {
+            keyboard: BootKeyboardReport::new(keys),
+            fn_key,
+        }
+    }
+}
+
+/// Interface implementing a keyboard with an Apple Fn/Globe key, compatible with the HID boot
+/// keyboard specification
+///
+/// macOS only gives the Fn key its native behaviour, such as the Globe key shortcuts, when it
+/// recognises the keyboard, which may require an Apple vendor and product ID.
+///
+/// **Note:** This is a managed interfaces that support HID idle, [`UsbHidClass::tick()`] must be called every 1ms, or
+/// [`UsbHidClass::tick_clock()`] called regularly.
+pub struct AppleKeyboard<'a, B: UsbBus> {
+    keyboard:
+        TypingKeyboard<'a, ManagedIdleInterface<'a, B, AppleKeyboardReport, InBytes16, OutBytes8>>,
+}
+
+impl<'a, B> AppleKeyboard<'a, B>
+where
+    B: UsbBus,
+{
+    pub fn write_report<K: IntoIterator<Item = Keyboard>>(
+        &mut self,
+        keys: K,
+        fn_key: bool,
+    ) -> Result<(), UsbHidError> {
+        self.keyboard
+            .interface
+            .write_report(&AppleKeyboardReport::new(keys, fn_key))
+    }
+
+    /// Type `text` as it would be entered on a keyboard with the host's `layout`, replacing any
+    /// text still being typed
+    ///
+    /// Keys are pressed and released by [`UsbHidClass::tick()`], so reports should not be written
+    /// while [`Self::is_typing`]
+    pub fn type_str(&mut self, layout: Layout, text: &'a str) {
+        self.keyboard.type_str(layout, text);
+    }
+
+    #[must_use]
+    pub fn is_typing(&self) -> bool {
+        self.keyboard.is_typing()
+    }
+
+    /// Stop typing, releasing any pressed key
+    pub fn stop_typing(&mut self) {
+        self.keyboard.stop_typing();
+    }
+
+    pub fn read_report(&mut self) -> usb_device::Result<KeyboardLedsReport> {
+        self.keyboard.read_report()
+    }
+
+    /// The LEDs last set by the host, over either the out endpoint or the control pipe
+    #[must_use]
+    pub fn leds(&self) -> KeyboardLedsReport {
+        self.keyboard.leds()
+    }
+
+    /// Returns the LED state if the host has changed it since this was last called
+    ///
+    /// LED reports are received by [`UsbHidClass::tick()`], so this can be polled instead of
+    /// [`Self::read_report`] by firmware that only mirrors the lock LEDs
+    pub fn leds_changed(&mut self) -> Option<KeyboardLedsReport> {
+        self.keyboard.leds_changed()
+    }
+}
+
+pub struct AppleKeyboardConfig<'a> {
+    interface: ManagedIdleInterfaceConfig<'a, AppleKeyboardReport, InBytes16, OutBytes8>,
+}
+
+impl Default for AppleKeyboardConfig<'_> {
+    fn default() -> Self {
+        Self::new(ManagedIdleInterfaceConfig::new(
+            unwrap!(unwrap!(unwrap!(unwrap!(InterfaceBuilder::new(
+                APPLE_KEYBOARD_REPORT_DESCRIPTOR
+            ))
+            .description("Keyboard")
+            .boot_device(InterfaceProtocol::Keyboard)
+            .idle_default(500.millis()))
+            .in_endpoint(10.millis()))
+            .with_out_endpoint(100.millis()))
+            .build(),
+        ))
+    }
+}
+
+impl<'a> AppleKeyboardConfig<'a> {
+    #[must_use]
+    pub fn new(
+        interface: ManagedIdleInterfaceConfig<'a, AppleKeyboardReport, InBytes16, OutBytes8>,
+    ) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for AppleKeyboardConfig<'a> {
+    type Allocated = AppleKeyboard<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            keyboard: TypingKeyboard::new(self.interface.allocate(usb_alloc)),
+        }
+    }
+}
+
+impl<B: UsbBus> ReportWriter<AppleKeyboardReport> for AppleKeyboard<'_, B> {
+    fn write_report(&mut self, report: &AppleKeyboardReport) -> Result<(), UsbHidError> {
+        self.keyboard.interface.write_report(report)
+    }
+}
+
+impl<'a, B> DeviceClass<'a> for AppleKeyboard<'a, B>
+where
+    B: UsbBus,
+{
+    type I = Interface<'a, B, InBytes16, OutBytes8, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        self.keyboard.interface.interface()
+    }
+
+    fn reset(&mut self) {
+        self.keyboard.reset();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        self.keyboard.tick()
+    }
+}
+
+/// How [`KeyboardState`] reports more than 6 simultaneously pressed keys
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum Rollover {
+    /// Report the first 6 non-modifier keys pressed. Further keys are reported as earlier keys
+    /// are released. Suitable for [`BootKeyboard`]
+    #[default]
+    SixKey,
+    /// Report all pressed keys. Suitable for [`NKROBootKeyboard`]
+    NKey,
+}
+
+/// Tracks the set of currently pressed keys and produces keyboard reports from it
+///
+/// ```
+/// # use usbd_human_interface_device::device::keyboard::{KeyboardState, Rollover};
+/// # use usbd_human_interface_device::page::Keyboard;
+/// let mut state = KeyboardState::new(Rollover::SixKey);
+/// state.press(Keyboard::LeftShift);
+/// state.press(Keyboard::A);
+/// assert_eq!(state.boot_report().keys[0], Keyboard::A);
+///
+/// state.release(Keyboard::A);
+/// assert!(!state.is_pressed(Keyboard::A));
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeyboardState {
+    rollover: Rollover,
+    pressed: [u8; 32],
+    //non-modifier keys reported under `Rollover::SixKey`, in press order
+    reported: heapless::Vec<Keyboard, 6>,
+}
+
+impl Default for KeyboardState {
+    fn default() -> Self {
+        Self::new(Rollover::default())
+    }
+}
+
+impl KeyboardState {
+    #[must_use]
+    pub const fn new(rollover: Rollover) -> Self {
+        Self {
+            rollover,
+            pressed: [0; 32],
+            reported: heapless::Vec::new(),
+        }
+    }
+
+    #[must_use]
+    pub fn rollover(&self) -> Rollover {
+        self.rollover
+    }
+
+    #[must_use]
+    pub fn is_pressed(&self, key: Keyboard) -> bool {
+        let k = u8::from(key);
+        self.pressed[usize::from(k / 8)] & (1 << (k % 8)) != 0
+    }
+
+    pub fn press(&mut self, key: Keyboard) {
+        if key == Keyboard::NoEventIndicated || self.is_pressed(key) {
+            return;
+        }
+        let k = u8::from(key);
+        self.pressed[usize::from(k / 8)] |= 1 << (k % 8);
+
+        if !Self::is_modifier(key) {
+            //Silently truncated once 6 keys are reported
+            self.reported.push(key).ok();
+        }
+    }
+
+    pub fn release(&mut self, key: Keyboard) {
+        if !self.is_pressed(key) {
+            return;
+        }
+        let k = u8::from(key);
+        self.pressed[usize::from(k / 8)] &= !(1 << (k % 8));
+
+        if let Some(i) = self.reported.iter().position(|&r| r == key) {
+            self.reported.remove(i);
+            //Report a held key that was previously truncated
+            let next = self
+                .held_keys()
+                .find(|k| !Self::is_modifier(*k) && !self.reported.contains(k));
+            if let Some(next) = next {
+                self.reported.push(next).ok();
+            }
+        }
+    }
+
+    pub fn release_all(&mut self) {
+        self.pressed = [0; 32];
+        self.reported.clear();
+    }
+
+    /// Keys to be reported according to the rollover policy
+    pub fn keys(&self) -> impl Iterator<Item = Keyboard> + '_ {
+        let modifiers = self.held_keys().filter(|&k| Self::is_modifier(k));
+        let (six_key, n_key) = match self.rollover {
+            Rollover::SixKey => (Some(self.reported.iter().copied()), None),
+            Rollover::NKey => (
+                None,
+                Some(self.held_keys().filter(|&k| !Self::is_modifier(k))),
+            ),
+        };
+        modifiers
+            .chain(six_key.into_iter().flatten())
+            .chain(n_key.into_iter().flatten())
+    }
+
+    #[must_use]
+    pub fn boot_report(&self) -> BootKeyboardReport {
+        BootKeyboardReport::new(self.keys())
+    }
+
+    #[must_use]
+    pub fn nkro_report(&self) -> NKROBootKeyboardReport {
+        NKROBootKeyboardReport::new(self.keys())
+    }
+
+    fn held_keys(&self) -> impl Iterator<Item = Keyboard> + '_ {
+        (0..=u8::MAX)
+            .filter(|k| self.pressed[usize::from(k / 8)] & (1 << (k % 8)) != 0)
+            .map(Keyboard::from)
+    }
+
+    fn is_modifier(key: Keyboard) -> bool {
+        (u8::from(Keyboard::LeftControl)..=u8::from(Keyboard::RightGUI)).contains(&u8::from(key))
+    }
+
+    /// Press the modifier and key of `stroke`
+    pub fn press_stroke(&mut self, stroke: KeyStroke) {
+        for k in stroke.keys() {
+            self.press(k);
+        }
+    }
+
+    /// Release the modifier and key of `stroke`
+    pub fn release_stroke(&mut self, stroke: KeyStroke) {
+        for k in stroke.keys() {
+            self.release(k);
+        }
+    }
+}
+
+/// Source of the keys currently held, such as a scanned key matrix
+///
+/// Used by [`KeyStateReports`] to answer `GET_REPORT` requests with the current state of the
+/// keyboard, rather than the last report written, as required by some KVMs and the HID
+/// compliance tests
+pub trait KeyStateProvider {
+    /// Report of the keys currently held, or `None` if the state isn't available, in which case
+    /// the last report written is sent
+    fn key_state(&self) -> Option<NKROBootKeyboardReport>;
+}
+
+impl KeyStateProvider for core::cell::RefCell<KeyboardState> {
+    fn key_state(&self) -> Option<NKROBootKeyboardReport> {
+        self.try_borrow().ok().map(|state| state.nkro_report())
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum KeyStateFormat {
+    Boot,
+    Nkro,
+    DualMode,
+}
+
+/// [`ReportHandler`] answering `GET_REPORT(Input)` requests with the keys currently held
+///
+/// Set as the report handler of the keyboard's interface:
+///
+/// ```
+/// # use core::cell::RefCell;
+/// # use usbd_human_interface_device::device::keyboard::*;
+/// # use usbd_human_interface_device::usb_class::prelude::*;
+/// # use fugit::ExtU32;
+/// let state = RefCell::new(KeyboardState::default());
+/// let reports = KeyStateReports::boot(&state);
+/// let config = BootKeyboardConfig::new(ManagedIdleInterfaceConfig::new(
+///     InterfaceBuilder::new(BOOT_KEYBOARD_REPORT_DESCRIPTOR)
+///         .unwrap()
+///         .boot_device(InterfaceProtocol::Keyboard)
+///         .report_handler(&reports)
+///         .in_endpoint(10.millis())
+///         .unwrap()
+///         .without_out_endpoint()
+///         .build(),
+/// ));
+/// ```
+pub struct KeyStateReports<'a> {
+    provider: &'a dyn KeyStateProvider,
+    format: KeyStateFormat,
+}
+
+impl<'a> KeyStateReports<'a> {
+    /// Boot keyboard reports, for a [`BootKeyboard`]
+    #[must_use]
+    pub fn boot(provider: &'a dyn KeyStateProvider) -> Self {
+        Self {
+            provider,
+            format: KeyStateFormat::Boot,
+        }
+    }
+
+    /// [`NKROBootKeyboardReport`]s, for a [`NKROBootKeyboard`]
+    ///
+    /// Hosts using the boot protocol only read the first 8 bytes, the boot keyboard report
+    #[must_use]
+    pub fn nkro(provider: &'a dyn KeyStateProvider) -> Self {
+        Self {
+            provider,
+            format: KeyStateFormat::Nkro,
+        }
+    }
+
+    /// The 6KRO and NKRO reports of a [`DualModeKeyboard`], selected by the requested report
+    /// ID, or the boot keyboard report if the report ID is 0
+    #[must_use]
+    pub fn dual_mode(provider: &'a dyn KeyStateProvider) -> Self {
+        Self {
+            provider,
+            format: KeyStateFormat::DualMode,
+        }
+    }
+}
+
+impl ReportHandler for KeyStateReports<'_> {
+    fn get_report(&self, report_type: ReportType, report_id: u8, data: &mut [u8]) -> Option<usize> {
+        if report_type != ReportType::Input {
+            return None;
+        }
+        let format = match (self.format, report_id) {
+            (KeyStateFormat::Boot, _) | (KeyStateFormat::DualMode, 0) => DualModeReport::Boot,
+            (KeyStateFormat::DualMode, SIX_KEY_REPORT_ID) => DualModeReport::SixKey,
+            (KeyStateFormat::DualMode, NKRO_REPORT_ID) => DualModeReport::NKey,
+            (KeyStateFormat::DualMode, _) => return None,
+            (KeyStateFormat::Nkro, _) => {
+                let report = self.provider.key_state()?;
+                let packed = report.pack().ok()?;
+                data.get_mut(..packed.len())?.copy_from_slice(&packed);
+                return Some(packed.len());
+            }
+        };
+        let report = self.provider.key_state()?;
+        format.pack(&report, data).ok()
+    }
+}
+
+/// Text being typed by a keyboard, one key press or release per tick
+struct Typing<'a> {
+    strokes: TypeStr<'a>,
+    //stroke that failed to be written
+    pending: Option<KeyStroke>,
+    pressed: bool,
+}
+
+impl<'a> Typing<'a> {
+    fn new(strokes: TypeStr<'a>) -> Self {
+        Self {
+            strokes,
+            pending: None,
+            pressed: false,
+        }
+    }
+
+    fn stop(&mut self) {
+        self.strokes = Layout::default().type_str("");
+        self.pending = None;
+    }
+
+    /// Write the next key press, or release with `None`, clearing `typing` once complete
+    fn tick<F>(typing: &mut Option<Self>, mut write: F) -> Result<(), UsbHidError>
+    where
+        F: FnMut(Option<KeyStroke>) -> Result<(), UsbHidError>,
+    {
+        let Some(t) = typing else {
+            return Ok(());
+        };
+
+        let stroke = if t.pressed {
+            None
+        } else if let Some(stroke) = t.pending.take().or_else(|| t.strokes.next()) {
+            Some(stroke)
+        } else {
+            *typing = None;
+            return Ok(());
+        };
+
+        match write(stroke) {
+            Ok(()) | Err(UsbHidError::Duplicate) => {
+                t.pressed = stroke.is_some();
+                Ok(())
+            }
+            Err(UsbHidError::WouldBlock | UsbHidError::QueueFull) => {
+                t.pending = stroke;
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Interface of a keyboard, typed to and read from by [`TypingKeyboard`]
+trait KeyboardInterface<'a>: DeviceClass<'a> {
+    /// Write a report of `keys` being pressed
+    fn write_keys<K: IntoIterator<Item = Keyboard>>(&mut self, keys: K) -> Result<(), UsbHidError>;
+
+    /// Read the LED output report into `data`
+    fn read_leds(&mut self, data: &mut [u8]) -> usb_device::Result<usize>;
+}
+
+/// Report written by a [`ManagedIdleInterface`] keyboard while typing
+trait KeysReport {
+    fn from_keys<K: IntoIterator<Item = Keyboard>>(keys: K) -> Self;
+}
+
+impl KeysReport for BootKeyboardReport {
+    fn from_keys<K: IntoIterator<Item = Keyboard>>(keys: K) -> Self {
+        Self::new(keys)
+    }
+}
+
+impl KeysReport for NKROBootKeyboardReport {
+    fn from_keys<K: IntoIterator<Item = Keyboard>>(keys: K) -> Self {
+        Self::new(keys)
+    }
+}
+
+impl KeysReport for AppleKeyboardReport {
+    fn from_keys<K: IntoIterator<Item = Keyboard>>(keys: K) -> Self {
+        Self::new(keys, false)
+    }
+}
+
+impl<'a, B, Report, I, O, const LEN: usize> KeyboardInterface<'a>
+    for ManagedIdleInterface<'a, B, Report, I, O>
+where
+    Report: KeysReport + Copy + Eq + PackedStruct<ByteArray = [u8; LEN]>,
+    B: UsbBus,
+    I: InSize,
+    O: OutSize,
+{
+    fn write_keys<K: IntoIterator<Item = Keyboard>>(&mut self, keys: K) -> Result<(), UsbHidError> {
+        self.write_report(&Report::from_keys(keys))
+    }
+
+    fn read_leds(&mut self, data: &mut [u8]) -> usb_device::Result<usize> {
+        self.read_report(data)
+    }
+}
+
+/// Typing and LED state shared by the keyboards, wrapping the keyboard's interface
+struct TypingKeyboard<'a, K> {
+    interface: K,
+    typing: Option<Typing<'a>>,
+    leds: LedState,
+}
+
+impl<'a, K: KeyboardInterface<'a>> TypingKeyboard<'a, K> {
+    fn new(interface: K) -> Self {
+        Self {
+            interface,
+            typing: None,
+            leds: LedState::default(),
+        }
+    }
+
+    fn type_str(&mut self, layout: Layout, text: &'a str) {
+        self.typing = Some(Typing::new(layout.type_str(text)));
+    }
+
+    fn is_typing(&self) -> bool {
+        self.typing.is_some()
+    }
+
+    fn stop_typing(&mut self) {
+        if let Some(typing) = &mut self.typing {
+            typing.stop();
+        }
+    }
+
+    fn read_report(&mut self) -> usb_device::Result<KeyboardLedsReport> {
+        let interface = &mut self.interface;
+        self.leds.read_report(|data| interface.read_leds(data))
+    }
+
+    fn leds(&self) -> KeyboardLedsReport {
+        self.leds.current
+    }
+
+    fn leds_changed(&mut self) -> Option<KeyboardLedsReport> {
+        core::mem::take(&mut self.leds.changed).then_some(self.leds.current)
+    }
+
+    fn reset(&mut self) {
+        self.interface.reset();
+        self.typing = None;
+        self.leds.reset();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        let interface = &mut self.interface;
+        Typing::tick(&mut self.typing, |stroke| {
+            interface.write_keys(stroke.iter().flat_map(KeyStroke::keys))
+        })?;
+        let interface = &mut self.interface;
+        self.leds.receive(|data| interface.read_leds(data));
+        self.interface.tick()
+    }
+}
+
+/// HID Keyboard report descriptor implementing an NKRO keyboard as a bitmap.
+///
+/// N.B. This is not compatible with the HID boot specification
+//18 bytes - derived from https://learn.adafruit.com/custom-hid-devices-in-circuitpython/n-key-rollover-nkro-hid-device
+//First byte modifiers, 17 byte key bit array
+#[rustfmt::skip]
+pub const NKRO_COMPACT_KEYBOARD_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,                     // Usage Page (Generic Desktop),
+    0x09, 0x06,                     // Usage (Keyboard),
+    0xA1, 0x01,                     // Collection (Application),
+    // bitmap of modifiers
+    0x75, 0x01,                     //   Report Size (1),
+    0x95, 0x08,                     //   Report Count (8),
+    0x05, 0x07,                     //   Usage Page (Key Codes),
+    0x19, 0xE0,                     //   Usage Minimum (224),
+    0x29, 0xE7,                     //   Usage Maximum (231),
+    0x15, 0x00,                     //   Logical Minimum (0),
+    0x25, 0x01,                     //   Logical Maximum (1),
+    0x81, 0x02,                     //   Input (Data, Variable, Absolute), ;Modifier byte
+    // LED output report
+    0x95, 0x05,                     //   Report Count (5),
+    0x75, 0x01,                     //   Report Size (1),
+    0x05, 0x08,                     //   Usage Page (LEDs),
+    0x19, 0x01,                     //   Usage Minimum (1),
+    0x29, 0x05,                     //   Usage Maximum (5),
+    0x91, 0x02,                     //   Output (Data, Variable, Absolute),
+    0x95, 0x01,                     //   Report Count (1),
+    0x75, 0x03,                     //   Report Size (3),
+    0x91, 0x03,                     //   Output (Constant),
+    // bitmap of keys
+    0x95, 0x88,                     //   Report Count () - (REPORT_BYTES-1)*8
+    0x75, 0x01,                     //   Report Size (1),
+    0x15, 0x00,                     //   Logical Minimum (0),
+    0x25, 0x01,                     //   Logical Maximum(1),
+    0x05, 0x07,                     //   Usage Page (Key Codes),
+    0x19, 0x00,                     //   Usage Minimum (0),
+    0x29, 0x87,                     //   Usage Maximum (), - (REPORT_BYTES-1)*8-1
+    0x81, 0x02,                     //   Input (Data, Variable, Absolute),
+    0xc0                            // End Collection
+];
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+    #![allow(clippy::expect_used)]
+
+    use packed_struct::prelude::*;
+
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+    use crate::device::keyboard::{
+        AppleKeyboardReport, BootKeyboardReport, KeyboardLedsReport, KeyboardState,
+        NKROBootKeyboardReport, Rollover, APPLE_KEYBOARD_REPORT_DESCRIPTOR,
+        DUAL_MODE_KEYBOARD_REPORT_DESCRIPTOR,
+    };
+    use crate::page::layout::{KeyStroke, Layout, Modifier};
+    use crate::page::Keyboard;
+    use heapless::Vec;
+
+    #[test]
+    fn leds_num_lock() {
+        assert_eq!(
+            KeyboardLedsReport::unpack(&[1]),
+            Ok(KeyboardLedsReport {
+                num_lock: true,
+                caps_lock: false,
+                scroll_lock: false,
+                compose: false,
+                kana: false,
+            })
+        );
+    }
+
+    #[test]
+    fn leds_caps_lock() {
+        assert_eq!(
+            KeyboardLedsReport::unpack(&[2]),
+            Ok(KeyboardLedsReport {
+                num_lock: false,
+                caps_lock: true,
+                scroll_lock: false,
+                compose: false,
+                kana: false,
+            })
+        );
+
+        assert_eq!(
+            KeyboardLedsReport {
+                num_lock: false,
+                caps_lock: true,
+                scroll_lock: false,
+                compose: false,
+                kana: false,
+            }
+            .pack(),
+            Ok([2])
+        );
+    }
+
+    #[test]
+    fn boot_keyboard_report_mixed() {
+        let bytes = BootKeyboardReport::new([
+            Keyboard::LeftAlt,
+            Keyboard::A,
+            Keyboard::B,
+            Keyboard::C,
+            Keyboard::RightGUI,
+        ])
+        .pack()
+        .unwrap();
+
+        let key_mod: u8 = 0x1_u8 << (u8::from(Keyboard::LeftAlt) - u8::from(Keyboard::LeftControl))
+            | 0x1_u8 << (u8::from(Keyboard::RightGUI) - u8::from(Keyboard::LeftControl));
+
+        assert_eq!(
+            bytes,
+            [
+                key_mod,
+                0,
+                Keyboard::A.into(),
+                Keyboard::B.into(),
+                Keyboard::C.into(),
+                0,
+                0,
+                0
+            ]
+        );
+    }
+
+    #[test]
+    fn dual_mode_reports_match_descriptor() {
+        let descriptor = DUAL_MODE_KEYBOARD_REPORT_DESCRIPTOR;
+        assert_eq!(report_length(descriptor, ReportType::Input, 1), Some(9));
+        assert_eq!(report_length(descriptor, ReportType::Input, 2), Some(19));
+        assert_eq!(report_length(descriptor, ReportType::Output, 1), Some(2));
+    }
+
+    #[test]
+    fn apple_keyboard_report_fn_key() {
+        assert_eq!(
+            report_length(APPLE_KEYBOARD_REPORT_DESCRIPTOR, ReportType::Input, 0),
+            Some(9)
+        );
+        let bytes = AppleKeyboardReport::new([Keyboard::LeftShift, Keyboard::A], true)
+            .pack()
+            .unwrap();
+        assert_eq!(bytes, [0x02, 0, Keyboard::A.into(), 0, 0, 0, 0, 0, 0x01]);
+    }
+
+    #[test]
+    fn boot_keyboard_report_keys() {
+        let bytes = BootKeyboardReport::new([
+            Keyboard::A,
+            Keyboard::B,
+            Keyboard::C,
+            Keyboard::D,
+            Keyboard::E,
+            Keyboard::F,
+        ])
+        .pack()
+        .unwrap();
+
+        assert_eq!(
+            bytes,
+            [
+                0,
+                0,
+                Keyboard::A.into(),
+                Keyboard::B.into(),
+                Keyboard::C.into(),
+                Keyboard::D.into(),
+                Keyboard::E.into(),
+                Keyboard::F.into()
+            ]
+        );
+    }
+
+    #[test]
+    fn boot_keyboard_report_rollover() {
+        let bytes = BootKeyboardReport::new([
+            Keyboard::LeftAlt,
+            Keyboard::A,
+            Keyboard::B,
+            Keyboard::C,
+            Keyboard::D,
+            Keyboard::E,
+            Keyboard::F,
+            Keyboard::G,
+            Keyboard::RightGUI,
+        ])
+        .pack()
+        .unwrap();
+
+        let key_mod: u8 = 0x1_u8 << (u8::from(Keyboard::LeftAlt) - u8::from(Keyboard::LeftControl))
+            | 0x1_u8 << (u8::from(Keyboard::RightGUI) - u8::from(Keyboard::LeftControl));
+        assert_eq!(
+            bytes,
+            [
+                key_mod,
+                0,
+                Keyboard::ErrorRollOver.into(),
+                Keyboard::ErrorRollOver.into(),
+                Keyboard::ErrorRollOver.into(),
+                Keyboard::ErrorRollOver.into(),
+                Keyboard::ErrorRollOver.into(),
+                Keyboard::ErrorRollOver.into(),
+            ]
+        );
+    }
+
+    #[test]
+    fn keyboard_state_six_key_truncates() {
+        let mut state = KeyboardState::new(Rollover::SixKey);
+        for k in [
+            Keyboard::G,
+            Keyboard::A,
+            Keyboard::B,
+            Keyboard::C,
+            Keyboard::D,
+            Keyboard::E,
+            Keyboard::F,
+            Keyboard::RightShift,
+        ] {
+            state.press(k);
+        }
+
+        let report = state.boot_report();
+        assert!(report.right_shift);
+        assert_eq!(
+            report.keys,
+            [
+                Keyboard::G,
+                Keyboard::A,
+                Keyboard::B,
+                Keyboard::C,
+                Keyboard::D,
+                Keyboard::E
+            ]
+        );
+
+        // the truncated key is reported once there is space
+        state.release(Keyboard::A);
+        assert_eq!(
+            state.boot_report().keys,
+            [
+                Keyboard::G,
+                Keyboard::B,
+                Keyboard::C,
+                Keyboard::D,
+                Keyboard::E,
+                Keyboard::F
+            ]
+        );
+
+        state.release_all();
+        assert_eq!(state.boot_report(), BootKeyboardReport::default());
+    }
+
+    #[test]
+    fn keyboard_state_n_key_reports_all() {
+        let mut state = KeyboardState::new(Rollover::NKey);
+        for k in [
+            Keyboard::A,
+            Keyboard::B,
+            Keyboard::C,
+            Keyboard::D,
+            Keyboard::E,
+            Keyboard::F,
+            Keyboard::G,
+        ] {
+            state.press(k);
+        }
+        state.press(Keyboard::A);
+        state.release(Keyboard::C);
+
+        assert_eq!(
+            state.nkro_report(),
+            NKROBootKeyboardReport::new([
+                Keyboard::A,
+                Keyboard::B,
+                Keyboard::D,
+                Keyboard::E,
+                Keyboard::F,
+                Keyboard::G,
+            ])
+        );
+    }
+
+    #[test]
+    fn layout_dead_keys_and_shifted_letters() {
+        let strokes: Vec<KeyStroke, 8> = Layout::Fr.type_str("A~1").collect();
+        assert_eq!(
+            strokes,
+            [
+                KeyStroke::new(Modifier::Shift, Keyboard::Q),
+                KeyStroke::new(Modifier::AltGr, Keyboard::Keyboard2),
+                KeyStroke::new(Modifier::None, Keyboard::Space),
+                KeyStroke::new(Modifier::Shift, Keyboard::Keyboard1),
+            ]
+        );
+
+        // Unmapped characters are skipped
+        let strokes: Vec<KeyStroke, 8> = Layout::Uk.type_str("£ß@").collect();
+        assert_eq!(
+            strokes,
+            [
+                KeyStroke::new(Modifier::Shift, Keyboard::Keyboard3),
+                KeyStroke::new(Modifier::Shift, Keyboard::Apostrophe),
             ]
         );
     }
@@ -0,0 +1,440 @@
+//!Keyboard and pointer preset for KVM-over-IP and test automation firmware
+//!
+//! A [`KvmBridge`] is a composite of a [`BootKeyboard`], a relative [`WheelMouse`], an
+//! [`AbsoluteWheelMouse`] and a [`ConsumerControl`], driven through a single set of input
+//! methods rather than by writing each device's reports. The keyboard and relative mouse support
+//! the boot protocol, so input also reaches BIOS setup screens.
+//!
+//! ```
+//! # use usbd_human_interface_device::device::kvm::KvmBridgeConfig;
+//! # use usbd_human_interface_device::page::{Consumer, Keyboard};
+//! # use usbd_human_interface_device::interface::UsbAllocatable;
+//! # use usbd_human_interface_device::prelude::*;
+//! # use usbd_human_interface_device::test_util::{TestUsbBus, UsbTestManager};
+//! # use usb_device::class_prelude::UsbBusAllocator;
+//! # use usb_device::prelude::*;
+//! # let manager = UsbTestManager::default();
+//! # let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+//! let mut kvm = KvmBridgeConfig::default().allocate(&usb_alloc);
+//! let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001)).build();
+//!
+//! kvm.press_key(Keyboard::LeftControl);
+//! kvm.send_key(Keyboard::C).unwrap();
+//! kvm.move_abs(16384, 16384);
+//! kvm.click(0x1).unwrap();
+//! kvm.media(Consumer::Mute).unwrap();
+//!
+//! // Every 1ms
+//! kvm.tick().unwrap();
+//! usb_dev.poll(&mut [&mut kvm]);
+//! ```
+use crate::device::consumer::{ConsumerControl, ConsumerControlConfig, MultipleConsumerReport};
+use crate::device::keyboard::{BootKeyboard, BootKeyboardConfig, KeyboardState, Rollover};
+use crate::device::mouse::{
+    AbsoluteWheelMouse, AbsoluteWheelMouseConfig, AbsoluteWheelMouseReport, MouseAccumulator,
+    WheelMouse, WheelMouseConfig,
+};
+use crate::page::{Consumer, Keyboard};
+use crate::usb_class::prelude::*;
+use frunk::HList;
+#[allow(clippy::wildcard_imports)]
+use usb_device::class_prelude::*;
+use usb_device::Result;
+
+/// Devices of a [`KvmBridge`], the keyboard being interface 0
+pub type KvmBridgeDevices<'a, B> = HList!(
+    BootKeyboard<'a, B>,
+    WheelMouse<'a, B>,
+    AbsoluteWheelMouse<'a, B>,
+    ConsumerControl<'a, B>,
+);
+
+/// The pointer that reports mouse buttons
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Pointer {
+    Relative,
+    Absolute,
+}
+
+/// Input pressed by a tap, released by the report following the one that pressed it
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Tap<T> {
+    input: T,
+    pressed: bool,
+}
+
+impl<T: Copy> Tap<T> {
+    /// The tapped input, while it should be reported as pressed
+    fn pressed(tap: Option<Self>) -> Option<T> {
+        tap.filter(|t| !t.pressed).map(|t| t.input)
+    }
+
+    /// Advance a tap once a report has been written, returns true if another report is needed
+    /// to release it
+    fn written(tap: &mut Option<Self>) -> bool {
+        match tap {
+            Some(t) if !t.pressed => {
+                t.pressed = true;
+                true
+            }
+            _ => {
+                *tap = None;
+                false
+            }
+        }
+    }
+}
+
+/// Composite keyboard, relative and absolute pointer and consumer control device, see the
+/// [module documentation](self)
+///
+/// Input methods only update the state to be reported, reports are written by [`Self::tick`],
+/// which must be called every 1ms. Taps, such as [`Self::send_key`], are pressed by one report
+/// and released by the next, and return [`UsbHidError::WouldBlock`] while a previous tap of the
+/// same kind is still being reported.
+pub struct KvmBridge<'a, B: UsbBus> {
+    class: UsbHidClass<'a, B, KvmBridgeDevices<'a, B>>,
+    keys: KeyboardState,
+    key_tap: Option<Tap<Keyboard>>,
+    keyboard_changed: bool,
+    buttons: u8,
+    click: Option<Tap<u8>>,
+    pointer: Pointer,
+    movement: MouseAccumulator,
+    relative_changed: bool,
+    position: (u16, u16),
+    absolute_changed: bool,
+    media: Option<Tap<Consumer>>,
+}
+
+impl<'a, B: UsbBus> KvmBridge<'a, B> {
+    /// The underlying class, e.g. to borrow one of the devices or take interface events
+    pub fn class(&mut self) -> &mut UsbHidClass<'a, B, KvmBridgeDevices<'a, B>> {
+        &mut self.class
+    }
+
+    /// Hold `key` down until [`Self::release_key`], e.g. a modifier for following taps
+    pub fn press_key(&mut self, key: Keyboard) {
+        self.keys.press(key);
+        self.keyboard_changed = true;
+    }
+
+    pub fn release_key(&mut self, key: Keyboard) {
+        self.keys.release(key);
+        self.keyboard_changed = true;
+    }
+
+    pub fn release_all_keys(&mut self) {
+        self.keys.release_all();
+        self.keyboard_changed = true;
+    }
+
+    /// Tap `key`, along with any keys held by [`Self::press_key`]
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::WouldBlock`] if the previous tap hasn't been released yet
+    pub fn send_key(&mut self, key: Keyboard) -> core::result::Result<(), UsbHidError> {
+        Self::tap(&mut self.key_tap, key)?;
+        self.keyboard_changed = true;
+        Ok(())
+    }
+
+    /// Move the relative pointer, movement beyond the range of a report is carried over into
+    /// following reports. Mouse buttons are then reported by the relative pointer
+    pub fn move_rel(&mut self, x: i32, y: i32) {
+        self.movement.add(x, y);
+        self.select_pointer(Pointer::Relative);
+    }
+
+    /// Scroll the relative pointer's wheels
+    pub fn scroll(&mut self, vertical: i32, horizontal: i32) {
+        self.movement.add_wheel(vertical, horizontal);
+    }
+
+    /// Move the absolute pointer, from 0 to 32767 across the screen. Mouse buttons are then
+    /// reported by the absolute pointer
+    pub fn move_abs(&mut self, x: u16, y: u16) {
+        self.position = (x.min(0x7FFF), y.min(0x7FFF));
+        self.absolute_changed = true;
+        self.select_pointer(Pointer::Absolute);
+    }
+
+    /// Hold `buttons` down, bit 0 being the primary button, e.g. for dragging
+    pub fn set_buttons(&mut self, buttons: u8) {
+        self.buttons = buttons;
+        self.pointer_changed();
+    }
+
+    /// Tap `buttons`, on the pointer last moved
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::WouldBlock`] if the previous click hasn't been released yet
+    pub fn click(&mut self, buttons: u8) -> core::result::Result<(), UsbHidError> {
+        Self::tap(&mut self.click, buttons)?;
+        self.pointer_changed();
+        Ok(())
+    }
+
+    /// Tap the consumer control `code`, such as [`Consumer::PlayPause`]
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::WouldBlock`] if the previous code hasn't been released yet
+    pub fn media(&mut self, code: Consumer) -> core::result::Result<(), UsbHidError> {
+        Self::tap(&mut self.media, code)
+    }
+
+    /// Write reports for input changed since the last tick, and tick the devices. Call every 1ms
+    ///
+    /// # Errors
+    ///
+    /// Errors other than [`UsbHidError::WouldBlock`] writing reports, busy devices are written by
+    /// a later tick
+    pub fn tick(&mut self) -> core::result::Result<(), UsbHidError> {
+        self.write_keyboard()?;
+        self.write_relative()?;
+        self.write_absolute()?;
+        self.write_media()?;
+        self.class.tick()
+    }
+
+    fn tap<T>(tap: &mut Option<Tap<T>>, input: T) -> core::result::Result<(), UsbHidError> {
+        if tap.is_some() {
+            return Err(UsbHidError::WouldBlock);
+        }
+        *tap = Some(Tap {
+            input,
+            pressed: false,
+        });
+        Ok(())
+    }
+
+    fn select_pointer(&mut self, pointer: Pointer) {
+        if pointer != self.pointer {
+            //Release the buttons held on the previous pointer
+            self.pointer = pointer;
+            self.relative_changed = true;
+            self.absolute_changed = true;
+        }
+    }
+
+    fn pointer_changed(&mut self) {
+        match self.pointer {
+            Pointer::Relative => self.relative_changed = true,
+            Pointer::Absolute => self.absolute_changed = true,
+        }
+    }
+
+    /// Buttons to report on `pointer`
+    fn buttons_of(&self, pointer: Pointer) -> u8 {
+        if pointer == self.pointer {
+            self.buttons | Tap::pressed(self.click).unwrap_or_default()
+        } else {
+            0
+        }
+    }
+
+    /// Advance the click once the pointer reporting it has been written
+    fn pointer_written(&mut self, pointer: Pointer) {
+        if pointer == self.pointer && self.click.is_some() && Tap::written(&mut self.click) {
+            self.pointer_changed();
+        }
+    }
+
+    fn write_keyboard(&mut self) -> core::result::Result<(), UsbHidError> {
+        if !self.keyboard_changed {
+            return Ok(());
+        }
+        let tapped = Tap::pressed(self.key_tap);
+        let keys = self.keys.keys().chain(tapped);
+        match self
+            .class
+            .device::<BootKeyboard<'a, B>, _>()
+            .write_report(keys)
+        {
+            Ok(()) | Err(UsbHidError::Duplicate) => {
+                self.keyboard_changed = self.key_tap.is_some() && Tap::written(&mut self.key_tap);
+                Ok(())
+            }
+            Err(UsbHidError::WouldBlock) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write_relative(&mut self) -> core::result::Result<(), UsbHidError> {
+        if !self.relative_changed && self.movement.is_empty() {
+            return Ok(());
+        }
+        //Only take the movement once the endpoint is free, so none is lost
+        let mouse = self.class.device::<WheelMouse<'a, B>, _>();
+        if mouse.interface().is_write_pending() {
+            return Ok(());
+        }
+        let report = self
+            .movement
+            .wheel_mouse_report(self.buttons_of(Pointer::Relative));
+        match self
+            .class
+            .device::<WheelMouse<'a, B>, _>()
+            .write_report(&report)
+        {
+            Ok(()) => {
+                self.relative_changed = false;
+                self.pointer_written(Pointer::Relative);
+                Ok(())
+            }
+            Err(UsbHidError::WouldBlock) => {
+                //Put the movement back for the next tick
+                self.movement.add(report.x.into(), report.y.into());
+                self.movement
+                    .add_wheel(report.vertical_wheel.into(), report.horizontal_wheel.into());
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write_absolute(&mut self) -> core::result::Result<(), UsbHidError> {
+        if !self.absolute_changed {
+            return Ok(());
+        }
+        let report = AbsoluteWheelMouseReport {
+            buttons: self.buttons_of(Pointer::Absolute),
+            x: self.position.0,
+            y: self.position.1,
+            wheel: 0,
+        };
+        match self
+            .class
+            .device::<AbsoluteWheelMouse<'a, B>, _>()
+            .write_report(&report)
+        {
+            Ok(()) => {
+                self.absolute_changed = false;
+                self.pointer_written(Pointer::Absolute);
+                Ok(())
+            }
+            Err(UsbHidError::WouldBlock) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write_media(&mut self) -> core::result::Result<(), UsbHidError> {
+        if self.media.is_none() {
+            return Ok(());
+        }
+        let report = MultipleConsumerReport::new(Tap::pressed(self.media));
+        match self
+            .class
+            .device::<ConsumerControl<'a, B>, _>()
+            .write_report(&report)
+        {
+            Ok(_) => {
+                Tap::written(&mut self.media);
+                Ok(())
+            }
+            Err(UsbHidError::WouldBlock) => Ok(()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn clear_input(&mut self) {
+        self.keys.release_all();
+        self.key_tap = None;
+        self.keyboard_changed = false;
+        self.buttons = 0;
+        self.click = None;
+        self.movement.clear();
+        self.relative_changed = false;
+        self.absolute_changed = false;
+        self.media = None;
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbClass<B> for KvmBridge<'a, B> {
+    fn get_configuration_descriptors(&self, writer: &mut DescriptorWriter) -> Result<()> {
+        self.class.get_configuration_descriptors(writer)
+    }
+
+    fn get_string(&self, index: StringIndex, lang_id: LangID) -> Option<&str> {
+        self.class.get_string(index, lang_id)
+    }
+
+    fn reset(&mut self) {
+        self.clear_input();
+        self.class.reset();
+    }
+
+    fn get_alt_setting(&mut self, interface: InterfaceNumber) -> Option<u8> {
+        self.class.get_alt_setting(interface)
+    }
+
+    fn set_alt_setting(&mut self, interface: InterfaceNumber, alternative: u8) -> bool {
+        self.class.set_alt_setting(interface, alternative)
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        self.class.endpoint_in_complete(addr);
+    }
+
+    fn control_out(&mut self, transfer: ControlOut<B>) {
+        self.class.control_out(transfer);
+    }
+
+    fn control_in(&mut self, transfer: ControlIn<B>) {
+        self.class.control_in(transfer);
+    }
+}
+
+#[derive(Default)]
+pub struct KvmBridgeConfig<'a> {
+    keyboard: BootKeyboardConfig<'a>,
+    mouse: WheelMouseConfig<'a>,
+    absolute: AbsoluteWheelMouseConfig<'a>,
+    consumer: ConsumerControlConfig<'a>,
+}
+
+impl<'a> KvmBridgeConfig<'a> {
+    #[must_use]
+    pub fn new(
+        keyboard: BootKeyboardConfig<'a>,
+        mouse: WheelMouseConfig<'a>,
+        absolute: AbsoluteWheelMouseConfig<'a>,
+        consumer: ConsumerControlConfig<'a>,
+    ) -> Self {
+        Self {
+            keyboard,
+            mouse,
+            absolute,
+            consumer,
+        }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for KvmBridgeConfig<'a> {
+    type Allocated = KvmBridge<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        KvmBridge {
+            class: UsbHidClassBuilder::new()
+                .add_device(self.consumer)
+                .add_device(self.absolute)
+                .add_device(self.mouse)
+                .add_device(self.keyboard)
+                .build(usb_alloc),
+            keys: KeyboardState::new(Rollover::SixKey),
+            key_tap: None,
+            keyboard_changed: false,
+            buttons: 0,
+            click: None,
+            pointer: Pointer::Relative,
+            movement: MouseAccumulator::new(),
+            relative_changed: false,
+            position: (0, 0),
+            absolute_changed: false,
+            media: None,
+        }
+    }
+}
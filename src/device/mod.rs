@@ -1,16 +1,31 @@
 //! Concrete implementation of Human Interface Devices
 
-use crate::interface::InterfaceClass;
+use crate::interface::{InterfaceClass, InterfaceEvent};
 use crate::UsbHidError;
 use frunk::{HCons, HNil, ToMut};
 #[allow(clippy::wildcard_imports)]
 use usb_device::class_prelude::*;
 
+pub mod barcode;
+pub mod camera;
+pub mod card_reader;
 pub mod consumer;
+#[cfg(feature = "alloc")]
+pub mod dynamic;
+pub mod encoder;
 pub mod fido;
+pub mod flight;
+pub mod gamepad;
+pub mod haptics;
 pub mod joystick;
 pub mod keyboard;
+pub mod kvm;
 pub mod mouse;
+pub mod scale;
+pub mod switch_gamepad;
+pub mod touchpad;
+pub mod vr;
+pub mod wireless_radio;
 
 pub trait DeviceClass<'a> {
     type I: InterfaceClass<'a>;
@@ -21,12 +36,25 @@ pub trait DeviceClass<'a> {
     fn tick(&mut self) -> Result<(), UsbHidError>;
 }
 
+/// Device that accepts reports of type `R`
+///
+/// Allows reports to be written to a device generically, e.g. by
+/// [`Sourced`](crate::source::Sourced) devices
+pub trait ReportWriter<R> {
+    fn write_report(&mut self, report: &R) -> Result<(), UsbHidError>;
+}
+
 pub trait DeviceHList<'a>: ToMut<'a> {
     fn get(&mut self, id: u8) -> Option<&mut dyn InterfaceClass<'a>>;
     fn reset(&mut self);
     fn write_descriptors(&mut self, writer: &mut DescriptorWriter) -> usb_device::Result<()>;
-    fn get_string(&mut self, index: StringIndex, lang_id: u16) -> Option<&'a str>;
+    fn get_string(&mut self, index: StringIndex, lang_id: LangID) -> Option<&'a str>;
     fn tick(&mut self) -> Result<(), UsbHidError>;
+    fn take_event(&mut self) -> Option<(u8, InterfaceEvent)>;
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress);
+    fn endpoint_halt(&mut self, addr: EndpointAddress, halted: bool);
+    /// Set the suspend state of all interfaces, returns true if any requested remote wakeup
+    fn set_suspended(&mut self, suspended: bool, remote_wakeup_enabled: bool) -> bool;
 }
 
 impl<'a> DeviceHList<'a> for HNil {
@@ -40,13 +68,25 @@ impl<'a> DeviceHList<'a> for HNil {
         Ok(())
     }
 
-    fn get_string(&mut self, _: StringIndex, _: u16) -> Option<&'a str> {
+    fn get_string(&mut self, _: StringIndex, _: LangID) -> Option<&'a str> {
         None
     }
 
     fn tick(&mut self) -> Result<(), UsbHidError> {
         Ok(())
     }
+
+    fn take_event(&mut self) -> Option<(u8, InterfaceEvent)> {
+        None
+    }
+
+    fn endpoint_in_complete(&mut self, _: EndpointAddress) {}
+
+    fn endpoint_halt(&mut self, _: EndpointAddress, _: bool) {}
+
+    fn set_suspended(&mut self, _: bool, _: bool) -> bool {
+        false
+    }
 }
 
 impl<'a, Head: DeviceClass<'a> + 'a, Tail: DeviceHList<'a>> DeviceHList<'a> for HCons<Head, Tail> {
@@ -69,7 +109,7 @@ impl<'a, Head: DeviceClass<'a> + 'a, Tail: DeviceHList<'a>> DeviceHList<'a> for
         self.tail.write_descriptors(writer)
     }
 
-    fn get_string(&mut self, index: StringIndex, lang_id: u16) -> Option<&'a str> {
+    fn get_string(&mut self, index: StringIndex, lang_id: LangID) -> Option<&'a str> {
         let s = self.head.interface().get_string(index, lang_id);
         if s.is_some() {
             s
@@ -79,7 +119,33 @@ impl<'a, Head: DeviceClass<'a> + 'a, Tail: DeviceHList<'a>> DeviceHList<'a> for
     }
 
     fn tick(&mut self) -> Result<(), UsbHidError> {
+        self.head.interface().tick_rate_limit();
         self.head.tick()?;
         self.tail.tick()
     }
+
+    fn take_event(&mut self) -> Option<(u8, InterfaceEvent)> {
+        let interface = self.head.interface();
+        match interface.take_event() {
+            Some(event) => Some((u8::from(interface.id()), event)),
+            None => self.tail.take_event(),
+        }
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        self.head.interface().endpoint_in_complete(addr);
+        self.tail.endpoint_in_complete(addr);
+    }
+
+    fn endpoint_halt(&mut self, addr: EndpointAddress, halted: bool) {
+        self.head.interface().endpoint_halt(addr, halted);
+        self.tail.endpoint_halt(addr, halted);
+    }
+
+    fn set_suspended(&mut self, suspended: bool, remote_wakeup_enabled: bool) -> bool {
+        let interface = self.head.interface();
+        interface.set_suspended(suspended, remote_wakeup_enabled);
+        let wakeup = interface.take_wakeup_request();
+        self.tail.set_suspended(suspended, remote_wakeup_enabled) || wakeup
+    }
 }
@@ -110,6 +110,110 @@ pub struct WheelMouseReport {
     pub horizontal_wheel: i8,
 }
 
+/// Collects relative movement between reports
+///
+/// Sensors often produce movement faster, or in larger steps, than can be reported. Movement is
+/// added as it's read and taken once per report, each axis being clamped to the report's logical
+/// range (±127 for `i8` fields, ±32767 for `i16`) with the remainder carried into the next report.
+///
+/// ```
+/// # use usbd_human_interface_device::device::mouse::MouseAccumulator;
+/// let mut acc = MouseAccumulator::new();
+/// acc.add(100, -5);
+/// acc.add(100, -5);
+/// let report = acc.wheel_mouse_report(0x1);
+/// assert_eq!((report.x, report.y), (127, -10));
+/// assert_eq!(acc.take_i8(), (73, 0));
+/// assert!(acc.is_empty());
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub struct MouseAccumulator {
+    x: i32,
+    y: i32,
+    vertical_wheel: i32,
+    horizontal_wheel: i32,
+}
+
+impl MouseAccumulator {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            vertical_wheel: 0,
+            horizontal_wheel: 0,
+        }
+    }
+
+    pub fn add(&mut self, x: i32, y: i32) {
+        self.x = self.x.saturating_add(x);
+        self.y = self.y.saturating_add(y);
+    }
+
+    pub fn add_wheel(&mut self, vertical: i32, horizontal: i32) {
+        self.vertical_wheel = self.vertical_wheel.saturating_add(vertical);
+        self.horizontal_wheel = self.horizontal_wheel.saturating_add(horizontal);
+    }
+
+    /// True if there is no movement left to report
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        *self == Self::new()
+    }
+
+    /// Discard any movement not yet reported
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Take up to ±127 of `x` and `y` movement
+    pub fn take_i8(&mut self) -> (i8, i8) {
+        (take_i8(&mut self.x), take_i8(&mut self.y))
+    }
+
+    /// Take up to ±32767 of `x` and `y` movement
+    pub fn take_i16(&mut self) -> (i16, i16) {
+        (take_i16(&mut self.x), take_i16(&mut self.y))
+    }
+
+    /// Take up to ±127 of vertical and horizontal wheel movement
+    pub fn take_wheel_i8(&mut self) -> (i8, i8) {
+        (
+            take_i8(&mut self.vertical_wheel),
+            take_i8(&mut self.horizontal_wheel),
+        )
+    }
+
+    pub fn boot_mouse_report(&mut self, buttons: u8) -> BootMouseReport {
+        let (x, y) = self.take_i8();
+        BootMouseReport { buttons, x, y }
+    }
+
+    pub fn wheel_mouse_report(&mut self, buttons: u8) -> WheelMouseReport {
+        let (x, y) = self.take_i8();
+        let (vertical_wheel, horizontal_wheel) = self.take_wheel_i8();
+        WheelMouseReport {
+            buttons,
+            x,
+            y,
+            vertical_wheel,
+            horizontal_wheel,
+        }
+    }
+}
+
+fn take_i8(value: &mut i32) -> i8 {
+    let taken = (*value).clamp(-i32::from(i8::MAX), i32::from(i8::MAX));
+    *value -= taken;
+    i8::try_from(taken).unwrap_or_default()
+}
+
+fn take_i16(value: &mut i32) -> i16 {
+    let taken = (*value).clamp(-i32::from(i16::MAX), i32::from(i16::MAX));
+    *value -= taken;
+    i16::try_from(taken).unwrap_or_default()
+}
+
 /// Absolute mouse with wheel and eight buttons
 ///
 /// Note - absolute pointer support is relatively uncommon. This has been tested on Windows 11
@@ -176,16 +280,13 @@ pub struct BootMouse<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
 }
 
-impl<'a, B: UsbBus> BootMouse<'a, B> {
+impl<B: UsbBus> BootMouse<'_, B> {
     pub fn write_report(&mut self, report: &BootMouseReport) -> Result<(), UsbHidError> {
         let data = report.pack().map_err(|_| {
             error!("Error packing BootMouseReport");
             UsbHidError::SerializationError
         })?;
-        self.interface
-            .write_report(&data)
-            .map(|_| ())
-            .map_err(UsbHidError::from)
+        self.interface.write_report(&data).map(|_| ())
     }
 }
 
@@ -200,8 +301,7 @@ impl<'a> BootMouseConfig<'a> {
     }
 }
 
-impl<'a> Default for BootMouseConfig<'a> {
-    #[must_use]
+impl Default for BootMouseConfig<'_> {
     fn default() -> Self {
         Self::new(
             unwrap!(unwrap!(InterfaceBuilder::new(BOOT_MOUSE_REPORT_DESCRIPTOR))
@@ -209,6 +309,7 @@ impl<'a> Default for BootMouseConfig<'a> {
                 .description("Mouse")
                 .in_endpoint(10.millis()))
             .without_out_endpoint()
+            .send_unchanged_reports()
             .build(),
         )
     }
@@ -224,6 +325,12 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for BootMouseConfig<'a> {
     }
 }
 
+impl<B: UsbBus> ReportWriter<BootMouseReport> for BootMouse<'_, B> {
+    fn write_report(&mut self, report: &BootMouseReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
 impl<'a, B: UsbBus> DeviceClass<'a> for BootMouse<'a, B> {
     type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
 
@@ -242,16 +349,13 @@ pub struct WheelMouse<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
 }
 
-impl<'a, B: UsbBus> WheelMouse<'a, B> {
+impl<B: UsbBus> WheelMouse<'_, B> {
     pub fn write_report(&mut self, report: &WheelMouseReport) -> Result<(), UsbHidError> {
         let data = report.pack().map_err(|_| {
             error!("Error packing WheelMouseReport");
             UsbHidError::SerializationError
         })?;
-        self.interface
-            .write_report(&data)
-            .map(|_| ())
-            .map_err(UsbHidError::from)
+        self.interface.write_report(&data).map(|_| ())
     }
 }
 pub struct WheelMouseConfig<'a> {
@@ -265,8 +369,7 @@ impl<'a> WheelMouseConfig<'a> {
     }
 }
 
-impl<'a> Default for WheelMouseConfig<'a> {
-    #[must_use]
+impl Default for WheelMouseConfig<'_> {
     fn default() -> Self {
         WheelMouseConfig::new(
             unwrap!(
@@ -276,6 +379,7 @@ impl<'a> Default for WheelMouseConfig<'a> {
                     .in_endpoint(10.millis())
             )
             .without_out_endpoint()
+            .send_unchanged_reports()
             .build(),
         )
     }
@@ -291,6 +395,12 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for WheelMouseConfig<'a> {
     }
 }
 
+impl<B: UsbBus> ReportWriter<WheelMouseReport> for WheelMouse<'_, B> {
+    fn write_report(&mut self, report: &WheelMouseReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
 impl<'a, B: UsbBus> DeviceClass<'a> for WheelMouse<'a, B> {
     type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
 
@@ -309,16 +419,13 @@ pub struct AbsoluteWheelMouse<'a, B: UsbBus> {
     interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
 }
 
-impl<'a, B: UsbBus> AbsoluteWheelMouse<'a, B> {
+impl<B: UsbBus> AbsoluteWheelMouse<'_, B> {
     pub fn write_report(&mut self, report: &AbsoluteWheelMouseReport) -> Result<(), UsbHidError> {
         let data = report.pack().map_err(|_| {
             error!("Error packing WheelMouseReport");
             UsbHidError::SerializationError
         })?;
-        self.interface
-            .write_report(&data)
-            .map(|_| ())
-            .map_err(UsbHidError::from)
+        self.interface.write_report(&data).map(|_| ())
     }
 }
 
@@ -333,8 +440,7 @@ impl<'a> AbsoluteWheelMouseConfig<'a> {
     }
 }
 
-impl<'a> Default for AbsoluteWheelMouseConfig<'a> {
-    #[must_use]
+impl Default for AbsoluteWheelMouseConfig<'_> {
     fn default() -> Self {
         AbsoluteWheelMouseConfig::new(
             unwrap!(unwrap!(InterfaceBuilder::new(
@@ -343,6 +449,7 @@ impl<'a> Default for AbsoluteWheelMouseConfig<'a> {
             .description("Absolute Wheel Mouse")
             .in_endpoint(10.millis()))
             .without_out_endpoint()
+            .send_unchanged_reports()
             .build(),
         )
     }
@@ -358,6 +465,12 @@ impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for AbsoluteWheelMouseConfig<'a>
     }
 }
 
+impl<B: UsbBus> ReportWriter<AbsoluteWheelMouseReport> for AbsoluteWheelMouse<'_, B> {
+    fn write_report(&mut self, report: &AbsoluteWheelMouseReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
 impl<'a, B: UsbBus> DeviceClass<'a> for AbsoluteWheelMouse<'a, B> {
     type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
 
@@ -371,3 +484,201 @@ impl<'a, B: UsbBus> DeviceClass<'a> for AbsoluteWheelMouse<'a, B> {
         Ok(())
     }
 }
+
+const RELATIVE_REPORT_ID: u8 = 0x01;
+const ABSOLUTE_REPORT_ID: u8 = 0x02;
+
+/// HID Mouse report descriptor with a relative and an absolute pointer, for [`HybridMouse`]
+///
+/// Report 1 has the fields of a [`WheelMouseReport`], report 2 the fields of an
+/// [`AbsoluteWheelMouseReport`]. Each is in its own application collection, so hosts treat them
+/// as separate pointers.
+#[rustfmt::skip]
+pub const HYBRID_MOUSE_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01,                 // Usage Page (Generic Desktop),
+    0x09, 0x02,                 // Usage (Mouse),
+    0xA1, 0x01,                 // Collection (Application),
+    0x85, RELATIVE_REPORT_ID,   //   Report ID (1),
+    0x09, 0x01,                 //   Usage (Pointer),
+    0xA1, 0x00,                 //   Collection (Physical),
+    0x95, 0x08,                 //     Report Count (8),
+    0x75, 0x01,                 //     Report Size (1),
+    0x05, 0x09,                 //     Usage Page (Buttons),
+    0x19, 0x01,                 //     Usage Minimum (1),
+    0x29, 0x08,                 //     Usage Maximum (8),
+    0x15, 0x00,                 //     Logical Minimum (0),
+    0x25, 0x01,                 //     Logical Maximum (1),
+    0x81, 0x02,                 //     Input (Data, Variable, Absolute),
+
+    0x75, 0x08,                 //     Report Size (8),
+    0x95, 0x02,                 //     Report Count (2),
+    0x05, 0x01,                 //     Usage Page (Generic Desktop),
+    0x09, 0x30,                 //     Usage (X),
+    0x09, 0x31,                 //     Usage (Y),
+    0x15, 0x81,                 //     Logical Minimum (-127),
+    0x25, 0x7F,                 //     Logical Maximum (127),
+    0x81, 0x06,                 //     Input (Data, Variable, Relative),
+
+    0x09, 0x38,                 //     Usage (Wheel)
+    0x95, 0x01,                 //     Report Count (1)
+    0x81, 0x06,                 //     Input (Data,Var,Rel,No Wrap,Linear,Preferred State,No Null Position)
+    0x05, 0x0C,                 //     Usage Page (Consumer)
+    0x0A, 0x38, 0x02,           //     Usage (AC Pan)
+    0x81, 0x06,                 //     Input (Data,Var,Rel,No Wrap,Linear,Preferred State,No Null Position)
+    0xC0,                       //   End Collection
+    0xC0,                       // End Collection
+
+    0x05, 0x01,                 // Usage Page (Generic Desktop),
+    0x09, 0x02,                 // Usage (Mouse),
+    0xA1, 0x01,                 // Collection (Application),
+    0x85, ABSOLUTE_REPORT_ID,   //   Report ID (2),
+    0x09, 0x01,                 //   Usage (Pointer),
+    0xA1, 0x00,                 //   Collection (Physical),
+    0x05, 0x09,                 //     Usage Page (Buttons),
+    0x19, 0x01,                 //     Usage Minimum (1),
+    0x29, 0x08,                 //     Usage Maximum (8),
+    0x15, 0x00,                 //     Logical Minimum (0),
+    0x25, 0x01,                 //     Logical Maximum (1),
+    0x95, 0x08,                 //     Report Count (8),
+    0x75, 0x01,                 //     Report Size (1),
+    0x81, 0x02,                 //     Input (Data, Variable, Absolute),
+
+    0x05, 0x01,                 //     Usage Page (Generic Desktop),
+    0x09, 0x30,                 //     Usage (X),
+    0x09, 0x31,                 //     Usage (Y),
+    0x15, 0x00,                 //     Logical Minimum (0),
+    0x26, 0xFF, 0x7F,           //     Logical Maximum (32767),
+    0x35, 0x00,                 //     Physical Minimum (0),
+    0x46, 0xFF, 0x7F,           //     Physical Maximum (32767),
+    0x95, 0x02,                 //     Report Count (2),
+    0x75, 0x10,                 //     Report Size (16),
+    0x81, 0x02,                 //     Input (Data, Variable, Absolute),
+
+    0x09, 0x38,                 //     Usage (Wheel)
+    0x15, 0x81,                 //     Logical Minimum (-127)
+    0x25, 0x7F,                 //     Logical Maximum (127)
+    0x35, 0x81,                 //     Physical Minimum (-127),
+    0x45, 0x7F,                 //     Physical Maximum (127),
+    0x75, 0x08,                 //     Report Size (8)
+    0x95, 0x01,                 //     Report Count (1)
+    0x81, 0x06,                 //     Input (Data,Var,Rel,No Wrap,Linear,Preferred State,No Null Position)
+    0xC0,                       //   End Collection
+    0xC0,                       // End Collection
+];
+
+/// Mouse with both a relative and an absolute pointer, such as for KVM firmware that positions
+/// the pointer absolutely, but falls back to relative movement for hosts or games that ignore
+/// absolute pointers
+///
+/// Buttons are reported separately by each pointer, release any pressed buttons with one before
+/// switching to the other.
+pub struct HybridMouse<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, Reports8>,
+}
+
+impl<B: UsbBus> HybridMouse<'_, B> {
+    pub fn write_relative(&mut self, report: &WheelMouseReport) -> Result<(), UsbHidError> {
+        let packed = report.pack().map_err(|_| {
+            error!("Error packing WheelMouseReport");
+            UsbHidError::SerializationError
+        })?;
+        let mut data = [RELATIVE_REPORT_ID; 6];
+        data[1..].copy_from_slice(&packed);
+        self.write(&data)
+    }
+
+    pub fn write_absolute(&mut self, report: &AbsoluteWheelMouseReport) -> Result<(), UsbHidError> {
+        let packed = report.pack().map_err(|_| {
+            error!("Error packing AbsoluteWheelMouseReport");
+            UsbHidError::SerializationError
+        })?;
+        let mut data = [ABSOLUTE_REPORT_ID; 7];
+        data[1..].copy_from_slice(&packed);
+        self.write(&data)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), UsbHidError> {
+        self.interface.write_report(data).map(|_| ())
+    }
+}
+
+pub struct HybridMouseConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, Reports8>,
+}
+
+impl<'a> HybridMouseConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, Reports8>) -> Self {
+        Self { interface }
+    }
+}
+
+impl Default for HybridMouseConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(HYBRID_MOUSE_REPORT_DESCRIPTOR))
+                    .description("Mouse")
+                    .in_endpoint(10.millis())
+            )
+            .without_out_endpoint()
+            .send_unchanged_reports()
+            .build(),
+        )
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for HybridMouseConfig<'a> {
+    type Allocated = HybridMouse<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        HybridMouse {
+            interface: self.interface.allocate(usb_alloc),
+        }
+    }
+}
+
+impl<B: UsbBus> ReportWriter<WheelMouseReport> for HybridMouse<'_, B> {
+    fn write_report(&mut self, report: &WheelMouseReport) -> Result<(), UsbHidError> {
+        self.write_relative(report)
+    }
+}
+
+impl<B: UsbBus> ReportWriter<AbsoluteWheelMouseReport> for HybridMouse<'_, B> {
+    fn write_report(&mut self, report: &AbsoluteWheelMouseReport) -> Result<(), UsbHidError> {
+        self.write_absolute(report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for HybridMouse<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, Reports8>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::descriptor::item::report_length;
+
+    #[test]
+    fn hybrid_reports_match_descriptor() {
+        let descriptor = HYBRID_MOUSE_REPORT_DESCRIPTOR;
+        assert_eq!(
+            report_length(descriptor, ReportType::Input, RELATIVE_REPORT_ID),
+            Some(6)
+        );
+        assert_eq!(
+            report_length(descriptor, ReportType::Input, ABSOLUTE_REPORT_ID),
+            Some(7)
+        );
+    }
+}
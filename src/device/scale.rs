@@ -0,0 +1,306 @@
+//!HID Point of Sale weighing scale
+//!
+//! Reports the same scale data report as common USB postal scales, so existing host software can
+//! read the weight. [`ScaleStatusTracker`] derives the scale status from raw weight readings.
+//!
+//! Defined by the Scale page (0x8D) of the [HID Point of Sale Usage Tables](<https://www.usb.org/sites/default/files/pos1_02.pdf>)
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+const SCALE_DATA_REPORT_ID: u8 = 0x03;
+
+/// Scale with a scale data report of status, weight unit, scaling exponent and weight
+#[rustfmt::skip]
+pub const SCALE_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x8D,                   // Usage Page (Scale)
+    0x09, 0x01,                   // Usage (Scales)
+    0xA1, 0x01,                   // Collection (Application)
+    0x09, 0x20,                   //   Usage (Scale Device)
+    0xA1, 0x02,                   //   Collection (Logical)
+    0x09, 0x32,                   //     Usage (Scale Data Report)
+    0xA1, 0x02,                   //     Collection (Logical)
+    0x85, SCALE_DATA_REPORT_ID,   //       Report ID
+    0x75, 0x08,                   //       Report Size (8)
+    0x95, 0x01,                   //       Report Count (1)
+    0x09, 0x70,                   //       Usage (Scale Status)
+    0xA1, 0x02,                   //       Collection (Logical)
+    0x15, 0x01,                   //         Logical Minimum (1)
+    0x25, 0x08,                   //         Logical Maximum (8)
+    0x19, 0x71,                   //         Usage Minimum (Fault)
+    0x29, 0x78,                   //         Usage Maximum (Requires Rezeroing)
+    0x81, 0x00,                   //         Input (Data, Array, Absolute)
+    0xC0,                         //       End Collection
+    0x09, 0x50,                   //       Usage (Weight Unit)
+    0xA1, 0x02,                   //       Collection (Logical)
+    0x15, 0x01,                   //         Logical Minimum (1)
+    0x25, 0x0C,                   //         Logical Maximum (12)
+    0x19, 0x51,                   //         Usage Minimum (Milligram)
+    0x29, 0x5C,                   //         Usage Maximum (Pound)
+    0x81, 0x00,                   //         Input (Data, Array, Absolute)
+    0xC0,                         //       End Collection
+    0x09, 0x41,                   //       Usage (Data Scaling)
+    0x15, 0x80,                   //       Logical Minimum (-128)
+    0x25, 0x7F,                   //       Logical Maximum (127)
+    0x81, 0x02,                   //       Input (Data, Variable, Absolute)
+    0x09, 0x40,                   //       Usage (Data Weight)
+    0x15, 0x00,                   //       Logical Minimum (0)
+    0x27, 0xFF, 0xFF, 0x00, 0x00, //       Logical Maximum (65535)
+    0x75, 0x10,                   //       Report Size (16)
+    0x81, 0x02,                   //       Input (Data, Variable, Absolute)
+    0xC0,                         //     End Collection
+    0xC0,                         //   End Collection
+    0xC0,                         // End Collection
+];
+
+/// Status of a scale, from the Scale Status usages
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PrimitiveEnum)]
+#[repr(u8)]
+pub enum ScaleStatus {
+    Fault = 1,
+    /// Stable with no weight on the scale
+    StableAtZero = 2,
+    #[default]
+    InMotion = 3,
+    Stable = 4,
+    UnderZero = 5,
+    OverWeight = 6,
+    RequiresCalibration = 7,
+    RequiresRezeroing = 8,
+}
+
+/// Unit of the weight in a [`ScaleReport`], from the Weight Unit usages
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PrimitiveEnum)]
+#[repr(u8)]
+pub enum WeightUnit {
+    Milligram = 1,
+    #[default]
+    Gram = 2,
+    Kilogram = 3,
+    Carat = 4,
+    Tael = 5,
+    Grain = 6,
+    Pennyweight = 7,
+    MetricTon = 8,
+    AvoirTon = 9,
+    TroyOunce = 10,
+    Ounce = 11,
+    Pound = 12,
+}
+
+/// Report for a [`Scale`]
+///
+/// The weight is `weight * 10^exponent` [`Self::unit`]s
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "6")]
+pub struct ScaleReport {
+    #[packed_field]
+    pub report_id: u8,
+    #[packed_field(ty = "enum", size_bytes = "1")]
+    pub status: ScaleStatus,
+    #[packed_field(ty = "enum", size_bytes = "1")]
+    pub unit: WeightUnit,
+    #[packed_field]
+    pub exponent: i8,
+    #[packed_field]
+    pub weight: u16,
+}
+
+impl Default for ScaleReport {
+    fn default() -> Self {
+        Self {
+            report_id: SCALE_DATA_REPORT_ID,
+            status: ScaleStatus::default(),
+            unit: WeightUnit::default(),
+            exponent: 0,
+            weight: 0,
+        }
+    }
+}
+
+/// Derives the [`ScaleStatus`] of a scale from successive weight readings
+///
+/// A reading is in motion until `settle_readings` consecutive readings are within
+/// `motion_tolerance` of each other. Readings below zero, or above the scale's `capacity`, are out
+/// of range regardless of motion.
+///
+/// ```
+/// # use usbd_human_interface_device::device::scale::{ScaleStatus, ScaleStatusTracker};
+/// let mut tracker = ScaleStatusTracker::new(5000, 2, 3);
+/// assert_eq!(tracker.update(100), ScaleStatus::InMotion);
+/// assert_eq!(tracker.update(101), ScaleStatus::InMotion);
+/// assert_eq!(tracker.update(100), ScaleStatus::Stable);
+/// assert_eq!(tracker.update(250), ScaleStatus::InMotion);
+/// assert_eq!(tracker.update(-5), ScaleStatus::UnderZero);
+/// assert_eq!(tracker.update(6000), ScaleStatus::OverWeight);
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ScaleStatusTracker {
+    capacity: i32,
+    motion_tolerance: i32,
+    settle_readings: u8,
+    last: Option<i32>,
+    stable_readings: u8,
+}
+
+impl ScaleStatusTracker {
+    #[must_use]
+    pub const fn new(capacity: i32, motion_tolerance: i32, settle_readings: u8) -> Self {
+        Self {
+            capacity,
+            motion_tolerance,
+            settle_readings,
+            last: None,
+            stable_readings: 0,
+        }
+    }
+
+    /// Record a new weight reading, returning the resulting status
+    pub fn update(&mut self, weight: i32) -> ScaleStatus {
+        let settled = self
+            .last
+            .is_some_and(|last| (weight - last).abs() <= self.motion_tolerance);
+        self.stable_readings = if settled {
+            self.stable_readings.saturating_add(1)
+        } else {
+            1
+        };
+        self.last = Some(weight);
+        self.status()
+    }
+
+    /// The status after the last reading
+    #[must_use]
+    pub fn status(&self) -> ScaleStatus {
+        match self.last {
+            None => ScaleStatus::InMotion,
+            Some(weight) if weight < 0 => ScaleStatus::UnderZero,
+            Some(weight) if weight > self.capacity => ScaleStatus::OverWeight,
+            Some(_) if self.stable_readings < self.settle_readings => ScaleStatus::InMotion,
+            Some(0) => ScaleStatus::StableAtZero,
+            Some(_) => ScaleStatus::Stable,
+        }
+    }
+
+    /// Forget previous readings, such as after the scale is re-zeroed
+    pub fn reset(&mut self) {
+        self.last = None;
+        self.stable_readings = 0;
+    }
+}
+
+pub struct Scale<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutNone, ReportSingle>,
+}
+
+impl<B: UsbBus> Scale<'_, B> {
+    pub fn write_report(&mut self, report: &ScaleReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing ScaleReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+}
+
+impl<B: UsbBus> ReportWriter<ScaleReport> for Scale<'_, B> {
+    fn write_report(&mut self, report: &ScaleReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for Scale<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutNone, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct ScaleConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>,
+}
+
+impl Default for ScaleConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(SCALE_REPORT_DESCRIPTOR))
+                .description("Scale")
+                .in_endpoint(10.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> ScaleConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutNone, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for ScaleConfig<'a> {
+    type Allocated = Scale<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn report_matches_descriptor() {
+        assert_eq!(
+            report_length(
+                SCALE_REPORT_DESCRIPTOR,
+                ReportType::Input,
+                SCALE_DATA_REPORT_ID
+            ),
+            Some(6)
+        );
+        let report = ScaleReport {
+            status: ScaleStatus::Stable,
+            unit: WeightUnit::Ounce,
+            exponent: -1,
+            weight: 0x0123,
+            ..Default::default()
+        };
+        assert_eq!(report.pack().unwrap(), [0x03, 4, 11, 0xFF, 0x23, 0x01]);
+    }
+
+    #[test]
+    fn status_tracker() {
+        let mut tracker = ScaleStatusTracker::new(1000, 1, 2);
+        assert_eq!(tracker.status(), ScaleStatus::InMotion);
+        assert_eq!(tracker.update(0), ScaleStatus::InMotion);
+        assert_eq!(tracker.update(0), ScaleStatus::StableAtZero);
+        assert_eq!(tracker.update(500), ScaleStatus::InMotion);
+        assert_eq!(tracker.update(501), ScaleStatus::Stable);
+        assert_eq!(tracker.update(502), ScaleStatus::Stable);
+        assert_eq!(tracker.update(1001), ScaleStatus::OverWeight);
+        assert_eq!(tracker.update(-1), ScaleStatus::UnderZero);
+        tracker.reset();
+        assert_eq!(tracker.status(), ScaleStatus::InMotion);
+    }
+}
@@ -1,140 +1,258 @@
-//!HID mice
-use crate::hid_class::descriptor::HidProtocol;
+//!HID gamepad accepted by the Nintendo Switch as a wired controller
+//!
+//! Uses the report descriptor of Hori Co. Ltd. fight sticks, which the Switch recognises when the
+//! device uses their vendor and product IDs (`0x0F0D`, `0x0092`).
+//!
+//! The 8 byte output report the host sends is treated as a subcommand, its first byte the
+//! subcommand ID and the rest the payload. [`SwitchGamepadInterface::dispatch_subcommand`] routes
+//! subcommands to a [`SubcommandHandler`], and the acknowledgement it returns is sent to the host
+//! in the vendor specific byte of the following input reports.
+use crate::usb_class::prelude::*;
 use core::default::Default;
-use delegate::delegate;
-use embedded_time::duration::Milliseconds;
-use log::error;
+use fugit::ExtU32;
 use packed_struct::prelude::*;
-use usb_device::bus::{InterfaceNumber, StringIndex, UsbBus};
-use usb_device::class_prelude::DescriptorWriter;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
 
-use crate::hid_class::prelude::*;
-use crate::interface::raw::{RawInterface, RawInterfaceConfig};
-use crate::interface::{InterfaceClass, WrappedInterface, WrappedInterfaceConfig};
-use crate::UsbHidError;
-
-/// HID Descriptors cloned from Hori Co. Ltd. Fight Sticks
+/// Gamepad with 16 buttons, a hat switch and two 8 bit analog sticks, with a vendor specific
+/// input byte and an 8 byte vendor specific output report
 #[rustfmt::skip]
 pub const SWITCH_GAMEPAD_REPORT_DESCRIPTOR: &[u8] = &[
-    0x08, 0x01,                   // USAGE_PAGE Generic Desktop
-    0x08, 0x05,                   // USAGE Joystick
-    0x08, 0x01,                   // COLLECTION Application
-        0x08, 0x00,               // Logical Min
-        0x08, 0x01,               // Logical Max
-        0x08, 0x00,               // Physical Min
-        0x08, 0x01,               // Physical Max
-        0x08, 0x01,               // REPORT_SIZE 1
-        0x08, 0x10,               // REPORT_COUNT 16
-        0x08, 0x09,               // USAGE PAGE
-        0x08, 0x01,               // USAGE Min
-        0x08, 0x10,               // USAGE Max
-        0x08, 0x02,               // INPUT
-        // Hat switch, 1 nibble with a spare nibble
-        0x08, 0x01,               // USAGE Page
-        0x08, 0x07,               // LOGICAL Max
-        0x10, 0x01, 0x3B,            // PHYSICAL Max
-        0x08, 0x04,               // REPORT_SIZE
-        0x08, 0x01,               // REPORT_COUNT
-        0x08, 0x14,              // UNIT
-        0x08, 0x39,              // USAGE
-        0x08, 0x42,              // INPUT
-        // this is where the spare nibble goes
-        0x08, 0x00,               // UNIT
-        0x08, 0x01,               // REPORT_COUNT
-        0x08, 0x01,               // INPUT
-        0x10, 0xFF, 0xFF,         // LOGICAL Max
-        0x10, 255,                // PHYSICAL Max
-        0x08, 0x08,               // USAGE
-        0x08, 0x31,               // USAGE
-        0x08, 0x32,               // USAGE
-        0x08, 0x35,               // USAGE
-        0x08, 0x08,               // REPORT SIZE
-        0x08, 0x04,               // REPORT COUNT
-        0x08, 0x02,               // INPUT
-        // vendor specific byte
-        0x10, 0xFF, 0x00,         // USAGE PAGE  (16-bit value, this hack is ugly)
-        0x08, 0x20,               // USAGE
-        0x08, 0x01,               // REPORT COUNT
-        0x08, 0x02,               // INPUT
-        // Output, 8 bytes
-        0x10, 0x26, 0x21,         // USAGE  (16-bit value, this hack is ugly)
-        0x08, 0x08,               // REPORT COUNT
-        0x08, 0x02,               // OUTPUT
-    0x00 // END COLLECTION
+    0x05, 0x01,                   // Usage Page (Generic Desktop)
+    0x09, 0x05,                   // Usage (Gamepad)
+    0xA1, 0x01,                   // Collection (Application)
+    0x15, 0x00,                   //   Logical Minimum (0)
+    0x25, 0x01,                   //   Logical Maximum (1)
+    0x35, 0x00,                   //   Physical Minimum (0)
+    0x45, 0x01,                   //   Physical Maximum (1)
+    0x75, 0x01,                   //   Report Size (1)
+    0x95, 0x10,                   //   Report Count (16)
+    0x05, 0x09,                   //   Usage Page (Button)
+    0x19, 0x01,                   //   Usage Minimum (1)
+    0x29, 0x10,                   //   Usage Maximum (16)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x05, 0x01,                   //   Usage Page (Generic Desktop)
+    0x25, 0x07,                   //   Logical Maximum (7)
+    0x46, 0x3B, 0x01,             //   Physical Maximum (315)
+    0x75, 0x04,                   //   Report Size (4)
+    0x95, 0x01,                   //   Report Count (1)
+    0x65, 0x14,                   //   Unit (English Rotation, Degrees)
+    0x09, 0x39,                   //   Usage (Hat Switch)
+    0x81, 0x42,                   //   Input (Data, Variable, Absolute, Null State)
+    0x65, 0x00,                   //   Unit (None)
+    0x95, 0x01,                   //   Report Count (1)
+    0x81, 0x01,                   //   Input (Constant)
+    0x26, 0xFF, 0x00,             //   Logical Maximum (255)
+    0x46, 0xFF, 0x00,             //   Physical Maximum (255)
+    0x09, 0x30,                   //   Usage (X)
+    0x09, 0x31,                   //   Usage (Y)
+    0x09, 0x32,                   //   Usage (Z)
+    0x09, 0x35,                   //   Usage (Rz)
+    0x75, 0x08,                   //   Report Size (8)
+    0x95, 0x04,                   //   Report Count (4)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x06, 0x00, 0xFF,             //   Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x20,                   //   Usage (0x20)
+    0x95, 0x01,                   //   Report Count (1)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x0A, 0x21, 0x26,             //   Usage (0x2621)
+    0x95, 0x08,                   //   Report Count (8)
+    0x91, 0x02,                   //   Output (Data, Variable, Absolute)
+    0xC0,                         // End Collection
 ];
 
-#[derive(Clone, Copy, Debug, PartialEq, Default, PackedStruct)]
-#[packed_struct(endian = "lsb", size_bytes = "3")]
+/// Hat switch value when no direction is pressed, directions are 0 (up) to 7 clockwise
+pub const HAT_CENTERED: u8 = 0x08;
+
+/// Stick axis value when the stick is centered
+pub const AXIS_CENTERED: u8 = 0x80;
+
+/// Length of a subcommand payload, the output report after the subcommand ID
+pub const SUBCOMMAND_PAYLOAD_LEN: usize = 7;
+
+/// Report for a [`SwitchGamepadInterface`], without the vendor specific byte, which is the
+/// acknowledgement of the last subcommand
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "7")]
 pub struct SwitchGamepadReport {
+    /// Bit 0 is button 1
     #[packed_field]
     pub buttons: u16,
+    /// Direction of the hat switch, or [`HAT_CENTERED`]
     #[packed_field]
     pub hat: u8,
     #[packed_field]
-    pub padding: u8,
-    #[packed_field]
-    pub lx: u8,
+    pub left_x: u8,
     #[packed_field]
-    pub ly: u8,
+    pub left_y: u8,
     #[packed_field]
-    pub rx: u8,
+    pub right_x: u8,
     #[packed_field]
-    pub ry: u8,
+    pub right_y: u8,
+}
+
+impl Default for SwitchGamepadReport {
+    fn default() -> Self {
+        Self {
+            buttons: 0,
+            hat: HAT_CENTERED,
+            left_x: AXIS_CENTERED,
+            left_y: AXIS_CENTERED,
+            right_x: AXIS_CENTERED,
+            right_y: AXIS_CENTERED,
+        }
+    }
+}
+
+/// Handles subcommands sent by the host, see [`SwitchGamepadInterface::dispatch_subcommand`]
+pub trait SubcommandHandler {
+    /// Handle subcommand `id`, returning the acknowledgement sent to the host
+    ///
+    /// Subcommands the handler doesn't recognise should be passed to
+    /// [`Self::unknown_subcommand`]. By default all subcommands are.
+    fn subcommand(&mut self, id: u8, payload: &[u8]) -> u8 {
+        self.unknown_subcommand(id, payload)
+    }
+
+    /// Acknowledge a subcommand without acting on it, by echoing its ID
+    fn unknown_subcommand(&mut self, id: u8, _payload: &[u8]) -> u8 {
+        id
+    }
 }
 
+/// [`SubcommandHandler`] acknowledging every subcommand without acting on it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AcknowledgeSubcommands;
+
+impl SubcommandHandler for AcknowledgeSubcommands {}
+
 pub struct SwitchGamepadInterface<'a, B: UsbBus> {
-    inner: RawInterface<'a, B>,
+    interface: Interface<'a, B, InBytes8, OutBytes8, ReportSingle>,
+    ack: u8,
 }
 
-impl<'a, B: UsbBus> SwitchGamepadInterface<'a, B> {
-    pub fn write_report(&self, report: &SwitchGamepadReport) -> Result<(), UsbHidError> {
-        let data = report.pack().map_err(|e| {
-            error!("Error packing SwitchGamepadReport: {:?}", e);
+impl<B: UsbBus> SwitchGamepadInterface<'_, B> {
+    pub fn write_report(&mut self, report: &SwitchGamepadReport) -> Result<(), UsbHidError> {
+        let mut data = [0; 8];
+        report.pack_to_slice(&mut data[..7]).map_err(|_| {
+            error!("Error packing SwitchGamepadReport");
             UsbHidError::SerializationError
         })?;
-        self.inner
-            .write_report(&data)
-            .map(|_| ())
-            .map_err(UsbHidError::from)
+        data[7] = self.ack;
+        self.interface.write_report(&data).map(|_| ())
     }
 
-    pub fn default_config() -> WrappedInterfaceConfig<Self, RawInterfaceConfig<'a>> {
-        WrappedInterfaceConfig::new(
-            RawInterfaceBuilder::new(SWITCH_GAMEPAD_REPORT_DESCRIPTOR)
-                .boot_device(InterfaceProtocol::Gamepad)
-                .description("Switch Gamepad")
-                .idle_default(Milliseconds(10))
-                .unwrap()
-                .in_endpoint(UsbPacketSize::Bytes8, Milliseconds(1))
-                .unwrap()
-                .without_out_endpoint()
-                .build(),
-            (),
+    /// Read a subcommand sent by the host, if any, and pass it to `handler`, returning its ID
+    ///
+    /// The acknowledgement returned by `handler` is sent in the following input reports.
+    ///
+    /// # Errors
+    ///
+    /// Errors reading the output report, as [`Interface::read_report`]
+    pub fn dispatch_subcommand<H: SubcommandHandler + ?Sized>(
+        &mut self,
+        handler: &mut H,
+    ) -> Result<Option<u8>, UsbHidError> {
+        let mut data = [0; 1 + SUBCOMMAND_PAYLOAD_LEN];
+        match self.interface.read_report(&mut data) {
+            Ok(n) => {
+                let Some((&id, payload)) = data[..n].split_first() else {
+                    warn!("Empty subcommand");
+                    return Ok(None);
+                };
+                trace!("Subcommand {:X}", id);
+                self.ack = handler.subcommand(id, payload);
+                Ok(Some(id))
+            }
+            Err(UsbError::WouldBlock) => Ok(None),
+            Err(e) => Err(UsbHidError::from(e)),
+        }
+    }
+}
+
+impl<B: UsbBus> ReportWriter<SwitchGamepadReport> for SwitchGamepadInterface<'_, B> {
+    fn write_report(&mut self, report: &SwitchGamepadReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for SwitchGamepadInterface<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutBytes8, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.ack = 0;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct SwitchGamepadConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutBytes8, ReportSingle>,
+}
+
+impl Default for SwitchGamepadConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(
+                unwrap!(InterfaceBuilder::new(SWITCH_GAMEPAD_REPORT_DESCRIPTOR))
+                    .description("Switch Gamepad")
+                    .in_endpoint(1.millis())
+            )
+            .with_out_endpoint(1.millis()))
+            .build(),
         )
     }
 }
 
-impl<'a, B: UsbBus> InterfaceClass<'a> for SwitchGamepadInterface<'a, B> {
-    delegate! {
-        to self.inner{
-           fn report_descriptor(&self) -> &'_ [u8];
-           fn id(&self) -> InterfaceNumber;
-           fn write_descriptors(&self, writer: &mut DescriptorWriter) -> usb_device::Result<()>;
-           fn get_string(&self, index: StringIndex, _lang_id: u16) -> Option<&'_ str>;
-           fn reset(&mut self);
-           fn set_report(&mut self, data: &[u8]) -> usb_device::Result<()>;
-           fn get_report(&mut self, data: &mut [u8]) -> usb_device::Result<usize>;
-           fn get_report_ack(&mut self) -> usb_device::Result<()>;
-           fn set_idle(&mut self, report_id: u8, value: u8);
-           fn get_idle(&self, report_id: u8) -> u8;
-           fn set_protocol(&mut self, protocol: HidProtocol);
-           fn get_protocol(&self) -> HidProtocol;
+impl<'a> SwitchGamepadConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutBytes8, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for SwitchGamepadConfig<'a> {
+    type Allocated = SwitchGamepadInterface<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            ack: 0,
         }
     }
 }
 
-impl<'a, B: UsbBus> WrappedInterface<'a, B, RawInterface<'a, B>> for SwitchGamepadInterface<'a, B> {
-    fn new(interface: RawInterface<'a, B>, _: ()) -> Self {
-        Self { inner: interface }
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn report_matches_descriptor() {
+        assert_eq!(
+            report_length(SWITCH_GAMEPAD_REPORT_DESCRIPTOR, ReportType::Input, 0),
+            Some(8)
+        );
+        assert_eq!(
+            report_length(SWITCH_GAMEPAD_REPORT_DESCRIPTOR, ReportType::Output, 0),
+            Some(1 + SUBCOMMAND_PAYLOAD_LEN)
+        );
+        let report = SwitchGamepadReport {
+            buttons: 0x8001,
+            hat: 2,
+            left_x: 0,
+            ..Default::default()
+        };
+        assert_eq!(report.pack().unwrap(), [0x01, 0x80, 2, 0, 0x80, 0x80, 0x80]);
     }
 }
@@ -0,0 +1,493 @@
+//!Windows Precision Touchpad
+//!
+//! A multi-touch touchpad implementing the report and feature reports required by the Windows
+//! Precision Touchpad driver, with up to [`MAX_CONTACTS`] simultaneous contacts.
+//!
+//! The touchpad's dimensions are set by its report descriptor. [`PRECISION_TOUCHPAD_REPORT_DESCRIPTOR`]
+//! describes a 100mm by 65mm pad with coordinates from 0 to 4095, other pads can generate a
+//! descriptor with [`precision_touchpad_descriptor`]:
+//!
+//! ```
+//! # use usbd_human_interface_device::descriptor::item::ReportDescriptor;
+//! # use usbd_human_interface_device::device::touchpad::precision_touchpad_descriptor;
+//! // 80mm by 50mm, reporting 0.1mm resolution
+//! const LEN: usize = precision_touchpad_descriptor::<512>(800, 500, 800, 500).as_bytes().len();
+//! static DESCRIPTOR: ReportDescriptor<LEN> = precision_touchpad_descriptor(800, 500, 800, 500);
+//! ```
+//!
+//! The device certification status feature report, which Windows reads to decide whether a
+//! touchpad has been certified, is not provided by this device.
+//!
+//! Defined by [Windows Precision Touchpad required HID top-level collections](<https://learn.microsoft.com/en-us/windows-hardware/design/component-guidelines/windows-precision-touchpad-required-hid-top-level-collections>)
+
+use crate::descriptor::item::{Item, ReportDescriptor, Unit};
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Most contacts reported at once
+pub const MAX_CONTACTS: usize = 5;
+
+const INPUT_REPORT_ID: u8 = 0x01;
+const CAPABILITIES_REPORT_ID: u8 = 0x02;
+const LATENCY_MODE_REPORT_ID: u8 = 0x03;
+const INPUT_MODE_REPORT_ID: u8 = 0x04;
+const FUNCTION_SWITCH_REPORT_ID: u8 = 0x05;
+
+/// Length of [`PRECISION_TOUCHPAD_REPORT_DESCRIPTOR`]
+const DEFAULT_DESCRIPTOR_LEN: usize = precision_touchpad_descriptor::<512>(4095, 4095, 1000, 650)
+    .as_bytes()
+    .len();
+
+/// 100mm by 65mm touchpad with coordinates from 0 to 4095
+pub const PRECISION_TOUCHPAD_REPORT_DESCRIPTOR: &[u8] = DEFAULT_DESCRIPTOR.as_bytes();
+
+const DEFAULT_DESCRIPTOR: &ReportDescriptor<DEFAULT_DESCRIPTOR_LEN> =
+    &precision_touchpad_descriptor(4095, 4095, 1000, 650).validate();
+
+#[rustfmt::skip]
+const TOUCHPAD_COLLECTION: &[u8] = &[
+    0x05, 0x0D,                   // Usage Page (Digitizer)
+    0x09, 0x05,                   // Usage (Touch Pad)
+    0xA1, 0x01,                   // Collection (Application)
+    0x85, INPUT_REPORT_ID,        //   Report ID
+];
+
+#[rustfmt::skip]
+const CONTACT_FLAGS_AND_ID: &[u8] = &[
+    0x05, 0x0D,                   // Usage Page (Digitizer)
+    0x09, 0x22,                   // Usage (Finger)
+    0xA1, 0x02,                   // Collection (Logical)
+    0x15, 0x00,                   //   Logical Minimum (0)
+    0x25, 0x01,                   //   Logical Maximum (1)
+    0x75, 0x01,                   //   Report Size (1)
+    0x95, 0x01,                   //   Report Count (1)
+    0x09, 0x47,                   //   Usage (Confidence)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x09, 0x42,                   //   Usage (Tip Switch)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x95, 0x06,                   //   Report Count (6)
+    0x81, 0x03,                   //   Input (Constant)
+    0x25, 0x7F,                   //   Logical Maximum (127)
+    0x75, 0x08,                   //   Report Size (8)
+    0x95, 0x01,                   //   Report Count (1)
+    0x09, 0x51,                   //   Usage (Contact Identifier)
+    0x81, 0x02,                   //   Input (Data, Variable, Absolute)
+    0x05, 0x01,                   //   Usage Page (Generic Desktop)
+    0x75, 0x10,                   //   Report Size (16)
+];
+
+#[rustfmt::skip]
+const SCAN_TIME: &[u8] = &[
+    0x75, 0x10,                   // Report Size (16)
+    0x95, 0x01,                   // Report Count (1)
+    0x09, 0x56,                   // Usage (Scan Time)
+    0x81, 0x02,                   // Input (Data, Variable, Absolute)
+];
+
+#[rustfmt::skip]
+const CONTACT_COUNT_BUTTON_AND_FEATURES: &[u8] = &[
+    0x25, 0x7F,                   // Logical Maximum (127)
+    0x75, 0x08,                   // Report Size (8)
+    0x09, 0x54,                   // Usage (Contact Count)
+    0x81, 0x02,                   // Input (Data, Variable, Absolute)
+    0x05, 0x09,                   // Usage Page (Button)
+    0x09, 0x01,                   // Usage (Button 1)
+    0x25, 0x01,                   // Logical Maximum (1)
+    0x75, 0x01,                   // Report Size (1)
+    0x81, 0x02,                   // Input (Data, Variable, Absolute)
+    0x95, 0x07,                   // Report Count (7)
+    0x81, 0x03,                   // Input (Constant)
+    0x05, 0x0D,                   // Usage Page (Digitizer)
+    0x85, CAPABILITIES_REPORT_ID, // Report ID
+    0x09, 0x55,                   // Usage (Contact Count Maximum)
+    0x09, 0x59,                   // Usage (Pad Type)
+    0x25, 0x0F,                   // Logical Maximum (15)
+    0x75, 0x04,                   // Report Size (4)
+    0x95, 0x02,                   // Report Count (2)
+    0xB1, 0x02,                   // Feature (Data, Variable, Absolute)
+    0x85, LATENCY_MODE_REPORT_ID, // Report ID
+    0x09, 0x60,                   // Usage (Latency Mode)
+    0x25, 0x01,                   // Logical Maximum (1)
+    0x75, 0x01,                   // Report Size (1)
+    0x95, 0x01,                   // Report Count (1)
+    0xB1, 0x02,                   // Feature (Data, Variable, Absolute)
+    0x95, 0x07,                   // Report Count (7)
+    0xB1, 0x03,                   // Feature (Constant)
+    0xC0,                         // End Collection
+];
+
+#[rustfmt::skip]
+const CONFIGURATION_COLLECTION: &[u8] = &[
+    0x05, 0x0D,                   // Usage Page (Digitizer)
+    0x09, 0x0E,                   // Usage (Device Configuration)
+    0xA1, 0x01,                   // Collection (Application)
+    0x85, INPUT_MODE_REPORT_ID,   //   Report ID
+    0x09, 0x22,                   //   Usage (Finger)
+    0xA1, 0x02,                   //   Collection (Logical)
+    0x09, 0x52,                   //     Usage (Input Mode)
+    0x15, 0x00,                   //     Logical Minimum (0)
+    0x25, 0x0A,                   //     Logical Maximum (10)
+    0x75, 0x08,                   //     Report Size (8)
+    0x95, 0x01,                   //     Report Count (1)
+    0xB1, 0x02,                   //     Feature (Data, Variable, Absolute)
+    0xC0,                         //   End Collection
+    0x09, 0x22,                   //   Usage (Finger)
+    0xA1, 0x00,                   //   Collection (Physical)
+    0x85, FUNCTION_SWITCH_REPORT_ID, //   Report ID
+    0x09, 0x57,                   //     Usage (Surface Switch)
+    0x09, 0x58,                   //     Usage (Button Switch)
+    0x25, 0x01,                   //     Logical Maximum (1)
+    0x75, 0x01,                   //     Report Size (1)
+    0x95, 0x02,                   //     Report Count (2)
+    0xB1, 0x02,                   //     Feature (Data, Variable, Absolute)
+    0x95, 0x06,                   //     Report Count (6)
+    0xB1, 0x03,                   //     Feature (Constant)
+    0xC0,                         //   End Collection
+    0xC0,                         // End Collection
+];
+
+/// Report descriptor of a touchpad with coordinates from 0 to `x_max` and `y_max`, `width` and
+/// `height` in tenths of a millimeter
+///
+/// # Panics
+///
+/// If the descriptor is longer than `N` bytes
+#[must_use]
+pub const fn precision_touchpad_descriptor<const N: usize>(
+    x_max: u16,
+    y_max: u16,
+    width: u16,
+    height: u16,
+) -> ReportDescriptor<N> {
+    let mut descriptor = ReportDescriptor::new().bytes(TOUCHPAD_COLLECTION);
+
+    let mut i = 0;
+    while i < MAX_CONTACTS {
+        descriptor = descriptor
+            .bytes(CONTACT_FLAGS_AND_ID)
+            .item(Item::unit_exponent(-2))
+            .item(Item::unit(Unit::CENTIMETER))
+            .item(Item::physical_minimum(0))
+            .bytes(&[0x09, 0x30]) // Usage (X)
+            .item(Item::physical_maximum(width as i32))
+            .item(Item::logical_maximum(x_max as i32))
+            .bytes(&[0x81, 0x02]) // Input (Data, Variable, Absolute)
+            .bytes(&[0x09, 0x31]) // Usage (Y)
+            .item(Item::physical_maximum(height as i32))
+            .item(Item::logical_maximum(y_max as i32))
+            .bytes(&[0x81, 0x02]) // Input (Data, Variable, Absolute)
+            .bytes(&[0xC0]); // End Collection
+        i += 1;
+    }
+
+    descriptor
+        .bytes(&[0x05, 0x0D]) // Usage Page (Digitizer)
+        // Scan time in units of 100us
+        .item(Item::unit_exponent(-4))
+        .item(Item::unit(Unit::SECOND))
+        .item(Item::physical_maximum(0xFFFF))
+        .item(Item::logical_maximum(0xFFFF))
+        .bytes(SCAN_TIME)
+        .item(Item::unit_exponent(0))
+        .item(Item::unit(Unit::NONE))
+        .item(Item::physical_maximum(0))
+        .bytes(CONTACT_COUNT_BUTTON_AND_FEATURES)
+        .bytes(CONFIGURATION_COLLECTION)
+}
+
+/// A single contact with the touchpad surface
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "msb0", size_bytes = "6")]
+pub struct TouchpadContact {
+    /// False if the contact is unintentional, such as a palm
+    #[packed_field(bits = "7")]
+    pub confidence: bool,
+    /// True while the contact is touching the surface
+    #[packed_field(bits = "6")]
+    pub tip: bool,
+    /// Identifies the contact while it is tracked across reports
+    #[packed_field(bits = "8..=15")]
+    pub id: u8,
+    #[packed_field(bits = "16..=31")]
+    pub x: u16,
+    #[packed_field(bits = "32..=47")]
+    pub y: u16,
+}
+
+/// Report for a [`PrecisionTouchpad`]
+///
+/// Only the first `contact_count` contacts are valid, the contact count of reports continuing a
+/// frame of more than [`MAX_CONTACTS`] contacts is 0
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "msb0", size_bytes = "34")]
+pub struct TouchpadReport {
+    #[packed_field(element_size_bytes = "6")]
+    pub contacts: [TouchpadContact; 5],
+    /// Time of the scan in units of 100us, relative to an arbitrary start
+    #[packed_field]
+    pub scan_time: u16,
+    #[packed_field]
+    pub contact_count: u8,
+    /// The state of the touchpad's button, or of clicking the surface of a clickpad
+    #[packed_field(bits = "271")]
+    pub button: bool,
+}
+
+/// The physical button arrangement of a touchpad
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+#[repr(u8)]
+pub enum PadType {
+    /// Clicks by depressing the surface
+    #[default]
+    Clickpad = 0,
+    /// Clicks by detecting pressure on the surface
+    Pressurepad = 1,
+    /// Separate buttons
+    NonClickable = 2,
+}
+
+/// Reporting mode selected by the host
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum InputMode {
+    /// The host hasn't loaded the Precision Touchpad driver
+    #[default]
+    Mouse,
+    Touchpad,
+}
+
+/// Interface implementing a Windows Precision Touchpad
+///
+/// Reports should only be written while the host has selected [`InputMode::Touchpad`] and the
+/// [`PrecisionTouchpad::surface_enabled`]
+pub struct PrecisionTouchpad<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes64, OutBytes8, Reports8>,
+    max_contacts: u8,
+    pad_type: PadType,
+    input_mode: InputMode,
+    latency_mode: bool,
+    surface_enabled: bool,
+    button_enabled: bool,
+}
+
+impl<B: UsbBus> PrecisionTouchpad<'_, B> {
+    pub fn write_report(&mut self, report: &TouchpadReport) -> Result<(), UsbHidError> {
+        let mut data = [0; 35];
+        data[0] = INPUT_REPORT_ID;
+        report.pack_to_slice(&mut data[1..]).map_err(|_| {
+            error!("Error packing TouchpadReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+
+    #[must_use]
+    pub fn input_mode(&self) -> InputMode {
+        self.input_mode
+    }
+
+    /// True if the host has requested high latency reporting, to reduce power consumption
+    #[must_use]
+    pub fn latency_mode(&self) -> bool {
+        self.latency_mode
+    }
+
+    /// False if the host has disabled reporting surface contacts
+    #[must_use]
+    pub fn surface_enabled(&self) -> bool {
+        self.surface_enabled
+    }
+
+    /// False if the host has disabled reporting the button
+    #[must_use]
+    pub fn button_enabled(&self) -> bool {
+        self.button_enabled
+    }
+
+    fn set_feature_reports(&mut self) -> usb_device::Result<()> {
+        self.interface.set_feature_report(&[
+            CAPABILITIES_REPORT_ID,
+            (self.max_contacts & 0xF) | ((self.pad_type as u8) << 4),
+        ])?;
+        self.interface
+            .set_feature_report(&[LATENCY_MODE_REPORT_ID, u8::from(self.latency_mode)])?;
+        self.interface.set_feature_report(&[
+            INPUT_MODE_REPORT_ID,
+            match self.input_mode {
+                InputMode::Mouse => 0,
+                InputMode::Touchpad => 3,
+            },
+        ])?;
+        self.interface.set_feature_report(&[
+            FUNCTION_SWITCH_REPORT_ID,
+            u8::from(self.surface_enabled) | (u8::from(self.button_enabled) << 1),
+        ])
+    }
+
+    fn feature_report_set(&mut self, data: &[u8]) {
+        match *data {
+            [LATENCY_MODE_REPORT_ID, value, ..] => {
+                self.latency_mode = value & 0x1 != 0;
+                info!("Touchpad latency mode {}", self.latency_mode);
+            }
+            [INPUT_MODE_REPORT_ID, value, ..] => {
+                self.input_mode = if value == 3 {
+                    InputMode::Touchpad
+                } else {
+                    InputMode::Mouse
+                };
+                info!("Touchpad input mode {:?}", self.input_mode);
+            }
+            [FUNCTION_SWITCH_REPORT_ID, value, ..] => {
+                self.surface_enabled = value & 0x1 != 0;
+                self.button_enabled = value & 0x2 != 0;
+                info!(
+                    "Touchpad surface enabled {}, button enabled {}",
+                    self.surface_enabled, self.button_enabled
+                );
+            }
+            _ => warn!("Unexpected touchpad report"),
+        }
+    }
+}
+
+impl<B: UsbBus> ReportWriter<TouchpadReport> for PrecisionTouchpad<'_, B> {
+    fn write_report(&mut self, report: &TouchpadReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for PrecisionTouchpad<'a, B> {
+    type I = Interface<'a, B, InBytes64, OutBytes8, Reports8>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.input_mode = InputMode::Mouse;
+        self.latency_mode = false;
+        self.surface_enabled = true;
+        self.button_enabled = true;
+        if self.set_feature_reports().is_err() {
+            error!("Failed to set touchpad feature reports");
+        }
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        let mut data = [0; 8];
+        match self.interface.read_report(&mut data) {
+            Ok(n) => self.feature_report_set(&data[..n]),
+            Err(UsbError::WouldBlock) => {}
+            Err(e) => return Err(UsbHidError::from(e)),
+        }
+        Ok(())
+    }
+}
+
+pub struct PrecisionTouchpadConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes64, OutBytes8, Reports8>,
+    max_contacts: u8,
+    pad_type: PadType,
+}
+
+impl Default for PrecisionTouchpadConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(PRECISION_TOUCHPAD_REPORT_DESCRIPTOR))
+                    .description("Touchpad")
+                    .in_endpoint(1.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+            PadType::Clickpad,
+        )
+    }
+}
+
+impl<'a> PrecisionTouchpadConfig<'a> {
+    /// `interface` must have a report descriptor generated by [`precision_touchpad_descriptor`]
+    #[must_use]
+    pub fn new(
+        interface: InterfaceConfig<'a, InBytes64, OutBytes8, Reports8>,
+        pad_type: PadType,
+    ) -> Self {
+        Self {
+            interface,
+            #[allow(clippy::cast_possible_truncation)]
+            max_contacts: MAX_CONTACTS as u8,
+            pad_type,
+        }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for PrecisionTouchpadConfig<'a> {
+    type Allocated = PrecisionTouchpad<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        let mut touchpad = Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            max_contacts: self.max_contacts,
+            pad_type: self.pad_type,
+            input_mode: InputMode::Mouse,
+            latency_mode: false,
+            surface_enabled: true,
+            button_enabled: true,
+        };
+        if touchpad.set_feature_reports().is_err() {
+            error!("Failed to set touchpad feature reports");
+        }
+        touchpad
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn reports_match_descriptor() {
+        let descriptor = PRECISION_TOUCHPAD_REPORT_DESCRIPTOR;
+        let lengths = [
+            (ReportType::Input, INPUT_REPORT_ID, 35),
+            (ReportType::Feature, CAPABILITIES_REPORT_ID, 2),
+            (ReportType::Feature, LATENCY_MODE_REPORT_ID, 2),
+            (ReportType::Feature, INPUT_MODE_REPORT_ID, 2),
+            (ReportType::Feature, FUNCTION_SWITCH_REPORT_ID, 2),
+        ];
+        for (report_type, report_id, len) in lengths {
+            assert_eq!(
+                report_length(descriptor, report_type, report_id),
+                Some(len),
+                "{report_type:?} report {report_id}"
+            );
+        }
+
+        let report = TouchpadReport {
+            contacts: [TouchpadContact {
+                confidence: true,
+                tip: true,
+                id: 3,
+                x: 0x0102,
+                y: 0x0304,
+            }; MAX_CONTACTS],
+            scan_time: 0x0506,
+            contact_count: 1,
+            button: true,
+        };
+        let packed = report.pack().unwrap();
+        assert_eq!(packed[..6], [0x3, 3, 0x02, 0x01, 0x04, 0x03]);
+        assert_eq!(packed[30..], [0x06, 0x05, 1, 1]);
+    }
+}
@@ -0,0 +1,275 @@
+//!HID VR controller with buttons, analog controls and orientation
+//!
+//! A single interface with two reports: a gamepad style controls report, and a HID sensor device
+//! orientation report holding the controller's rotation as a quaternion. The reports are written
+//! separately, so orientation can be reported at a different rate to the controls.
+//!
+//! The sensor collection only declares the orientation data, the property feature reports used
+//! by the Windows sensor class driver aren't provided. Hosts reading the HID reports directly,
+//! such as VR runtime drivers, can use the orientation report as it is.
+//!
+//! Sensor usages are defined by [HID Sensor Usages](<https://www.usb.org/sites/default/files/hutrr39b_0.pdf>)
+use crate::descriptor::item::{Item, ReportDescriptor};
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+
+const CONTROLS_REPORT_ID: u8 = 0x01;
+const ORIENTATION_REPORT_ID: u8 = 0x02;
+
+/// Value of a quaternion component of 1.0 in a [`VrOrientationReport`]
+pub const QUATERNION_ONE: i16 = 10000;
+
+const SENSORS_PAGE: u16 = 0x20;
+const DEVICE_ORIENTATION: u16 = 0x8A;
+const ROTATION_QUATERNION: u16 = 0x0483;
+
+#[rustfmt::skip]
+const DESCRIPTOR: &ReportDescriptor<112> = &ReportDescriptor::new()
+    .bytes(&[
+        0x05, 0x01,               // Usage Page (Generic Desktop)
+        0x09, 0x05,               // Usage (Gamepad)
+        0xA1, 0x01,               // Collection (Application)
+        0x85, CONTROLS_REPORT_ID, //   Report ID
+        0x05, 0x09,               //   Usage Page (Button)
+        0x19, 0x01,               //   Usage Minimum (1)
+        0x29, 0x10,               //   Usage Maximum (16)
+        0x15, 0x00,               //   Logical Minimum (0)
+        0x25, 0x01,               //   Logical Maximum (1)
+        0x75, 0x01,               //   Report Size (1)
+        0x95, 0x10,               //   Report Count (16)
+        0x81, 0x02,               //   Input (Data, Variable, Absolute)
+        0x05, 0x01,               //   Usage Page (Generic Desktop)
+        0x09, 0x30,               //   Usage (X)
+        0x09, 0x31,               //   Usage (Y)
+    ])
+    .item(Item::logical_minimum(-32767))
+    .item(Item::logical_maximum(32767))
+    .bytes(&[
+        0x75, 0x10,               //   Report Size (16)
+        0x95, 0x02,               //   Report Count (2)
+        0x81, 0x02,               //   Input (Data, Variable, Absolute)
+        0x09, 0x32,               //   Usage (Z), trigger
+        0x09, 0x35,               //   Usage (Rz), grip
+    ])
+    .item(Item::logical_minimum(0))
+    .item(Item::logical_maximum(65535))
+    .bytes(&[
+        0x81, 0x02,               //   Input (Data, Variable, Absolute)
+        0xC0,                     // End Collection
+    ])
+    .item(Item::usage_page(SENSORS_PAGE))
+    .item(Item::usage(DEVICE_ORIENTATION))
+    .bytes(&[
+        0xA1, 0x01,                  // Collection (Application)
+        0x85, ORIENTATION_REPORT_ID, //   Report ID
+    ])
+    .item(Item::usage(ROTATION_QUATERNION))
+    .item(Item::logical_minimum(-(QUATERNION_ONE as i32)))
+    .item(Item::logical_maximum(QUATERNION_ONE as i32))
+    .item(Item::unit_exponent(-4))
+    .bytes(&[
+        0x75, 0x10,               //   Report Size (16)
+        0x95, 0x04,               //   Report Count (4)
+        0x81, 0x02,               //   Input (Data, Variable, Absolute)
+    ])
+    .item(Item::unit_exponent(0))
+    .bytes(&[0xC0]) // End Collection
+    .validate();
+
+/// Gamepad collection with 16 buttons, a thumbstick, trigger and grip, and a sensor collection
+/// with the controller's orientation
+pub const VR_CONTROLLER_REPORT_DESCRIPTOR: &[u8] = DESCRIPTOR.as_bytes();
+
+/// Controls report for a [`VrController`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "11")]
+pub struct VrControlsReport {
+    #[packed_field]
+    pub report_id: u8,
+    /// Bit 0 is button 1
+    #[packed_field]
+    pub buttons: u16,
+    #[packed_field]
+    pub thumbstick_x: i16,
+    #[packed_field]
+    pub thumbstick_y: i16,
+    #[packed_field]
+    pub trigger: u16,
+    #[packed_field]
+    pub grip: u16,
+}
+
+impl Default for VrControlsReport {
+    fn default() -> Self {
+        Self {
+            report_id: CONTROLS_REPORT_ID,
+            buttons: 0,
+            thumbstick_x: 0,
+            thumbstick_y: 0,
+            trigger: 0,
+            grip: 0,
+        }
+    }
+}
+
+/// Orientation report for a [`VrController`]
+///
+/// Quaternion components are scaled so that [`QUATERNION_ONE`] is 1.0
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PackedStruct)]
+#[packed_struct(endian = "lsb", size_bytes = "9")]
+pub struct VrOrientationReport {
+    #[packed_field]
+    pub report_id: u8,
+    #[packed_field]
+    pub x: i16,
+    #[packed_field]
+    pub y: i16,
+    #[packed_field]
+    pub z: i16,
+    #[packed_field]
+    pub w: i16,
+}
+
+impl VrOrientationReport {
+    /// Orientation from a unit quaternion with components from -1.0 to 1.0
+    #[must_use]
+    pub fn from_quaternion(x: f32, y: f32, z: f32, w: f32) -> Self {
+        #[allow(clippy::cast_possible_truncation)]
+        let scale = |v: f32| (v.clamp(-1.0, 1.0) * f32::from(QUATERNION_ONE)) as i16;
+        Self {
+            x: scale(x),
+            y: scale(y),
+            z: scale(z),
+            w: scale(w),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for VrOrientationReport {
+    /// The identity rotation
+    fn default() -> Self {
+        Self {
+            report_id: ORIENTATION_REPORT_ID,
+            x: 0,
+            y: 0,
+            z: 0,
+            w: QUATERNION_ONE,
+        }
+    }
+}
+
+pub struct VrController<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes16, OutNone, Reports8>,
+}
+
+impl<B: UsbBus> VrController<'_, B> {
+    pub fn write_controls(&mut self, report: &VrControlsReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing VrControlsReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+
+    pub fn write_orientation(&mut self, report: &VrOrientationReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing VrOrientationReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+}
+
+impl<B: UsbBus> ReportWriter<VrControlsReport> for VrController<'_, B> {
+    fn write_report(&mut self, report: &VrControlsReport) -> Result<(), UsbHidError> {
+        self.write_controls(report)
+    }
+}
+
+impl<B: UsbBus> ReportWriter<VrOrientationReport> for VrController<'_, B> {
+    fn write_report(&mut self, report: &VrOrientationReport) -> Result<(), UsbHidError> {
+        self.write_orientation(report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for VrController<'a, B> {
+    type I = Interface<'a, B, InBytes16, OutNone, Reports8>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {}
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        Ok(())
+    }
+}
+
+pub struct VrControllerConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes16, OutNone, Reports8>,
+}
+
+impl Default for VrControllerConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(
+                unwrap!(InterfaceBuilder::new(VR_CONTROLLER_REPORT_DESCRIPTOR))
+                    .description("VR Controller")
+                    .in_endpoint(1.millis())
+            )
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> VrControllerConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes16, OutNone, Reports8>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for VrControllerConfig<'a> {
+    type Allocated = VrController<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn reports_match_descriptor() {
+        let descriptor = VR_CONTROLLER_REPORT_DESCRIPTOR;
+        assert_eq!(
+            report_length(descriptor, ReportType::Input, CONTROLS_REPORT_ID),
+            Some(11)
+        );
+        assert_eq!(
+            report_length(descriptor, ReportType::Input, ORIENTATION_REPORT_ID),
+            Some(9)
+        );
+
+        let report = VrOrientationReport::from_quaternion(0.0, -0.5, 2.0, 0.0);
+        assert_eq!(
+            report.pack().unwrap(),
+            [0x02, 0, 0, 0x78, 0xEC, 0x10, 0x27, 0, 0]
+        );
+    }
+}
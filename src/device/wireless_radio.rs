@@ -0,0 +1,169 @@
+//!HID wireless radio controls, such as an airplane mode button
+//!
+//! Defined by the Generic Desktop usage page, and [Wireless Radio Controls](<https://learn.microsoft.com/en-us/windows-hardware/drivers/hid/airplane-mode-radio-management>)
+use crate::usb_class::prelude::*;
+use core::default::Default;
+use fugit::ExtU32;
+use packed_struct::prelude::*;
+use usb_device::bus::UsbBus;
+use usb_device::class_prelude::UsbBusAllocator;
+use usb_device::UsbError;
+
+/// Wireless radio button and radio LED
+#[rustfmt::skip]
+pub const WIRELESS_RADIO_CONTROLS_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop)
+    0x09, 0x0C, // Usage (Wireless Radio Controls)
+    0xA1, 0x01, // Collection (Application)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x09, 0xC6, //   Usage (Wireless Radio Button)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x06, //   Input (Data, Variable, Relative)
+    0x75, 0x07, //   Report Size (7)
+    0x81, 0x03, //   Input (Constant)
+    0x09, 0xC7, //   Usage (Wireless Radio LED)
+    0x75, 0x01, //   Report Size (1)
+    0x91, 0x02, //   Output (Data, Variable, Absolute)
+    0x75, 0x07, //   Report Size (7)
+    0x91, 0x03, //   Output (Constant)
+    0xC0,       // End Collection
+];
+
+/// Report for [`WirelessRadioControls`]
+///
+/// The host toggles the state of all radios each time a report with [`Self::button`] set is
+/// received, so a press should be followed by a report with the button released
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "lsb0", size_bytes = "1")]
+pub struct WirelessRadioReport {
+    #[packed_field(bits = "0")]
+    pub button: bool,
+}
+
+/// Output report of [`WirelessRadioControls`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "lsb0", size_bytes = "1")]
+pub struct WirelessRadioLedReport {
+    /// Lit while any of the host's radios are on
+    #[packed_field(bits = "0")]
+    pub led: bool,
+}
+
+pub struct WirelessRadioControls<'a, B: UsbBus> {
+    interface: Interface<'a, B, InBytes8, OutBytes8, ReportSingle>,
+    led: WirelessRadioLedReport,
+    led_changed: bool,
+}
+
+impl<B: UsbBus> WirelessRadioControls<'_, B> {
+    pub fn write_report(&mut self, report: &WirelessRadioReport) -> Result<(), UsbHidError> {
+        let data = report.pack().map_err(|_| {
+            error!("Error packing WirelessRadioReport");
+            UsbHidError::SerializationError
+        })?;
+        self.interface.write_report(&data).map(|_| ())
+    }
+
+    /// The radio LED last set by the host
+    #[must_use]
+    pub fn led(&self) -> WirelessRadioLedReport {
+        self.led
+    }
+
+    /// Returns the radio LED if the host has changed it since this was last called
+    pub fn led_changed(&mut self) -> Option<WirelessRadioLedReport> {
+        core::mem::take(&mut self.led_changed).then_some(self.led)
+    }
+}
+
+impl<B: UsbBus> ReportWriter<WirelessRadioReport> for WirelessRadioControls<'_, B> {
+    fn write_report(&mut self, report: &WirelessRadioReport) -> Result<(), UsbHidError> {
+        Self::write_report(self, report)
+    }
+}
+
+impl<'a, B: UsbBus> DeviceClass<'a> for WirelessRadioControls<'a, B> {
+    type I = Interface<'a, B, InBytes8, OutBytes8, ReportSingle>;
+
+    fn interface(&mut self) -> &mut Self::I {
+        &mut self.interface
+    }
+
+    fn reset(&mut self) {
+        self.led = WirelessRadioLedReport::default();
+        self.led_changed = false;
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        let mut data = [0; 1];
+        match self.interface.read_report(&mut data) {
+            Ok(_) => {
+                let led = WirelessRadioLedReport::unpack(&data).map_err(|_| {
+                    error!("Error unpacking WirelessRadioLedReport");
+                    UsbHidError::SerializationError
+                })?;
+                if led != self.led {
+                    self.led = led;
+                    self.led_changed = true;
+                }
+                Ok(())
+            }
+            Err(UsbError::WouldBlock) => Ok(()),
+            Err(e) => Err(UsbHidError::from(e)),
+        }
+    }
+}
+
+pub struct WirelessRadioControlsConfig<'a> {
+    interface: InterfaceConfig<'a, InBytes8, OutBytes8, ReportSingle>,
+}
+
+impl Default for WirelessRadioControlsConfig<'_> {
+    fn default() -> Self {
+        Self::new(
+            unwrap!(unwrap!(InterfaceBuilder::new(
+                WIRELESS_RADIO_CONTROLS_REPORT_DESCRIPTOR
+            ))
+            .description("Wireless Radio Controls")
+            .in_endpoint(50.millis()))
+            .without_out_endpoint()
+            .build(),
+        )
+    }
+}
+
+impl<'a> WirelessRadioControlsConfig<'a> {
+    #[must_use]
+    pub fn new(interface: InterfaceConfig<'a, InBytes8, OutBytes8, ReportSingle>) -> Self {
+        Self { interface }
+    }
+}
+
+impl<'a, B: UsbBus + 'a> UsbAllocatable<'a, B> for WirelessRadioControlsConfig<'a> {
+    type Allocated = WirelessRadioControls<'a, B>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Self::Allocated {
+            interface: Interface::new(usb_alloc, self.interface),
+            led: WirelessRadioLedReport::default(),
+            led_changed: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::descriptor::item::report_length;
+    use crate::descriptor::ReportType;
+
+    #[test]
+    fn reports_match_descriptor() {
+        let descriptor = WIRELESS_RADIO_CONTROLS_REPORT_DESCRIPTOR;
+        assert_eq!(report_length(descriptor, ReportType::Input, 0), Some(1));
+        assert_eq!(report_length(descriptor, ReportType::Output, 0), Some(1));
+        assert_eq!(WirelessRadioReport { button: true }.pack(), Ok([0x01]));
+    }
+}
@@ -228,15 +228,18 @@ macro_rules! unwrap {
     }
 }
 
+#[cfg(not(feature = "defmt"))]
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct NoneError;
 
+#[cfg(not(feature = "defmt"))]
 pub trait Try {
     type Ok;
     type Error;
     fn into_result(self) -> Result<Self::Ok, Self::Error>;
 }
 
+#[cfg(not(feature = "defmt"))]
 impl<T> Try for Option<T> {
     type Ok = T;
     type Error = NoneError;
@@ -247,6 +250,7 @@ impl<T> Try for Option<T> {
     }
 }
 
+#[cfg(not(feature = "defmt"))]
 impl<T, E> Try for Result<T, E> {
     type Ok = T;
     type Error = E;
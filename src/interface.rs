@@ -1,16 +1,19 @@
 //! Human Interface Device Interfaces
+use crate::descriptor::item::{check_report_descriptor, max_report_length, report_length};
 use crate::descriptor::{
-    DescriptorType, HidProtocol, InterfaceProtocol, InterfaceSubClass, COUNTRY_CODE_NOT_SUPPORTED,
+    DescriptorType, HidCountryCode, HidProtocol, InterfaceProtocol, InterfaceSubClass, ReportType,
     SPEC_VERSION_1_11, USB_CLASS_HID,
 };
-use crate::device::DeviceClass;
+use crate::device::{DeviceClass, ReportWriter};
 use crate::private::Sealed;
+use crate::serialize::{ReportSerializer, MAX_SERIALIZED_REPORT_LEN};
 use crate::usb_class::{BuilderResult, UsbHidBuilderError};
 use crate::UsbHidError;
+use core::cell::Cell;
 use core::marker::PhantomData;
 use frunk::{HCons, HNil};
-use fugit::{ExtU32, MillisDurationU32};
-use heapless::Vec;
+use fugit::{ExtU32, MicrosDurationU32, MillisDurationU32};
+use heapless::{Deque, Vec};
 use option_block::{Block128, Block16, Block32, Block64, Block8};
 use packed_struct::prelude::*;
 use packed_struct::PackedStruct;
@@ -18,7 +21,26 @@ use usb_device::bus::{StringIndex, UsbBus, UsbBusAllocator};
 #[allow(clippy::wildcard_imports)]
 use usb_device::class_prelude::*;
 use usb_device::class_prelude::{DescriptorWriter, InterfaceNumber};
-use usb_device::UsbError;
+use usb_device::control::Request;
+use usb_device::endpoint::{Endpoint, EndpointDirection};
+use usb_device::{UsbDirection, UsbError};
+
+/// Number of report IDs for which the last input report is cached for `GET_REPORT` requests
+const INPUT_REPORT_CACHE_LEN: usize = 4;
+
+/// Number of report IDs for which a feature report can be set with
+/// [`Interface::set_feature_report`]
+pub const FEATURE_REPORT_CACHE_LEN: usize = 4;
+
+/// Number of [`InterfaceEvent`]s queued before the oldest is discarded
+const EVENT_QUEUE_LEN: usize = 4;
+
+/// Number of reports held by an interface built with [`InterfaceBuilder::queue_reports`] while
+/// its IN endpoint is busy
+pub const REPORT_QUEUE_LEN: usize = 2;
+
+/// Number of alternate settings an interface can declare in addition to the default setting
+pub const MAX_ALTERNATE_SETTINGS: usize = 3;
 
 #[derive(Debug, PackedStruct)]
 #[packed_struct(endian = "lsb", size_bytes = 7)]
@@ -62,18 +84,322 @@ where
 
 pub trait InterfaceClass<'a> {
     fn hid_descriptor_body(&self) -> [u8; 7];
+    /// The report descriptor, empty if it is provided by a [`ReportDescriptorSource`]
     fn report_descriptor(&self) -> &'_ [u8];
+    /// Copy the first `length` bytes of the report descriptor, or the whole descriptor if it's
+    /// shorter, into `buf`, returning the number of bytes copied
+    ///
+    /// # Errors
+    ///
+    /// [`UsbError::BufferOverflow`] if the bytes to copy are longer than `buf`
+    fn read_report_descriptor(&self, length: usize, buf: &mut [u8]) -> usb_device::Result<usize> {
+        let descriptor = self.report_descriptor();
+        copy_descriptor(&descriptor[..length.min(descriptor.len())], buf)
+    }
     fn id(&self) -> InterfaceNumber;
     fn write_descriptors(&self, writer: &mut DescriptorWriter) -> usb_device::Result<()>;
-    fn get_string(&self, index: StringIndex, _lang_id: u16) -> Option<&'a str>;
+    fn get_string(&self, index: StringIndex, lang_id: LangID) -> Option<&'a str>;
     fn reset(&mut self);
-    fn set_report(&mut self, data: &[u8]) -> usb_device::Result<()>;
-    fn get_report(&self, data: &mut [u8]) -> usb_device::Result<usize>;
+    fn write_report(&mut self, data: &[u8]) -> Result<usize, UsbHidError>;
+    fn read_report(&mut self, data: &mut [u8]) -> usb_device::Result<usize>;
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()>;
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize>;
     fn get_report_ack(&mut self) -> usb_device::Result<()>;
     fn set_idle(&mut self, report_id: u8, value: u8);
     fn get_idle(&self, report_id: u8) -> u8;
-    fn set_protocol(&mut self, protocol: HidProtocol);
+    /// Handle a `SET_PROTOCOL` request, returns false if the interface doesn't support `protocol`
+    fn set_protocol(&mut self, protocol: HidProtocol) -> bool;
     fn get_protocol(&self) -> HidProtocol;
+    /// Called every 1ms by [`UsbHidClass::tick`](crate::usb_class::UsbHidClass::tick), before the
+    /// device is ticked, to count down the minimum report interval
+    fn tick_rate_limit(&mut self);
+    /// Counters of the interface's activity since it was created
+    fn stats(&self) -> InterfaceStats;
+    /// The current alternate setting, 0 unless the host has selected another
+    fn alt_setting(&self) -> u8;
+    /// Handle a `SET_INTERFACE` request, returns false if the interface doesn't have `alt_setting`
+    fn set_alt_setting(&mut self, alt_setting: u8) -> bool;
+    /// Take the oldest unhandled request from the host, see [`InterfaceEvent`]
+    fn take_event(&mut self) -> Option<InterfaceEvent>;
+    /// Called when the host has read from the IN endpoint `addr`, which may belong to another
+    /// interface
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress);
+    /// True from writing a report to the IN endpoint until the host has read it
+    fn is_write_pending(&self) -> bool;
+    /// Returns true if a report written to the IN endpoint has been read by the host since this
+    /// was last called
+    fn take_write_complete(&mut self) -> bool;
+    /// Handle a device to host control request that isn't a HID request, see
+    /// [`ControlRequestHandler::control_in`]
+    fn control_in(&mut self, request: &Request, data: &mut [u8]) -> Option<usize>;
+    /// Handle a host to device control request that isn't a HID request, see
+    /// [`ControlRequestHandler::control_out`]
+    fn control_out(&mut self, request: &Request, data: &[u8]) -> bool;
+    /// Set whether the bus is suspended, and if the host has enabled remote wakeup
+    fn set_suspended(&mut self, suspended: bool, remote_wakeup_enabled: bool);
+    /// Returns true if the interface has requested remote wakeup since this was last called
+    fn take_wakeup_request(&mut self) -> bool;
+    /// False if the interface has been deactivated, when only standard requests and HID idle
+    /// and protocol requests are handled
+    fn is_active(&self) -> bool;
+    /// Called when the host sets or clears the halt feature of endpoint `addr`, which may belong
+    /// to another interface
+    fn endpoint_halt(&mut self, addr: EndpointAddress, halted: bool);
+}
+
+fn copy_descriptor(descriptor: &[u8], buf: &mut [u8]) -> usb_device::Result<usize> {
+    buf.get_mut(..descriptor.len())
+        .ok_or(UsbError::BufferOverflow)?
+        .copy_from_slice(descriptor);
+    Ok(descriptor.len())
+}
+
+/// Request from the host that changes how an interface should report
+///
+/// Events are queued by the interface as the requests are handled, so devices can reconfigure
+/// as soon as the host changes a setting. Only the most recent events are kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InterfaceEvent {
+    /// `SET_PROTOCOL`, which may not have changed the protocol
+    SetProtocol(HidProtocol),
+    /// `SET_IDLE` for `report_id`, 0 for all reports. An `idle` of zero only reports changes
+    SetIdle {
+        report_id: u8,
+        idle: MillisDurationU32,
+    },
+    /// `SET_INTERFACE` selecting a different alternate setting
+    SetInterface(u8),
+    /// The endpoint was halted, by [`Interface::stall`] or the host's
+    /// `SET_FEATURE(ENDPOINT_HALT)`
+    EndpointHalted(EndpointAddress),
+    /// The endpoint's halt was cleared, by [`Interface::unstall`] or the host's
+    /// `CLEAR_FEATURE(ENDPOINT_HALT)`. Any report being written to an IN endpoint was discarded,
+    /// so devices should write their current state again
+    EndpointHaltCleared(EndpointAddress),
+    /// `SET_REPORT` stalled as the report was malformed, e.g. its length didn't match the report
+    /// descriptor. The interface's state is unchanged
+    ReportRejected {
+        report_type: ReportType,
+        report_id: u8,
+    },
+}
+
+/// Diagnostic counters for an interface, see [`InterfaceClass::stats`]
+///
+/// Counters wrap on overflow and are not cleared by a USB reset.
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct InterfaceStats {
+    /// Input reports written to the host
+    pub reports_written: u32,
+    /// Input reports not written as the endpoint was busy or rate limited
+    pub reports_blocked: u32,
+    /// Input reports not written as they duplicated the last report
+    pub duplicates_suppressed: u32,
+    /// Output reports read from the host
+    pub out_reports_read: u32,
+    pub get_report_requests: u32,
+    pub set_report_requests: u32,
+    pub get_idle_requests: u32,
+    pub set_idle_requests: u32,
+    pub get_protocol_requests: u32,
+    pub set_protocol_requests: u32,
+    /// `SET_PROTOCOL` requests that changed the protocol
+    pub protocol_changes: u32,
+    /// `SET_INTERFACE` requests that changed the alternate setting
+    pub alt_setting_changes: u32,
+    /// Input reports read by the host from the IN endpoint
+    pub reports_completed: u32,
+    /// Vendor and non-HID class requests, see [`ControlRequestHandler`]
+    pub control_requests: u32,
+    /// `SET_REPORT` requests stalled as the report was malformed
+    pub reports_rejected: u32,
+    pub resets: u32,
+}
+
+/// Properties of an allocated endpoint, see [`Interface::in_endpoint_info`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EndpointInfo {
+    pub address: EndpointAddress,
+    pub max_packet_size: u16,
+    /// Polling interval, `bInterval`. In milliseconds at full speed, or 2<sup>interval-1</sup>
+    /// 125µs microframes for endpoints configured for high speed, e.g. with
+    /// [`InterfaceBuilder::in_endpoint_high_speed`]
+    pub interval: u8,
+}
+
+impl EndpointInfo {
+    fn new<B: UsbBus, D: EndpointDirection>(endpoint: &Endpoint<'_, B, D>) -> Self {
+        Self {
+            address: endpoint.address(),
+            max_packet_size: endpoint.max_packet_size(),
+            interval: endpoint.interval(),
+        }
+    }
+}
+
+/// Application handler for control pipe `GET_REPORT` and `SET_REPORT` requests
+///
+/// Allows an interface to serve live state, such as feature reports, rather than only reports
+/// previously queued with `write_report`. Methods take `&self`, use interior mutability (e.g.
+/// [`core::cell::RefCell`]) to store state received from the host.
+pub trait ReportHandler {
+    /// Called on `GET_REPORT`. Return `Some(n)` after writing an `n` byte report to `data`, or `None`
+    /// to fall back to the last report queued by the interface
+    fn get_report(&self, report_type: ReportType, report_id: u8, data: &mut [u8]) -> Option<usize> {
+        let _ = (report_type, report_id, data);
+        None
+    }
+
+    /// Called on `SET_REPORT`. Return `true` if the report was consumed, or `false` to buffer the
+    /// report for `read_report`
+    fn set_report(&self, report_type: ReportType, report_id: u8, data: &[u8]) -> bool {
+        let _ = (report_type, report_id, data);
+        false
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ReportHandlerRef<'a>(&'a dyn ReportHandler);
+
+impl core::fmt::Debug for ReportHandlerRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ReportHandler")
+    }
+}
+
+impl PartialEq for ReportHandlerRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::addr_eq(self.0, other.0)
+    }
+}
+
+impl Eq for ReportHandlerRef<'_> {}
+
+/// Application handler for control requests directed at the interface that aren't standard HID
+/// requests
+///
+/// Called for vendor requests, and class requests with an unrecognised `bRequest`, allowing a
+/// vendor configuration protocol, such as uploading a key map, to share the HID interface.
+/// Requests the handler doesn't accept are stalled.
+pub trait ControlRequestHandler {
+    /// Called on a device to host request. Return `Some(n)` after writing an `n` byte response to
+    /// `data`, or `None` to stall the request
+    fn control_in(&self, request: &Request, data: &mut [u8]) -> Option<usize> {
+        let _ = (request, data);
+        None
+    }
+
+    /// Called on a host to device request with its data stage in `data`. Return `true` if the
+    /// request was accepted, or `false` to stall it
+    fn control_out(&self, request: &Request, data: &[u8]) -> bool {
+        let _ = (request, data);
+        false
+    }
+}
+
+#[derive(Clone, Copy)]
+struct ControlHandlerRef<'a>(&'a dyn ControlRequestHandler);
+
+impl core::fmt::Debug for ControlHandlerRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("ControlRequestHandler")
+    }
+}
+
+impl PartialEq for ControlHandlerRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        core::ptr::addr_eq(self.0, other.0)
+    }
+}
+
+impl Eq for ControlHandlerRef<'_> {}
+
+/// Report descriptor provided in parts rather than as a single slice, see
+/// [`InterfaceBuilder::from_source`]
+///
+/// Allows descriptors to be read from external flash, or generated, straight into the control
+/// endpoint buffer when the host requests them, rather than being kept in RAM. Only the part of
+/// the descriptor the host requests is read, in chunks of up to
+/// [`REPORT_DESCRIPTOR_CHUNK_SIZE`] bytes from increasing offsets. That part must still fit in
+/// the control buffer of `usb-device`, which is 128 bytes, or 256 with its `control-buffer-256`
+/// feature.
+pub trait ReportDescriptorSource {
+    /// Length of the whole descriptor in bytes
+    fn length(&self) -> usize;
+
+    /// Copy the descriptor from `offset` into `buf`, returning the number of bytes copied, which
+    /// is only less than the length of `buf` at the end of the descriptor
+    fn read(&self, offset: usize, buf: &mut [u8]) -> usize;
+}
+
+/// Most bytes read from a [`ReportDescriptorSource`] at once, the largest control endpoint packet
+pub const REPORT_DESCRIPTOR_CHUNK_SIZE: usize = 64;
+
+#[derive(Clone, Copy)]
+enum ReportDescriptorRef<'a> {
+    Slice(&'a [u8]),
+    Source(&'a dyn ReportDescriptorSource),
+}
+
+impl ReportDescriptorRef<'_> {
+    fn as_slice(&self) -> &[u8] {
+        match self {
+            Self::Slice(descriptor) => descriptor,
+            Self::Source(_) => &[],
+        }
+    }
+}
+
+impl core::fmt::Debug for ReportDescriptorRef<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Slice(descriptor) => descriptor.fmt(f),
+            Self::Source(_) => f.write_str("ReportDescriptorSource"),
+        }
+    }
+}
+
+impl PartialEq for ReportDescriptorRef<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Slice(a), Self::Slice(b)) => a == b,
+            (Self::Source(a), Self::Source(b)) => core::ptr::addr_eq(*a, *b),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for ReportDescriptorRef<'_> {}
+
+/// Report already laid out as the bytes sent to the host
+///
+/// Byte aligned reports, such as raw 64 byte packets, can implement this to be written by
+/// reference with [`Interface::write_report_bytes`] rather than packed into a copy first
+pub trait ReportBytes {
+    fn report_bytes(&self) -> &[u8];
+}
+
+impl ReportBytes for [u8] {
+    fn report_bytes(&self) -> &[u8] {
+        self
+    }
+}
+
+impl<const N: usize> ReportBytes for [u8; N] {
+    fn report_bytes(&self) -> &[u8] {
+        self
+    }
 }
 
 pub trait ReportBuffer: Default {
@@ -239,7 +565,42 @@ option_block_idle_storage!(Reports32, Block32);
 option_block_idle_storage!(Reports64, Block64);
 option_block_idle_storage!(Reports128, Block128);
 
+/// A string descriptor associated with an interface, with optional translations
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterfaceString<'a> {
+    default: &'a str,
+    translations: &'a [(LangID, &'a str)],
+}
+
+impl<'a> InterfaceString<'a> {
+    /// String served for all languages
+    #[must_use]
+    pub const fn new(s: &'a str) -> Self {
+        Self {
+            default: s,
+            translations: &[],
+        }
+    }
+
+    /// Add language specific versions of the string. The default string is served for languages
+    /// without a translation
+    #[must_use]
+    pub const fn with_translations(mut self, translations: &'a [(LangID, &'a str)]) -> Self {
+        self.translations = translations;
+        self
+    }
+
+    /// The string for the requested language
+    #[must_use]
+    pub fn get(&self, lang_id: LangID) -> &'a str {
+        self.translations
+            .iter()
+            .find(|(l, _)| *l == lang_id)
+            .map_or(self.default, |&(_, s)| s)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct InterfaceConfig<'a, I, O, R>
 where
     I: InSize,
@@ -247,13 +608,77 @@ where
     R: ReportCount,
 {
     marker: PhantomData<(I, O, R)>,
-    report_descriptor: &'a [u8],
+    report_descriptor: ReportDescriptorRef<'a>,
     report_descriptor_length: u16,
-    description: Option<&'a str>,
-    protocol: InterfaceProtocol,
+    description: Option<InterfaceString<'a>>,
+    strings: &'a [InterfaceString<'a>],
+    subclass: u8,
+    protocol: u8,
+    country_code: HidCountryCode,
     idle_default: u8,
     out_endpoint: Option<EndpointConfig>,
     in_endpoint: EndpointConfig,
+    alt_settings: [EndpointConfig; MAX_ALTERNATE_SETTINGS],
+    alt_setting_count: usize,
+    report_handler: Option<ReportHandlerRef<'a>>,
+    control_handler: Option<ControlHandlerRef<'a>>,
+    duplicate_reports: DuplicateReports,
+    boot_only: bool,
+    min_report_interval: u32,
+    wake_on_input: bool,
+    queue_reports: bool,
+    active: bool,
+}
+
+/// Which reports identical to the last report written with the same report ID are not sent
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DuplicateReports {
+    SuppressWhileIdleZero,
+    Suppress,
+    Send,
+}
+
+//Not derived, as that would require the size marker types to be Copy
+impl<I, O, R> Clone for InterfaceConfig<'_, I, O, R>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I, O, R> Copy for InterfaceConfig<'_, I, O, R>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+{
+}
+
+impl<I, O, R> InterfaceConfig<'_, I, O, R>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+{
+    /// Devices default to report protocol - HID spec 7.2.6 `Set_Protocol` Request, unless they
+    /// only support the boot protocol
+    fn initial_protocol(&self) -> HidProtocol {
+        if self.boot_only {
+            HidProtocol::Boot
+        } else {
+            HidProtocol::Report
+        }
+    }
+}
+
+/// Endpoints of an alternate setting, allocated alongside the default setting's endpoints
+struct AltEndpoints<'a, B: UsbBus> {
+    in_endpoint: EndpointIn<'a, B>,
+    out_endpoint: Option<EndpointOut<'a, B>>,
 }
 
 pub struct Interface<'a, B, I, O, R>
@@ -267,12 +692,33 @@ where
     config: InterfaceConfig<'a, I, O, R>,
     out_endpoint: Option<EndpointOut<'a, B>>,
     in_endpoint: EndpointIn<'a, B>,
+    alt_endpoints: Vec<AltEndpoints<'a, B>, MAX_ALTERNATE_SETTINGS>,
+    alt_setting: u8,
+    alt_setting_changed: bool,
+    write_pending: bool,
+    write_completed: bool,
+    submitted_buffer: Option<&'a mut [u8]>,
+    completed_buffer: Option<&'a mut [u8]>,
     description_index: Option<StringIndex>,
+    strings_index: Option<StringIndex>,
     protocol: HidProtocol,
     report_idle: R::IdleStorage,
     global_idle: u8,
-    control_in_report_buffer: I::Buffer,
     control_out_report_buffer: O::Buffer,
+    //Report ID, last report sent and milliseconds since it was sent
+    input_report_cache: Vec<(u8, I::Buffer, u32), INPUT_REPORT_CACHE_LEN>,
+    feature_reports: Vec<(u8, I::Buffer), FEATURE_REPORT_CACHE_LEN>,
+    report_holdoff: u32,
+    stats: Cell<InterfaceStats>,
+    events: Deque<InterfaceEvent, EVENT_QUEUE_LEN>,
+    suspended: bool,
+    remote_wakeup_enabled: bool,
+    wakeup_requested: bool,
+    wake_report: Option<I::Buffer>,
+    report_queue: Deque<I::Buffer, REPORT_QUEUE_LEN>,
+    active: bool,
+    in_halted: bool,
+    out_halted: bool,
 }
 
 impl<'a, B: UsbBus + 'a, I, O, R> UsbAllocatable<'a, B> for InterfaceConfig<'a, I, O, R>
@@ -303,7 +749,7 @@ where
     }
 
     fn reset(&mut self) {
-        <Self as InterfaceClass<'a>>::reset(self);
+        //The interface itself is reset through `DeviceClass::interface`, there's no device state
     }
 
     fn tick(&mut self) -> Result<(), crate::UsbHidError> {
@@ -311,6 +757,18 @@ where
     }
 }
 
+impl<B: UsbBus, I, O, R, T> ReportWriter<T> for Interface<'_, B, I, O, R>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+    T: ReportBytes,
+{
+    fn write_report(&mut self, report: &T) -> Result<(), UsbHidError> {
+        self.write_report_bytes(report).map(|_| ())
+    }
+}
+
 impl<'a, B: UsbBus, I, O, R> Interface<'a, B, I, O, R>
 where
     B: UsbBus,
@@ -325,17 +783,61 @@ where
             out_endpoint: config
                 .out_endpoint
                 .map(|c| usb_alloc.interrupt(O::Buffer::CAPACITY, c.poll_interval)),
+            alt_endpoints: config.alt_settings[..config.alt_setting_count]
+                .iter()
+                .map(|c| AltEndpoints {
+                    in_endpoint: usb_alloc.interrupt(I::Buffer::CAPACITY, c.poll_interval),
+                    out_endpoint: config
+                        .out_endpoint
+                        .map(|_| usb_alloc.interrupt(O::Buffer::CAPACITY, c.poll_interval)),
+                })
+                .collect(),
+            alt_setting: usb_device::device::DEFAULT_ALTERNATE_SETTING,
+            alt_setting_changed: false,
+            write_pending: false,
+            write_completed: false,
+            submitted_buffer: None,
+            completed_buffer: None,
             description_index: config.description.map(|_| usb_alloc.string()),
-            //When initialized, all devices default to report protocol - Hid spec 7.2.6 Set_Protocol Request
-            protocol: HidProtocol::Report,
+            //String indices are allocated sequentially, only the first needs storing
+            strings_index: config
+                .strings
+                .iter()
+                .map(|_| usb_alloc.string())
+                .reduce(|first, _| first),
+            protocol: config.initial_protocol(),
             report_idle: R::IdleStorage::default(),
             global_idle: config.idle_default,
-            control_in_report_buffer: I::Buffer::default(),
             control_out_report_buffer: O::Buffer::default(),
+            input_report_cache: Vec::new(),
+            feature_reports: Vec::new(),
+            report_holdoff: 0,
+            stats: Cell::new(InterfaceStats::default()),
+            events: Deque::new(),
+            suspended: false,
+            remote_wakeup_enabled: false,
+            wakeup_requested: false,
+            wake_report: None,
+            report_queue: Deque::new(),
+            active: config.active,
+            in_halted: false,
+            out_halted: false,
             config,
         }
     }
 
+    fn count(&self, counter: fn(&mut InterfaceStats) -> &mut u32) {
+        let mut stats = self.stats.get();
+        let value = counter(&mut stats);
+        *value = value.wrapping_add(1);
+        self.stats.set(stats);
+    }
+    fn push_event(&mut self, event: InterfaceEvent) {
+        if self.events.is_full() {
+            self.events.pop_front();
+        }
+        self.events.push_back(event).ok();
+    }
     fn clear_report_idle(&mut self) {
         self.report_idle = R::IdleStorage::default();
     }
@@ -346,6 +848,141 @@ where
             None
         }
     }
+    /// String descriptor index of the `n`th string added with [`InterfaceBuilder::strings`], for
+    /// use with `String Index` items in the report descriptor, `None` if there's no `n`th string
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidBuilderError::ValueOverflow`] if the index is larger than 255
+    pub fn string_index(&self, n: usize) -> BuilderResult<Option<u8>> {
+        let Some(first) = self.strings_index else {
+            return Ok(None);
+        };
+        if n >= self.config.strings.len() {
+            return Ok(None);
+        }
+        u8::try_from(n)
+            .ok()
+            .and_then(|n| u8::from(first).checked_add(n))
+            .map(Some)
+            .ok_or(UsbHidBuilderError::ValueOverflow)
+    }
+    /// Replace the configuration of the interface, e.g. to change the report descriptor when
+    /// switching between a normal and a configuration mode
+    ///
+    /// The new configuration must use the same endpoints, and the same number of strings, as the
+    /// interface was allocated with. The interface is reset as if by a USB reset, and feature
+    /// reports are cleared. The host only sees the change after it has enumerated the device
+    /// again, see [`reenumerate`](crate::usb_class::reenumerate).
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidBuilderError::AllocationMismatch`] if `config` needs different endpoints or strings
+    pub fn reconfigure(&mut self, config: InterfaceConfig<'a, I, O, R>) -> BuilderResult<()> {
+        let current = &self.config;
+        if config.in_endpoint != current.in_endpoint
+            || config.out_endpoint != current.out_endpoint
+            || config.alt_settings[..config.alt_setting_count]
+                != current.alt_settings[..current.alt_setting_count]
+            || config.description.is_some() != current.description.is_some()
+            || config.strings.len() != current.strings.len()
+        {
+            error!("Interface configuration doesn't match allocation");
+            return Err(UsbHidBuilderError::AllocationMismatch);
+        }
+        self.config = config;
+        self.feature_reports.clear();
+        <Self as InterfaceClass<'a>>::reset(self);
+        Ok(())
+    }
+    /// Counters of the interface's activity since it was created
+    #[must_use]
+    pub fn stats(&self) -> InterfaceStats {
+        self.stats.get()
+    }
+    /// The alternate setting selected by the host, 0 is the default setting
+    ///
+    /// Devices declaring alternate settings with [`InterfaceBuilder::alternate_setting`] should
+    /// size and rate their reports to suit the current setting
+    #[must_use]
+    pub fn alt_setting(&self) -> u8 {
+        self.alt_setting
+    }
+
+    /// Take the oldest unhandled request from the host, see [`InterfaceEvent`]
+    pub fn take_event(&mut self) -> Option<InterfaceEvent> {
+        self.events.pop_front()
+    }
+
+    /// Returns the new alternate setting if the host has changed it since this was last called
+    pub fn take_alt_setting_change(&mut self) -> Option<u8> {
+        core::mem::take(&mut self.alt_setting_changed).then_some(self.alt_setting)
+    }
+
+    /// True from writing a report to the IN endpoint until the host has read it
+    ///
+    /// Devices can use this to keep exactly one report queued ahead of the host, writing the next
+    /// report as soon as the previous one has left rather than on a fixed schedule
+    #[must_use]
+    pub fn is_write_pending(&self) -> bool {
+        self.write_pending || !self.report_queue.is_empty()
+    }
+
+    /// Returns true if a report written to the IN endpoint has been read by the host since this
+    /// was last called
+    pub fn take_write_complete(&mut self) -> bool {
+        core::mem::take(&mut self.write_completed)
+    }
+
+    fn is_in_endpoint(&self, addr: EndpointAddress) -> bool {
+        self.in_endpoint.address() == addr
+            || self
+                .alt_endpoints
+                .iter()
+                .any(|e| e.in_endpoint.address() == addr)
+    }
+
+    fn active_in_endpoint(&self) -> &EndpointIn<'a, B> {
+        match usize::from(self.alt_setting).checked_sub(1) {
+            Some(n) => &self.alt_endpoints[n].in_endpoint,
+            None => &self.in_endpoint,
+        }
+    }
+
+    fn active_out_endpoint(&self) -> Option<&EndpointOut<'a, B>> {
+        match usize::from(self.alt_setting).checked_sub(1) {
+            Some(n) => self.alt_endpoints[n].out_endpoint.as_ref(),
+            None => self.out_endpoint.as_ref(),
+        }
+    }
+
+    #[must_use]
+    pub fn report_descriptor_length(&self) -> u16 {
+        self.config.report_descriptor_length
+    }
+
+    /// Length of reports declared by the report descriptor, see [`report_length`]
+    #[must_use]
+    pub fn report_length(&self, report_type: ReportType, report_id: u8) -> Option<usize> {
+        report_length(
+            self.config.report_descriptor.as_slice(),
+            report_type,
+            report_id,
+        )
+    }
+
+    /// The in endpoint of the current alternate setting
+    #[must_use]
+    pub fn in_endpoint_info(&self) -> EndpointInfo {
+        EndpointInfo::new(self.active_in_endpoint())
+    }
+
+    /// The out endpoint of the current alternate setting, if the interface has one
+    #[must_use]
+    pub fn out_endpoint_info(&self) -> Option<EndpointInfo> {
+        self.active_out_endpoint().map(EndpointInfo::new)
+    }
+
     #[must_use]
     pub fn protocol(&self) -> HidProtocol {
         self.protocol
@@ -359,121 +996,425 @@ where
         if report_id == 0 {
             None
         } else {
-            self.get_report_idle(report_id)
+            self.get_report_idle(report_id - 1)
                 .map(|i| (u32::from(i) * 4).millis())
         }
     }
-    pub fn write_report(&mut self, data: &[u8]) -> usb_device::Result<usize> {
-        //Try to write report to the report buffer for the config endpoint
-        let control_result = if self.control_in_report_buffer.is_empty() {
-            match self.control_in_report_buffer.extend_from_slice(data) {
-                Ok(_) => Ok(data.len()),
-                Err(_) => Err(UsbError::BufferOverflow),
-            }
-        } else {
-            Err(UsbError::WouldBlock)
-        };
-
-        //Also try to write report to the in endpoint
-        let endpoint_result = self.in_endpoint.write(data);
-
-        match (control_result, endpoint_result) {
-            //OK if either succeeded
-            (_, Ok(n)) | (Ok(n), _) => Ok(n),
-            //non-WouldBlock errors take preference
-            (Err(e), Err(UsbError::WouldBlock)) | (_, Err(e)) => Err(e),
+    /// Interval at which input reports with `report_id` should be repeated while unchanged, or
+    /// `None` if they should only be sent when they change
+    ///
+    /// The idle rate set for `report_id` takes precedence over the global idle rate. Use a
+    /// `report_id` of 0 for interfaces without report IDs.
+    #[must_use]
+    pub fn get_idle_duration(&self, report_id: u8) -> Option<MillisDurationU32> {
+        match self.idle_value(report_id) {
+            0 => None,
+            value => Some((u32::from(value) * 4).millis()),
         }
     }
-    pub fn read_report(&mut self, data: &mut [u8]) -> usb_device::Result<usize> {
-        //If there is an out endpoint, try to read from it first
-        let ep_result = if let Some(ep) = &self.out_endpoint {
-            ep.read(data)
-        } else {
-            Err(UsbError::WouldBlock)
+    /// Idle rate in units of 4ms
+    fn idle_value(&self, report_id: u8) -> u8 {
+        report_id
+            .checked_sub(1)
+            .and_then(|idx| self.get_report_idle(idx))
+            .unwrap_or(self.global_idle)
+    }
+    /// Write a report to the host
+    ///
+    /// A report identical to the last report written with the same report ID is not sent, and
+    /// `Ok` is returned, while the idle rate for the report is 0, as the host has requested
+    /// reports only when they change - HID spec 7.2.4. Unchanged reports are never suppressed
+    /// with [`InterfaceBuilder::send_unchanged_reports`]. With
+    /// [`InterfaceBuilder::suppress_duplicate_reports`] they are suppressed until the report's
+    /// idle period has passed since it was last sent.
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::WouldBlock`] if the IN endpoint is busy, or [`UsbHidError::QueueFull`] if
+    /// it's busy and the [report queue](InterfaceBuilder::queue_reports) is full
+    pub fn write_report(&mut self, data: &[u8]) -> Result<usize, UsbHidError> {
+        if self.config.wake_on_input && self.suspended && self.remote_wakeup_enabled {
+            return self.hold_wake_report(data);
+        }
+        //A report written since resuming supersedes one held while suspended
+        self.wake_report = None;
+
+        let report_id = self.report_id_of(data);
+        let suppress = match self.config.duplicate_reports {
+            DuplicateReports::SuppressWhileIdleZero => self.idle_value(report_id) == 0,
+            DuplicateReports::Suppress => !self.idle_elapsed(report_id),
+            DuplicateReports::Send => false,
         };
+        if suppress && self.cached_input_report(report_id) == Some(data) {
+            trace!("Suppressed duplicate report");
+            self.count(|s| &mut s.duplicates_suppressed);
+            return Ok(data.len());
+        }
+        self.send_report(data)
+    }
+    /// Write a report to the host from a buffer owned by the caller, returning the buffer once the
+    /// host has read it with [`Self::take_completed_buffer`]
+    ///
+    /// Unlike [`Self::write_report`], the report is not copied into the interface, it is passed
+    /// straight to the endpoint. This avoids a copy per report when reports are built in place,
+    /// e.g. by DMA, and several buffers are rotated through. The report is not kept for
+    /// `GET_REPORT` requests and duplicate suppression is not applied.
+    ///
+    /// Only one buffer can be in use at a time, a submitted buffer must be completed and taken back
+    /// before another is submitted.
+    ///
+    /// # Errors
+    ///
+    /// The error and the buffer, which is [`UsbError::WouldBlock`] if a buffer is already in use,
+    /// the report is rate limited or the endpoint is busy
+    pub fn submit_report(&mut self, buffer: &'a mut [u8]) -> Result<(), (UsbError, &'a mut [u8])> {
+        if self.submitted_buffer.is_some()
+            || self.completed_buffer.is_some()
+            || !self.report_queue.is_empty()
+            || !self.active
+            || self.in_halted
+        {
+            return Err((UsbError::WouldBlock, buffer));
+        }
+        if self.report_holdoff > 0 {
+            trace!("Report rate limited");
+            self.count(|s| &mut s.reports_blocked);
+            return Err((UsbError::WouldBlock, buffer));
+        }
 
-        match ep_result {
-            Err(UsbError::WouldBlock) => {
-                //If there wasn't data available from the in endpoint
-                //try the config endpoint report buffer
-                let out_len = self.control_out_report_buffer.len();
-                if self.control_out_report_buffer.is_empty() {
-                    Err(UsbError::WouldBlock)
-                } else if data.len() < out_len {
-                    Err(UsbError::BufferOverflow)
-                } else {
-                    data[..out_len].copy_from_slice(self.control_out_report_buffer.as_ref());
-                    self.control_out_report_buffer.clear();
-                    Ok(out_len)
+        match self.active_in_endpoint().write(buffer) {
+            Ok(_) => {
+                self.write_pending = true;
+                self.submitted_buffer = Some(buffer);
+                self.report_holdoff = self.config.min_report_interval;
+                self.count(|s| &mut s.reports_written);
+                Ok(())
+            }
+            Err(e) => {
+                if e == UsbError::WouldBlock {
+                    self.count(|s| &mut s.reports_blocked);
                 }
+                Err((e, buffer))
             }
-            _ => ep_result,
         }
     }
-}
-impl<'a, B: UsbBus, I, O, R> InterfaceClass<'a> for Interface<'a, B, I, O, R>
-where
-    B: UsbBus,
-    I: InSize,
-    O: OutSize,
-    R: ReportCount,
-{
-    fn hid_descriptor_body(&self) -> [u8; 7] {
-        match (HidDescriptorBody {
-            bcd_hid: SPEC_VERSION_1_11,
-            country_code: COUNTRY_CODE_NOT_SUPPORTED,
-            num_descriptors: 1,
-            descriptor_type: DescriptorType::Report,
-            descriptor_length: self.config.report_descriptor_length,
-        }
-        .pack())
-        {
-            Ok(d) => d,
-            Err(_) => panic!("Failed to pack HidDescriptor"),
-        }
+    /// Take back the buffer passed to [`Self::submit_report`] once the host has read the report,
+    /// or the device has been reset
+    pub fn take_completed_buffer(&mut self) -> Option<&'a mut [u8]> {
+        self.completed_buffer.take()
     }
-
-    fn report_descriptor(&self) -> &'_ [u8] {
-        self.config.report_descriptor
+    /// Write a report to the host without packing, see [`ReportBytes`]
+    pub fn write_report_bytes<T: ReportBytes + ?Sized>(
+        &mut self,
+        report: &T,
+    ) -> Result<usize, UsbHidError> {
+        self.write_report(report.report_bytes())
     }
-
-    fn id(&self) -> InterfaceNumber {
-        self.id
+    /// Write a report to the host, serialized by `S`
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::SerializationError`] if the report can't be serialized, otherwise as
+    /// [`Self::write_report`]
+    pub fn write_serialized<S: ReportSerializer<T>, T>(
+        &mut self,
+        report: &T,
+    ) -> Result<(), UsbHidError> {
+        let mut buf = [0; MAX_SERIALIZED_REPORT_LEN];
+        let len = S::serialize(report, &mut buf)?;
+        self.write_report(&buf[..len]).map(|_| ())
     }
-    fn write_descriptors(&self, writer: &mut DescriptorWriter) -> usb_device::Result<()> {
-        writer.interface_alt(
-            self.id,
-            usb_device::device::DEFAULT_ALTERNATE_SETTING,
-            USB_CLASS_HID,
-            InterfaceSubClass::from(self.config.protocol).into(),
-            self.config.protocol.into(),
-            self.description_index,
-        )?;
+    /// Read a report from the host, deserialized by `S`
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::SerializationError`] if the report can't be deserialized, otherwise as
+    /// [`Self::read_report`]
+    pub fn read_serialized<S: ReportSerializer<T>, T>(&mut self) -> Result<T, UsbHidError> {
+        let mut buf = [0; MAX_SERIALIZED_REPORT_LEN];
+        let len = self.read_report(&mut buf)?;
+        S::deserialize(&buf[..len])
+    }
+    /// Write a `usbd-hid` report type to the host, as [`Interface::write_report`]
+    ///
+    /// Requires the `usbd-hid` feature
+    #[cfg(feature = "usbd-hid")]
+    pub fn write_usbd_hid_report<T: usbd_hid::descriptor::AsInputReport>(
+        &mut self,
+        report: &T,
+    ) -> Result<usize, UsbHidError> {
+        let mut data = [0; 64];
+        let len = report.serialize(&mut data).map_err(|_| {
+            error!("Error serializing usbd-hid report");
+            UsbHidError::SerializationError
+        })?;
+        self.write_report(&data[..len])
+    }
+    /// Write a report to the host, regardless of whether it duplicates the last report
+    pub(crate) fn send_report(&mut self, data: &[u8]) -> Result<usize, UsbHidError> {
+        if !self.active || self.in_halted {
+            trace!("Interface inactive or halted");
+            self.count(|s| &mut s.reports_blocked);
+            return Err(UsbHidError::WouldBlock);
+        }
+        let rate_limited = self.report_holdoff > 0;
+        if rate_limited && !self.config.queue_reports {
+            trace!("Report rate limited");
+            self.count(|s| &mut s.reports_blocked);
+            return Err(UsbHidError::WouldBlock);
+        }
 
-        //Hid descriptor
-        writer.write(DescriptorType::Hid.into(), &self.hid_descriptor_body())?;
+        //Write the report to the in endpoint, behind any queued reports
+        let result = if self.report_queue.is_empty() && !rate_limited {
+            self.active_in_endpoint().write(data)
+        } else {
+            Err(UsbError::WouldBlock)
+        };
+        let mut queued = false;
+        let result = match result {
+            Ok(n) => {
+                self.write_pending = true;
+                Ok(n)
+            }
+            Err(UsbError::WouldBlock) if self.config.queue_reports => {
+                queued = true;
+                self.queue_report(data)
+            }
+            Err(e) => Err(e.into()),
+        };
 
-        //Endpoint descriptors
-        writer.endpoint(&self.in_endpoint)?;
-        if let Some(e) = &self.out_endpoint {
-            writer.endpoint(e)?;
+        match result {
+            Ok(_) => {
+                self.cache_input_report(data);
+                //Queued reports are rate limited when they leave the queue
+                if !queued {
+                    self.report_holdoff = self.config.min_report_interval;
+                }
+                self.count(|s| &mut s.reports_written);
+            }
+            Err(UsbHidError::WouldBlock | UsbHidError::QueueFull) => {
+                self.count(|s| &mut s.reports_blocked);
+            }
+            Err(_) => {}
         }
 
-        Ok(())
+        result
     }
-    fn get_string(&self, index: StringIndex, _lang_id: u16) -> Option<&'a str> {
-        self.description_index
-            .filter(|&i| i == index)
-            .and(self.config.description)
+    fn hold_wake_report(&mut self, data: &[u8]) -> Result<usize, UsbHidError> {
+        if !self.active {
+            return Err(UsbHidError::WouldBlock);
+        }
+        let mut report = I::Buffer::default();
+        report
+            .extend_from_slice(data)
+            .map_err(|()| UsbHidError::ReportTooLarge)?;
+        if self.wake_report.replace(report).is_none() {
+            info!("Report written while suspended, requesting remote wakeup");
+            self.wakeup_requested = true;
+        }
+        Ok(data.len())
     }
-    fn reset(&mut self) {
-        self.protocol = HidProtocol::Report;
-        self.global_idle = self.config.idle_default;
-        self.clear_report_idle();
-        self.control_in_report_buffer = I::Buffer::default();
-        self.control_out_report_buffer = O::Buffer::default();
+    fn send_wake_report(&mut self) {
+        if self.suspended {
+            return;
+        }
+        if let Some(report) = self.wake_report.take() {
+            match self.send_report(report.as_ref()) {
+                Err(UsbHidError::WouldBlock) => self.wake_report = Some(report),
+                Err(e) => error!("Failed to send report held while suspended - {:?}", e),
+                Ok(_) => trace!("Sent report held while suspended"),
+            }
+        }
+    }
+    /// Called by [`UsbHidClass::update_suspend`](crate::usb_class::UsbHidClass::update_suspend)
+    /// with the state of the bus
+    pub fn set_suspended(&mut self, suspended: bool, remote_wakeup_enabled: bool) {
+        self.remote_wakeup_enabled = remote_wakeup_enabled;
+        if suspended != self.suspended {
+            self.suspended = suspended;
+            if !suspended {
+                self.wakeup_requested = false;
+                self.send_wake_report();
+            }
+        }
+    }
+    /// Activate or deactivate the interface, e.g. to only expose a configuration interface after
+    /// a key chord
+    ///
+    /// An inactive interface keeps its descriptors, so the host still enumerates it, but its
+    /// endpoints NAK: reports can't be written and OUT reports aren't read. `GET_REPORT`,
+    /// `SET_REPORT` and vendor requests are stalled, other HID requests are still handled. The
+    /// state is kept across USB resets.
+    pub fn set_active(&mut self, active: bool) {
+        if active != self.active {
+            info!("Interface {} active: {}", u8::from(self.id), active);
+            self.active = active;
+            if !active {
+                self.control_out_report_buffer.clear();
+                self.input_report_cache.clear();
+                self.report_queue.clear();
+                self.wake_report = None;
+                self.wakeup_requested = false;
+            }
+        }
+    }
+    /// False if the interface has been deactivated, see [`Self::set_active`]
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+    /// True while the bus is suspended, as last set by [`Self::set_suspended`]
+    #[must_use]
+    pub fn is_suspended(&self) -> bool {
+        self.suspended
+    }
+    /// Returns true if remote wakeup should be signalled since a report was written while
+    /// suspended, see [`InterfaceBuilder::wake_on_input`]
+    pub fn take_wakeup_request(&mut self) -> bool {
+        core::mem::take(&mut self.wakeup_requested)
+    }
+    /// Halt the current alternate setting's endpoint in `direction`, so the host's transfers to it
+    /// are stalled, e.g. after an OUT report the device can't handle
+    ///
+    /// Reports aren't written to a halted IN endpoint, or read from a halted OUT endpoint, until
+    /// the halt is cleared with [`Self::unstall`] or by the host. Clearing the halt discards any
+    /// report being written, so the next report written is the next the host reads. Both are
+    /// reported with an [`InterfaceEvent`].
+    pub fn stall(&mut self, direction: UsbDirection) {
+        if self.is_stalled(direction) {
+            return;
+        }
+        match direction {
+            UsbDirection::In => self.active_in_endpoint().stall(),
+            UsbDirection::Out => match self.active_out_endpoint() {
+                Some(ep) => ep.stall(),
+                None => return,
+            },
+        }
+        self.halt_changed(direction, true);
+    }
+    /// Clear a halt set by [`Self::stall`] or the host, see [`Self::stall`]
+    pub fn unstall(&mut self, direction: UsbDirection) {
+        if !self.is_stalled(direction) {
+            return;
+        }
+        match direction {
+            UsbDirection::In => self.active_in_endpoint().unstall(),
+            UsbDirection::Out => {
+                if let Some(ep) = self.active_out_endpoint() {
+                    ep.unstall();
+                }
+            }
+        }
+        self.halt_changed(direction, false);
+    }
+    /// True while the current alternate setting's endpoint in `direction` is halted
+    #[must_use]
+    pub fn is_stalled(&self, direction: UsbDirection) -> bool {
+        match direction {
+            UsbDirection::In => self.in_halted,
+            UsbDirection::Out => self.out_halted,
+        }
+    }
+    fn halt_changed(&mut self, direction: UsbDirection, halted: bool) {
+        let address = match direction {
+            UsbDirection::In => {
+                self.in_halted = halted;
+                //The host won't read the report that was being written
+                self.write_pending = false;
+                if let Some(buffer) = self.submitted_buffer.take() {
+                    self.completed_buffer = Some(buffer);
+                }
+                self.report_queue.clear();
+                self.active_in_endpoint().address()
+            }
+            UsbDirection::Out => {
+                self.out_halted = halted;
+                match self.active_out_endpoint() {
+                    Some(ep) => ep.address(),
+                    None => return,
+                }
+            }
+        };
+        if halted {
+            warn!("Interface {} endpoint halted", u8::from(self.id));
+            self.push_event(InterfaceEvent::EndpointHalted(address));
+        } else {
+            info!("Interface {} endpoint halt cleared", u8::from(self.id));
+            self.push_event(InterfaceEvent::EndpointHaltCleared(address));
+        }
     }
-    fn set_report(&mut self, data: &[u8]) -> usb_device::Result<()> {
+    fn clear_halts(&mut self) {
+        if self.in_halted {
+            self.active_in_endpoint().unstall();
+        }
+        if self.out_halted {
+            if let Some(ep) = self.active_out_endpoint() {
+                ep.unstall();
+            }
+        }
+        self.in_halted = false;
+        self.out_halted = false;
+    }
+    fn queue_report(&mut self, data: &[u8]) -> Result<usize, UsbHidError> {
+        let mut report = I::Buffer::default();
+        report
+            .extend_from_slice(data)
+            .map_err(|()| UsbHidError::ReportTooLarge)?;
+        self.report_queue.push_back(report).map_err(|_| {
+            trace!("Report queue full");
+            UsbHidError::QueueFull
+        })?;
+        Ok(data.len())
+    }
+    fn send_queued_report(&mut self) {
+        if self.write_pending || self.report_holdoff > 0 || self.in_halted {
+            return;
+        }
+        let Some(report) = self.report_queue.pop_front() else {
+            return;
+        };
+        match self.active_in_endpoint().write(report.as_ref()) {
+            Ok(_) => {
+                self.write_pending = true;
+                self.report_holdoff = self.config.min_report_interval;
+            }
+            Err(UsbError::WouldBlock) => {
+                //There was space for the report when it was taken
+                let _ = self.report_queue.push_front(report);
+            }
+            Err(e) => error!("Failed to write queued report - {:?}", e),
+        }
+    }
+    fn store_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        //Reports must have the length given by the report descriptor - HID spec 7.2.2
+        if let Some(len) = self
+            .report_length(report_type, report_id)
+            .filter(|&len| len != data.len())
+        {
+            warn!(
+                "Set report length {:X} doesn't match descriptor, expected {:X}",
+                data.len(),
+                len
+            );
+            return Err(UsbError::ParseError);
+        }
+        if let Some(handler) = self.config.report_handler {
+            if handler.0.set_report(report_type, report_id, data) {
+                trace!("Set report handled, {:X} bytes", data.len());
+                return Ok(());
+            }
+        }
+
+        if report_type == ReportType::Feature && self.feature_report(report_id).is_some() {
+            self.set_feature_report(data)?;
+        }
+
         if self.control_out_report_buffer.is_empty() {
             if self
                 .control_out_report_buffer
@@ -498,32 +1439,332 @@ where
             Err(UsbError::WouldBlock)
         }
     }
-
-    fn get_report(&self, data: &mut [u8]) -> usb_device::Result<usize> {
-        if self.control_in_report_buffer.is_empty() {
-            trace!("GetReport would block, empty buffer");
-            Err(UsbError::WouldBlock)
-        } else if data.len() < self.control_in_report_buffer.len() {
-            error!("GetReport failed, buffer too short");
-            Err(UsbError::BufferOverflow)
+    fn report_id_of(&self, data: &[u8]) -> u8 {
+        //Without report IDs, reports are requested with ID 0. Boot protocol reports, including
+        //those of boot only interfaces, don't have report IDs - HID spec appendix B
+        if R::IdleStorage::CAPACITY == 0 || self.protocol == HidProtocol::Boot {
+            0
         } else {
-            data[..self.control_in_report_buffer.len()]
-                .copy_from_slice(self.control_in_report_buffer.as_ref());
-            Ok(self.control_in_report_buffer.len())
+            data.first().copied().unwrap_or_default()
         }
     }
+    fn cached_input_report(&self, report_id: u8) -> Option<&[u8]> {
+        self.input_report_cache
+            .iter()
+            .find(|(id, _, _)| *id == report_id)
+            .map(|(_, report, _)| report.as_ref())
+    }
+    /// True if the idle rate for `report_id` is non-zero and at least that long has passed since
+    /// the last report with `report_id` was sent, so it should be repeated - HID spec 7.2.4
+    fn idle_elapsed(&self, report_id: u8) -> bool {
+        let idle_ms = u32::from(self.idle_value(report_id)) * 4;
+        idle_ms > 0
+            && !self
+                .input_report_cache
+                .iter()
+                .any(|(id, _, sent_ms)| *id == report_id && *sent_ms < idle_ms)
+    }
+    fn cache_input_report(&mut self, data: &[u8]) {
+        let report_id = self.report_id_of(data);
+        let mut report = I::Buffer::default();
+        if report.extend_from_slice(data).is_err() {
+            return;
+        }
 
-    fn get_report_ack(&mut self) -> usb_device::Result<()> {
-        if self.control_in_report_buffer.is_empty() {
-            error!("GetReport ACK failed, empty buffer");
-            Err(UsbError::WouldBlock)
+        if let Some(entry) = self
+            .input_report_cache
+            .iter_mut()
+            .find(|(id, _, _)| *id == report_id)
+        {
+            entry.1 = report;
+            entry.2 = 0;
         } else {
-            self.control_in_report_buffer.clear();
+            if self.input_report_cache.is_full() {
+                //Evict the oldest report ID
+                self.input_report_cache.remove(0);
+            }
+            self.input_report_cache.push((report_id, report, 0)).ok();
+        }
+    }
+    /// Set the feature report served on `GET_REPORT` requests not handled by a
+    /// [`ReportHandler`]
+    ///
+    /// `data` begins with the report ID if the interface has report IDs. A `SET_REPORT` from the
+    /// host for the same feature report replaces it, and is also buffered for [`Self::read_report`].
+    ///
+    /// # Errors
+    ///
+    /// [`UsbError::BufferOverflow`] if `data` is longer than the interface's input reports, or
+    /// feature reports have already been set for [`FEATURE_REPORT_CACHE_LEN`] other report IDs
+    pub fn set_feature_report(&mut self, data: &[u8]) -> usb_device::Result<()> {
+        let report_id = self.report_id_of(data);
+        let mut report = I::Buffer::default();
+        report
+            .extend_from_slice(data)
+            .map_err(|()| UsbError::BufferOverflow)?;
+
+        if let Some(entry) = self
+            .feature_reports
+            .iter_mut()
+            .find(|(id, _)| *id == report_id)
+        {
+            entry.1 = report;
             Ok(())
+        } else {
+            self.feature_reports
+                .push((report_id, report))
+                .map_err(|_| UsbError::BufferOverflow)
+        }
+    }
+    fn feature_report(&self, report_id: u8) -> Option<&[u8]> {
+        self.feature_reports
+            .iter()
+            .find(|(id, _)| *id == report_id)
+            .map(|(_, report)| report.as_ref())
+    }
+    pub fn read_report(&mut self, data: &mut [u8]) -> usb_device::Result<usize> {
+        //Leave OUT reports unread, so the endpoint NAKs, while inactive
+        if !self.active {
+            return Err(UsbError::WouldBlock);
+        }
+        //If there is an out endpoint, try to read from it first
+        let ep_result = if let Some(ep) = self.active_out_endpoint().filter(|_| !self.out_halted) {
+            ep.read(data)
+        } else {
+            Err(UsbError::WouldBlock)
+        };
+
+        let result = match ep_result {
+            Err(UsbError::WouldBlock) => {
+                //If there wasn't data available from the in endpoint
+                //try the config endpoint report buffer
+                let out_len = self.control_out_report_buffer.len();
+                if self.control_out_report_buffer.is_empty() {
+                    Err(UsbError::WouldBlock)
+                } else if data.len() < out_len {
+                    Err(UsbError::BufferOverflow)
+                } else {
+                    data[..out_len].copy_from_slice(self.control_out_report_buffer.as_ref());
+                    self.control_out_report_buffer.clear();
+                    Ok(out_len)
+                }
+            }
+            _ => ep_result,
+        };
+
+        if result.is_ok() {
+            self.count(|s| &mut s.out_reports_read);
+        }
+        result
+    }
+}
+impl<'a, B: UsbBus, I, O, R> InterfaceClass<'a> for Interface<'a, B, I, O, R>
+where
+    B: UsbBus,
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+{
+    fn hid_descriptor_body(&self) -> [u8; 7] {
+        match (HidDescriptorBody {
+            bcd_hid: SPEC_VERSION_1_11,
+            country_code: self.config.country_code.into(),
+            num_descriptors: 1,
+            descriptor_type: DescriptorType::Report,
+            descriptor_length: self.config.report_descriptor_length,
+        }
+        .pack())
+        {
+            Ok(d) => d,
+            Err(_) => panic!("Failed to pack HidDescriptor"),
+        }
+    }
+
+    fn report_descriptor(&self) -> &'_ [u8] {
+        self.config.report_descriptor.as_slice()
+    }
+
+    fn read_report_descriptor(&self, length: usize, buf: &mut [u8]) -> usb_device::Result<usize> {
+        match self.config.report_descriptor {
+            ReportDescriptorRef::Slice(descriptor) => {
+                copy_descriptor(&descriptor[..length.min(descriptor.len())], buf)
+            }
+            ReportDescriptorRef::Source(source) => {
+                let buf = buf
+                    .get_mut(..length.min(source.length()))
+                    .ok_or(UsbError::BufferOverflow)?;
+                let mut offset = 0;
+                for chunk in buf.chunks_mut(REPORT_DESCRIPTOR_CHUNK_SIZE) {
+                    let read = source.read(offset, chunk);
+                    offset += read;
+                    if read < chunk.len() {
+                        break;
+                    }
+                }
+                Ok(offset)
+            }
+        }
+    }
+
+    fn id(&self) -> InterfaceNumber {
+        self.id
+    }
+    fn write_descriptors(&self, writer: &mut DescriptorWriter) -> usb_device::Result<()> {
+        let alt_settings = core::iter::once((&self.in_endpoint, self.out_endpoint.as_ref())).chain(
+            self.alt_endpoints
+                .iter()
+                .map(|e| (&e.in_endpoint, e.out_endpoint.as_ref())),
+        );
+
+        //Only written if the device is composite with IADs, so other classes' functions can be
+        //told apart from HID interfaces
+        writer.iad(
+            self.id,
+            1,
+            USB_CLASS_HID,
+            self.config.subclass,
+            self.config.protocol,
+            self.description_index,
+        )?;
+
+        for (alt_setting, (in_endpoint, out_endpoint)) in (0..).zip(alt_settings) {
+            writer.interface_alt(
+                self.id,
+                alt_setting,
+                USB_CLASS_HID,
+                self.config.subclass,
+                self.config.protocol,
+                self.description_index,
+            )?;
+
+            //Hid descriptor
+            writer.write(DescriptorType::Hid.into(), &self.hid_descriptor_body())?;
+
+            //Endpoint descriptors
+            writer.endpoint(in_endpoint)?;
+            if let Some(e) = out_endpoint {
+                writer.endpoint(e)?;
+            }
+        }
+
+        Ok(())
+    }
+    fn get_string(&self, index: StringIndex, lang_id: LangID) -> Option<&'a str> {
+        if self.description_index == Some(index) {
+            return self.config.description.map(|s| s.get(lang_id));
+        }
+
+        let first = u8::from(self.strings_index?);
+        let n = u8::from(index).checked_sub(first)?;
+        self.config
+            .strings
+            .get(usize::from(n))
+            .map(|s| s.get(lang_id))
+    }
+    fn reset(&mut self) {
+        self.protocol = self.config.initial_protocol();
+        self.global_idle = self.config.idle_default;
+        self.clear_report_idle();
+        self.control_out_report_buffer = O::Buffer::default();
+        self.input_report_cache.clear();
+        self.report_holdoff = 0;
+        self.alt_setting = usb_device::device::DEFAULT_ALTERNATE_SETTING;
+        self.alt_setting_changed = false;
+        self.write_pending = false;
+        self.write_completed = false;
+        //The report won't be read, but the buffer still needs returning
+        if let Some(buffer) = self.submitted_buffer.take() {
+            self.completed_buffer = Some(buffer);
+        }
+        self.events.clear();
+        self.suspended = false;
+        self.remote_wakeup_enabled = false;
+        self.wakeup_requested = false;
+        self.wake_report = None;
+        self.report_queue.clear();
+        //The bus clears endpoint halts on reset
+        self.in_halted = false;
+        self.out_halted = false;
+        self.count(|s| &mut s.resets);
+    }
+    fn write_report(&mut self, data: &[u8]) -> Result<usize, UsbHidError> {
+        Interface::write_report(self, data)
+    }
+    fn read_report(&mut self, data: &mut [u8]) -> usb_device::Result<usize> {
+        Interface::read_report(self, data)
+    }
+    fn set_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) -> usb_device::Result<()> {
+        self.count(|s| &mut s.set_report_requests);
+        let result = self.store_report(report_type, report_id, data);
+        if matches!(result, Err(e) if e != UsbError::WouldBlock) {
+            self.count(|s| &mut s.reports_rejected);
+            self.push_event(InterfaceEvent::ReportRejected {
+                report_type,
+                report_id,
+            });
         }
+        result
+    }
+
+    fn get_report(
+        &mut self,
+        report_type: ReportType,
+        report_id: u8,
+        data: &mut [u8],
+    ) -> usb_device::Result<usize> {
+        self.count(|s| &mut s.get_report_requests);
+        if let Some(handler) = self.config.report_handler {
+            if let Some(n) = handler.0.get_report(report_type, report_id, data) {
+                return Ok(n);
+            }
+        }
+
+        let report = match report_type {
+            //Serve the most recent report written with this ID - HID spec 7.2.1
+            ReportType::Input => self.cached_input_report(report_id),
+            ReportType::Feature if self.feature_report(report_id).is_some() => {
+                self.feature_report(report_id)
+            }
+            _ => {
+                trace!(
+                    "GetReport unsupported, no handler for {:?} reports",
+                    report_type
+                );
+                return Err(UsbError::Unsupported);
+            }
+        };
+
+        match report {
+            None => {
+                trace!("GetReport would block, no report for ID{:X}", report_id);
+                Err(UsbError::WouldBlock)
+            }
+            Some(report) if data.len() < report.len() => {
+                error!("GetReport failed, buffer too short");
+                Err(UsbError::BufferOverflow)
+            }
+            Some(report) => {
+                data[..report.len()].copy_from_slice(report);
+                Ok(report.len())
+            }
+        }
+    }
+
+    fn get_report_ack(&mut self) -> usb_device::Result<()> {
+        Ok(())
     }
 
     fn set_idle(&mut self, report_id: u8, value: u8) {
+        self.count(|s| &mut s.set_idle_requests);
+        self.push_event(InterfaceEvent::SetIdle {
+            report_id,
+            idle: (u32::from(value) * 4).millis(),
+        });
         if report_id == 0 {
             self.global_idle = value;
             //"If the lower byte of value is zero, then the idle rate applies to all
@@ -546,21 +1787,119 @@ where
         }
     }
     fn get_idle(&self, report_id: u8) -> u8 {
-        if report_id == 0 {
-            self.global_idle
-        } else {
-            let idx = report_id - 1;
-            self.get_report_idle(idx).unwrap_or(self.global_idle)
-        }
+        self.count(|s| &mut s.get_idle_requests);
+        self.idle_value(report_id)
     }
-    fn set_protocol(&mut self, protocol: HidProtocol) {
+    fn set_protocol(&mut self, protocol: HidProtocol) -> bool {
+        self.count(|s| &mut s.set_protocol_requests);
+        if self.config.boot_only && protocol != HidProtocol::Boot {
+            warn!("Report protocol unsupported by boot only interface");
+            return false;
+        }
+        if self.protocol != protocol {
+            self.count(|s| &mut s.protocol_changes);
+        }
         self.protocol = protocol;
+        self.push_event(InterfaceEvent::SetProtocol(protocol));
         info!("Set protocol to {:?}", protocol);
+        true
     }
 
     fn get_protocol(&self) -> HidProtocol {
+        self.count(|s| &mut s.get_protocol_requests);
         self.protocol
     }
+    fn tick_rate_limit(&mut self) {
+        self.report_holdoff = self.report_holdoff.saturating_sub(1);
+        for (_, _, sent_ms) in &mut self.input_report_cache {
+            *sent_ms = sent_ms.saturating_add(1);
+        }
+        self.send_wake_report();
+        self.send_queued_report();
+    }
+    fn stats(&self) -> InterfaceStats {
+        Interface::stats(self)
+    }
+    fn alt_setting(&self) -> u8 {
+        self.alt_setting
+    }
+    fn set_alt_setting(&mut self, alt_setting: u8) -> bool {
+        if usize::from(alt_setting) > self.alt_endpoints.len() {
+            warn!("Unsupported alternate setting {}", alt_setting);
+            return false;
+        }
+
+        info!("Set alternate setting {}", alt_setting);
+        //SET_INTERFACE clears endpoint halts - USB spec 9.4.10
+        self.clear_halts();
+        if alt_setting != self.alt_setting {
+            self.alt_setting = alt_setting;
+            self.alt_setting_changed = true;
+            //Reports for the previous setting may no longer be valid
+            self.input_report_cache.clear();
+            self.report_queue.clear();
+            self.count(|s| &mut s.alt_setting_changes);
+            self.push_event(InterfaceEvent::SetInterface(alt_setting));
+        }
+        true
+    }
+    fn take_event(&mut self) -> Option<InterfaceEvent> {
+        Interface::take_event(self)
+    }
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        if self.write_pending && self.is_in_endpoint(addr) {
+            self.write_pending = false;
+            self.write_completed = true;
+            if let Some(buffer) = self.submitted_buffer.take() {
+                self.completed_buffer = Some(buffer);
+            }
+            self.count(|s| &mut s.reports_completed);
+            self.send_queued_report();
+        }
+    }
+    fn is_write_pending(&self) -> bool {
+        Interface::is_write_pending(self)
+    }
+    fn take_write_complete(&mut self) -> bool {
+        Interface::take_write_complete(self)
+    }
+    fn control_in(&mut self, request: &Request, data: &mut [u8]) -> Option<usize> {
+        self.count(|s| &mut s.control_requests);
+        self.config
+            .control_handler
+            .and_then(|handler| handler.0.control_in(request, data))
+    }
+    fn control_out(&mut self, request: &Request, data: &[u8]) -> bool {
+        self.count(|s| &mut s.control_requests);
+        self.config
+            .control_handler
+            .is_some_and(|handler| handler.0.control_out(request, data))
+    }
+    fn set_suspended(&mut self, suspended: bool, remote_wakeup_enabled: bool) {
+        Interface::set_suspended(self, suspended, remote_wakeup_enabled);
+    }
+    fn take_wakeup_request(&mut self) -> bool {
+        Interface::take_wakeup_request(self)
+    }
+    fn is_active(&self) -> bool {
+        Interface::is_active(self)
+    }
+    fn endpoint_halt(&mut self, addr: EndpointAddress, halted: bool) {
+        let direction = if addr == self.active_in_endpoint().address() {
+            UsbDirection::In
+        } else if self
+            .active_out_endpoint()
+            .is_some_and(|ep| ep.address() == addr)
+        {
+            UsbDirection::Out
+        } else {
+            return;
+        };
+        //The host also clears halts that aren't set, to reset the endpoint after an error
+        if !(halted && self.is_stalled(direction)) {
+            self.halt_changed(direction, halted);
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -568,8 +1907,42 @@ struct EndpointConfig {
     pub poll_interval: u8,
 }
 
+impl EndpointConfig {
+    #[allow(clippy::cast_possible_truncation)]
+    const fn new(poll_interval: MillisDurationU32) -> BuilderResult<Self> {
+        let millis = poll_interval.to_millis();
+        if millis > u8::MAX as u32 {
+            Err(UsbHidBuilderError::ValueOverflow)
+        } else {
+            Ok(Self {
+                poll_interval: millis as u8,
+            })
+        }
+    }
+    /// High speed interrupt endpoints are polled every 2<sup>bInterval-1</sup> microframes of
+    /// 125µs - USB spec 9.6.6
+    #[allow(clippy::cast_possible_truncation)]
+    const fn high_speed(poll_interval: MicrosDurationU32) -> BuilderResult<Self> {
+        let micros = poll_interval.to_micros();
+        let microframes = micros / 125;
+        if micros % 125 != 0 || !microframes.is_power_of_two() || microframes > 1 << 15 {
+            Err(UsbHidBuilderError::ValueOverflow)
+        } else {
+            Ok(Self {
+                poll_interval: microframes.trailing_zeros() as u8 + 1,
+            })
+        }
+    }
+}
+
+/// Builder for an [`InterfaceConfig`]
+///
+/// Any report descriptor can be used, including those generated by the
+/// [`usbd-hid`](https://crates.io/crates/usbd-hid) `#[gen_hid_descriptor]` macro. With the
+/// `usbd-hid` feature, build the interface with `InterfaceBuilder::from_usbd_hid` and write
+/// reports with `Interface::write_usbd_hid_report`.
 #[must_use = "this `UsbHidInterfaceBuilder` must be assigned or consumed by `::build_interface()`"]
-#[derive(Copy, Clone, Debug)]
+#[derive(Debug)]
 pub struct InterfaceBuilder<'a, I, O, R>
 where
     I: InSize,
@@ -579,34 +1952,122 @@ where
     config: InterfaceConfig<'a, I, O, R>,
 }
 
+impl<I, O, R> Clone for InterfaceBuilder<'_, I, O, R>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+{
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<I, O, R> Copy for InterfaceBuilder<'_, I, O, R>
+where
+    I: InSize,
+    O: OutSize,
+    R: ReportCount,
+{
+}
+
 impl<'a, I, O, R> InterfaceBuilder<'a, I, O, R>
 where
     I: InSize,
     O: OutSize,
     R: ReportCount,
 {
-    pub fn new(report_descriptor: &'a [u8]) -> BuilderResult<Self> {
+    pub const fn new(report_descriptor: &'a [u8]) -> BuilderResult<Self> {
+        Self::with_report_descriptor(
+            ReportDescriptorRef::Slice(report_descriptor),
+            report_descriptor.len(),
+        )
+    }
+
+    /// Builder for an interface whose report descriptor is read from `source` when requested
+    ///
+    /// [`Interface::report_length`] is not available for these interfaces, as the descriptor
+    /// isn't held in memory
+    pub fn from_source(source: &'a dyn ReportDescriptorSource) -> BuilderResult<Self> {
+        Self::with_report_descriptor(ReportDescriptorRef::Source(source), source.length())
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    const fn with_report_descriptor(
+        report_descriptor: ReportDescriptorRef<'a>,
+        length: usize,
+    ) -> BuilderResult<Self> {
+        if length > u16::MAX as usize {
+            return Err(UsbHidBuilderError::SliceLengthOverflow);
+        }
         Ok(InterfaceBuilder {
             config: InterfaceConfig {
                 marker: PhantomData,
                 report_descriptor,
-                report_descriptor_length: u16::try_from(report_descriptor.len())
-                    .map_err(|_| UsbHidBuilderError::SliceLengthOverflow)?,
+                report_descriptor_length: length as u16,
                 description: None,
-                protocol: InterfaceProtocol::None,
+                strings: &[],
+                subclass: InterfaceSubClass::None as u8,
+                protocol: InterfaceProtocol::None as u8,
+                country_code: HidCountryCode::NotSupported,
                 idle_default: 0,
                 out_endpoint: None,
                 in_endpoint: EndpointConfig { poll_interval: 20 },
+                alt_settings: [EndpointConfig { poll_interval: 20 }; MAX_ALTERNATE_SETTINGS],
+                alt_setting_count: 0,
+                report_handler: None,
+                control_handler: None,
+                duplicate_reports: DuplicateReports::SuppressWhileIdleZero,
+                boot_only: false,
+                min_report_interval: 0,
+                wake_on_input: false,
+                queue_reports: false,
+                active: true,
             },
         })
     }
 
-    pub fn boot_device(mut self, protocol: InterfaceProtocol) -> Self {
+    /// Builder for an interface with the report descriptor of the `usbd-hid` report type `T`
+    ///
+    /// Requires the `usbd-hid` feature
+    #[cfg(feature = "usbd-hid")]
+    pub fn from_usbd_hid<T: usbd_hid::descriptor::SerializedDescriptor>() -> BuilderResult<Self> {
+        Self::new(T::desc())
+    }
+
+    /// Set `bInterfaceProtocol` to `protocol`, and `bInterfaceSubClass` to boot if it is a boot
+    /// device protocol
+    pub const fn boot_device(mut self, protocol: InterfaceProtocol) -> Self {
+        self.config.subclass = if matches!(protocol, InterfaceProtocol::None) {
+            InterfaceSubClass::None as u8
+        } else {
+            InterfaceSubClass::Boot as u8
+        };
+        self.config.protocol = protocol as u8;
+        self
+    }
+
+    /// Set `bInterfaceProtocol` to a value not defined by the HID spec, such as the protocol of a
+    /// vendor device. The subclass is unchanged
+    pub const fn protocol_raw(mut self, protocol: u8) -> Self {
         self.config.protocol = protocol;
         self
     }
 
-    pub fn idle_default(mut self, duration: MillisDurationU32) -> BuilderResult<Self> {
+    /// Set `bInterfaceSubClass`, overriding the subclass set by [`Self::boot_device`]
+    pub const fn subclass(mut self, subclass: InterfaceSubClass) -> Self {
+        self.config.subclass = subclass as u8;
+        self
+    }
+
+    /// Country code of localized hardware, such as a keyboard with a national layout
+    pub const fn country_code(mut self, country_code: HidCountryCode) -> Self {
+        self.config.country_code = country_code;
+        self
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub const fn idle_default(mut self, duration: MillisDurationU32) -> BuilderResult<Self> {
         if duration.ticks() == 0 {
             self.config.idle_default = 0;
         } else {
@@ -615,47 +2076,274 @@ where
             if scaled_duration == 0 {
                 //round up for 1-3ms
                 self.config.idle_default = 1;
+            } else if scaled_duration > u8::MAX as u32 {
+                return Err(UsbHidBuilderError::ValueOverflow);
             } else {
-                self.config.idle_default =
-                    u8::try_from(scaled_duration).map_err(|_| UsbHidBuilderError::ValueOverflow)?;
+                self.config.idle_default = scaled_duration as u8;
             }
         }
         Ok(self)
     }
 
-    pub fn description(mut self, s: &'a str) -> Self {
+    pub const fn description(mut self, s: &'a str) -> Self {
+        self.config.description = Some(InterfaceString::new(s));
+        self
+    }
+
+    /// Interface description with translations for other languages
+    pub const fn localized_description(mut self, s: InterfaceString<'a>) -> Self {
         self.config.description = Some(s);
         self
     }
 
-    pub fn with_out_endpoint(mut self, poll_interval: MillisDurationU32) -> BuilderResult<Self> {
-        self.config.out_endpoint = Some(EndpointConfig {
-            poll_interval: u8::try_from(poll_interval.to_millis())
-                .map_err(|_| UsbHidBuilderError::ValueOverflow)?,
-        });
+    /// Additional strings associated with the interface, e.g. referenced by `String Index` items
+    /// in the report descriptor. See [`Interface::string_index`]
+    pub const fn strings(mut self, strings: &'a [InterfaceString<'a>]) -> BuilderResult<Self> {
+        if strings.len() > u8::MAX as usize {
+            return Err(UsbHidBuilderError::SliceLengthOverflow);
+        }
+        self.config.strings = strings;
+        Ok(self)
+    }
+
+    pub const fn with_out_endpoint(
+        mut self,
+        poll_interval: MillisDurationU32,
+    ) -> BuilderResult<Self> {
+        match EndpointConfig::new(poll_interval) {
+            Ok(config) => self.config.out_endpoint = Some(config),
+            Err(e) => return Err(e),
+        }
         Ok(self)
     }
 
-    pub fn without_out_endpoint(mut self) -> Self {
+    /// Add an OUT endpoint polled every `poll_interval` on a high speed bus, see
+    /// [`Self::in_endpoint_high_speed`]
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidBuilderError::ValueOverflow`] unless `poll_interval` is 125µs multiplied by a power
+    /// of two, up to 4.096s
+    pub const fn with_out_endpoint_high_speed(
+        mut self,
+        poll_interval: MicrosDurationU32,
+    ) -> BuilderResult<Self> {
+        match EndpointConfig::high_speed(poll_interval) {
+            Ok(config) => self.config.out_endpoint = Some(config),
+            Err(e) => return Err(e),
+        }
+        Ok(self)
+    }
+
+    pub const fn without_out_endpoint(mut self) -> Self {
         self.config.out_endpoint = None;
         self
     }
 
-    pub fn in_endpoint(mut self, poll_interval: MillisDurationU32) -> BuilderResult<Self> {
-        self.config.in_endpoint = EndpointConfig {
-            poll_interval: u8::try_from(poll_interval.to_millis())
-                .map_err(|_| UsbHidBuilderError::ValueOverflow)?,
-        };
+    pub const fn in_endpoint(mut self, poll_interval: MillisDurationU32) -> BuilderResult<Self> {
+        match EndpointConfig::new(poll_interval) {
+            Ok(config) => self.config.in_endpoint = config,
+            Err(e) => return Err(e),
+        }
+        Ok(self)
+    }
+
+    /// Poll the IN endpoint every `poll_interval` on a high speed bus, e.g. every 125µs
+    /// microframe to stream a report per microframe with [`Self::queue_reports`]
+    ///
+    /// High speed intervals are encoded differently to full speed intervals, so this should only
+    /// be used with a high speed [`UsbBus`].
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidBuilderError::ValueOverflow`] unless `poll_interval` is 125µs multiplied by a power
+    /// of two, up to 4.096s
+    pub const fn in_endpoint_high_speed(
+        mut self,
+        poll_interval: MicrosDurationU32,
+    ) -> BuilderResult<Self> {
+        match EndpointConfig::high_speed(poll_interval) {
+            Ok(config) => self.config.in_endpoint = config,
+            Err(e) => return Err(e),
+        }
         Ok(self)
     }
 
+    /// Declare an alternate setting whose endpoints are polled every `poll_interval`
+    ///
+    /// Alternate settings are numbered from 1 in the order declared, 0 is the default setting
+    /// configured by [`Self::in_endpoint`] and [`Self::with_out_endpoint`]. Each alternate setting
+    /// has its own endpoints, with an out endpoint if the default setting has one, so tighter
+    /// polling is only reserved once the host selects it with `SET_INTERFACE`. Reports are
+    /// written to the endpoints of the current [`Interface::alt_setting`].
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidBuilderError::SliceLengthOverflow`] if more than [`MAX_ALTERNATE_SETTINGS`] are
+    /// declared, [`UsbHidBuilderError::ValueOverflow`] if `poll_interval` is over 255ms
+    pub const fn alternate_setting(
+        mut self,
+        poll_interval: MillisDurationU32,
+    ) -> BuilderResult<Self> {
+        if self.config.alt_setting_count >= MAX_ALTERNATE_SETTINGS {
+            return Err(UsbHidBuilderError::SliceLengthOverflow);
+        }
+        match EndpointConfig::new(poll_interval) {
+            Ok(config) => self.config.alt_settings[self.config.alt_setting_count] = config,
+            Err(e) => return Err(e),
+        }
+        self.config.alt_setting_count += 1;
+        Ok(self)
+    }
+
+    /// Handle control pipe `GET_REPORT` and `SET_REPORT` requests with `handler`
+    pub const fn report_handler(mut self, handler: &'a dyn ReportHandler) -> Self {
+        self.config.report_handler = Some(ReportHandlerRef(handler));
+        self
+    }
+
+    /// Handle vendor control requests, and class requests that aren't HID requests, directed at
+    /// the interface with `handler`
+    pub const fn control_handler(mut self, handler: &'a dyn ControlRequestHandler) -> Self {
+        self.config.control_handler = Some(ControlHandlerRef(handler));
+        self
+    }
+
+    /// Don't send reports identical to the last report written with the same report ID, e.g. when
+    /// a key matrix is scanned and written on every pass
+    ///
+    /// If the host has set a non-zero idle rate for the report, an identical report is still sent
+    /// once the idle period has passed since the last was sent, as the host has requested the
+    /// report be repeated - HID spec 7.2.4. This requires
+    /// [`UsbHidClass::tick`](crate::usb_class::UsbHidClass::tick) to be called every 1ms.
+    pub const fn suppress_duplicate_reports(mut self) -> Self {
+        self.config.duplicate_reports = DuplicateReports::Suppress;
+        self
+    }
+
+    /// Send reports identical to the last report written with the same report ID, even while the
+    /// host has set an idle rate of 0
+    ///
+    /// By default, unchanged reports aren't sent while the idle rate is 0, as the host has
+    /// requested reports only when they change. Interfaces with relative data, such as mouse
+    /// movement, or that send the same report for separate events, such as a bar code scanned
+    /// twice, must send every report.
+    pub const fn send_unchanged_reports(mut self) -> Self {
+        self.config.duplicate_reports = DuplicateReports::Send;
+        self
+    }
+
+    /// Only support the boot protocol, for minimal devices that only send boot reports, such as
+    /// keyboards used with a BIOS
+    ///
+    /// The interface starts in the boot protocol, and `SET_PROTOCOL` requests for the report
+    /// protocol are rejected. Use with [`Self::boot_device`], as hosts only select the boot
+    /// protocol for boot devices.
+    pub const fn boot_only(mut self) -> Self {
+        self.config.boot_only = true;
+        self
+    }
+
+    /// Minimum time between input reports, regardless of the endpoint's poll interval. Reports
+    /// written sooner fail with [`UsbHidError::WouldBlock`], e.g. 8ms to throttle to 125Hz
+    pub const fn min_report_interval(mut self, interval: MillisDurationU32) -> Self {
+        self.config.min_report_interval = interval.to_millis();
+        self
+    }
+
+    /// Request remote wakeup when a report is written while the bus is suspended and the host
+    /// has enabled remote wakeup. The last report written while suspended is sent once the bus
+    /// resumes, see [`UsbHidClass::update_suspend`](crate::usb_class::UsbHidClass::update_suspend)
+    pub const fn wake_on_input(mut self) -> Self {
+        self.config.wake_on_input = true;
+        self
+    }
+
+    /// Hold up to [`REPORT_QUEUE_LEN`] reports written while the IN endpoint is busy, writing each
+    /// to the endpoint as soon as the host has read the previous one
+    ///
+    /// Without a queue a report can only be written once the previous one has been read, so a
+    /// device producing reports at the poll rate loses a frame whenever it's slightly late. With
+    /// a queue, e.g. for a data logger streaming 64 byte reports every 1ms, a report is ready for
+    /// every poll of the endpoint.
+    ///
+    /// Reports written while the queue is full fail with [`UsbHidError::QueueFull`], rather than
+    /// [`UsbHidError::WouldBlock`], so the writer can tell it's producing reports faster than the
+    /// host reads them.
+    pub const fn queue_reports(mut self) -> Self {
+        self.config.queue_reports = true;
+        self
+    }
+
+    /// Separate reports by at least `interval`, queueing reports written sooner rather than
+    /// failing with [`UsbHidError::WouldBlock`]. Queued reports are sent by
+    /// [`UsbHidClass::tick`](crate::usb_class::UsbHidClass::tick)
+    ///
+    /// Some hosts drop keys typed by macros unless key down and key up reports are a few
+    /// milliseconds apart. This combines [`Self::min_report_interval`] and
+    /// [`Self::queue_reports`], so fast typing is paced rather than lost.
+    pub const fn pace_reports(self, interval: MillisDurationU32) -> Self {
+        self.min_report_interval(interval).queue_reports()
+    }
+
+    /// Start the interface inactive, see [`Interface::set_active`]
+    pub const fn inactive(mut self) -> Self {
+        self.config.active = false;
+        self
+    }
+
+    /// Check the report descriptor is well formed and its reports fit the interface's buffers,
+    /// see [`check_report_descriptor`]
+    ///
+    /// In a `const` a failed check is a build error, along with errors unwrapped by
+    /// [`unwrap_builder`](crate::usb_class::unwrap_builder). Descriptors read from a [`ReportDescriptorSource`] aren't checked.
+    ///
+    /// ```
+    /// # use usbd_human_interface_device::device::keyboard::BOOT_KEYBOARD_REPORT_DESCRIPTOR;
+    /// # use usbd_human_interface_device::usb_class::prelude::*;
+    /// # use usbd_human_interface_device::usb_class::unwrap_builder;
+    /// # use fugit::MillisDurationU32;
+    /// const KEYBOARD: InterfaceConfig<'static, InBytes8, OutBytes8, ReportSingle> =
+    ///     unwrap_builder(
+    ///         unwrap_builder(
+    ///             unwrap_builder(InterfaceBuilder::new(BOOT_KEYBOARD_REPORT_DESCRIPTOR))
+    ///                 .boot_device(InterfaceProtocol::Keyboard)
+    ///                 .idle_default(MillisDurationU32::millis(500)),
+    ///         )
+    ///         .in_endpoint(MillisDurationU32::millis(10)),
+    ///     )
+    ///     .validate()
+    ///     .build();
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// If the descriptor isn't well formed, its input reports are longer than the in endpoint,
+    /// or its output reports are longer than the out endpoint
+    pub const fn validate(self) -> Self {
+        if let ReportDescriptorRef::Slice(descriptor) = self.config.report_descriptor {
+            check_report_descriptor(descriptor);
+            ::core::assert!(
+                max_report_length(descriptor, ReportType::Input) <= I::Buffer::CAPACITY as usize,
+                "input reports are longer than the in endpoint"
+            );
+            ::core::assert!(
+                self.config.out_endpoint.is_none()
+                    || max_report_length(descriptor, ReportType::Output)
+                        <= O::Buffer::CAPACITY as usize,
+                "output reports are longer than the out endpoint"
+            );
+        }
+        self
+    }
+
     #[must_use]
-    pub fn build(self) -> InterfaceConfig<'a, I, O, R> {
+    pub const fn build(self) -> InterfaceConfig<'a, I, O, R> {
         self.config
     }
 }
 
-struct IdleManager<R> {
+pub(crate) struct IdleManager<R> {
     last_report: Option<R>,
     since_last_report: MillisDurationU32,
 }
@@ -729,7 +2417,7 @@ where
 }
 
 #[allow(clippy::inline_always)]
-impl<'a, B: UsbBus, Report, I, O, const LEN: usize> ManagedIdleInterface<'a, B, Report, I, O>
+impl<B: UsbBus, Report, I, O, const LEN: usize> ManagedIdleInterface<'_, B, Report, I, O>
 where
     Report: Copy + Eq + PackedStruct<ByteArray = [u8; LEN]>,
     B: UsbBus,
@@ -745,12 +2433,9 @@ where
                 UsbHidError::SerializationError
             })?;
 
-            self.interface
-                .write_report(&data)
-                .map_err(UsbHidError::from)
-                .map(|_| {
-                    self.idle_manager.report_written(*report);
-                })
+            self.interface.write_report(&data).map(|_| {
+                self.idle_manager.report_written(*report);
+            })
         }
     }
 
@@ -778,20 +2463,21 @@ where
     }
 
     fn tick(&mut self) -> Result<(), UsbHidError> {
-        if !(self.idle_manager.tick(self.interface.global_idle())) {
+        //IdleManager treats a zero timeout as indefinite
+        let idle = self.interface.get_idle_duration(0).unwrap_or(0.millis());
+        if !(self.idle_manager.tick(idle)) {
             Ok(())
         } else if let Some(r) = self.idle_manager.last_report() {
             let data = r.pack().map_err(|_| {
                 error!("Error packing report");
                 UsbHidError::SerializationError
             })?;
-            match self.interface.write_report(&data) {
+            match self.interface.send_report(&data) {
                 Ok(n) => {
                     self.idle_manager.report_written(r);
                     Ok(n)
                 }
-                Err(UsbError::WouldBlock) => Err(UsbHidError::WouldBlock),
-                Err(e) => Err(UsbHidError::UsbError(e)),
+                Err(e) => Err(e),
             }
             .map(|_| ())
         } else {
@@ -2,6 +2,7 @@
 #![warn(clippy::pedantic)]
 #![warn(clippy::style)]
 #![warn(clippy::cargo)]
+#![allow(clippy::multiple_crate_versions)]
 #![allow(clippy::module_name_repetitions)]
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::missing_panics_doc)]
@@ -101,9 +102,10 @@
 //!     .build(&usb_alloc);
 //!
 //! let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
-//!     .manufacturer("usbd-human-interface-device")
-//!     .product("NKRO Keyboard")
-//!     .serial_number("TEST")
+//!     .strings(&[StringDescriptors::default()
+//!         .manufacturer("usbd-human-interface-device")
+//!         .product("NKRO Keyboard")
+//!         .serial_number("TEST")]).unwrap()
 //!     .build();
 //!
 //! let mut tick_timer = timer.count_down();
@@ -140,24 +142,53 @@
 
 pub(crate) mod fmt;
 
-//Allow the use of std in tests
-#[cfg(test)]
+//Allow the use of std in tests and with the std feature
+#[cfg(any(test, feature = "std"))]
 extern crate std;
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+use core::fmt::{Display, Formatter};
+use usb_class::UsbHidBuilderError;
 use usb_device::UsbError;
 
+pub mod axis;
+pub mod clock;
 pub mod descriptor;
 pub mod device;
 pub mod interface;
 pub mod page;
 pub mod prelude;
+pub mod remap;
+pub mod sequence;
+pub mod serialize;
+pub mod source;
+pub mod split;
+#[cfg(feature = "test-util")]
+pub mod test_util;
 pub mod usb_class;
 
-#[derive(Debug)]
+/// Error returned by HID devices and interfaces
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum UsbHidError {
+    /// The endpoint is busy, try again later
     WouldBlock,
+    /// The report is identical to the last report sent and was not sent again
     Duplicate,
+    /// The report is larger than the endpoint or buffer it is being written to
+    ReportTooLarge,
+    /// The host or application made a request that the device does not support
+    UnsupportedRequest,
+    /// A queue of pending reports or events is full
+    QueueFull,
+    /// An interface or report descriptor is invalid
+    Descriptor(UsbHidBuilderError),
+    /// Any other error from the USB stack
     UsbError(UsbError),
+    /// A report could not be serialized
     SerializationError,
 }
 
@@ -165,13 +196,90 @@ impl From<UsbError> for UsbHidError {
     fn from(e: UsbError) -> Self {
         match e {
             UsbError::WouldBlock => Self::WouldBlock,
+            UsbError::BufferOverflow => Self::ReportTooLarge,
+            UsbError::Unsupported => Self::UnsupportedRequest,
             _ => Self::UsbError(e),
         }
     }
 }
 
+impl From<UsbHidBuilderError> for UsbHidError {
+    fn from(e: UsbHidBuilderError) -> Self {
+        Self::Descriptor(e)
+    }
+}
+
+impl Display for UsbHidError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::WouldBlock => f.write_str("endpoint busy"),
+            Self::Duplicate => f.write_str("duplicate report"),
+            Self::ReportTooLarge => f.write_str("report too large"),
+            Self::UnsupportedRequest => f.write_str("unsupported request"),
+            Self::QueueFull => f.write_str("queue full"),
+            Self::Descriptor(e) => write!(f, "invalid descriptor: {e:?}"),
+            Self::UsbError(e) => write!(f, "USB error: {e:?}"),
+            Self::SerializationError => f.write_str("report serialization failed"),
+        }
+    }
+}
+
 mod private {
     /// Super trait used to mark traits with an exhaustive set of
     /// implementations
     pub trait Sealed {}
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::string::ToString;
+
+    #[test]
+    fn usb_errors_are_mapped() {
+        assert_eq!(
+            UsbHidError::from(UsbError::WouldBlock),
+            UsbHidError::WouldBlock
+        );
+        assert_eq!(
+            UsbHidError::from(UsbError::BufferOverflow),
+            UsbHidError::ReportTooLarge
+        );
+        assert_eq!(
+            UsbHidError::from(UsbError::Unsupported),
+            UsbHidError::UnsupportedRequest
+        );
+        assert_eq!(
+            UsbHidError::from(UsbError::InvalidEndpoint),
+            UsbHidError::UsbError(UsbError::InvalidEndpoint)
+        );
+        assert_eq!(
+            UsbHidError::from(UsbHidBuilderError::ValueOverflow),
+            UsbHidError::Descriptor(UsbHidBuilderError::ValueOverflow)
+        );
+    }
+
+    #[test]
+    fn errors_are_displayed() {
+        assert_eq!(UsbHidError::WouldBlock.to_string(), "endpoint busy");
+        assert_eq!(UsbHidError::Duplicate.to_string(), "duplicate report");
+        assert_eq!(UsbHidError::ReportTooLarge.to_string(), "report too large");
+        assert_eq!(
+            UsbHidError::UnsupportedRequest.to_string(),
+            "unsupported request"
+        );
+        assert_eq!(UsbHidError::QueueFull.to_string(), "queue full");
+        assert_eq!(
+            UsbHidError::Descriptor(UsbHidBuilderError::SliceLengthOverflow).to_string(),
+            "invalid descriptor: SliceLengthOverflow"
+        );
+        assert_eq!(
+            UsbHidError::UsbError(UsbError::InvalidState).to_string(),
+            "USB error: InvalidState"
+        );
+        assert_eq!(
+            UsbHidError::SerializationError.to_string(),
+            "report serialization failed"
+        );
+    }
+}
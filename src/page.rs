@@ -3,6 +3,8 @@
 //! See Universal Serial Bus (USB) HID Usage Tables Version 1.12
 //! <https://www.usb.org/sites/default/files/documents/hut1_12v2.pdf>
 
+pub mod layout;
+
 use core::hash::Hash;
 use num_enum::{FromPrimitive, IntoPrimitive};
 use packed_struct::prelude::*;
@@ -13,6 +15,9 @@ use packed_struct::prelude::*;
 // * Fix casing: (\b[a-z]) - \u$1
 // * Squash spaces and punctuation: [^\w=,]
 // * Unmangle reserved: (.*)(reserved)=(.*) - //0x$1-$3 $2
+//
+// Usages added after version 1.12 are taken from HID Usage Tables Version 1.4
+// <https://usb.org/sites/default/files/hut1_4.pdf>
 
 /// LEDs usage page
 ///
@@ -31,10 +36,11 @@ use packed_struct::prelude::*;
     Hash,
     IntoPrimitive,
     FromPrimitive,
+    Default,
 )]
 #[repr(u8)]
 pub enum Leds {
-    #[num_enum(default)]
+    #[default]
     Undefined = 0x00,
     NumLock = 0x01,
     CapsLock = 0x02,
@@ -114,9 +120,325 @@ pub enum Leds {
     //0x4C-0xFFFF Reserved
 }
 
-impl Default for Leds {
-    fn default() -> Self {
-        Self::Undefined
+/// Button usage page
+///
+/// See [Universal Serial Bus (USB) HID Usage Tables Version 1.12](<https://www.usb.org/sites/default/files/documents/hut1_12v2.pdf>):
+/// Section 12 Button Page (0x09)
+///
+/// Usage `n` is button `n`. Only the first 255 buttons are enumerated
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+    Hash,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+    Default,
+)]
+#[repr(u8)]
+pub enum Button {
+    #[default]
+    NoButton = 0x00,
+    Button1 = 0x01,
+    Button2 = 0x02,
+    Button3 = 0x03,
+    Button4 = 0x04,
+    Button5 = 0x05,
+    Button6 = 0x06,
+    Button7 = 0x07,
+    Button8 = 0x08,
+    Button9 = 0x09,
+    Button10 = 0x0A,
+    Button11 = 0x0B,
+    Button12 = 0x0C,
+    Button13 = 0x0D,
+    Button14 = 0x0E,
+    Button15 = 0x0F,
+    Button16 = 0x10,
+    Button17 = 0x11,
+    Button18 = 0x12,
+    Button19 = 0x13,
+    Button20 = 0x14,
+    Button21 = 0x15,
+    Button22 = 0x16,
+    Button23 = 0x17,
+    Button24 = 0x18,
+    Button25 = 0x19,
+    Button26 = 0x1A,
+    Button27 = 0x1B,
+    Button28 = 0x1C,
+    Button29 = 0x1D,
+    Button30 = 0x1E,
+    Button31 = 0x1F,
+    Button32 = 0x20,
+    Button33 = 0x21,
+    Button34 = 0x22,
+    Button35 = 0x23,
+    Button36 = 0x24,
+    Button37 = 0x25,
+    Button38 = 0x26,
+    Button39 = 0x27,
+    Button40 = 0x28,
+    Button41 = 0x29,
+    Button42 = 0x2A,
+    Button43 = 0x2B,
+    Button44 = 0x2C,
+    Button45 = 0x2D,
+    Button46 = 0x2E,
+    Button47 = 0x2F,
+    Button48 = 0x30,
+    Button49 = 0x31,
+    Button50 = 0x32,
+    Button51 = 0x33,
+    Button52 = 0x34,
+    Button53 = 0x35,
+    Button54 = 0x36,
+    Button55 = 0x37,
+    Button56 = 0x38,
+    Button57 = 0x39,
+    Button58 = 0x3A,
+    Button59 = 0x3B,
+    Button60 = 0x3C,
+    Button61 = 0x3D,
+    Button62 = 0x3E,
+    Button63 = 0x3F,
+    Button64 = 0x40,
+    Button65 = 0x41,
+    Button66 = 0x42,
+    Button67 = 0x43,
+    Button68 = 0x44,
+    Button69 = 0x45,
+    Button70 = 0x46,
+    Button71 = 0x47,
+    Button72 = 0x48,
+    Button73 = 0x49,
+    Button74 = 0x4A,
+    Button75 = 0x4B,
+    Button76 = 0x4C,
+    Button77 = 0x4D,
+    Button78 = 0x4E,
+    Button79 = 0x4F,
+    Button80 = 0x50,
+    Button81 = 0x51,
+    Button82 = 0x52,
+    Button83 = 0x53,
+    Button84 = 0x54,
+    Button85 = 0x55,
+    Button86 = 0x56,
+    Button87 = 0x57,
+    Button88 = 0x58,
+    Button89 = 0x59,
+    Button90 = 0x5A,
+    Button91 = 0x5B,
+    Button92 = 0x5C,
+    Button93 = 0x5D,
+    Button94 = 0x5E,
+    Button95 = 0x5F,
+    Button96 = 0x60,
+    Button97 = 0x61,
+    Button98 = 0x62,
+    Button99 = 0x63,
+    Button100 = 0x64,
+    Button101 = 0x65,
+    Button102 = 0x66,
+    Button103 = 0x67,
+    Button104 = 0x68,
+    Button105 = 0x69,
+    Button106 = 0x6A,
+    Button107 = 0x6B,
+    Button108 = 0x6C,
+    Button109 = 0x6D,
+    Button110 = 0x6E,
+    Button111 = 0x6F,
+    Button112 = 0x70,
+    Button113 = 0x71,
+    Button114 = 0x72,
+    Button115 = 0x73,
+    Button116 = 0x74,
+    Button117 = 0x75,
+    Button118 = 0x76,
+    Button119 = 0x77,
+    Button120 = 0x78,
+    Button121 = 0x79,
+    Button122 = 0x7A,
+    Button123 = 0x7B,
+    Button124 = 0x7C,
+    Button125 = 0x7D,
+    Button126 = 0x7E,
+    Button127 = 0x7F,
+    Button128 = 0x80,
+    Button129 = 0x81,
+    Button130 = 0x82,
+    Button131 = 0x83,
+    Button132 = 0x84,
+    Button133 = 0x85,
+    Button134 = 0x86,
+    Button135 = 0x87,
+    Button136 = 0x88,
+    Button137 = 0x89,
+    Button138 = 0x8A,
+    Button139 = 0x8B,
+    Button140 = 0x8C,
+    Button141 = 0x8D,
+    Button142 = 0x8E,
+    Button143 = 0x8F,
+    Button144 = 0x90,
+    Button145 = 0x91,
+    Button146 = 0x92,
+    Button147 = 0x93,
+    Button148 = 0x94,
+    Button149 = 0x95,
+    Button150 = 0x96,
+    Button151 = 0x97,
+    Button152 = 0x98,
+    Button153 = 0x99,
+    Button154 = 0x9A,
+    Button155 = 0x9B,
+    Button156 = 0x9C,
+    Button157 = 0x9D,
+    Button158 = 0x9E,
+    Button159 = 0x9F,
+    Button160 = 0xA0,
+    Button161 = 0xA1,
+    Button162 = 0xA2,
+    Button163 = 0xA3,
+    Button164 = 0xA4,
+    Button165 = 0xA5,
+    Button166 = 0xA6,
+    Button167 = 0xA7,
+    Button168 = 0xA8,
+    Button169 = 0xA9,
+    Button170 = 0xAA,
+    Button171 = 0xAB,
+    Button172 = 0xAC,
+    Button173 = 0xAD,
+    Button174 = 0xAE,
+    Button175 = 0xAF,
+    Button176 = 0xB0,
+    Button177 = 0xB1,
+    Button178 = 0xB2,
+    Button179 = 0xB3,
+    Button180 = 0xB4,
+    Button181 = 0xB5,
+    Button182 = 0xB6,
+    Button183 = 0xB7,
+    Button184 = 0xB8,
+    Button185 = 0xB9,
+    Button186 = 0xBA,
+    Button187 = 0xBB,
+    Button188 = 0xBC,
+    Button189 = 0xBD,
+    Button190 = 0xBE,
+    Button191 = 0xBF,
+    Button192 = 0xC0,
+    Button193 = 0xC1,
+    Button194 = 0xC2,
+    Button195 = 0xC3,
+    Button196 = 0xC4,
+    Button197 = 0xC5,
+    Button198 = 0xC6,
+    Button199 = 0xC7,
+    Button200 = 0xC8,
+    Button201 = 0xC9,
+    Button202 = 0xCA,
+    Button203 = 0xCB,
+    Button204 = 0xCC,
+    Button205 = 0xCD,
+    Button206 = 0xCE,
+    Button207 = 0xCF,
+    Button208 = 0xD0,
+    Button209 = 0xD1,
+    Button210 = 0xD2,
+    Button211 = 0xD3,
+    Button212 = 0xD4,
+    Button213 = 0xD5,
+    Button214 = 0xD6,
+    Button215 = 0xD7,
+    Button216 = 0xD8,
+    Button217 = 0xD9,
+    Button218 = 0xDA,
+    Button219 = 0xDB,
+    Button220 = 0xDC,
+    Button221 = 0xDD,
+    Button222 = 0xDE,
+    Button223 = 0xDF,
+    Button224 = 0xE0,
+    Button225 = 0xE1,
+    Button226 = 0xE2,
+    Button227 = 0xE3,
+    Button228 = 0xE4,
+    Button229 = 0xE5,
+    Button230 = 0xE6,
+    Button231 = 0xE7,
+    Button232 = 0xE8,
+    Button233 = 0xE9,
+    Button234 = 0xEA,
+    Button235 = 0xEB,
+    Button236 = 0xEC,
+    Button237 = 0xED,
+    Button238 = 0xEE,
+    Button239 = 0xEF,
+    Button240 = 0xF0,
+    Button241 = 0xF1,
+    Button242 = 0xF2,
+    Button243 = 0xF3,
+    Button244 = 0xF4,
+    Button245 = 0xF5,
+    Button246 = 0xF6,
+    Button247 = 0xF7,
+    Button248 = 0xF8,
+    Button249 = 0xF9,
+    Button250 = 0xFA,
+    Button251 = 0xFB,
+    Button252 = 0xFC,
+    Button253 = 0xFD,
+    Button254 = 0xFE,
+    Button255 = 0xFF,
+}
+
+/// Camera Control usage page
+///
+/// See [HID Usage Tables for Universal Serial Bus (USB) Version 1.4](<https://www.usb.org/sites/default/files/hut1_4.pdf>):
+/// Section 27 Camera Control Page (0x90)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    Hash,
+    PartialOrd,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+    Default,
+)]
+#[repr(u8)]
+pub enum Camera {
+    #[default]
+    Undefined = 0x00,
+    //0x01-0x1F Reserved
+    CameraAutoFocus = 0x20,
+    CameraShutter = 0x21,
+    //0x22-0xFFFF Reserved
+}
+
+impl Camera {
+    /// Usage page ID of the Camera Control page
+    pub const PAGE: u16 = 0x90;
+
+    /// The usage ID, for use in `const` report descriptors
+    #[must_use]
+    pub const fn id(self) -> u16 {
+        self as u16
     }
 }
 
@@ -137,10 +459,11 @@ impl Default for Leds {
     Hash,
     IntoPrimitive,
     FromPrimitive,
+    Default,
 )]
 #[repr(u16)]
 pub enum Consumer {
-    #[num_enum(default)]
+    #[default]
     Unassigned = 0x00,
     ConsumerControl = 0x01,
     NumericKeyPad = 0x02,
@@ -152,7 +475,7 @@ pub enum Consumer {
     Plus10 = 0x20,
     Plus100 = 0x21,
     AmPm = 0x22,
-    //0x23-0x3F Reserved
+    //0x23-0x2F Reserved
     Power = 0x30,
     Reset = 0x31,
     Sleep = 0x32,
@@ -178,7 +501,31 @@ pub enum Consumer {
     BroadcastMode = 0x64,
     Snapshot = 0x65,
     Still = 0x66,
-    //0x67-0x7F Reserved
+    PictureInPictureToggle = 0x67,
+    PictureInPictureSwap = 0x68,
+    RedMenuButton = 0x69,
+    GreenMenuButton = 0x6A,
+    BlueMenuButton = 0x6B,
+    YellowMenuButton = 0x6C,
+    Aspect = 0x6D,
+    ThreeDModeSelect = 0x6E,
+    DisplayBrightnessIncrement = 0x6F,
+    DisplayBrightnessDecrement = 0x70,
+    DisplayBrightness = 0x71,
+    DisplayBacklightToggle = 0x72,
+    DisplaySetBrightnessToMinimum = 0x73,
+    DisplaySetBrightnessToMaximum = 0x74,
+    DisplaySetAutoBrightness = 0x75,
+    CameraAccessEnabled = 0x76,
+    CameraAccessDisabled = 0x77,
+    CameraAccessToggle = 0x78,
+    KeyboardBrightnessIncrement = 0x79,
+    KeyboardBrightnessDecrement = 0x7A,
+    KeyboardBacklightSetLevel = 0x7B,
+    KeyboardBacklightOOC = 0x7C,
+    KeyboardBacklightSetMinimum = 0x7D,
+    KeyboardBacklightSetMaximum = 0x7E,
+    KeyboardBacklightAuto = 0x7F,
     Selection = 0x80,
     AssignSelection = 0x81,
     ModeStep = 0x82,
@@ -248,7 +595,18 @@ pub enum Consumer {
     StopEject = 0xCC,
     PlayPause = 0xCD,
     PlaySkip = 0xCE,
-    //0xCF-0xDF Reserved
+    VoiceCommand = 0xCF,
+    InvokeCaptureInterface = 0xD0,
+    StartOrStopGameRecording = 0xD1,
+    HistoricalGameCapture = 0xD2,
+    CaptureGameScreenshot = 0xD3,
+    ShowOrHideRecordingIndicator = 0xD4,
+    StartOrStopMicrophoneCapture = 0xD5,
+    StartOrStopCameraCapture = 0xD6,
+    StartOrStopGameBroadcast = 0xD7,
+    StartOrStopVoiceDictationSession = 0xD8,
+    InvokeDismissEmojiPicker = 0xD9,
+    //0xDA-0xDF Reserved
     Volume = 0xE0,
     Balance = 0xE1,
     Mute = 0xE2,
@@ -367,7 +725,7 @@ pub enum Consumer {
     ALMovieBrowser = 0x1B8,
     ALDigitalRightsManager = 0x1B9,
     ALDigitalWallet = 0x1BA,
-    //0x-0x1BB Reserved
+    //0x1BB Reserved
     ALInstantMessaging = 0x1BC,
     ALOemFeaturesTipsTutorialBrowser = 0x1BD,
     ALOemHelp = 0x1BE,
@@ -380,7 +738,11 @@ pub enum Consumer {
     ALOnlineActivityBrowser = 0x1C5,
     ALResearchSearchBrowser = 0x1C6,
     ALAudioPlayer = 0x1C7,
-    //0x1C8-0x1FF Reserved
+    ALMessageStatus = 0x1C8,
+    ALContactSync = 0x1C9,
+    ALNavigation = 0x1CA,
+    ALContextAwareDesktopAssistant = 0x1CB,
+    //0x1CC-0x1FF Reserved
     GenericGUIApplicationControls = 0x200,
     ACNew = 0x201,
     ACOpen = 0x202,
@@ -522,13 +884,35 @@ pub enum Consumer {
     ACSplit = 0x29A,
     ACDistributeHorizontally = 0x29B,
     ACDistributeVertically = 0x29C,
-    //0x29D-0xFFFF Reserved
-}
-
-impl Default for Consumer {
-    fn default() -> Self {
-        Self::Unassigned
-    }
+    ACKeyboardLayoutSelect = 0x29D,
+    ACNavigationGuidance = 0x29E,
+    ACDesktopShowAllWindows = 0x29F,
+    ACSoftKeyLeft = 0x2A0,
+    ACSoftKeyRight = 0x2A1,
+    ACDesktopShowAllApplications = 0x2A2,
+    //0x2A3-0x2AF Reserved
+    ACIdleKeepAlive = 0x2B0,
+    //0x2B1-0x2BF Reserved
+    ExtendedKeyboardAttributesCollection = 0x2C0,
+    KeyboardFormFactor = 0x2C1,
+    KeyboardKeyType = 0x2C2,
+    KeyboardPhysicalLayout = 0x2C3,
+    VendorSpecificKeyboardPhysicalLayout = 0x2C4,
+    KeyboardIetfLanguageTagIndex = 0x2C5,
+    ImplementedKeyboardInputAssistControls = 0x2C6,
+    KeyboardInputAssistPrevious = 0x2C7,
+    KeyboardInputAssistNext = 0x2C8,
+    KeyboardInputAssistPreviousGroup = 0x2C9,
+    KeyboardInputAssistNextGroup = 0x2CA,
+    KeyboardInputAssistAccept = 0x2CB,
+    KeyboardInputAssistCancel = 0x2CC,
+    //0x2CD-0x2CF Reserved
+    PrivacyScreenToggle = 0x2D0,
+    PrivacyScreenLevelDecrement = 0x2D1,
+    PrivacyScreenLevelIncrement = 0x2D2,
+    PrivacyScreenLevelMinimum = 0x2D3,
+    PrivacyScreenLevelMaximum = 0x2D4,
+    //0x2D5-0xFFFF Reserved
 }
 
 /// Generic Desktop usage page
@@ -548,10 +932,11 @@ impl Default for Consumer {
     PrimitiveEnum,
     IntoPrimitive,
     FromPrimitive,
+    Default,
 )]
 #[repr(u8)]
 pub enum Desktop {
-    #[num_enum(default)]
+    #[default]
     Undefined = 0x00,
     Pointer = 0x01,
     Mouse = 0x02,
@@ -562,7 +947,17 @@ pub enum Desktop {
     Keypad = 0x07,
     MultiAxisController = 0x08,
     TabletPcSystemControls = 0x09,
-    //0x0A-0x2F Reserved
+    WaterCoolingDevice = 0x0A,
+    ComputerChassisDevice = 0x0B,
+    WirelessRadioControls = 0x0C,
+    PortableDeviceControl = 0x0D,
+    SystemMultiAxisController = 0x0E,
+    SpatialController = 0x0F,
+    AssistiveControl = 0x10,
+    DeviceDock = 0x11,
+    DockableDevice = 0x12,
+    CallStateManagementControl = 0x13,
+    //0x14-0x2F Reserved
     X = 0x30,
     Y = 0x31,
     Z = 0x32,
@@ -588,7 +983,11 @@ pub enum Desktop {
     Vno = 0x46,
     FeatureNotification = 0x47,
     ResolutionMultiplier = 0x48,
-    //0x49-0x7F Reserved
+    Qx = 0x49,
+    Qy = 0x4A,
+    Qz = 0x4B,
+    Qw = 0x4C,
+    //0x4D-0x7F Reserved
     SystemControl = 0x80,
     SystemPowerDown = 0x81,
     SystemSleep = 0x82,
@@ -609,13 +1008,60 @@ pub enum Desktop {
     DPadDown = 0x91,
     DPadRight = 0x92,
     DPadLeft = 0x93,
-    //0x94-0xFFFF Reserved
-}
-
-impl Default for Desktop {
-    fn default() -> Self {
-        Self::Undefined
-    }
+    IndexTrigger = 0x94,
+    PalmTrigger = 0x95,
+    Thumbstick = 0x96,
+    SystemFunctionShift = 0x97,
+    SystemFunctionShiftLock = 0x98,
+    SystemFunctionShiftLockIndicator = 0x99,
+    SystemDismissNotification = 0x9A,
+    SystemDoNotDisturb = 0x9B,
+    //0x9C-0x9F Reserved
+    SystemDock = 0xA0,
+    SystemUndock = 0xA1,
+    SystemSetup = 0xA2,
+    SystemBreak = 0xA3,
+    SystemDebuggerBreak = 0xA4,
+    ApplicationBreak = 0xA5,
+    ApplicationDebuggerBreak = 0xA6,
+    SystemSpeakerMute = 0xA7,
+    SystemHibernate = 0xA8,
+    SystemMicrophoneMute = 0xA9,
+    //0xAA-0xAF Reserved
+    SystemDisplayInvert = 0xB0,
+    SystemDisplayInternal = 0xB1,
+    SystemDisplayExternal = 0xB2,
+    SystemDisplayBoth = 0xB3,
+    SystemDisplayDual = 0xB4,
+    SystemDisplayToggleIntExtMode = 0xB5,
+    SystemDisplaySwapPrimarySecondary = 0xB6,
+    SystemDisplayToggleLcdAutoscale = 0xB7,
+    //0xB8-0xBF Reserved
+    SensorZone = 0xC0,
+    Rpm = 0xC1,
+    CoolantLevel = 0xC2,
+    CoolantCriticalLevel = 0xC3,
+    CoolantPump = 0xC4,
+    ChassisEnclosure = 0xC5,
+    WirelessRadioButton = 0xC6,
+    WirelessRadioLed = 0xC7,
+    WirelessRadioSliderSwitch = 0xC8,
+    SystemDisplayRotationLockButton = 0xC9,
+    SystemDisplayRotationLockSliderSwitch = 0xCA,
+    ControlEnable = 0xCB,
+    //0xCC-0xCF Reserved
+    DockableDeviceUniqueId = 0xD0,
+    DockableDeviceVendorId = 0xD1,
+    DockableDevicePrimaryUsagePage = 0xD2,
+    DockableDevicePrimaryUsageId = 0xD3,
+    DockableDeviceDockingState = 0xD4,
+    DockableDeviceDisplayOcclusion = 0xD5,
+    DockableDeviceObjectType = 0xD6,
+    //0xD7-0xDF Reserved
+    CallActiveLed = 0xE0,
+    CallMuteToggle = 0xE1,
+    CallMuteLed = 0xE2,
+    //0xE3-0xFF Reserved
 }
 
 /// Game Controls usage page
@@ -635,10 +1081,11 @@ impl Default for Desktop {
     PrimitiveEnum,
     IntoPrimitive,
     FromPrimitive,
+    Default,
 )]
 #[repr(u8)]
 pub enum Game {
-    #[num_enum(default)]
+    #[default]
     Undefined = 0x00,
     Game3DController = 0x01,
     PinballDevice = 0x02,
@@ -672,9 +1119,64 @@ pub enum Game {
     //0x3A-0xFFFF Reserved
 }
 
-impl Default for Game {
-    fn default() -> Self {
-        Self::Undefined
+/// Haptics usage page
+///
+/// See [HID Usage Table Review Request 63b: Haptics Page](<https://www.usb.org/sites/default/files/hutrr63b_-_haptics_page_redline_0.pdf>):
+/// Haptics Page (0x0E)
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(
+    Debug,
+    Copy,
+    Clone,
+    Eq,
+    PartialEq,
+    Ord,
+    Hash,
+    PartialOrd,
+    PrimitiveEnum,
+    IntoPrimitive,
+    FromPrimitive,
+    Default,
+)]
+#[repr(u16)]
+pub enum Haptics {
+    #[default]
+    Undefined = 0x00,
+    SimpleHapticController = 0x01,
+    //0x02-0x0F Reserved
+    WaveformList = 0x10,
+    DurationList = 0x11,
+    //0x12-0x1F Reserved
+    AutoTrigger = 0x20,
+    ManualTrigger = 0x21,
+    AutoTriggerAssociatedControl = 0x22,
+    Intensity = 0x23,
+    RepeatCount = 0x24,
+    RetriggerPeriod = 0x25,
+    WaveformVendorPage = 0x26,
+    WaveformVendorId = 0x27,
+    WaveformCutoffTime = 0x28,
+    //0x29-0x1000 Reserved
+    WaveformNone = 0x1001,
+    WaveformStop = 0x1002,
+    WaveformClick = 0x1003,
+    WaveformBuzzContinuous = 0x1004,
+    WaveformRumbleContinuous = 0x1005,
+    WaveformPress = 0x1006,
+    WaveformRelease = 0x1007,
+    //0x1008-0x1FFF Reserved
+    //0x2000-0x2FFF Vendor Waveforms
+    //0x3000-0xFFFF Reserved
+}
+
+impl Haptics {
+    /// Usage page ID of the Haptics page
+    pub const PAGE: u16 = 0x0E;
+
+    /// The usage ID, for use in `const` report descriptors
+    #[must_use]
+    pub const fn id(self) -> u16 {
+        self as u16
     }
 }
 
@@ -698,10 +1200,11 @@ impl Default for Game {
     PrimitiveEnum,
     IntoPrimitive,
     FromPrimitive,
+    Default,
 )]
 #[repr(u8)]
 pub enum Keyboard {
-    #[num_enum(default)]
+    #[default]
     NoEventIndicated = 0x00,
     ErrorRollOver = 0x01,
     POSTFail = 0x02,
@@ -867,7 +1370,54 @@ pub enum Keyboard {
     ClearAgain = 0xA2,
     CrSelProps = 0xA3,
     ExSel = 0xA4,
-    //0xA5-0xDF Reserved
+    //0xA5-0xAF Reserved
+    Keypad00 = 0xB0,
+    Keypad000 = 0xB1,
+    ThousandsSeparator = 0xB2,
+    DecimalSeparator = 0xB3,
+    CurrencyUnit = 0xB4,
+    CurrencySubUnit = 0xB5,
+    KeypadOpenParens = 0xB6,
+    KeypadCloseParens = 0xB7,
+    KeypadOpenBrace = 0xB8,
+    KeypadCloseBrace = 0xB9,
+    KeypadTab = 0xBA,
+    KeypadBackspace = 0xBB,
+    KeypadA = 0xBC,
+    KeypadB = 0xBD,
+    KeypadC = 0xBE,
+    KeypadD = 0xBF,
+    KeypadE = 0xC0,
+    KeypadF = 0xC1,
+    KeypadXor = 0xC2,
+    KeypadCaret = 0xC3,
+    KeypadPercent = 0xC4,
+    KeypadLessThan = 0xC5,
+    KeypadGreaterThan = 0xC6,
+    KeypadAmpersand = 0xC7,
+    KeypadDoubleAmpersand = 0xC8,
+    KeypadBar = 0xC9,
+    KeypadDoubleBar = 0xCA,
+    KeypadColon = 0xCB,
+    KeypadHash = 0xCC,
+    KeypadSpace = 0xCD,
+    KeypadAt = 0xCE,
+    KeypadExclamation = 0xCF,
+    KeypadMemoryStore = 0xD0,
+    KeypadMemoryRecall = 0xD1,
+    KeypadMemoryClear = 0xD2,
+    KeypadMemoryAdd = 0xD3,
+    KeypadMemorySubtract = 0xD4,
+    KeypadMemoryMultiply = 0xD5,
+    KeypadMemoryDivide = 0xD6,
+    KeypadPlusMinus = 0xD7,
+    KeypadClear = 0xD8,
+    KeypadClearEntry = 0xD9,
+    KeypadBinary = 0xDA,
+    KeypadOctal = 0xDB,
+    KeypadDecimal = 0xDC,
+    KeypadHexadecimal = 0xDD,
+    //0xDE-0xDF Reserved
     LeftControl = 0xE0,
     LeftShift = 0xE1,
     LeftAlt = 0xE2,
@@ -879,12 +1429,6 @@ pub enum Keyboard {
     //0xE8-0xFFFF Reserved
 }
 
-impl Default for Keyboard {
-    fn default() -> Self {
-        Self::NoEventIndicated
-    }
-}
-
 /// Simulation Controls usage page
 ///
 /// See [Universal Serial Bus (USB) HID Usage Tables Version 1.12](<https://www.usb.org/sites/default/files/documents/hut1_12v2.pdf>):
@@ -902,10 +1446,11 @@ impl Default for Keyboard {
     PrimitiveEnum,
     IntoPrimitive,
     FromPrimitive,
+    Default,
 )]
 #[repr(u8)]
 pub enum Simulation {
-    #[num_enum(default)]
+    #[default]
     Undefined = 0x00,
     FlightSimulationDevice = 0x01,
     AutomobileSimulationDevice = 0x02,
@@ -964,9 +1509,14 @@ pub enum Simulation {
     //0xD1-0xFFFF Reserved
 }
 
-impl Default for Simulation {
-    fn default() -> Self {
-        Self::Undefined
+impl Simulation {
+    /// Usage page ID of the Simulation Controls page
+    pub const PAGE: u16 = 0x02;
+
+    /// The usage ID, for use in `const` report descriptors
+    #[must_use]
+    pub const fn id(self) -> u16 {
+        self as u16
     }
 }
 
@@ -987,10 +1537,11 @@ impl Default for Simulation {
     PrimitiveEnum,
     IntoPrimitive,
     FromPrimitive,
+    Default,
 )]
 #[repr(u8)]
 pub enum Telephony {
-    #[num_enum(default)]
+    #[default]
     Unassigned = 0x00,
     Phone = 0x01,
     AnsweringMachine = 0x02,
@@ -1064,8 +1615,125 @@ pub enum Telephony {
     PhoneKeyD = 0xBF,
     //0xC0-0xFFFF Reserved
 }
-impl Default for Telephony {
-    fn default() -> Self {
-        Self::Unassigned
+
+#[cfg(test)]
+mod test {
+    use super::{Button, Consumer, Desktop, Keyboard};
+    use crate::device::consumer::{
+        FIXED_FUNCTION_REPORT_DESCRIPTOR, MULTIPLE_CODE_REPORT_DESCRIPTOR,
+    };
+    use crate::device::flight::FLIGHT_CONTROLS_REPORT_DESCRIPTOR;
+    use crate::device::gamepad::GAMEPAD_REPORT_DESCRIPTOR;
+    use crate::device::joystick::JOYSTICK_DESCRIPTOR;
+    use crate::device::keyboard::{
+        BOOT_KEYBOARD_REPORT_DESCRIPTOR, NKRO_BOOT_KEYBOARD_REPORT_DESCRIPTOR,
+    };
+    use crate::device::mouse::WHEEL_MOUSE_REPORT_DESCRIPTOR;
+    use packed_struct::PrimitiveEnum;
+    use std::vec::Vec;
+
+    const USAGE_PAGE: u8 = 0x04;
+    const LOGICAL_MAXIMUM: u8 = 0x24;
+    const USAGE: u8 = 0x08;
+    const USAGE_MAXIMUM: u8 = 0x28;
+
+    /// `(usage page, tag, value)` of the short global and local items of `descriptor`
+    fn items(descriptor: &[u8]) -> Vec<(u32, u8, u32)> {
+        let mut items = Vec::new();
+        let mut page = 0;
+        let mut i = 0;
+        while i < descriptor.len() {
+            let prefix = descriptor[i];
+            let size = [0, 1, 2, 4][usize::from(prefix & 0x3)];
+            let value = descriptor[i + 1..i + 1 + size]
+                .iter()
+                .rev()
+                .fold(0, |acc, &b| (acc << 8) | u32::from(b));
+            if prefix & 0xFC == USAGE_PAGE {
+                page = value;
+            }
+            items.push((page, prefix & 0xFC, value));
+            i += 1 + size;
+        }
+        items
+    }
+
+    fn is_enumerated(page: u32, usage: u32) -> bool {
+        match page {
+            0x01 => u8::try_from(usage).is_ok_and(|u| Desktop::from_primitive(u).is_some()),
+            0x07 => u8::try_from(usage).is_ok_and(|u| Keyboard::from_primitive(u).is_some()),
+            0x09 => u8::try_from(usage).is_ok_and(|u| Button::from_primitive(u).is_some()),
+            0x0C => u16::try_from(usage).is_ok_and(|u| Consumer::from_primitive(u).is_some()),
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn usages_match_usage_tables() {
+        assert_eq!(u8::from(Desktop::Mouse), 0x02);
+        assert_eq!(u8::from(Desktop::GamePad), 0x05);
+        assert_eq!(u8::from(Desktop::X), 0x30);
+        assert_eq!(u8::from(Desktop::Wheel), 0x38);
+        assert_eq!(u8::from(Desktop::SystemSleep), 0x82);
+        assert_eq!(u8::from(Keyboard::A), 0x04);
+        assert_eq!(u8::from(Keyboard::ReturnEnter), 0x28);
+        assert_eq!(u8::from(Keyboard::KeypadEqual), 0x67);
+        assert_eq!(u8::from(Keyboard::LeftControl), 0xE0);
+        assert_eq!(u8::from(Button::Button1), 0x01);
+        assert_eq!(u8::from(Button::Button255), 0xFF);
+        assert_eq!(u16::from(Consumer::PlayPause), 0xCD);
+        assert_eq!(u16::from(Consumer::VolumeIncrement), 0xE9);
+        assert_eq!(u16::from(Consumer::ALCalculator), 0x192);
+        assert_eq!(u16::from(Consumer::ACPan), 0x238);
+    }
+
+    #[test]
+    fn descriptor_usages_are_enumerated() {
+        for descriptor in [
+            BOOT_KEYBOARD_REPORT_DESCRIPTOR,
+            NKRO_BOOT_KEYBOARD_REPORT_DESCRIPTOR,
+            WHEEL_MOUSE_REPORT_DESCRIPTOR,
+            GAMEPAD_REPORT_DESCRIPTOR,
+            JOYSTICK_DESCRIPTOR,
+            FLIGHT_CONTROLS_REPORT_DESCRIPTOR,
+            MULTIPLE_CODE_REPORT_DESCRIPTOR,
+            FIXED_FUNCTION_REPORT_DESCRIPTOR,
+        ] {
+            for (page, _, usage) in items(descriptor)
+                .into_iter()
+                .filter(|&(_, tag, _)| tag == USAGE)
+            {
+                assert!(
+                    is_enumerated(page, usage),
+                    "Usage {usage:#X} of page {page:#X} isn't enumerated"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn enums_end_at_descriptor_maximums() {
+        //Array of every consumer usage, ending at the last enumerated usage
+        let items = items(MULTIPLE_CODE_REPORT_DESCRIPTOR);
+        assert!(items.contains(&(0x0C, LOGICAL_MAXIMUM, 0x2D4)));
+        assert!(items.contains(&(0x0C, USAGE_MAXIMUM, 0x2D4)));
+        assert_eq!(
+            Consumer::from_primitive(0x2D4),
+            Some(Consumer::PrivacyScreenLevelMaximum)
+        );
+        assert_eq!(Consumer::from_primitive(0x2D5), None);
+
+        //Modifier bitmap, ending at the last enumerated usage
+        let items = self::items(BOOT_KEYBOARD_REPORT_DESCRIPTOR);
+        assert!(items.contains(&(0x07, USAGE_MAXIMUM, 0xE7)));
+        assert_eq!(Keyboard::from_primitive(0xE7), Some(Keyboard::RightGUI));
+        assert_eq!(Keyboard::from_primitive(0xE8), None);
+
+        let items = self::items(GAMEPAD_REPORT_DESCRIPTOR);
+        assert!(items.contains(&(0x09, USAGE_MAXIMUM, 32)));
+        assert_eq!(Button::from_primitive(32), Some(Button::Button32));
+
+        assert_eq!(Desktop::from_primitive(0xE2), Some(Desktop::CallMuteLed));
+        assert_eq!(Desktop::from_primitive(0xE3), None);
     }
 }
@@ -0,0 +1,415 @@
+//! Keyboard layouts for translating text into key strokes
+//!
+//! The host interprets keyboard usages according to its configured layout, so to type a
+//! character the device must send the key at that character's position on the host's layout.
+//!
+//! ```
+//! # use usbd_human_interface_device::page::layout::{KeyStroke, Layout, Modifier};
+//! # use usbd_human_interface_device::page::Keyboard;
+//! let mut strokes = Layout::De.type_str("Yz");
+//! assert_eq!(strokes.next(), Some(KeyStroke::new(Modifier::Shift, Keyboard::Z)));
+//! assert_eq!(strokes.next(), Some(KeyStroke::new(Modifier::None, Keyboard::Y)));
+//! assert_eq!(strokes.next(), None);
+//! ```
+
+use crate::page::Keyboard;
+use core::iter::once;
+use core::str::Chars;
+use heapless::Vec;
+
+/// Host keyboard layout
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum Layout {
+    /// US English (QWERTY)
+    #[default]
+    Us,
+    /// UK English (QWERTY)
+    Uk,
+    /// German (QWERTZ)
+    De,
+    /// French (AZERTY)
+    Fr,
+}
+
+/// Modifier held while a key is pressed
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Default)]
+pub enum Modifier {
+    #[default]
+    None,
+    Shift,
+    /// `AltGr`, sent as [`Keyboard::RightAlt`]
+    AltGr,
+}
+
+impl Modifier {
+    #[must_use]
+    pub fn key(self) -> Option<Keyboard> {
+        match self {
+            Self::None => None,
+            Self::Shift => Some(Keyboard::LeftShift),
+            Self::AltGr => Some(Keyboard::RightAlt),
+        }
+    }
+}
+
+/// A key pressed together with a modifier
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct KeyStroke {
+    pub modifier: Modifier,
+    pub key: Keyboard,
+}
+
+impl KeyStroke {
+    #[must_use]
+    pub const fn new(modifier: Modifier, key: Keyboard) -> Self {
+        Self { modifier, key }
+    }
+
+    /// The modifier key, if any, followed by the key
+    pub fn keys(&self) -> impl Iterator<Item = Keyboard> {
+        self.modifier.key().into_iter().chain(once(self.key))
+    }
+}
+
+/// Key strokes typing a single character. Characters produced by dead keys are followed by a
+/// space
+pub type CharStrokes = Vec<KeyStroke, 2>;
+
+impl Layout {
+    /// Key strokes typing `c`, or `None` if the layout has no mapping for it
+    ///
+    /// Assumes caps lock is off
+    #[must_use]
+    pub fn strokes(self, c: char) -> Option<CharStrokes> {
+        let (stroke, dead) = self.map(c).or_else(|| {
+            //Upper case letters are the shifted lower case key
+            if !c.is_ascii_uppercase() {
+                return None;
+            }
+            match self.map(c.to_ascii_lowercase())? {
+                (
+                    KeyStroke {
+                        modifier: Modifier::None,
+                        key,
+                    },
+                    false,
+                ) => Some((KeyStroke::new(Modifier::Shift, key), false)),
+                _ => None,
+            }
+        })?;
+
+        let mut strokes = CharStrokes::new();
+        strokes.push(stroke).ok();
+        if dead {
+            strokes
+                .push(KeyStroke::new(Modifier::None, Keyboard::Space))
+                .ok();
+        }
+        Some(strokes)
+    }
+
+    /// Key strokes typing `s`. Characters without a mapping are skipped
+    #[must_use]
+    pub fn type_str(self, s: &str) -> TypeStr<'_> {
+        TypeStr {
+            layout: self,
+            chars: s.chars(),
+            pending: CharStrokes::new(),
+        }
+    }
+
+    /// Map a character to a key stroke and whether the stroke is a dead key
+    fn map(self, c: char) -> Option<(KeyStroke, bool)> {
+        let stroke = match c {
+            '\n' => key(Keyboard::ReturnEnter),
+            '\t' => key(Keyboard::Tab),
+            ' ' => key(Keyboard::Space),
+            _ => match self {
+                Self::Us => us(c),
+                Self::Uk => uk(c),
+                Self::De => de(c),
+                Self::Fr => fr(c),
+            }?,
+        };
+        Some(stroke)
+    }
+}
+
+/// Iterator of the key strokes typing a string, see [`Layout::type_str`]
+#[derive(Clone, Debug)]
+pub struct TypeStr<'a> {
+    layout: Layout,
+    chars: Chars<'a>,
+    //stored in reverse order
+    pending: CharStrokes,
+}
+
+impl Iterator for TypeStr<'_> {
+    type Item = KeyStroke;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let c = self.chars.next()?;
+            if let Some(mut strokes) = self.layout.strokes(c) {
+                strokes.reverse();
+                self.pending = strokes;
+            } else {
+                warn!("No key mapping for character {:X}", u32::from(c));
+            }
+        }
+        self.pending.pop()
+    }
+}
+
+fn key(k: Keyboard) -> (KeyStroke, bool) {
+    (KeyStroke::new(Modifier::None, k), false)
+}
+
+fn shift(k: Keyboard) -> (KeyStroke, bool) {
+    (KeyStroke::new(Modifier::Shift, k), false)
+}
+
+fn alt_gr(k: Keyboard) -> (KeyStroke, bool) {
+    (KeyStroke::new(Modifier::AltGr, k), false)
+}
+
+fn dead((stroke, _): (KeyStroke, bool)) -> (KeyStroke, bool) {
+    (stroke, true)
+}
+
+/// Letter keys, in alphabetical order
+const LETTERS: [Keyboard; 26] = [
+    Keyboard::A,
+    Keyboard::B,
+    Keyboard::C,
+    Keyboard::D,
+    Keyboard::E,
+    Keyboard::F,
+    Keyboard::G,
+    Keyboard::H,
+    Keyboard::I,
+    Keyboard::J,
+    Keyboard::K,
+    Keyboard::L,
+    Keyboard::M,
+    Keyboard::N,
+    Keyboard::O,
+    Keyboard::P,
+    Keyboard::Q,
+    Keyboard::R,
+    Keyboard::S,
+    Keyboard::T,
+    Keyboard::U,
+    Keyboard::V,
+    Keyboard::W,
+    Keyboard::X,
+    Keyboard::Y,
+    Keyboard::Z,
+];
+
+/// Number row keys, in the order 1-9 then 0
+const DIGITS: [Keyboard; 10] = [
+    Keyboard::Keyboard1,
+    Keyboard::Keyboard2,
+    Keyboard::Keyboard3,
+    Keyboard::Keyboard4,
+    Keyboard::Keyboard5,
+    Keyboard::Keyboard6,
+    Keyboard::Keyboard7,
+    Keyboard::Keyboard8,
+    Keyboard::Keyboard9,
+    Keyboard::Keyboard0,
+];
+
+fn letter(c: char) -> Option<Keyboard> {
+    if c.is_ascii_lowercase() {
+        let i = usize::try_from(u32::from(c) - u32::from('a')).ok()?;
+        LETTERS.get(i).copied()
+    } else {
+        None
+    }
+}
+
+fn digit(c: char) -> Option<Keyboard> {
+    let n = c.to_digit(10)?;
+    // '0' is the last key on the number row
+    DIGITS.get(usize::try_from((n + 9) % 10).ok()?).copied()
+}
+
+fn us(c: char) -> Option<(KeyStroke, bool)> {
+    if let Some(k) = letter(c).or_else(|| digit(c)) {
+        return Some(key(k));
+    }
+    Some(match c {
+        '!' => shift(Keyboard::Keyboard1),
+        '@' => shift(Keyboard::Keyboard2),
+        '#' => shift(Keyboard::Keyboard3),
+        '$' => shift(Keyboard::Keyboard4),
+        '%' => shift(Keyboard::Keyboard5),
+        '^' => shift(Keyboard::Keyboard6),
+        '&' => shift(Keyboard::Keyboard7),
+        '*' => shift(Keyboard::Keyboard8),
+        '(' => shift(Keyboard::Keyboard9),
+        ')' => shift(Keyboard::Keyboard0),
+        '-' => key(Keyboard::Minus),
+        '_' => shift(Keyboard::Minus),
+        '=' => key(Keyboard::Equal),
+        '+' => shift(Keyboard::Equal),
+        '[' => key(Keyboard::LeftBrace),
+        '{' => shift(Keyboard::LeftBrace),
+        ']' => key(Keyboard::RightBrace),
+        '}' => shift(Keyboard::RightBrace),
+        '\\' => key(Keyboard::Backslash),
+        '|' => shift(Keyboard::Backslash),
+        ';' => key(Keyboard::Semicolon),
+        ':' => shift(Keyboard::Semicolon),
+        '\'' => key(Keyboard::Apostrophe),
+        '"' => shift(Keyboard::Apostrophe),
+        '`' => key(Keyboard::Grave),
+        '~' => shift(Keyboard::Grave),
+        ',' => key(Keyboard::Comma),
+        '<' => shift(Keyboard::Comma),
+        '.' => key(Keyboard::Dot),
+        '>' => shift(Keyboard::Dot),
+        '/' => key(Keyboard::ForwardSlash),
+        '?' => shift(Keyboard::ForwardSlash),
+        _ => return None,
+    })
+}
+
+fn uk(c: char) -> Option<(KeyStroke, bool)> {
+    Some(match c {
+        '"' => shift(Keyboard::Keyboard2),
+        '£' => shift(Keyboard::Keyboard3),
+        '€' => alt_gr(Keyboard::Keyboard4),
+        '@' => shift(Keyboard::Apostrophe),
+        '#' => key(Keyboard::NonUSHash),
+        '~' => shift(Keyboard::NonUSHash),
+        '\\' => key(Keyboard::NonUSBackslash),
+        '|' => shift(Keyboard::NonUSBackslash),
+        '¬' => shift(Keyboard::Grave),
+        '¦' => alt_gr(Keyboard::Grave),
+        _ => return us(c),
+    })
+}
+
+fn de(c: char) -> Option<(KeyStroke, bool)> {
+    if let Some(k) = digit(c) {
+        return Some(key(k));
+    }
+    Some(match c {
+        'y' => key(Keyboard::Z),
+        'z' => key(Keyboard::Y),
+        'a'..='x' => key(letter(c)?),
+        'ü' => key(Keyboard::LeftBrace),
+        'Ü' => shift(Keyboard::LeftBrace),
+        'ö' => key(Keyboard::Semicolon),
+        'Ö' => shift(Keyboard::Semicolon),
+        'ä' => key(Keyboard::Apostrophe),
+        'Ä' => shift(Keyboard::Apostrophe),
+        'ß' => key(Keyboard::Minus),
+        '!' => shift(Keyboard::Keyboard1),
+        '"' => shift(Keyboard::Keyboard2),
+        '§' => shift(Keyboard::Keyboard3),
+        '$' => shift(Keyboard::Keyboard4),
+        '%' => shift(Keyboard::Keyboard5),
+        '&' => shift(Keyboard::Keyboard6),
+        '/' => shift(Keyboard::Keyboard7),
+        '(' => shift(Keyboard::Keyboard8),
+        ')' => shift(Keyboard::Keyboard9),
+        '=' => shift(Keyboard::Keyboard0),
+        '²' => alt_gr(Keyboard::Keyboard2),
+        '³' => alt_gr(Keyboard::Keyboard3),
+        '{' => alt_gr(Keyboard::Keyboard7),
+        '[' => alt_gr(Keyboard::Keyboard8),
+        ']' => alt_gr(Keyboard::Keyboard9),
+        '}' => alt_gr(Keyboard::Keyboard0),
+        '?' => shift(Keyboard::Minus),
+        '\\' => alt_gr(Keyboard::Minus),
+        '´' => dead(key(Keyboard::Equal)),
+        '`' => dead(shift(Keyboard::Equal)),
+        '@' => alt_gr(Keyboard::Q),
+        '€' => alt_gr(Keyboard::E),
+        'µ' => alt_gr(Keyboard::M),
+        '+' => key(Keyboard::RightBrace),
+        '*' => shift(Keyboard::RightBrace),
+        '~' => alt_gr(Keyboard::RightBrace),
+        '#' => key(Keyboard::NonUSHash),
+        '\'' => shift(Keyboard::NonUSHash),
+        '^' => dead(key(Keyboard::Grave)),
+        '°' => shift(Keyboard::Grave),
+        '<' => key(Keyboard::NonUSBackslash),
+        '>' => shift(Keyboard::NonUSBackslash),
+        '|' => alt_gr(Keyboard::NonUSBackslash),
+        ',' => key(Keyboard::Comma),
+        ';' => shift(Keyboard::Comma),
+        '.' => key(Keyboard::Dot),
+        ':' => shift(Keyboard::Dot),
+        '-' => key(Keyboard::ForwardSlash),
+        '_' => shift(Keyboard::ForwardSlash),
+        _ => return None,
+    })
+}
+
+fn fr(c: char) -> Option<(KeyStroke, bool)> {
+    //Digits are shifted on the French number row
+    if let Some(k) = digit(c) {
+        return Some(shift(k));
+    }
+    Some(match c {
+        'a' => key(Keyboard::Q),
+        'q' => key(Keyboard::A),
+        'z' => key(Keyboard::W),
+        'w' => key(Keyboard::Z),
+        'm' => key(Keyboard::Semicolon),
+        'b'..='y' => key(letter(c)?),
+        '&' => key(Keyboard::Keyboard1),
+        'é' => key(Keyboard::Keyboard2),
+        '"' => key(Keyboard::Keyboard3),
+        '\'' => key(Keyboard::Keyboard4),
+        '(' => key(Keyboard::Keyboard5),
+        '-' => key(Keyboard::Keyboard6),
+        'è' => key(Keyboard::Keyboard7),
+        '_' => key(Keyboard::Keyboard8),
+        'ç' => key(Keyboard::Keyboard9),
+        'à' => key(Keyboard::Keyboard0),
+        '~' => dead(alt_gr(Keyboard::Keyboard2)),
+        '#' => alt_gr(Keyboard::Keyboard3),
+        '{' => alt_gr(Keyboard::Keyboard4),
+        '[' => alt_gr(Keyboard::Keyboard5),
+        '|' => alt_gr(Keyboard::Keyboard6),
+        '`' => dead(alt_gr(Keyboard::Keyboard7)),
+        '\\' => alt_gr(Keyboard::Keyboard8),
+        '^' => alt_gr(Keyboard::Keyboard9),
+        '@' => alt_gr(Keyboard::Keyboard0),
+        ')' => key(Keyboard::Minus),
+        '°' => shift(Keyboard::Minus),
+        ']' => alt_gr(Keyboard::Minus),
+        '=' => key(Keyboard::Equal),
+        '+' => shift(Keyboard::Equal),
+        '}' => alt_gr(Keyboard::Equal),
+        '$' => key(Keyboard::RightBrace),
+        '£' => shift(Keyboard::RightBrace),
+        '¤' => alt_gr(Keyboard::RightBrace),
+        '€' => alt_gr(Keyboard::E),
+        'ù' => key(Keyboard::Apostrophe),
+        '%' => shift(Keyboard::Apostrophe),
+        '*' => key(Keyboard::NonUSHash),
+        'µ' => shift(Keyboard::NonUSHash),
+        '²' => key(Keyboard::Grave),
+        '<' => key(Keyboard::NonUSBackslash),
+        '>' => shift(Keyboard::NonUSBackslash),
+        ',' => key(Keyboard::M),
+        '?' => shift(Keyboard::M),
+        ';' => key(Keyboard::Comma),
+        '.' => shift(Keyboard::Comma),
+        ':' => key(Keyboard::Dot),
+        '/' => shift(Keyboard::Dot),
+        '!' => key(Keyboard::ForwardSlash),
+        '§' => shift(Keyboard::ForwardSlash),
+        _ => return None,
+    })
+}
@@ -8,5 +8,7 @@
 //! use usbd_human_interface_device::prelude::*;
 //! ```
 
+pub use crate::clock::{FugitClock, HidClock, MillisClock};
+pub use crate::source::{Debounce, InputSource, SourcedConfig};
 pub use crate::usb_class::{UsbHidClass, UsbHidClassBuilder};
 pub use crate::UsbHidError;
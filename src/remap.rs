@@ -0,0 +1,455 @@
+//! Runtime remapping of gamepad and joystick buttons and axes
+//!
+//! A [`RemapTable`] sits between report construction and a device's write path, so firmware can
+//! let users rearrange buttons, swap or invert axes and set deadzones without changing how
+//! reports are built. Reports are built for the physical controls, and remapped as they're
+//! written:
+//!
+//! ```
+//! # use usbd_human_interface_device::device::gamepad::GamepadReport;
+//! # use usbd_human_interface_device::remap::{AxisMapping, RemapTable};
+//! let mut table = RemapTable::<32, 6>::new();
+//! table.swap_buttons(0, 1);
+//! table.map_axis(1, AxisMapping::new(1).inverted());
+//! table.map_axis(0, AxisMapping::new(0).deadzone(1000));
+//!
+//! let report = GamepadReport {
+//!     buttons: 0x1,
+//!     left_x: 500,
+//!     left_y: 32767,
+//!     ..Default::default()
+//! };
+//! let remapped = table.remap(&report);
+//! assert_eq!(remapped.buttons, 0x2);
+//! assert_eq!((remapped.left_x, remapped.left_y), (0, -32767));
+//! ```
+//!
+//! With a device from [`UsbHidClass::device`](crate::usb_class::UsbHidClass::device),
+//! `table.write(hid.device(), &report)` writes the remapped report.
+
+use crate::device::gamepad::GamepadReport;
+use crate::device::joystick::{CustomJoystickReport, JoystickReport};
+use crate::device::ReportWriter;
+use crate::UsbHidError;
+
+/// Scale of [`AxisMapping::deadzone`]
+const AXIS_SCALE: i64 = 32767;
+
+/// A report whose buttons and axes can be remapped by a [`RemapTable`]
+///
+/// Axes are numbered in the order of the report's fields
+pub trait Remappable: Copy {
+    /// Number of buttons in the report
+    const BUTTONS: usize;
+    /// Number of axes in the report
+    const AXES: usize;
+
+    /// State of button `n`, numbered from 0
+    fn button(&self, n: usize) -> bool;
+    fn set_button(&mut self, n: usize, pressed: bool);
+    fn axis(&self, n: usize) -> i32;
+    /// Set axis `n` to `value`, which is within [`Self::axis_range`]
+    fn set_axis(&mut self, n: usize, value: i32);
+    /// Logical minimum and maximum of axis `n`
+    ///
+    /// The axis rests at 0, or at the end of the range closest to 0, such as for triggers
+    fn axis_range(n: usize) -> (i32, i32);
+}
+
+/// Source and adjustments of an axis in a [`RemapTable`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AxisMapping {
+    /// Axis the value is taken from, or `None` to hold the axis at rest
+    pub source: Option<u8>,
+    /// Mirror the value within the axis's range
+    pub invert: bool,
+    /// Distance from rest within which the source reads as at rest, out of 32767 for the full
+    /// distance from rest to either end of the axis. The remaining travel is scaled to cover
+    /// the whole axis
+    pub deadzone: u16,
+}
+
+impl AxisMapping {
+    /// Take the value of axis `source` unchanged
+    #[must_use]
+    pub const fn new(source: u8) -> Self {
+        Self {
+            source: Some(source),
+            invert: false,
+            deadzone: 0,
+        }
+    }
+
+    /// Hold the axis at rest
+    #[must_use]
+    pub const fn disabled() -> Self {
+        Self {
+            source: None,
+            invert: false,
+            deadzone: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn inverted(mut self) -> Self {
+        self.invert = true;
+        self
+    }
+
+    #[must_use]
+    pub const fn deadzone(mut self, deadzone: u16) -> Self {
+        self.deadzone = deadzone;
+        self
+    }
+
+    /// Map `value` from an axis with `source` range to an axis with `target` range
+    fn apply(self, value: i32, source: (i32, i32), target: (i32, i32)) -> i32 {
+        let (value, source_rest) = Self::offset(value, source);
+        let source_travel = Self::travel(value, source, source_rest);
+        let (min, max) = (i64::from(target.0), i64::from(target.1));
+        let target_rest = 0.clamp(min, max);
+        let target_travel = Self::travel(value, target, target_rest);
+
+        let mut value = if source_travel == 0 {
+            target_rest
+        } else {
+            let deadzone = i64::from(self.deadzone).min(AXIS_SCALE) * source_travel / AXIS_SCALE;
+            let magnitude = value.abs();
+            let magnitude = if magnitude <= deadzone {
+                0
+            } else {
+                (magnitude - deadzone) * source_travel / (source_travel - deadzone)
+            };
+            target_rest + value.signum() * magnitude * target_travel / source_travel
+        };
+        if self.invert {
+            value = min + max - value;
+        }
+        i32::try_from(value.clamp(min, max)).unwrap_or_default()
+    }
+
+    /// `value` clamped to `range` and made relative to its rest position, and the rest position
+    fn offset(value: i32, (min, max): (i32, i32)) -> (i64, i64) {
+        let rest = 0.clamp(min, max);
+        (
+            i64::from(value.clamp(min, max)) - i64::from(rest),
+            rest.into(),
+        )
+    }
+
+    /// Distance from `rest` to the end of `range` on the side of `offset`
+    fn travel(offset: i64, (min, max): (i32, i32), rest: i64) -> i64 {
+        if offset >= 0 {
+            i64::from(max) - rest
+        } else {
+            rest - i64::from(min)
+        }
+    }
+}
+
+/// Remaps up to `BUTTONS` buttons and `AXES` axes of a [`Remappable`] report
+///
+/// Each output button and axis takes its value from a source button or axis of the original
+/// report. Buttons and axes beyond the table are passed through unchanged, mappings beyond the
+/// report, or from sources the report doesn't have, are ignored.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct RemapTable<const BUTTONS: usize, const AXES: usize> {
+    buttons: [Option<u8>; BUTTONS],
+    axes: [AxisMapping; AXES],
+}
+
+impl<const BUTTONS: usize, const AXES: usize> Default for RemapTable<BUTTONS, AXES> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const BUTTONS: usize, const AXES: usize> RemapTable<BUTTONS, AXES> {
+    /// Table mapping every button and axis to itself
+    #[must_use]
+    pub const fn new() -> Self {
+        ::core::assert!(
+            BUTTONS <= 256 && AXES <= 256,
+            "remap table larger than 256 buttons or axes"
+        );
+
+        let mut buttons = [None; BUTTONS];
+        let mut axes = [AxisMapping::disabled(); AXES];
+        let mut i = 0;
+        while i < BUTTONS {
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                buttons[i] = Some(i as u8);
+            }
+            i += 1;
+        }
+        i = 0;
+        while i < AXES {
+            #[allow(clippy::cast_possible_truncation)]
+            {
+                axes[i] = AxisMapping::new(i as u8);
+            }
+            i += 1;
+        }
+        Self { buttons, axes }
+    }
+
+    /// Take output button `output` from button `source`, or leave it released if `None`
+    pub fn map_button(&mut self, output: usize, source: Option<u8>) {
+        if let Some(button) = self.buttons.get_mut(output) {
+            *button = source;
+        }
+    }
+
+    pub fn swap_buttons(&mut self, a: usize, b: usize) {
+        if a < BUTTONS && b < BUTTONS {
+            self.buttons.swap(a, b);
+        }
+    }
+
+    pub fn map_axis(&mut self, output: usize, mapping: AxisMapping) {
+        if let Some(axis) = self.axes.get_mut(output) {
+            *axis = mapping;
+        }
+    }
+
+    #[must_use]
+    pub fn button_mapping(&self, output: usize) -> Option<u8> {
+        self.buttons.get(output).copied().flatten()
+    }
+
+    #[must_use]
+    pub fn axis_mapping(&self, output: usize) -> Option<AxisMapping> {
+        self.axes.get(output).copied()
+    }
+
+    /// Restore the identity mapping
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// The remapped copy of `report`
+    #[must_use]
+    pub fn remap<R: Remappable>(&self, report: &R) -> R {
+        let mut remapped = *report;
+        for (output, source) in self.buttons.iter().enumerate().take(R::BUTTONS) {
+            let pressed = source
+                .map(usize::from)
+                .is_some_and(|s| s < R::BUTTONS && report.button(s));
+            remapped.set_button(output, pressed);
+        }
+        for (output, mapping) in self.axes.iter().enumerate().take(R::AXES) {
+            let target = R::axis_range(output);
+            let value = match mapping.source.map(usize::from) {
+                Some(source) if source < R::AXES => {
+                    mapping.apply(report.axis(source), R::axis_range(source), target)
+                }
+                //Disabled, or from an axis the report doesn't have
+                _ => AxisMapping::disabled().apply(0, target, target),
+            };
+            remapped.set_axis(output, value);
+        }
+        remapped
+    }
+
+    /// Write the remapped copy of `report` to `writer`
+    pub fn write<R, W>(&self, writer: &mut W, report: &R) -> Result<(), UsbHidError>
+    where
+        R: Remappable,
+        W: ReportWriter<R>,
+    {
+        writer.write_report(&self.remap(report))
+    }
+}
+
+impl Remappable for GamepadReport {
+    const BUTTONS: usize = 32;
+    /// Left X and Y, right X and Y, left and right triggers
+    const AXES: usize = 6;
+
+    fn button(&self, n: usize) -> bool {
+        n < Self::BUTTONS && self.buttons & (1 << n) != 0
+    }
+
+    fn set_button(&mut self, n: usize, pressed: bool) {
+        if n < Self::BUTTONS {
+            if pressed {
+                self.buttons |= 1 << n;
+            } else {
+                self.buttons &= !(1 << n);
+            }
+        }
+    }
+
+    fn axis(&self, n: usize) -> i32 {
+        match n {
+            0 => self.left_x.into(),
+            1 => self.left_y.into(),
+            2 => self.right_x.into(),
+            3 => self.right_y.into(),
+            4 => self.left_trigger.into(),
+            5 => self.right_trigger.into(),
+            _ => 0,
+        }
+    }
+
+    fn set_axis(&mut self, n: usize, value: i32) {
+        let stick = i16::try_from(value).unwrap_or_default();
+        let trigger = u16::try_from(value).unwrap_or_default();
+        match n {
+            0 => self.left_x = stick,
+            1 => self.left_y = stick,
+            2 => self.right_x = stick,
+            3 => self.right_y = stick,
+            4 => self.left_trigger = trigger,
+            5 => self.right_trigger = trigger,
+            _ => {}
+        }
+    }
+
+    fn axis_range(n: usize) -> (i32, i32) {
+        if n < 4 {
+            (-32767, 32767)
+        } else {
+            (0, 65535)
+        }
+    }
+}
+
+impl Remappable for JoystickReport {
+    const BUTTONS: usize = 8;
+    /// X and Y
+    const AXES: usize = 2;
+
+    fn button(&self, n: usize) -> bool {
+        n < Self::BUTTONS && self.buttons & (1 << n) != 0
+    }
+
+    fn set_button(&mut self, n: usize, pressed: bool) {
+        if n < Self::BUTTONS {
+            if pressed {
+                self.buttons |= 1 << n;
+            } else {
+                self.buttons &= !(1 << n);
+            }
+        }
+    }
+
+    fn axis(&self, n: usize) -> i32 {
+        match n {
+            0 => self.x.into(),
+            1 => self.y.into(),
+            _ => 0,
+        }
+    }
+
+    fn set_axis(&mut self, n: usize, value: i32) {
+        let value = i8::try_from(value).unwrap_or_default();
+        match n {
+            0 => self.x = value,
+            1 => self.y = value,
+            _ => {}
+        }
+    }
+
+    fn axis_range(_n: usize) -> (i32, i32) {
+        (-127, 127)
+    }
+}
+
+impl<const AXES: usize, const BUTTONS: usize> Remappable for CustomJoystickReport<AXES, BUTTONS> {
+    const BUTTONS: usize = BUTTONS;
+    const AXES: usize = AXES;
+
+    fn button(&self, n: usize) -> bool {
+        n < BUTTONS && self.buttons & (1 << n) != 0
+    }
+
+    fn set_button(&mut self, n: usize, pressed: bool) {
+        Self::set_button(self, n, pressed);
+    }
+
+    fn axis(&self, n: usize) -> i32 {
+        self.axes.get(n).copied().map_or(0, i32::from)
+    }
+
+    fn set_axis(&mut self, n: usize, value: i32) {
+        if let Some(axis) = self.axes.get_mut(n) {
+            *axis = i16::try_from(value).unwrap_or_default();
+        }
+    }
+
+    fn axis_range(_n: usize) -> (i32, i32) {
+        (-32767, 32767)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn remaps_gamepad() {
+        let mut table = RemapTable::<32, 6>::new();
+        table.map_button(2, None);
+        table.map_button(31, Some(3));
+        table.map_axis(0, AxisMapping::new(2));
+        table.map_axis(2, AxisMapping::new(0).deadzone(16384));
+        table.map_axis(4, AxisMapping::new(4).inverted());
+        table.map_axis(5, AxisMapping::new(1));
+
+        let report = GamepadReport {
+            buttons: 0b1100,
+            left_x: 16383,
+            left_y: -32767,
+            right_x: -100,
+            right_y: 7,
+            left_trigger: 0,
+            right_trigger: 40000,
+        };
+        assert_eq!(
+            table.remap(&report),
+            GamepadReport {
+                buttons: 0x8000_0008,
+                left_x: -100,
+                left_y: -32767,
+                right_x: 0,
+                right_y: 7,
+                left_trigger: 65535,
+                //Sticks below rest hold triggers released
+                right_trigger: 0,
+            }
+        );
+
+        table.reset();
+        assert_eq!(table.remap(&report), report);
+    }
+
+    #[test]
+    fn deadzone_rescales_remaining_travel() {
+        let mapping = AxisMapping::new(0).deadzone(1000);
+        let range = (-127, 127);
+        assert_eq!(mapping.apply(3, range, range), 0);
+        assert_eq!(mapping.apply(127, range, range), 127);
+        assert_eq!(mapping.apply(-127, range, range), -127);
+        assert_eq!(mapping.apply(64, range, range), 62);
+        assert_eq!(mapping.apply(64, range, (0, 255)), 124);
+    }
+
+    #[test]
+    fn remaps_custom_joystick() {
+        let mut table = RemapTable::<4, 2>::new();
+        table.swap_buttons(0, 3);
+        table.map_axis(1, AxisMapping::new(5));
+
+        let mut report = CustomJoystickReport::<2, 12>::default();
+        report.set_button(0, true);
+        report.set_button(10, true);
+        report.axes = [100, -200];
+
+        let remapped = table.remap(&report);
+        assert_eq!(remapped.buttons, 0b100_0000_1000);
+        assert_eq!(remapped.axes, [100, 0]);
+    }
+}
@@ -0,0 +1,421 @@
+//! Playback of timed sequences of keyboard, mouse and consumer control reports
+//!
+//! A [`SequencePlayer`] steps through a slice of [`Step`]s, handing each resulting report to a
+//! write function. Reports that can't be written yet are retried on the next tick, so macro-pad
+//! style firmware only needs to call [`SequencePlayer::tick`] every 1ms alongside
+//! [`UsbHidClass::tick`](crate::usb_class::UsbHidClass::tick).
+//!
+//! ```
+//! # use usbd_human_interface_device::sequence::{SequencePlayer, SequenceReport, Step};
+//! # use usbd_human_interface_device::page::Keyboard;
+//! # use usbd_human_interface_device::UsbHidError;
+//! # use fugit::MillisDurationU32;
+//! const COPY: &[Step] = &[
+//!     Step::KeyDown(Keyboard::LeftControl),
+//!     Step::KeyDown(Keyboard::C),
+//!     Step::Delay(MillisDurationU32::millis(20)),
+//!     Step::KeyUp(Keyboard::C),
+//!     Step::KeyUp(Keyboard::LeftControl),
+//! ];
+//!
+//! let mut player = SequencePlayer::new();
+//! player.play(COPY);
+//!
+//! while player.is_playing() {
+//!     player
+//!         .tick(|report| {
+//!             match report {
+//!                 SequenceReport::Keyboard(keys) => { /* keyboard.write_report(keys.keys()) */ }
+//!                 _ => {}
+//!             }
+//!             Ok(())
+//!         })
+//!         .unwrap();
+//! }
+//! ```
+
+use crate::device::consumer::MultipleConsumerReport;
+use crate::device::keyboard::KeyboardState;
+use crate::device::mouse::WheelMouseReport;
+use crate::page::{Consumer, Keyboard};
+use crate::UsbHidError;
+use fugit::MillisDurationU32;
+use heapless::Deque;
+
+/// A single action in a sequence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Step {
+    KeyDown(Keyboard),
+    KeyUp(Keyboard),
+    /// Set the pressed mouse buttons, bit 0 is the primary button
+    MouseButtons(u8),
+    /// Move the mouse relative to its current position
+    MouseMove {
+        x: i8,
+        y: i8,
+    },
+    MouseScroll {
+        vertical: i8,
+        horizontal: i8,
+    },
+    /// Press a consumer control, a [`Step::ConsumerUp`] should follow
+    ConsumerDown(Consumer),
+    ConsumerUp,
+    /// Wait before playing the next step
+    Delay(MillisDurationU32),
+}
+
+/// Report to be written by the [`SequencePlayer::tick`] write function
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SequenceReport<'a> {
+    Keyboard(&'a KeyboardState),
+    Mouse(WheelMouseReport),
+    Consumer(MultipleConsumerReport),
+}
+
+/// State of a [`SequencePlayer`]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum PlayerState {
+    /// No sequence is playing
+    #[default]
+    Stopped,
+    Playing,
+    Paused,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Pending {
+    Keyboard,
+    Mouse(WheelMouseReport),
+    Consumer(MultipleConsumerReport),
+}
+
+/// Plays back a sequence of [`Step`]s, see the [module documentation](self)
+#[derive(Clone, Debug)]
+pub struct SequencePlayer<'a> {
+    steps: &'a [Step],
+    next: usize,
+    delay: u32,
+    state: PlayerState,
+    keyboard: KeyboardState,
+    buttons: u8,
+    consumer: bool,
+    pending: Deque<Pending, 3>,
+}
+
+impl Default for SequencePlayer<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> SequencePlayer<'a> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            steps: &[],
+            next: 0,
+            delay: 0,
+            state: PlayerState::Stopped,
+            keyboard: KeyboardState::default(),
+            buttons: 0,
+            consumer: false,
+            pending: Deque::new(),
+        }
+    }
+
+    /// Start playing `steps`, aborting any sequence already playing
+    pub fn play(&mut self, steps: &'a [Step]) {
+        self.abort();
+        self.steps = steps;
+        self.next = 0;
+        self.state = PlayerState::Playing;
+    }
+
+    /// Pause playback. Keys and buttons remain held until playback is resumed
+    pub fn pause(&mut self) {
+        if self.state == PlayerState::Playing {
+            self.state = PlayerState::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.state == PlayerState::Paused {
+            self.state = PlayerState::Playing;
+        }
+    }
+
+    /// Stop playback, releasing any held keys and buttons on the following ticks
+    pub fn abort(&mut self) {
+        self.steps = &[];
+        self.next = 0;
+        self.delay = 0;
+        self.pending.clear();
+
+        //The queue has just been emptied, and holds a release for each device
+        if self.keyboard.keys().next().is_some() {
+            self.keyboard.release_all();
+            let _ = self.push(Pending::Keyboard);
+        }
+        if self.buttons != 0 {
+            self.buttons = 0;
+            let _ = self.push(Pending::Mouse(WheelMouseReport::default()));
+        }
+        if self.consumer {
+            self.consumer = false;
+            let _ = self.push(Pending::Consumer(MultipleConsumerReport::default()));
+        }
+
+        //Releases are still sent while paused
+        self.state = if self.pending.is_empty() {
+            PlayerState::Stopped
+        } else {
+            PlayerState::Playing
+        };
+    }
+
+    #[must_use]
+    pub fn state(&self) -> PlayerState {
+        self.state
+    }
+
+    /// True until the sequence, including any trailing delay, has finished
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.state != PlayerState::Stopped
+    }
+
+    /// Advance playback by 1ms. Call this every 1ms / at 1KHz
+    ///
+    /// At most one report is passed to `write` per tick. If `write` returns
+    /// [`UsbHidError::WouldBlock`], or [`UsbHidError::QueueFull`] from an interface queueing
+    /// reports, the report is retried on the next tick. [`UsbHidError::Duplicate`] is treated as
+    /// success and any other error is returned.
+    pub fn tick<F>(&mut self, mut write: F) -> Result<(), UsbHidError>
+    where
+        F: FnMut(SequenceReport<'_>) -> Result<(), UsbHidError>,
+    {
+        if self.state != PlayerState::Playing {
+            return Ok(());
+        }
+
+        if self.pending.is_empty() {
+            if self.delay > 0 {
+                self.delay -= 1;
+                return Ok(());
+            }
+            self.advance()?;
+        }
+
+        if let Some(&pending) = self.pending.front() {
+            let report = match pending {
+                Pending::Keyboard => SequenceReport::Keyboard(&self.keyboard),
+                Pending::Mouse(r) => SequenceReport::Mouse(r),
+                Pending::Consumer(r) => SequenceReport::Consumer(r),
+            };
+            match write(report) {
+                Ok(()) | Err(UsbHidError::Duplicate) => {
+                    self.pending.pop_front();
+                }
+                Err(UsbHidError::WouldBlock | UsbHidError::QueueFull) => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        if self.pending.is_empty() && self.delay == 0 && self.next >= self.steps.len() {
+            self.state = PlayerState::Stopped;
+        }
+        Ok(())
+    }
+
+    /// Apply steps until one produces a report or a delay
+    fn advance(&mut self) -> Result<(), UsbHidError> {
+        while let Some(&step) = self.steps.get(self.next) {
+            self.next += 1;
+            match step {
+                Step::KeyDown(k) => {
+                    self.keyboard.press(k);
+                    self.push(Pending::Keyboard)?;
+                }
+                Step::KeyUp(k) => {
+                    self.keyboard.release(k);
+                    self.push(Pending::Keyboard)?;
+                }
+                Step::MouseButtons(buttons) => {
+                    self.buttons = buttons;
+                    self.push(Pending::Mouse(self.mouse_report()))?;
+                }
+                Step::MouseMove { x, y } => {
+                    self.push(Pending::Mouse(WheelMouseReport {
+                        x,
+                        y,
+                        ..self.mouse_report()
+                    }))?;
+                }
+                Step::MouseScroll {
+                    vertical,
+                    horizontal,
+                } => {
+                    self.push(Pending::Mouse(WheelMouseReport {
+                        vertical_wheel: vertical,
+                        horizontal_wheel: horizontal,
+                        ..self.mouse_report()
+                    }))?;
+                }
+                Step::ConsumerDown(code) => {
+                    self.consumer = true;
+                    self.push(Pending::Consumer(MultipleConsumerReport::new([code])))?;
+                }
+                Step::ConsumerUp => {
+                    self.consumer = false;
+                    self.push(Pending::Consumer(MultipleConsumerReport::default()))?;
+                }
+                Step::Delay(d) => {
+                    self.delay = d.to_millis();
+                }
+            }
+            if !self.pending.is_empty() || self.delay > 0 {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn mouse_report(&self) -> WheelMouseReport {
+        WheelMouseReport {
+            buttons: self.buttons,
+            ..Default::default()
+        }
+    }
+
+    fn push(&mut self, pending: Pending) -> Result<(), UsbHidError> {
+        //Only a single report is queued while playing, the queue is sized for abort releases
+        self.pending.push_back(pending).map_err(|_| {
+            error!("Sequence report queue full");
+            UsbHidError::QueueFull
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #![allow(clippy::unwrap_used)]
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Written {
+        Keys(Vec<Keyboard>),
+        Mouse(WheelMouseReport),
+    }
+
+    fn record(written: &mut Vec<(u32, Written)>, tick: u32, report: SequenceReport<'_>) {
+        written.push((
+            tick,
+            match report {
+                SequenceReport::Keyboard(k) => Written::Keys(k.keys().collect()),
+                SequenceReport::Mouse(m) => Written::Mouse(m),
+                SequenceReport::Consumer(_) => unreachable!(),
+            },
+        ));
+    }
+
+    #[test]
+    fn plays_steps_with_delay_and_retry() {
+        const STEPS: &[Step] = &[
+            Step::KeyDown(Keyboard::A),
+            Step::Delay(MillisDurationU32::millis(3)),
+            Step::KeyUp(Keyboard::A),
+            Step::MouseMove { x: 5, y: -5 },
+        ];
+
+        let mut player = SequencePlayer::new();
+        player.play(STEPS);
+
+        let mut written = Vec::new();
+        let mut tick = 0;
+        while player.is_playing() {
+            player
+                .tick(|r| {
+                    // endpoint busy on the first attempt to release the key
+                    if tick == 5 {
+                        return Err(UsbHidError::WouldBlock);
+                    }
+                    record(&mut written, tick, r);
+                    Ok(())
+                })
+                .unwrap();
+            tick += 1;
+        }
+
+        assert_eq!(
+            written,
+            [
+                (0, Written::Keys([Keyboard::A].into())),
+                (6, Written::Keys([].into())),
+                (
+                    7,
+                    Written::Mouse(WheelMouseReport {
+                        x: 5,
+                        y: -5,
+                        ..Default::default()
+                    })
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn abort_releases_held_keys() {
+        const STEPS: &[Step] = &[
+            Step::KeyDown(Keyboard::B),
+            Step::MouseButtons(0x1),
+            Step::Delay(MillisDurationU32::millis(100)),
+            Step::KeyUp(Keyboard::B),
+        ];
+
+        let mut player = SequencePlayer::new();
+        player.play(STEPS);
+
+        let mut written = Vec::new();
+        for tick in 0..3 {
+            player
+                .tick(|r| {
+                    record(&mut written, tick, r);
+                    Ok(())
+                })
+                .unwrap();
+        }
+        player.pause();
+        player.abort();
+        assert_eq!(player.state(), PlayerState::Playing);
+        for tick in 3..6 {
+            player
+                .tick(|r| {
+                    record(&mut written, tick, r);
+                    Ok(())
+                })
+                .unwrap();
+        }
+
+        assert!(!player.is_playing());
+        assert_eq!(
+            written,
+            [
+                (0, Written::Keys([Keyboard::B].into())),
+                (
+                    1,
+                    Written::Mouse(WheelMouseReport {
+                        buttons: 0x1,
+                        ..Default::default()
+                    })
+                ),
+                (3, Written::Keys([].into())),
+                (4, Written::Mouse(WheelMouseReport::default())),
+            ]
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Report serialization backends
+//!
+//! Devices in this crate pack their reports with `packed_struct`. Reports defined for a different
+//! serialization library can be written to an [`Interface`](crate::interface::Interface) by
+//! implementing [`ReportSerializer`] for it, and passing the serializer to
+//! [`Interface::write_serialized`](crate::interface::Interface::write_serialized) and
+//! [`Interface::read_serialized`](crate::interface::Interface::read_serialized).
+//! `SsmarshalSerializer`, with the optional `ssmarshal` feature, serializes `serde` reports as
+//! used by `usbd-hid` projects.
+//!
+//! ```
+//! # use usbd_human_interface_device::serialize::{PackedStructSerializer, ReportSerializer};
+//! # use usbd_human_interface_device::device::mouse::BootMouseReport;
+//! let report = BootMouseReport { buttons: 0x1, x: 2, y: 3 };
+//!
+//! let mut buf = [0; 8];
+//! let len = PackedStructSerializer::serialize(&report, &mut buf).unwrap();
+//! assert_eq!(&buf[..len], [0x1, 2, 3]);
+//! assert_eq!(PackedStructSerializer::deserialize(&buf[..len]), Ok(report));
+//! ```
+
+use crate::UsbHidError;
+use packed_struct::{PackedStruct, PackedStructSlice};
+
+/// The largest report that can be serialized for writing, the size of the largest endpoint
+pub const MAX_SERIALIZED_REPORT_LEN: usize = 64;
+
+/// Converts reports of type `R` to and from the bytes sent to and received from the host
+pub trait ReportSerializer<R> {
+    /// Write `report` to the start of `buf`, returning the length written
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::SerializationError`] if the report couldn't be serialized or doesn't fit
+    fn serialize(report: &R, buf: &mut [u8]) -> Result<usize, UsbHidError>;
+
+    /// Read a report from `data`
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::SerializationError`] if `data` isn't a valid report
+    fn deserialize(data: &[u8]) -> Result<R, UsbHidError>;
+}
+
+/// [`ReportSerializer`] for `packed_struct` reports, as used by the devices in this crate
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PackedStructSerializer;
+
+impl<R: PackedStruct> ReportSerializer<R> for PackedStructSerializer {
+    fn serialize(report: &R, buf: &mut [u8]) -> Result<usize, UsbHidError> {
+        let len =
+            R::packed_bytes_size(Some(report)).map_err(|_| UsbHidError::SerializationError)?;
+        let buf = buf.get_mut(..len).ok_or(UsbHidError::SerializationError)?;
+        report.pack_to_slice(buf).map_err(|_| {
+            error!("Error packing report");
+            UsbHidError::SerializationError
+        })?;
+        Ok(len)
+    }
+
+    fn deserialize(data: &[u8]) -> Result<R, UsbHidError> {
+        R::unpack_from_slice(data).map_err(|_| {
+            error!("Error unpacking report");
+            UsbHidError::SerializationError
+        })
+    }
+}
+
+/// [`ReportSerializer`] for `serde` reports, serialized with `ssmarshal` as `usbd-hid` reports
+/// commonly are
+///
+/// Requires the `ssmarshal` feature
+#[cfg(feature = "ssmarshal")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SsmarshalSerializer;
+
+#[cfg(feature = "ssmarshal")]
+impl<R> ReportSerializer<R> for SsmarshalSerializer
+where
+    R: serde::Serialize + serde::de::DeserializeOwned,
+{
+    fn serialize(report: &R, buf: &mut [u8]) -> Result<usize, UsbHidError> {
+        //ssmarshal panics rather than failing if the buffer is too short, but never writes more
+        //than the size of the report type
+        if core::mem::size_of::<R>() > MAX_SERIALIZED_REPORT_LEN {
+            error!("Report too large to serialize");
+            return Err(UsbHidError::SerializationError);
+        }
+        let mut serialized = [0; MAX_SERIALIZED_REPORT_LEN];
+        let len = ssmarshal::serialize(&mut serialized, report).map_err(|_| {
+            error!("Error serializing report");
+            UsbHidError::SerializationError
+        })?;
+        buf.get_mut(..len)
+            .ok_or(UsbHidError::SerializationError)?
+            .copy_from_slice(&serialized[..len]);
+        Ok(len)
+    }
+
+    fn deserialize(data: &[u8]) -> Result<R, UsbHidError> {
+        //Short data is padded to the size of the report type, to avoid the panic, and the bytes
+        //read checked afterwards
+        let mut padded = [0; MAX_SERIALIZED_REPORT_LEN];
+        let buf = if data.len() >= core::mem::size_of::<R>() {
+            data
+        } else if let Some(start) = padded.get_mut(..data.len()) {
+            start.copy_from_slice(data);
+            &padded
+        } else {
+            error!("Report too large to deserialize");
+            return Err(UsbHidError::SerializationError);
+        };
+        match ssmarshal::deserialize(buf) {
+            Ok((report, len)) if len <= data.len() => Ok(report),
+            _ => {
+                error!("Error deserializing report");
+                Err(UsbHidError::SerializationError)
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "ssmarshal"))]
+mod test {
+    #![allow(clippy::unwrap_used)]
+    use super::*;
+    use packed_struct::prelude::*;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PackedStruct, Serialize, Deserialize)]
+    #[packed_struct(endian = "lsb", size_bytes = "5")]
+    struct Report {
+        #[packed_field]
+        buttons: u8,
+        #[packed_field]
+        x: i8,
+        #[packed_field]
+        y: i8,
+        #[packed_field]
+        wheel: i16,
+    }
+
+    #[test]
+    fn ssmarshal_matches_packed_struct() {
+        let report = Report {
+            buttons: 0x5,
+            x: -2,
+            y: 3,
+            wheel: -300,
+        };
+
+        let mut packed = [0; 8];
+        let packed_len = PackedStructSerializer::serialize(&report, &mut packed).unwrap();
+        let mut marshalled = [0; 8];
+        let marshalled_len = SsmarshalSerializer::serialize(&report, &mut marshalled).unwrap();
+        assert_eq!(&marshalled[..marshalled_len], &packed[..packed_len]);
+
+        assert_eq!(
+            SsmarshalSerializer::deserialize(&packed[..packed_len]),
+            Ok(report)
+        );
+        assert_eq!(
+            PackedStructSerializer::deserialize(&marshalled[..marshalled_len]),
+            Ok(report)
+        );
+
+        assert_eq!(
+            <SsmarshalSerializer as ReportSerializer<Report>>::serialize(&report, &mut [0; 4]),
+            Err(UsbHidError::SerializationError)
+        );
+        assert_eq!(
+            <SsmarshalSerializer as ReportSerializer<Report>>::deserialize(&packed[..4]),
+            Err(UsbHidError::SerializationError)
+        );
+    }
+}
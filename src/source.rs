@@ -0,0 +1,220 @@
+//! Devices that poll an [`InputSource`] for reports
+//!
+//! Wrapping a device's config in a [`SourcedConfig`] before adding it to
+//! [`UsbHidClassBuilder`](crate::usb_class::UsbHidClassBuilder) makes
+//! [`UsbHidClass::tick`](crate::usb_class::UsbHidClass::tick) poll the source every 1ms and
+//! write any report it produces to the device. Reports that can't be written yet are retried on
+//! the next tick.
+//!
+//! ```
+//! # use usbd_human_interface_device::source::{Debounce, InputSource};
+//! # use usbd_human_interface_device::device::mouse::BootMouseReport;
+//! # use fugit::MillisDurationU32;
+//! # let mut read_buttons = || 0x1;
+//! // Only report button changes that have been stable for 5ms
+//! let mut source = Debounce::new(
+//!     move || Some(BootMouseReport { buttons: read_buttons(), ..Default::default() }),
+//!     MillisDurationU32::millis(5),
+//! );
+//!
+//! for _ in 0..4 {
+//!     assert_eq!(source.poll(), None);
+//! }
+//! assert_eq!(source.poll(), Some(BootMouseReport { buttons: 0x1, ..Default::default() }));
+//! assert_eq!(source.poll(), None);
+//! ```
+
+use crate::device::{DeviceClass, ReportWriter};
+use crate::interface::UsbAllocatable;
+use crate::UsbHidError;
+use core::marker::PhantomData;
+use fugit::MillisDurationU32;
+use usb_device::bus::{UsbBus, UsbBusAllocator};
+
+/// Producer of reports of type `R`
+pub trait InputSource<R> {
+    /// Called every 1ms. Returns the next report to write, or `None` if there is nothing new to
+    /// report
+    fn poll(&mut self) -> Option<R>;
+}
+
+impl<R, F: FnMut() -> Option<R>> InputSource<R> for F {
+    fn poll(&mut self) -> Option<R> {
+        self()
+    }
+}
+
+/// [`InputSource`] adapter that only produces a report once the wrapped source has produced the
+/// same report for the debounce period, and it differs from the last report produced
+#[derive(Clone, Debug)]
+pub struct Debounce<S, R> {
+    source: S,
+    period: u32,
+    candidate: Option<R>,
+    stable: u32,
+    reported: Option<R>,
+}
+
+impl<S, R> Debounce<S, R> {
+    /// `period` of zero reports every change immediately, suppressing repeated reports
+    pub fn new(source: S, period: MillisDurationU32) -> Self {
+        Self {
+            source,
+            period: period.to_millis(),
+            candidate: None,
+            stable: 0,
+            reported: None,
+        }
+    }
+
+    pub fn source(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
+
+impl<S, R> InputSource<R> for Debounce<S, R>
+where
+    S: InputSource<R>,
+    R: Copy + PartialEq,
+{
+    fn poll(&mut self) -> Option<R> {
+        if let Some(report) = self.source.poll() {
+            if self.candidate != Some(report) {
+                self.candidate = Some(report);
+                self.stable = 0;
+            }
+        }
+
+        let candidate = self.candidate?;
+        if self.stable < self.period {
+            self.stable += 1;
+        }
+        if self.stable >= self.period && self.reported != Some(candidate) {
+            self.reported = Some(candidate);
+            Some(candidate)
+        } else {
+            None
+        }
+    }
+}
+
+/// Config for a device of type `C` that is written to from an [`InputSource`] of reports of
+/// type `R`
+pub struct SourcedConfig<C, S, R> {
+    config: C,
+    source: S,
+    _report: PhantomData<R>,
+}
+
+impl<C, S, R> SourcedConfig<C, S, R> {
+    #[must_use]
+    pub fn new(config: C, source: S) -> Self {
+        Self {
+            config,
+            source,
+            _report: PhantomData,
+        }
+    }
+}
+
+impl<'a, B, C, S, R> UsbAllocatable<'a, B> for SourcedConfig<C, S, R>
+where
+    B: UsbBus + 'a,
+    C: UsbAllocatable<'a, B>,
+{
+    type Allocated = Sourced<C::Allocated, S, R>;
+
+    fn allocate(self, usb_alloc: &'a UsbBusAllocator<B>) -> Self::Allocated {
+        Sourced {
+            device: self.config.allocate(usb_alloc),
+            source: self.source,
+            pending: None,
+        }
+    }
+}
+
+/// Device that writes reports polled from an [`InputSource`] on every tick
+pub struct Sourced<D, S, R> {
+    device: D,
+    source: S,
+    pending: Option<R>,
+}
+
+impl<D, S, R> Sourced<D, S, R> {
+    pub fn device(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    pub fn source(&mut self) -> &mut S {
+        &mut self.source
+    }
+}
+
+impl<'a, D, S, R> DeviceClass<'a> for Sourced<D, S, R>
+where
+    D: DeviceClass<'a> + ReportWriter<R>,
+    S: InputSource<R>,
+{
+    type I = D::I;
+
+    fn interface(&mut self) -> &mut Self::I {
+        self.device.interface()
+    }
+
+    fn reset(&mut self) {
+        self.pending = None;
+        self.device.reset();
+    }
+
+    fn tick(&mut self) -> Result<(), UsbHidError> {
+        self.device.tick()?;
+
+        //A newer report replaces any that couldn't be written yet
+        if let Some(report) = self.source.poll() {
+            self.pending = Some(report);
+        }
+
+        if let Some(report) = &self.pending {
+            match self.device.write_report(report) {
+                Ok(()) | Err(UsbHidError::Duplicate) => {
+                    self.pending = None;
+                }
+                Err(UsbHidError::WouldBlock) => {}
+                Err(e) => {
+                    self.pending = None;
+                    return Err(e);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn debounce_waits_for_stable_report() {
+        let mut inputs = [1, 1, 2, 1, 1, 1, 1, 1].into_iter();
+        let mut source = Debounce::new(move || inputs.next(), MillisDurationU32::millis(3));
+
+        let polled: std::vec::Vec<_> = (0..10).map(|_| source.poll()).collect();
+
+        assert_eq!(
+            polled,
+            [
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(1),
+                None,
+                None,
+                None,
+                None
+            ]
+        );
+    }
+}
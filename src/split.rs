@@ -0,0 +1,250 @@
+//! Merging the key state of the two halves of a split keyboard
+//!
+//! A [`SplitAggregator`] combines the keys held on the half connected to USB with those held on
+//! the remote half, e.g. received as matrix state over UART or I2C, into a single
+//! [`KeyboardState`]. A key held on both halves stays pressed until both release it, and the
+//! remote half's keys are released if it stops sending updates, so a lost link can't leave keys
+//! stuck down.
+//!
+//! ```
+//! # use usbd_human_interface_device::device::keyboard::Rollover;
+//! # use usbd_human_interface_device::page::Keyboard;
+//! # use usbd_human_interface_device::split::{Half, SplitAggregator};
+//! # use fugit::MillisDurationU32;
+//! let mut split = SplitAggregator::new(Rollover::NKey, MillisDurationU32::millis(100));
+//! split.press(Half::Local, Keyboard::LeftShift);
+//! split.set_keys(Half::Remote, [Keyboard::H, Keyboard::J]);
+//!
+//! if split.take_changed() {
+//!     // keyboard.write_report(split.state().keys())
+//!     # assert!(split.state().is_pressed(Keyboard::J));
+//! }
+//! ```
+
+use crate::device::keyboard::{KeyboardState, Rollover};
+use crate::page::Keyboard;
+use fugit::{ExtU32, MillisDurationU32};
+
+/// One of the halves of a split keyboard
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Half {
+    /// The half connected to the host
+    Local,
+    /// The half whose keys are received from the local half
+    Remote,
+}
+
+/// Combines the keys held on both halves of a split keyboard into one keyboard report stream
+///
+/// **Note:** [`Self::tick`] must be called every 1ms to release the keys of a remote half that
+/// has stopped sending updates
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SplitAggregator {
+    local: [u8; 32],
+    remote: [u8; 32],
+    state: KeyboardState,
+    changed: bool,
+    remote_timeout: MillisDurationU32,
+    since_remote_update: MillisDurationU32,
+    remote_connected: bool,
+}
+
+impl SplitAggregator {
+    /// The remote half's keys are released if no update is received from it for
+    /// `remote_timeout`, a timeout of 0 never releases them
+    #[must_use]
+    pub const fn new(rollover: Rollover, remote_timeout: MillisDurationU32) -> Self {
+        Self {
+            local: [0; 32],
+            remote: [0; 32],
+            state: KeyboardState::new(rollover),
+            changed: false,
+            remote_timeout,
+            since_remote_update: MillisDurationU32::from_ticks(0),
+            remote_connected: false,
+        }
+    }
+
+    /// The combined keys of both halves
+    #[must_use]
+    pub fn state(&self) -> &KeyboardState {
+        &self.state
+    }
+
+    /// True if the keys of `half` include `key`
+    #[must_use]
+    pub fn is_held(&self, half: Half, key: Keyboard) -> bool {
+        let k = u8::from(key);
+        self.keys_of(half)[usize::from(k / 8)] & (1 << (k % 8)) != 0
+    }
+
+    /// Returns true if the combined keys have changed since this was last called, and a new
+    /// report should be written
+    pub fn take_changed(&mut self) -> bool {
+        core::mem::take(&mut self.changed)
+    }
+
+    pub fn press(&mut self, half: Half, key: Keyboard) {
+        self.remote_updated(half);
+        self.set_held(half, key, true);
+    }
+
+    pub fn release(&mut self, half: Half, key: Keyboard) {
+        self.remote_updated(half);
+        self.set_held(half, key, false);
+    }
+
+    /// Replace the keys held on `half`, e.g. with the matrix state received from the remote half
+    pub fn set_keys<K: IntoIterator<Item = Keyboard>>(&mut self, half: Half, keys: K) {
+        self.remote_updated(half);
+        self.replace_keys(half, keys);
+    }
+
+    /// Release all the keys held on `half`, e.g. when the link to the remote half is lost
+    pub fn release_half(&mut self, half: Half) {
+        if half == Half::Remote {
+            self.remote_connected = false;
+        }
+        self.replace_keys(half, core::iter::empty());
+    }
+
+    /// Record that the remote half is still connected, for links that only send updates when its
+    /// keys change and a periodic message otherwise
+    pub fn remote_alive(&mut self) {
+        self.remote_updated(Half::Remote);
+    }
+
+    /// True once an update has been received from the remote half, until it times out or is
+    /// released with [`Self::release_half`]
+    #[must_use]
+    pub fn is_remote_connected(&self) -> bool {
+        self.remote_connected
+    }
+
+    /// Call every 1ms
+    pub fn tick(&mut self) {
+        if !self.remote_connected || self.remote_timeout.ticks() == 0 {
+            return;
+        }
+        self.since_remote_update += 1.millis();
+        if self.since_remote_update >= self.remote_timeout {
+            warn!("Split keyboard remote half timed out");
+            self.release_half(Half::Remote);
+        }
+    }
+
+    fn remote_updated(&mut self, half: Half) {
+        if half == Half::Remote {
+            self.remote_connected = true;
+            self.since_remote_update = 0.millis();
+        }
+    }
+
+    fn replace_keys<K: IntoIterator<Item = Keyboard>>(&mut self, half: Half, keys: K) {
+        let mut held = [0u8; 32];
+        for key in keys {
+            let k = u8::from(key);
+            held[usize::from(k / 8)] |= 1 << (k % 8);
+        }
+        for k in 0..=u8::MAX {
+            let bit = held[usize::from(k / 8)] & (1 << (k % 8)) != 0;
+            self.set_held(half, Keyboard::from(k), bit);
+        }
+    }
+
+    fn keys_of(&self, half: Half) -> &[u8; 32] {
+        match half {
+            Half::Local => &self.local,
+            Half::Remote => &self.remote,
+        }
+    }
+
+    fn set_held(&mut self, half: Half, key: Keyboard, held: bool) {
+        if key == Keyboard::NoEventIndicated || self.is_held(half, key) == held {
+            return;
+        }
+        let k = u8::from(key);
+        let keys = match half {
+            Half::Local => &mut self.local,
+            Half::Remote => &mut self.remote,
+        };
+        keys[usize::from(k / 8)] ^= 1 << (k % 8);
+
+        //A key held on both halves is released once neither holds it
+        let other = match half {
+            Half::Local => Half::Remote,
+            Half::Remote => Half::Local,
+        };
+        if held {
+            if !self.state.is_pressed(key) {
+                self.state.press(key);
+                self.changed = true;
+            }
+        } else if !self.is_held(other, key) {
+            self.state.release(key);
+            self.changed = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn keys_held_on_both_halves() {
+        let mut split = SplitAggregator::new(Rollover::SixKey, 0.millis());
+        split.press(Half::Local, Keyboard::A);
+        assert!(split.take_changed());
+        split.press(Half::Remote, Keyboard::A);
+        assert!(!split.take_changed());
+
+        split.release(Half::Local, Keyboard::A);
+        assert!(!split.take_changed());
+        assert!(split.state().is_pressed(Keyboard::A));
+
+        split.release(Half::Remote, Keyboard::A);
+        assert!(split.take_changed());
+        assert_eq!(split.state().keys().count(), 0);
+    }
+
+    #[test]
+    fn remote_matrix_state() {
+        let mut split = SplitAggregator::new(Rollover::SixKey, 0.millis());
+        split.press(Half::Local, Keyboard::LeftControl);
+        split.set_keys(Half::Remote, [Keyboard::B, Keyboard::C]);
+        split.set_keys(Half::Remote, [Keyboard::C, Keyboard::D]);
+
+        let report = split.state().boot_report();
+        assert!(report.left_ctrl);
+        assert_eq!(
+            report.keys[..3],
+            [Keyboard::C, Keyboard::D, Keyboard::NoEventIndicated]
+        );
+        assert!(!split.is_held(Half::Remote, Keyboard::B));
+    }
+
+    #[test]
+    fn remote_timeout_releases_keys() {
+        let mut split = SplitAggregator::new(Rollover::NKey, 3.millis());
+        split.press(Half::Local, Keyboard::X);
+        split.tick();
+        assert!(!split.is_remote_connected());
+
+        split.press(Half::Remote, Keyboard::Y);
+        split.tick();
+        split.tick();
+        split.remote_alive();
+        split.tick();
+        split.tick();
+        assert!(split.state().is_pressed(Keyboard::Y));
+        split.take_changed();
+
+        split.tick();
+        assert!(!split.is_remote_connected());
+        assert!(split.take_changed());
+        assert!(!split.state().is_pressed(Keyboard::Y));
+        assert!(split.state().is_pressed(Keyboard::X));
+    }
+}
@@ -0,0 +1,683 @@
+//! Host simulation for testing devices without hardware
+//!
+//! Requires the `test-util` feature, which depends on `std`. [`TestUsbBus`] is a mock
+//! [`UsbBus`] whose endpoints are backed by the buffers of a [`UsbTestManager`], which plays the
+//! part of the host:
+//!
+//! ```
+//! # use usbd_human_interface_device::device::mouse::{BootMouse, BootMouseConfig, BootMouseReport};
+//! # use usbd_human_interface_device::prelude::*;
+//! # use usbd_human_interface_device::descriptor::HidProtocol;
+//! # use usbd_human_interface_device::test_util::{TestUsbBus, UsbTestManager};
+//! # use usb_device::class_prelude::UsbBusAllocator;
+//! # use usb_device::prelude::*;
+//! let manager = UsbTestManager::default();
+//! let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+//!
+//! let mut mouse = UsbHidClassBuilder::new()
+//!     .add_device(BootMouseConfig::default())
+//!     .build(&usb_alloc);
+//!
+//! let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+//!     .strings(&[StringDescriptors::default().product("Mouse")])
+//!     .unwrap()
+//!     .build();
+//!
+//! manager.enumerate(&mut usb_dev, &mut [&mut mouse]);
+//! assert_eq!(usb_dev.state(), UsbDeviceState::Configured);
+//!
+//! manager.set_protocol(&mut usb_dev, &mut [&mut mouse], 0, HidProtocol::Boot);
+//! assert_eq!(
+//!     manager.get_protocol(&mut usb_dev, &mut [&mut mouse], 0),
+//!     Some(HidProtocol::Boot)
+//! );
+//!
+//! mouse
+//!     .device()
+//!     .write_report(&BootMouseReport { buttons: 0x1, x: 5, y: -5 })
+//!     .unwrap();
+//! assert_eq!(manager.host_read_in(), [0x1, 5, (-5i8) as u8]);
+//! ```
+//!
+//! The mock has a single buffer for each direction, shared between all endpoints, so reports
+//! should be read before control requests are made.
+
+use crate::descriptor::{DescriptorType, HidProtocol, HidRequest, ReportType};
+use core::cell::RefCell;
+use packed_struct::prelude::*;
+use std::sync::Mutex;
+use std::vec::Vec;
+#[allow(clippy::wildcard_imports)]
+use usb_device::class_prelude::*;
+use usb_device::control::{Recipient, Request, RequestType};
+use usb_device::device::UsbDevice;
+use usb_device::{bus::PollResult, Result, UsbDirection};
+
+/// Control request setup packet - USB spec 9.3
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PackedStruct)]
+#[packed_struct(endian = "lsb", bit_numbering = "msb0", size_bytes = "8")]
+pub struct UsbRequest {
+    /// `true` for device to host
+    #[packed_field(bits = "0")]
+    pub direction: bool,
+    #[packed_field(bits = "1:2")]
+    pub request_type: u8,
+    #[packed_field(bits = "4:7")]
+    pub recipient: u8,
+    pub request: u8,
+    pub value: u16,
+    pub index: u16,
+    pub length: u16,
+}
+
+impl UsbRequest {
+    /// Standard request to the device
+    #[must_use]
+    pub fn standard(direction: UsbDirection, request: u8, value: u16, length: u16) -> Self {
+        Self {
+            direction: direction == UsbDirection::In,
+            request_type: RequestType::Standard as u8,
+            recipient: Recipient::Device as u8,
+            request,
+            value,
+            index: 0,
+            length,
+        }
+    }
+
+    /// Vendor request to `interface`
+    #[must_use]
+    pub fn vendor(
+        direction: UsbDirection,
+        request: u8,
+        value: u16,
+        interface: u8,
+        length: u16,
+    ) -> Self {
+        Self {
+            direction: direction == UsbDirection::In,
+            request_type: RequestType::Vendor as u8,
+            recipient: Recipient::Interface as u8,
+            request,
+            value,
+            index: interface.into(),
+            length,
+        }
+    }
+
+    fn hid(direction: UsbDirection, request: HidRequest, value: u16, interface: u8) -> Self {
+        Self {
+            direction: direction == UsbDirection::In,
+            request_type: RequestType::Class as u8,
+            recipient: Recipient::Interface as u8,
+            request: request.into(),
+            value,
+            index: interface.into(),
+            length: 0,
+        }
+    }
+
+    fn with_length(mut self, length: u16) -> Self {
+        self.length = length;
+        self
+    }
+}
+
+/// Simulated USB host, see the [module documentation](self)
+#[derive(Default)]
+#[allow(clippy::struct_field_names)]
+pub struct UsbTestManager {
+    in_buf: Mutex<RefCell<Vec<u8>>>,
+    setup_buf: Mutex<RefCell<Vec<u8>>>,
+    out_buf: Mutex<RefCell<Vec<u8>>>,
+    stalled: Mutex<RefCell<Vec<EndpointAddress>>>,
+    //Bit n set for IN endpoint n written by the device but not yet read by the host
+    in_written: Mutex<RefCell<u16>>,
+    //Bit n set for IN endpoint n read by the host but not yet polled by the device
+    in_complete: Mutex<RefCell<u16>>,
+    address: Mutex<RefCell<u8>>,
+    suspended: Mutex<RefCell<bool>>,
+}
+
+#[allow(clippy::missing_panics_doc, clippy::unwrap_used)]
+impl UsbTestManager {
+    /// Queue a setup packet for the control endpoint
+    ///
+    /// # Errors
+    ///
+    /// [`UsbError::WouldBlock`] if the previous setup packet hasn't been read by the device
+    pub fn host_write_setup(&self, data: &[u8]) -> Result<()> {
+        let buf = self.setup_buf.lock().unwrap();
+        if buf.borrow().is_empty() {
+            //A setup packet clears any control endpoint stall
+            self.stalled
+                .lock()
+                .unwrap()
+                .borrow_mut()
+                .retain(|ep| ep.index() != 0);
+            buf.borrow_mut().extend_from_slice(data);
+            Ok(())
+        } else {
+            Err(UsbError::WouldBlock)
+        }
+    }
+
+    /// Queue OUT data, either the data stage of a control request or an output report
+    ///
+    /// # Errors
+    ///
+    /// [`UsbError::WouldBlock`] if the previous OUT data hasn't been read by the device
+    pub fn host_write_out(&self, data: &[u8]) -> Result<()> {
+        let buf = self.out_buf.lock().unwrap();
+        if buf.borrow().is_empty() {
+            buf.borrow_mut().extend_from_slice(data);
+            Ok(())
+        } else {
+            Err(UsbError::WouldBlock)
+        }
+    }
+
+    #[must_use]
+    pub fn has_out_data(&self) -> bool {
+        !self.out_buf.lock().unwrap().borrow().is_empty()
+    }
+
+    /// Take the data written by the device to any IN endpoint, empty if nothing was written
+    ///
+    /// Reading completes the writes to the IN endpoints, which is reported to the device by its
+    /// next poll
+    pub fn host_read_in(&self) -> Vec<u8> {
+        let written = self.in_written.lock().unwrap().take();
+        *self.in_complete.lock().unwrap().borrow_mut() |= written;
+        self.in_buf.lock().unwrap().take()
+    }
+
+    #[must_use]
+    pub fn has_setup_data(&self) -> bool {
+        !self.setup_buf.lock().unwrap().borrow().is_empty()
+    }
+
+    #[must_use]
+    pub fn is_stalled(&self, ep_addr: EndpointAddress) -> bool {
+        self.stalled.lock().unwrap().borrow().contains(&ep_addr)
+    }
+
+    /// True if the device rejected the last control request
+    #[must_use]
+    pub fn control_stalled(&self) -> bool {
+        self.is_stalled(EndpointAddress::from_parts(0, UsbDirection::In))
+    }
+
+    /// Suspend the bus, the device enters the suspended state on its next poll
+    pub fn host_suspend(&self) {
+        self.suspended.lock().unwrap().replace(true);
+    }
+
+    /// Resume the bus, the device leaves the suspended state on its next poll
+    pub fn host_resume(&self) {
+        self.suspended.lock().unwrap().replace(false);
+    }
+
+    /// Address assigned by [`Self::enumerate`], 0 before enumeration
+    #[must_use]
+    pub fn device_address(&self) -> u8 {
+        *self.address.lock().unwrap().borrow()
+    }
+
+    fn device_read_setup(&self, data: &mut [u8]) -> Result<usize> {
+        let buf = self.setup_buf.lock().unwrap();
+        if buf.borrow().is_empty() {
+            self.device_read_out(data)
+        } else {
+            let tmp = buf.take();
+            data[..tmp.len()].copy_from_slice(&tmp);
+            Ok(tmp.len())
+        }
+    }
+
+    fn device_read_out(&self, data: &mut [u8]) -> Result<usize> {
+        let buf = self.out_buf.lock().unwrap();
+        if buf.borrow().is_empty() {
+            Err(UsbError::WouldBlock)
+        } else {
+            let tmp = buf.take();
+            data[..tmp.len()].copy_from_slice(&tmp);
+            Ok(tmp.len())
+        }
+    }
+
+    fn device_write(&self, data: &[u8]) -> Result<usize> {
+        let buf = self.in_buf.lock().unwrap();
+        if buf.borrow().is_empty() {
+            buf.borrow_mut().extend_from_slice(data);
+            Ok(data.len())
+        } else {
+            Err(UsbError::WouldBlock)
+        }
+    }
+
+    /// Make a control IN request, returning the data sent by the device. The data is empty if
+    /// the request was rejected
+    pub fn control_in<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        request: UsbRequest,
+    ) -> Vec<u8> {
+        self.host_read_in();
+        self.host_write_setup(&request.pack().unwrap()).unwrap();
+        usb_dev.poll(classes);
+
+        let mut data = Vec::new();
+        loop {
+            let read = self.host_read_in();
+            if read.is_empty() {
+                break;
+            }
+            data.extend_from_slice(&read);
+            usb_dev.poll(classes);
+        }
+        data
+    }
+
+    /// Make a control OUT request with a data stage of `data`, which may be empty
+    pub fn control_out<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        request: UsbRequest,
+        data: &[u8],
+    ) {
+        self.host_read_in();
+        let request = UsbRequest {
+            length: u16::try_from(data.len()).unwrap(),
+            ..request
+        };
+        self.host_write_setup(&request.pack().unwrap()).unwrap();
+        usb_dev.poll(classes);
+
+        if !data.is_empty() {
+            self.host_write_out(data).unwrap();
+            usb_dev.poll(classes);
+        }
+
+        //Complete the status stage
+        usb_dev.poll(classes);
+        self.host_read_in();
+    }
+
+    /// Read the device and configuration descriptors, then set an address and the first
+    /// configuration
+    pub fn enumerate<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+    ) {
+        self.get_descriptor(
+            usb_dev,
+            classes,
+            usb_device::descriptor::descriptor_type::DEVICE,
+            0,
+        );
+        self.control_out(
+            usb_dev,
+            classes,
+            UsbRequest::standard(UsbDirection::Out, Request::SET_ADDRESS, 1, 0),
+            &[],
+        );
+        self.get_configuration_descriptor(usb_dev, classes);
+        self.control_out(
+            usb_dev,
+            classes,
+            UsbRequest::standard(UsbDirection::Out, Request::SET_CONFIGURATION, 1, 0),
+            &[],
+        );
+    }
+
+    /// Standard `SET_FEATURE` request enabling remote wakeup
+    pub fn enable_remote_wakeup<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+    ) {
+        self.control_out(
+            usb_dev,
+            classes,
+            UsbRequest::standard(
+                UsbDirection::Out,
+                Request::SET_FEATURE,
+                Request::FEATURE_DEVICE_REMOTE_WAKEUP,
+                0,
+            ),
+            &[],
+        );
+    }
+
+    /// Standard `SET_FEATURE` or `CLEAR_FEATURE` request for the halt feature of `endpoint`
+    pub fn set_endpoint_halt<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        endpoint: EndpointAddress,
+        halted: bool,
+    ) {
+        self.control_out(
+            usb_dev,
+            classes,
+            UsbRequest {
+                recipient: Recipient::Endpoint as u8,
+                index: u8::from(endpoint).into(),
+                ..UsbRequest::standard(
+                    UsbDirection::Out,
+                    if halted {
+                        Request::SET_FEATURE
+                    } else {
+                        Request::CLEAR_FEATURE
+                    },
+                    Request::FEATURE_ENDPOINT_HALT,
+                    0,
+                )
+            },
+            &[],
+        );
+    }
+
+    /// Standard `GET_DESCRIPTOR` request for the descriptor of `descriptor_type` and `index`
+    pub fn get_descriptor<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        descriptor_type: u8,
+        index: u8,
+    ) -> Vec<u8> {
+        self.control_in(
+            usb_dev,
+            classes,
+            UsbRequest::standard(
+                UsbDirection::In,
+                Request::GET_DESCRIPTOR,
+                u16::from(descriptor_type) << 8 | u16::from(index),
+                0xFFFF,
+            ),
+        )
+    }
+
+    /// The configuration descriptor, including all interface, HID and endpoint descriptors
+    pub fn get_configuration_descriptor<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+    ) -> Vec<u8> {
+        self.get_descriptor(
+            usb_dev,
+            classes,
+            usb_device::descriptor::descriptor_type::CONFIGURATION,
+            0,
+        )
+    }
+
+    /// The report descriptor of `interface`
+    pub fn get_report_descriptor<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+    ) -> Vec<u8> {
+        self.control_in(
+            usb_dev,
+            classes,
+            UsbRequest {
+                request_type: RequestType::Standard as u8,
+                recipient: Recipient::Interface as u8,
+                request: Request::GET_DESCRIPTOR,
+                value: u16::from(u8::from(DescriptorType::Report)) << 8,
+                index: interface.into(),
+                ..UsbRequest::standard(UsbDirection::In, 0, 0, 0xFFFF)
+            },
+        )
+    }
+
+    /// `SET_IDLE` for `report_id` of `interface`, in units of 4ms
+    pub fn set_idle<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+        report_id: u8,
+        value: u8,
+    ) {
+        self.control_out(
+            usb_dev,
+            classes,
+            UsbRequest::hid(
+                UsbDirection::Out,
+                HidRequest::SetIdle,
+                u16::from(value) << 8 | u16::from(report_id),
+                interface,
+            ),
+            &[],
+        );
+    }
+
+    /// `GET_IDLE` for `report_id` of `interface`, in units of 4ms
+    pub fn get_idle<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+        report_id: u8,
+    ) -> Option<u8> {
+        self.control_in(
+            usb_dev,
+            classes,
+            UsbRequest::hid(
+                UsbDirection::In,
+                HidRequest::GetIdle,
+                report_id.into(),
+                interface,
+            )
+            .with_length(1),
+        )
+        .first()
+        .copied()
+    }
+
+    pub fn set_protocol<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+        protocol: HidProtocol,
+    ) {
+        self.control_out(
+            usb_dev,
+            classes,
+            UsbRequest::hid(
+                UsbDirection::Out,
+                HidRequest::SetProtocol,
+                u8::from(protocol).into(),
+                interface,
+            ),
+            &[],
+        );
+    }
+
+    pub fn get_protocol<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+    ) -> Option<HidProtocol> {
+        self.control_in(
+            usb_dev,
+            classes,
+            UsbRequest::hid(UsbDirection::In, HidRequest::GetProtocol, 0, interface).with_length(1),
+        )
+        .first()
+        .and_then(|&p| HidProtocol::try_from(p).ok())
+    }
+
+    pub fn set_report<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+        report_type: ReportType,
+        report_id: u8,
+        data: &[u8],
+    ) {
+        self.control_out(
+            usb_dev,
+            classes,
+            UsbRequest::hid(
+                UsbDirection::Out,
+                HidRequest::SetReport,
+                u16::from(u8::from(report_type)) << 8 | u16::from(report_id),
+                interface,
+            ),
+            data,
+        );
+    }
+
+    pub fn get_report<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+        report_type: ReportType,
+        report_id: u8,
+        length: u16,
+    ) -> Vec<u8> {
+        self.control_in(
+            usb_dev,
+            classes,
+            UsbRequest::hid(
+                UsbDirection::In,
+                HidRequest::GetReport,
+                u16::from(u8::from(report_type)) << 8 | u16::from(report_id),
+                interface,
+            )
+            .with_length(length),
+        )
+    }
+
+    /// Standard `SET_INTERFACE` request selecting `alt_setting` of `interface`
+    pub fn set_interface<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+        alt_setting: u8,
+    ) {
+        self.control_out(
+            usb_dev,
+            classes,
+            UsbRequest {
+                recipient: Recipient::Interface as u8,
+                index: interface.into(),
+                ..UsbRequest::standard(
+                    UsbDirection::Out,
+                    Request::SET_INTERFACE,
+                    alt_setting.into(),
+                    0,
+                )
+            },
+            &[],
+        );
+    }
+
+    /// Standard `GET_INTERFACE` request for the alternate setting of `interface`
+    pub fn get_interface<B: UsbBus>(
+        &self,
+        usb_dev: &mut UsbDevice<'_, B>,
+        classes: &mut [&mut dyn UsbClass<B>],
+        interface: u8,
+    ) -> Option<u8> {
+        self.control_in(
+            usb_dev,
+            classes,
+            UsbRequest {
+                recipient: Recipient::Interface as u8,
+                index: interface.into(),
+                ..UsbRequest::standard(UsbDirection::In, Request::GET_INTERFACE, 0, 1)
+            },
+        )
+        .first()
+        .copied()
+    }
+}
+
+/// Mock [`UsbBus`] backed by a [`UsbTestManager`]
+pub struct TestUsbBus<'a> {
+    next_ep_index: usize,
+    manager: &'a UsbTestManager,
+}
+
+impl<'a> TestUsbBus<'a> {
+    #[must_use]
+    pub fn new(manager: &'a UsbTestManager) -> Self {
+        TestUsbBus {
+            //Endpoint 0 is requested explicitly for the control pipe
+            next_ep_index: 1,
+            manager,
+        }
+    }
+}
+
+#[allow(clippy::unwrap_used)]
+impl UsbBus for TestUsbBus<'_> {
+    fn alloc_ep(
+        &mut self,
+        ep_dir: UsbDirection,
+        ep_addr: Option<EndpointAddress>,
+        _ep_type: EndpointType,
+        _max_packet_size: u16,
+        _interval: u8,
+    ) -> Result<EndpointAddress> {
+        if let Some(ep) = ep_addr {
+            return Ok(ep);
+        }
+        let ep = EndpointAddress::from_parts(self.next_ep_index, ep_dir);
+        self.next_ep_index += 1;
+        Ok(ep)
+    }
+
+    fn enable(&mut self) {}
+    fn reset(&self) {}
+    fn set_device_address(&self, addr: u8) {
+        *self.manager.address.lock().unwrap().borrow_mut() = addr;
+    }
+    fn write(&self, ep_addr: EndpointAddress, buf: &[u8]) -> Result<usize> {
+        let n = self.manager.device_write(buf)?;
+        if ep_addr.index() != 0 {
+            *self.manager.in_written.lock().unwrap().borrow_mut() |= 1 << ep_addr.index();
+        }
+        Ok(n)
+    }
+    fn read(&self, _ep_addr: EndpointAddress, buf: &mut [u8]) -> Result<usize> {
+        self.manager.device_read_setup(buf)
+    }
+    fn set_stalled(&self, ep_addr: EndpointAddress, stalled: bool) {
+        let eps = self.manager.stalled.lock().unwrap();
+        let mut eps = eps.borrow_mut();
+        eps.retain(|&ep| ep != ep_addr);
+        if stalled {
+            eps.push(ep_addr);
+        }
+    }
+    fn is_stalled(&self, ep_addr: EndpointAddress) -> bool {
+        self.manager.is_stalled(ep_addr)
+    }
+    fn suspend(&self) {}
+    fn resume(&self) {}
+    fn poll(&self) -> PollResult {
+        if *self.manager.suspended.lock().unwrap().borrow() {
+            return PollResult::Suspend;
+        }
+        PollResult::Data {
+            ep_out: u16::from(self.manager.has_out_data()),
+            ep_in_complete: 1 | self.manager.in_complete.lock().unwrap().take(),
+            ep_setup: u16::from(self.manager.has_setup_data()),
+        }
+    }
+}
@@ -1,17 +1,21 @@
 //! USB Class for implementing Human Interface Devices
 
-use crate::descriptor::{DescriptorType, HidProtocol, HidRequest};
-use crate::device::{DeviceClass, DeviceHList};
-use crate::interface::{InterfaceClass, UsbAllocatable};
+use crate::clock::{ElapsedTicks, HidClock};
+use crate::descriptor::{DescriptorType, HidProtocol, HidRequest, ReportType};
+use crate::device::{DeviceClass, DeviceHList, ReportWriter};
+use crate::interface::{InterfaceClass, InterfaceEvent, UsbAllocatable};
 use crate::UsbHidError;
 use core::cell::RefCell;
 use core::default::Default;
 use core::marker::PhantomData;
 use frunk::hlist::{HList, Selector};
 use frunk::{HCons, HNil, ToMut};
+use fugit::MillisDurationU32;
 #[allow(clippy::wildcard_imports)]
 use usb_device::class_prelude::*;
 use usb_device::control::{Recipient, Request};
+use usb_device::device::{UsbDevice, UsbDeviceState};
+use usb_device::UsbError;
 use usb_device::{control::RequestType, Result};
 
 pub mod prelude {
@@ -25,14 +29,20 @@ pub mod prelude {
     //! use usbd_human_interface_device::usb_class::prelude::*;
     //! ```
 
-    pub use crate::descriptor::{HidProtocol, InterfaceProtocol};
-    pub use crate::device::DeviceClass;
+    pub use crate::clock::{FugitClock, HidClock, MillisClock};
+    pub use crate::descriptor::{
+        HidCountryCode, HidProtocol, InterfaceProtocol, InterfaceSubClass, ReportType,
+    };
+    pub use crate::device::{DeviceClass, ReportWriter};
     pub use crate::interface::{
-        InBytes16, InBytes32, InBytes64, InBytes8, InNone, Interface, InterfaceBuilder,
-        InterfaceConfig, OutBytes16, OutBytes32, OutBytes64, OutBytes8, OutNone, ReportSingle,
-        Reports128, Reports16, Reports32, Reports64, Reports8, UsbAllocatable,
+        ControlRequestHandler, EndpointInfo, InBytes16, InBytes32, InBytes64, InBytes8, InNone,
+        Interface, InterfaceBuilder, InterfaceConfig, InterfaceEvent, InterfaceStats,
+        InterfaceString, OutBytes16, OutBytes32, OutBytes64, OutBytes8, OutNone, ReportBytes,
+        ReportDescriptorSource, ReportHandler, ReportSingle, Reports128, Reports16, Reports32,
+        Reports64, Reports8, UsbAllocatable,
     };
     pub use crate::interface::{ManagedIdleInterface, ManagedIdleInterfaceConfig};
+    pub use crate::source::{Debounce, InputSource, Sourced, SourcedConfig};
     pub use crate::usb_class::{UsbHidClass, UsbHidClassBuilder};
     pub use crate::UsbHidError;
 }
@@ -45,6 +55,29 @@ pub enum UsbHidBuilderError {
     ValueOverflow,
     /// A slice of data is longer than permitted
     SliceLengthOverflow,
+    /// A configuration needs different endpoints or strings to those already allocated
+    AllocationMismatch,
+}
+
+/// Make the host enumerate the device again, e.g. after interfaces have been changed with
+/// [`Interface::reconfigure`](crate::interface::Interface::reconfigure)
+///
+/// The USB peripheral is reset if the bus supports it, otherwise `detach` is called. `detach`
+/// should disconnect from the bus, e.g. by disabling the D+ pull-up, for long enough that the host
+/// notices, 10ms is usually sufficient, then connect again.
+///
+/// # Errors
+///
+/// Any error from the bus when resetting
+pub fn reenumerate<B: UsbBus>(usb_dev: &mut UsbDevice<'_, B>, detach: impl FnOnce()) -> Result<()> {
+    match usb_dev.force_reset() {
+        Err(UsbError::Unsupported) => {
+            info!("Bus reset unsupported, detaching");
+            detach();
+            Ok(())
+        }
+        result => result,
+    }
 }
 
 /// Builder for [`UsbHidClass`]
@@ -55,7 +88,7 @@ pub struct UsbHidClassBuilder<'a, B, Devices> {
     marker: PhantomData<&'a B>,
 }
 
-impl<'a, B> UsbHidClassBuilder<'a, B, HNil> {
+impl<B> UsbHidClassBuilder<'_, B, HNil> {
     pub fn new() -> Self {
         Self {
             devices: HNil,
@@ -64,7 +97,7 @@ impl<'a, B> UsbHidClassBuilder<'a, B, HNil> {
     }
 }
 
-impl<'a, B> Default for UsbHidClassBuilder<'a, B, HNil> {
+impl<B> Default for UsbHidClassBuilder<'_, B, HNil> {
     fn default() -> Self {
         Self::new()
     }
@@ -95,25 +128,55 @@ where
     pub fn build(
         self,
         usb_alloc: &'a UsbBusAllocator<B>,
-    ) -> UsbHidClass<B, HCons<Config::Allocated, Tail::Allocated>> {
-        UsbHidClass {
-            devices: RefCell::new(self.devices.allocate(usb_alloc)),
-            _marker: PhantomData,
-        }
+    ) -> UsbHidClass<'a, B, HCons<Config::Allocated, Tail::Allocated>> {
+        UsbHidClass::new(self.devices.allocate(usb_alloc))
     }
 }
 
 pub type BuilderResult<B> = core::result::Result<B, UsbHidBuilderError>;
 
+/// Unwrap a [`BuilderResult`] in a `const`, where a builder error is a build error rather than a
+/// panic at start up, see [`InterfaceBuilder::validate`](crate::interface::InterfaceBuilder::validate)
+///
+/// # Panics
+///
+/// If `result` is an error
+pub const fn unwrap_builder<T: Copy>(result: BuilderResult<T>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(UsbHidBuilderError::ValueOverflow) => ::core::panic!("builder value out of range"),
+        Err(UsbHidBuilderError::SliceLengthOverflow) => ::core::panic!("builder slice too long"),
+        Err(UsbHidBuilderError::AllocationMismatch) => {
+            ::core::panic!("builder configuration doesn't match the allocation")
+        }
+    }
+}
+
 /// USB Human Interface Device class
 pub struct UsbHidClass<'a, B, Devices> {
     // Using a RefCell makes it simpler to implement devices as all calls to interfaces are mut
     // this could be removed, but then each usb device would need to implement a non mut borrow
     // of its `RawInterface`.
     devices: RefCell<Devices>,
+    elapsed: ElapsedTicks,
     _marker: PhantomData<&'a B>,
 }
 
+impl<B, Devices> UsbHidClass<'_, B, Devices> {
+    pub(crate) fn new(devices: Devices) -> Self {
+        Self {
+            devices: RefCell::new(devices),
+            elapsed: ElapsedTicks::default(),
+            _marker: PhantomData,
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub(crate) fn devices_mut(&mut self) -> &mut Devices {
+        self.devices.get_mut()
+    }
+}
+
 impl<'a, B, Devices: DeviceHList<'a>> UsbHidClass<'a, B, Devices> {
     /// Borrow a single device selected by `T`
     pub fn device<T, Index>(&mut self) -> &mut T
@@ -124,24 +187,107 @@ impl<'a, B, Devices: DeviceHList<'a>> UsbHidClass<'a, B, Devices> {
     }
 
     /// Borrow an [`HList`] of all devices
-    pub fn devices(&'a mut self) -> <Devices as ToMut>::Output {
+    pub fn devices(&'a mut self) -> <Devices as ToMut<'a>>::Output {
         self.devices.get_mut().to_mut()
     }
 
+    /// Take the oldest unhandled request from the host to any interface, with the number of the
+    /// interface it was made to
+    pub fn take_event(&mut self) -> Option<(u8, InterfaceEvent)> {
+        self.devices.get_mut().take_event()
+    }
+
     /// Provide a clock tick to allow the tracking of time. Call this every 1ms / at 1KHz
     pub fn tick(&mut self) -> core::result::Result<(), UsbHidError> {
         self.devices.get_mut().tick()
     }
+
+    /// Pass the suspend state of `usb_dev` to interfaces built with
+    /// [`InterfaceBuilder::wake_on_input`](crate::interface::InterfaceBuilder::wake_on_input).
+    /// Call after each poll of `usb_dev`
+    ///
+    /// `remote_wakeup` is called when a report has been written to one of those interfaces while
+    /// suspended and the host has enabled remote wakeup. It should signal resume on the bus, which
+    /// isn't part of the `UsbBus` trait, e.g. using the HAL's USB peripheral. The report is sent
+    /// once the host has resumed the bus.
+    pub fn update_suspend(&mut self, usb_dev: &UsbDevice<'_, B>, remote_wakeup: impl FnOnce())
+    where
+        B: UsbBus,
+    {
+        let suspended = usb_dev.state() == UsbDeviceState::Suspend;
+        if self
+            .devices
+            .get_mut()
+            .set_suspended(suspended, usb_dev.remote_wakeup_enabled())
+            && suspended
+        {
+            remote_wakeup();
+        }
+    }
+
+    /// Provide the current time from `clock`, calling [`Self::tick`] once for each millisecond
+    /// elapsed since the previous call. Call this at least once per ms if possible.
+    ///
+    /// At most [`MAX_CATCH_UP_MS`](crate::clock::MAX_CATCH_UP_MS) ticks are applied per call. If
+    /// a tick fails the remaining elapsed ticks are discarded and the error is returned.
+    pub fn tick_clock(
+        &mut self,
+        clock: &mut impl HidClock,
+    ) -> core::result::Result<(), UsbHidError> {
+        let elapsed = self.elapsed.update(clock.now_millis());
+        for _ in 0..elapsed {
+            self.tick()?;
+        }
+        Ok(())
+    }
+
+    /// Write `report` to the device selected by `T`, retrying while the device is busy until
+    /// `timeout` has elapsed on `clock`
+    ///
+    /// `poll` is called with the class between attempts. Unless it is done elsewhere, e.g. by
+    /// an interrupt handler, it should poll the USB device so the host can read the previous
+    /// report, and call [`Self::tick_clock`] so rate limits expire. It may also wait before
+    /// returning.
+    ///
+    /// # Errors
+    ///
+    /// [`UsbHidError::WouldBlock`] if the report wasn't written before the timeout, or any other
+    /// error from writing the report
+    pub fn write_report_blocking<T, Index, R>(
+        &mut self,
+        report: &R,
+        clock: &mut impl HidClock,
+        timeout: MillisDurationU32,
+        mut poll: impl FnMut(&mut Self),
+    ) -> core::result::Result<(), UsbHidError>
+    where
+        Devices: Selector<T, Index>,
+        T: ReportWriter<R>,
+    {
+        let start = clock.now_millis();
+        loop {
+            match self.device::<T, Index>().write_report(report) {
+                Err(UsbHidError::WouldBlock) => {}
+                result => return result,
+            }
+            if clock.now_millis().wrapping_sub(start) >= timeout.to_millis() {
+                warn!("Timed out writing report");
+                return Err(UsbHidError::WouldBlock);
+            }
+            poll(self);
+        }
+    }
 }
 
-impl<'a, B: UsbBus + 'a, Devices> UsbHidClass<'a, B, Devices> {
+impl<'a, B: UsbBus + 'a, Devices: DeviceHList<'a>> UsbHidClass<'a, B, Devices> {
     fn get_descriptor(transfer: ControlIn<B>, interface: &mut dyn InterfaceClass<'a>) {
         let request: &Request = transfer.request();
         match DescriptorType::try_from((request.value >> 8) as u8) {
             Ok(DescriptorType::Report) => {
-                match transfer.accept_with(interface.report_descriptor()) {
+                let length = usize::from(request.length);
+                match transfer.accept(|buf| interface.read_report_descriptor(length, buf)) {
                     Err(e) => error!("Failed to send report descriptor - {:?}", e),
-                    Ok(_) => {
+                    Ok(()) => {
                         trace!("Sent report descriptor");
                     }
                 }
@@ -156,7 +302,7 @@ impl<'a, B: UsbBus + 'a, Devices> UsbHidClass<'a, B, Devices> {
                     Err(e) => {
                         error!("Failed to send Hid descriptor - {:?}", e);
                     }
-                    Ok(_) => {
+                    Ok(()) => {
                         trace!("Sent hid descriptor");
                     }
                 }
@@ -169,6 +315,77 @@ impl<'a, B: UsbBus + 'a, Devices> UsbHidClass<'a, B, Devices> {
             }
         }
     }
+
+    /// True if `interface` is inactive and `request` isn't a standard request or a HID idle or
+    /// protocol request, which are still handled so the host can configure the interface as usual
+    fn rejected_while_inactive(request: &Request, interface: &mut dyn InterfaceClass<'a>) -> bool {
+        let handled = match request.request_type {
+            RequestType::Standard => true,
+            RequestType::Class => matches!(
+                HidRequest::try_from(request.request),
+                Ok(HidRequest::GetIdle
+                    | HidRequest::SetIdle
+                    | HidRequest::GetProtocol
+                    | HidRequest::SetProtocol)
+            ),
+            _ => false,
+        };
+        if handled || interface.is_active() {
+            false
+        } else {
+            warn!("Interface inactive, rejecting request {}", request.request);
+            true
+        }
+    }
+
+    /// Pass a request that isn't a HID request to the interface's
+    /// [`ControlRequestHandler`](crate::interface::ControlRequestHandler), stalling it if it isn't
+    /// handled
+    fn control_in_unhandled(transfer: ControlIn<B>, interface: &mut dyn InterfaceClass<'a>) {
+        let request: &Request = transfer.request();
+        let mut data = [0_u8; 64];
+        let len = usize::from(request.length).min(data.len());
+        if let Some(n) = interface.control_in(request, &mut data[..len]) {
+            if let Err(e) = transfer.accept_with(&data[..n.min(len)]) {
+                error!("Failed to send control response - {:?}", e);
+            }
+        } else {
+            warn!(
+                "Unsupported control_in request type: {:?}, request: {}, value: {}",
+                request.request_type, request.request, request.value
+            );
+            transfer.reject().ok();
+        }
+    }
+
+    /// Pass the host setting or clearing an endpoint halt to the interfaces, returns false if
+    /// `request` isn't a halt request. The request itself is left for `UsbDevice` to handle
+    fn endpoint_halt_request(&mut self, request: &Request) -> bool {
+        let halted = match (request.request_type, request.recipient, request.request) {
+            (RequestType::Standard, Recipient::Endpoint, Request::SET_FEATURE) => true,
+            (RequestType::Standard, Recipient::Endpoint, Request::CLEAR_FEATURE) => false,
+            _ => return false,
+        };
+        if request.value != Request::FEATURE_ENDPOINT_HALT {
+            return false;
+        }
+        let addr = EndpointAddress::from((request.index & 0x8F) as u8);
+        self.devices.get_mut().endpoint_halt(addr, halted);
+        true
+    }
+
+    fn control_out_unhandled(transfer: ControlOut<B>, interface: &mut dyn InterfaceClass<'a>) {
+        let request: &Request = transfer.request();
+        if interface.control_out(request, transfer.data()) {
+            transfer.accept().ok();
+        } else {
+            warn!(
+                "Unsupported control_out request type: {:?}, request: {}, value: {}",
+                request.request_type, request.request, request.value
+            );
+            transfer.reject().ok();
+        }
+    }
 }
 
 impl<'a, B, Devices> UsbClass<B> for UsbHidClass<'a, B, Devices>
@@ -182,7 +399,7 @@ where
         Ok(())
     }
 
-    fn get_string(&self, index: StringIndex, lang_id: u16) -> Option<&str> {
+    fn get_string(&self, index: StringIndex, lang_id: LangID) -> Option<&str> {
         self.devices.borrow_mut().get_string(index, lang_id)
     }
 
@@ -191,19 +408,46 @@ where
         self.devices.get_mut().reset();
     }
 
+    fn get_alt_setting(&mut self, interface: InterfaceNumber) -> Option<u8> {
+        self.devices
+            .get_mut()
+            .get(u8::from(interface))
+            .map(|i| i.alt_setting())
+    }
+
+    fn set_alt_setting(&mut self, interface: InterfaceNumber, alternative: u8) -> bool {
+        self.devices
+            .get_mut()
+            .get(u8::from(interface))
+            .is_some_and(|i| i.set_alt_setting(alternative))
+    }
+
+    fn endpoint_in_complete(&mut self, addr: EndpointAddress) {
+        self.devices.get_mut().endpoint_in_complete(addr);
+    }
+
     fn control_out(&mut self, transfer: ControlOut<B>) {
         let request: &Request = transfer.request();
 
-        //only respond to Class requests for this interface
-        if !(request.request_type == RequestType::Class
-            && request.recipient == Recipient::Interface)
+        if self.endpoint_halt_request(request) {
+            return;
+        }
+
+        //only respond to Class and Vendor requests for this interface
+        if !(matches!(
+            request.request_type,
+            RequestType::Class | RequestType::Vendor
+        ) && request.recipient == Recipient::Interface)
         {
             return;
         }
 
         let Some(interface) = u8::try_from(request.index)
-                    .ok()
-                    .and_then(|id| self.devices.get_mut().get(id)) else { return };
+            .ok()
+            .and_then(|id| self.devices.get_mut().get(id))
+        else {
+            return;
+        };
 
         trace!(
             "ctrl_out: request type: {:?}, request: {}, value: {}",
@@ -212,10 +456,35 @@ where
             request.value
         );
 
+        if Self::rejected_while_inactive(request, interface) {
+            transfer.reject().ok();
+            return;
+        }
+
+        if request.request_type == RequestType::Vendor {
+            Self::control_out_unhandled(transfer, interface);
+            return;
+        }
+
         match HidRequest::try_from(request.request) {
             Ok(HidRequest::SetReport) => {
-                interface.set_report(transfer.data()).ok();
-                transfer.accept().ok();
+                if let Ok(report_type) = ReportType::try_from((request.value >> 8) as u8) {
+                    match interface.set_report(
+                        report_type,
+                        (request.value & 0xFF) as u8,
+                        transfer.data(),
+                    ) {
+                        //Dropped if the previous report hasn't been read yet
+                        Ok(()) | Err(UsbError::WouldBlock) => transfer.accept().ok(),
+                        //Stall malformed reports so the host sees the error
+                        Err(_) => transfer.reject().ok(),
+                    };
+                } else {
+                    error!(
+                        "Unable to set report, unsupported report type:{}",
+                        request.value >> 8
+                    );
+                }
             }
             Ok(HidRequest::SetIdle) => {
                 if request.length != 0 {
@@ -236,8 +505,11 @@ where
                     );
                 }
                 if let Ok(protocol) = HidProtocol::try_from((request.value & 0xFF) as u8) {
-                    interface.set_protocol(protocol);
-                    transfer.accept().ok();
+                    if interface.set_protocol(protocol) {
+                        transfer.accept().ok();
+                    } else {
+                        transfer.reject().ok();
+                    }
                 } else {
                     error!(
                         "Unable to set protocol, unsupported value:{}",
@@ -245,12 +517,7 @@ where
                     );
                 }
             }
-            _ => {
-                warn!(
-                    "Unsupported control_out request type: {:?}, request: {}, value: {}",
-                    request.request_type, request.request, request.value
-                );
-            }
+            _ => Self::control_out_unhandled(transfer, interface),
         }
     }
 
@@ -261,10 +528,18 @@ where
             return;
         }
 
-        let Ok(interface_id) = u8::try_from(request.index) else {
+        let Some(interface) = u8::try_from(request.index)
+            .ok()
+            .and_then(|id| self.devices.get_mut().get(id))
+        else {
             return;
         };
 
+        if Self::rejected_while_inactive(request, interface) {
+            transfer.reject().ok();
+            return;
+        }
+
         trace!(
             "ctrl_in: request type: {:?}, request: {}, value: {}",
             request.request_type,
@@ -274,78 +549,75 @@ where
 
         match request.request_type {
             RequestType::Standard => {
-                if let Some(interface) = self.devices.get_mut().get(interface_id) {
-                    if request.request == Request::GET_DESCRIPTOR {
-                        info!("Get descriptor");
-                        Self::get_descriptor(transfer, interface);
-                    }
+                if request.request == Request::GET_DESCRIPTOR {
+                    info!("Get descriptor");
+                    Self::get_descriptor(transfer, interface);
                 }
             }
 
-            RequestType::Class => {
-                let Some(interface) = self.devices.get_mut().get(interface_id) else {
-                    return;
-                };
-
-                match HidRequest::try_from(request.request) {
-                    Ok(HidRequest::GetReport) => {
-                        let mut data = [0_u8; 64];
-                        if let Ok(n) = interface.get_report(&mut data) {
-                            if n != transfer.request().length.into() {
-                                warn!(
-                                    "GetReport expected {} bytes, got {} bytes",
-                                    transfer.request().length,
-                                    data.len()
-                                );
-                            }
-                            if let Err(e) = transfer.accept_with(&data[..n]) {
-                                error!("Failed to send report - {:?}", e);
-                            } else {
-                                trace!("Sent report, {} bytes", n);
-                                unwrap!(interface.get_report_ack());
-                            }
-                        }
-                    }
-                    Ok(HidRequest::GetIdle) => {
-                        if request.length != 1 {
+            RequestType::Class => match HidRequest::try_from(request.request) {
+                Ok(HidRequest::GetReport) => {
+                    let Ok(report_type) = ReportType::try_from((request.value >> 8) as u8) else {
+                        error!(
+                            "Unable to get report, unsupported report type:{}",
+                            request.value >> 8
+                        );
+                        return;
+                    };
+                    let report_id = (request.value & 0xFF) as u8;
+
+                    let mut data = [0_u8; 64];
+                    if let Ok(n) = interface.get_report(report_type, report_id, &mut data) {
+                        if n != transfer.request().length.into() {
                             warn!(
-                                "Expected GetIdle to have length 1, received {}",
-                                request.length
+                                "GetReport expected {} bytes, got {} bytes",
+                                transfer.request().length,
+                                n
                             );
                         }
-
-                        let report_id = (request.value & 0xFF) as u8;
-                        let idle = interface.get_idle(report_id);
-                        if let Err(e) = transfer.accept_with(&[idle]) {
-                            error!("Failed to send idle data - {:?}", e);
+                        if let Err(e) = transfer.accept_with(&data[..n]) {
+                            error!("Failed to send report - {:?}", e);
                         } else {
-                            info!("Get Idle for ID{}: {}", report_id, idle);
+                            trace!("Sent report, {} bytes", n);
+                            unwrap!(interface.get_report_ack());
                         }
                     }
-                    Ok(HidRequest::GetProtocol) => {
-                        if request.length != 1 {
-                            warn!(
-                                "Expected GetProtocol to have length 1, received {}",
-                                request.length
-                            );
-                        }
+                }
+                Ok(HidRequest::GetIdle) => {
+                    if request.length != 1 {
+                        warn!(
+                            "Expected GetIdle to have length 1, received {}",
+                            request.length
+                        );
+                    }
 
-                        let protocol = interface.get_protocol();
-                        if let Err(e) = transfer.accept_with(&[protocol.into()]) {
-                            error!("Failed to send protocol data - {:?}", e);
-                        } else {
-                            info!("Get protocol: {:?}", protocol);
-                        }
+                    let report_id = (request.value & 0xFF) as u8;
+                    let idle = interface.get_idle(report_id);
+                    if let Err(e) = transfer.accept_with(&[idle]) {
+                        error!("Failed to send idle data - {:?}", e);
+                    } else {
+                        info!("Get Idle for ID{}: {}", report_id, idle);
                     }
-                    _ => {
+                }
+                Ok(HidRequest::GetProtocol) => {
+                    if request.length != 1 {
                         warn!(
-                            "Unsupported control_in request type: {:?}, request: {}, value: {}",
-                            request.request_type, request.request, request.value
+                            "Expected GetProtocol to have length 1, received {}",
+                            request.length
                         );
                     }
+
+                    let protocol = interface.get_protocol();
+                    if let Err(e) = transfer.accept_with(&[protocol.into()]) {
+                        error!("Failed to send protocol data - {:?}", e);
+                    } else {
+                        info!("Get protocol: {:?}", protocol);
+                    }
                 }
-            }
-            _ => {}
+                _ => Self::control_in_unhandled(transfer, interface),
+            },
+            RequestType::Vendor => Self::control_in_unhandled(transfer, interface),
+            RequestType::Reserved => {}
         }
     }
 }
@@ -355,17 +627,45 @@ mod test {
     #![allow(clippy::unwrap_used)]
     #![allow(clippy::expect_used)]
 
-    use std::cell::RefCell;
-    use std::sync::Mutex;
+    use std::cell::{Cell, RefCell};
     use std::vec::Vec;
 
-    use crate::descriptor::USB_CLASS_HID;
-    use crate::interface::{InBytes64, InterfaceBuilder, OutBytes64, ReportSingle, Reports8};
+    use crate::clock::{MillisClock, MAX_CATCH_UP_MS};
+    use crate::descriptor::{HidCountryCode, InterfaceProtocol, InterfaceSubClass, USB_CLASS_HID};
+    use crate::device::barcode::{BarcodeScannerConfig, BarcodeStatusReport, BarcodeTriggerReport};
+    use crate::device::card_reader::CardReaderConfig;
+    use crate::device::haptics::{HapticCommand, HapticControllerConfig};
+    use crate::device::keyboard::{
+        BootKeyboard, BootKeyboardConfig, DualModeKeyboardConfig, KeyStateReports,
+        KeyboardLedsReport, KeyboardState, Rollover, BOOT_KEYBOARD_REPORT_DESCRIPTOR,
+        DUAL_MODE_KEYBOARD_REPORT_DESCRIPTOR,
+    };
+    use crate::device::mouse::{
+        BootMouse, BootMouseConfig, BootMouseReport, BOOT_MOUSE_REPORT_DESCRIPTOR,
+    };
+    use crate::device::switch_gamepad::{
+        AcknowledgeSubcommands, SubcommandHandler, SwitchGamepadConfig, SwitchGamepadReport,
+        HAT_CENTERED,
+    };
+    use crate::device::touchpad::{InputMode, PrecisionTouchpadConfig};
+    use crate::device::wireless_radio::{
+        WirelessRadioControlsConfig, WirelessRadioLedReport, WirelessRadioReport,
+    };
+    use crate::device::ReportWriter;
+    use crate::interface::{
+        ControlRequestHandler, EndpointInfo, InBytes16, InBytes64, InBytes8, Interface,
+        InterfaceBuilder, InterfaceStats, InterfaceString, ManagedIdleInterfaceConfig, OutBytes64,
+        OutNone, ReportDescriptorSource, ReportHandler, ReportSingle, Reports8,
+    };
+    use crate::page::layout::Layout;
+    use crate::page::{Haptics, Keyboard};
+    use crate::serialize::PackedStructSerializer;
+    use crate::source::{Debounce, SourcedConfig};
+    use crate::test_util::{TestUsbBus, UsbRequest, UsbTestManager};
     use env_logger::Env;
     use fugit::MillisDurationU32;
     use log::SetLoggerError;
     use packed_struct::prelude::*;
-    use usb_device::bus::PollResult;
     use usb_device::prelude::*;
     use usb_device::UsbDirection;
 
@@ -378,128 +678,6 @@ mod test {
                 .try_init();
     }
 
-    #[derive(Default)]
-    struct UsbTestManager {
-        in_buf: Mutex<RefCell<Vec<u8>>>,
-        setup_buf: Mutex<RefCell<Vec<u8>>>,
-    }
-
-    impl UsbTestManager {
-        fn host_write_setup(&self, data: &[u8]) -> Result<()> {
-            let buf = self.setup_buf.lock().unwrap();
-            if buf.borrow().is_empty() {
-                buf.borrow_mut().extend_from_slice(data);
-                Ok(())
-            } else {
-                Err(UsbError::WouldBlock)
-            }
-        }
-
-        fn host_read_in(&self) -> Vec<u8> {
-            self.in_buf.lock().unwrap().take()
-        }
-
-        fn has_setup_data(&self) -> bool {
-            !self.setup_buf.lock().unwrap().borrow().is_empty()
-        }
-
-        fn device_read_setup(&self, data: &mut [u8]) -> Result<usize> {
-            let buf = self.setup_buf.lock().unwrap();
-            if buf.borrow().is_empty() {
-                Err(UsbError::WouldBlock)
-            } else {
-                let tmp = buf.take();
-                data[..tmp.len()].copy_from_slice(&tmp);
-                Ok(tmp.len())
-            }
-        }
-
-        fn device_write(&self, data: &[u8]) -> Result<usize> {
-            let buf = self.in_buf.lock().unwrap();
-            if buf.borrow().is_empty() {
-                buf.borrow_mut().extend_from_slice(data);
-                Ok(data.len())
-            } else {
-                Err(UsbError::WouldBlock)
-            }
-        }
-    }
-
-    struct TestUsbBus<'a> {
-        next_ep_index: usize,
-        manager: &'a UsbTestManager,
-    }
-
-    impl<'a> TestUsbBus<'a> {
-        fn new(manager: &'a UsbTestManager) -> Self {
-            TestUsbBus {
-                next_ep_index: 0,
-                manager,
-            }
-        }
-    }
-
-    impl UsbBus for TestUsbBus<'_> {
-        fn alloc_ep(
-            &mut self,
-            ep_dir: UsbDirection,
-            _ep_addr: Option<EndpointAddress>,
-            _ep_type: EndpointType,
-            _max_packet_size: u16,
-            _interval: u8,
-        ) -> Result<EndpointAddress> {
-            let ep = EndpointAddress::from_parts(self.next_ep_index, ep_dir);
-            self.next_ep_index += 1;
-            Ok(ep)
-        }
-
-        fn enable(&mut self) {}
-        fn reset(&self) {
-            todo!()
-        }
-        fn set_device_address(&self, _addr: u8) {
-            todo!()
-        }
-        fn write(&self, _ep_addr: EndpointAddress, buf: &[u8]) -> Result<usize> {
-            self.manager.device_write(buf)
-        }
-        fn read(&self, _ep_addr: EndpointAddress, buf: &mut [u8]) -> Result<usize> {
-            self.manager.device_read_setup(buf)
-        }
-        fn set_stalled(&self, _ep_addr: EndpointAddress, _stalled: bool) {}
-        fn is_stalled(&self, _ep_addr: EndpointAddress) -> bool {
-            todo!()
-        }
-        fn suspend(&self) {
-            todo!()
-        }
-        fn resume(&self) {
-            todo!()
-        }
-        fn poll(&self) -> PollResult {
-            PollResult::Data {
-                ep_out: 0,
-                ep_in_complete: 1,
-                ep_setup: u16::from(self.manager.has_setup_data()),
-            }
-        }
-    }
-
-    #[derive(Clone, Copy, Debug, PartialEq, Eq, PackedStruct)]
-    #[packed_struct(endian = "lsb", bit_numbering = "msb0", size_bytes = "8")]
-    struct UsbRequest {
-        #[packed_field(bits = "0")]
-        direction: bool,
-        #[packed_field(bits = "1:2")]
-        request_type: u8,
-        #[packed_field(bits = "4:7")]
-        recipient: u8,
-        request: u8,
-        value: u16,
-        index: u16,
-        length: u16,
-    }
-
     #[test]
     fn descriptor_ordering_satisfies_boot_spec() {
         init_logging();
@@ -597,7 +775,12 @@ mod test {
     }
 
     #[test]
-    fn get_protocol_default_to_report() {
+    fn get_interface_strings() {
+        const STRINGS: &[InterfaceString] = &[
+            InterfaceString::new("First"),
+            InterfaceString::new("Second").with_translations(&[(LangID::DE, "Zweite")]),
+        ];
+
         init_logging();
 
         let manager = UsbTestManager::default();
@@ -606,6 +789,9 @@ mod test {
         let mut hid = UsbHidClassBuilder::new()
             .add_device(
                 InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .description("Description")
+                    .strings(STRINGS)
                     .unwrap()
                     .build(),
             )
@@ -615,46 +801,102 @@ mod test {
             .device_class(USB_CLASS_HID)
             .build();
 
-        // Get protocol
-        manager
-            .host_write_setup(
-                &UsbRequest {
-                    direction: UsbDirection::In != UsbDirection::Out,
-                    request_type: RequestType::Class as u8,
-                    recipient: Recipient::Interface as u8,
-                    request: HidRequest::GetProtocol.into(),
-                    value: 0x0,
-                    index: 0x0,
-                    length: 0x1,
-                }
-                .pack()
-                .unwrap(),
-            )
+        let index = hid
+            .device::<Interface<_, InBytes64, OutBytes64, ReportSingle>, _>()
+            .string_index(1)
+            .unwrap()
             .unwrap();
 
-        assert!(usb_dev.poll(&mut [&mut hid]));
+        let mut get_string = |index: u8, lang_id: LangID| {
+            manager
+                .host_write_setup(
+                    &UsbRequest {
+                        direction: UsbDirection::In != UsbDirection::Out,
+                        request_type: RequestType::Standard as u8,
+                        recipient: Recipient::Device as u8,
+                        request: Request::GET_DESCRIPTOR,
+                        value: u16::from(usb_device::descriptor::descriptor_type::STRING) << 8
+                            | u16::from(index),
+                        index: lang_id.into(),
+                        length: 0xFF,
+                    }
+                    .pack()
+                    .unwrap(),
+                )
+                .unwrap();
 
-        // read and validate hid_protocol report
-        let data = manager.host_read_in();
-        assert_eq!(
-            data,
-            [HidProtocol::Report.into()],
-            "Expected protocol to be Report by default"
-        );
+            assert!(usb_dev.poll(&mut [&mut hid]));
+
+            let mut data = Vec::new();
+            loop {
+                let read = manager.host_read_in();
+                if read.is_empty() {
+                    break;
+                }
+                data.extend_from_slice(&read);
+                assert!(usb_dev.poll(&mut [&mut hid]));
+            }
+
+            char::decode_utf16(
+                data[2..]
+                    .chunks_exact(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]])),
+            )
+            .collect::<core::result::Result<std::string::String, _>>()
+            .unwrap()
+        };
+
+        assert_eq!(get_string(index, LangID::EN_US), "Second");
+        assert_eq!(get_string(index, LangID::DE), "Zweite");
+        assert_eq!(get_string(index - 1, LangID::DE), "First");
+        assert_eq!(get_string(index - 2, LangID::DE), "Description");
     }
 
     #[test]
-    fn set_protocol() {
+    fn last_string_index() {
+        //usb-device allocates string indices from 4, up to 254
+        const STRINGS: &[InterfaceString] = &[InterfaceString::new("String"); 251];
+
         init_logging();
 
         let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
 
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .strings(STRINGS)
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let device = hid.device::<Interface<_, InBytes64, OutBytes64, ReportSingle>, _>();
+        assert_eq!(device.string_index(0), Ok(Some(4)));
+        assert_eq!(device.string_index(250), Ok(Some(254)));
+        assert_eq!(device.string_index(251), Ok(None));
+    }
+
+    #[test]
+    fn interface_subclass_and_protocol() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
         let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
 
         let mut hid = UsbHidClassBuilder::new()
             .add_device(
                 InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
                     .unwrap()
+                    .boot_device(InterfaceProtocol::Keyboard)
+                    .subclass(InterfaceSubClass::None)
+                    .build(),
+            )
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .protocol_raw(0xFF)
                     .build(),
             )
             .build(&usb_alloc);
@@ -663,36 +905,50 @@ mod test {
             .device_class(USB_CLASS_HID)
             .build();
 
-        // Set protocol to boot
-        manager
-            .host_write_setup(
-                &UsbRequest {
-                    direction: UsbDirection::In != UsbDirection::In,
-                    request_type: RequestType::Class as u8,
-                    recipient: Recipient::Interface as u8,
-                    request: HidRequest::SetProtocol.into(),
-                    value: HidProtocol::Boot as u16,
-                    index: 0x0,
-                    length: 0x0,
-                }
-                .pack()
-                .unwrap(),
+        let descriptor = manager.get_configuration_descriptor(&mut usb_dev, &mut [&mut hid]);
+        //bInterfaceClass, bInterfaceSubClass and bInterfaceProtocol of each interface
+        let interfaces: Vec<_> = descriptor
+            .windows(2)
+            .enumerate()
+            .filter(|(_, w)| *w == [9, 4])
+            .map(|(i, _)| <[u8; 3]>::try_from(&descriptor[i + 5..i + 8]).unwrap())
+            .collect();
+        assert_eq!(interfaces.len(), 2);
+        assert!(interfaces.contains(&[USB_CLASS_HID, 0, 1]));
+        assert!(interfaces.contains(&[USB_CLASS_HID, 0, 0xFF]));
+    }
+
+    #[test]
+    fn get_hid_descriptor_country_code() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .country_code(HidCountryCode::German)
+                    .build(),
             )
-            .unwrap();
+            .build(&usb_alloc);
 
-        assert!(usb_dev.poll(&mut [&mut hid]));
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
 
-        // Get protocol
+        // Get Hid descriptor
         manager
             .host_write_setup(
                 &UsbRequest {
                     direction: UsbDirection::In != UsbDirection::Out,
-                    request_type: RequestType::Class as u8,
+                    request_type: RequestType::Standard as u8,
                     recipient: Recipient::Interface as u8,
-                    request: HidRequest::GetProtocol.into(),
-                    value: 0x0,
+                    request: Request::GET_DESCRIPTOR,
+                    value: u16::from(u8::from(DescriptorType::Hid)) << 8,
                     index: 0x0,
-                    length: 0x1,
+                    length: 0x9,
                 }
                 .pack()
                 .unwrap(),
@@ -701,15 +957,339 @@ mod test {
 
         assert!(usb_dev.poll(&mut [&mut hid]));
 
-        // read and validate hid_protocol report
-        let data = &manager.host_read_in();
-        assert_eq!(
-            data,
+        let mut data = manager.host_read_in();
+        assert!(usb_dev.poll(&mut [&mut hid]));
+        data.extend_from_slice(&manager.host_read_in());
+
+        assert_eq!(data.len(), 9);
+        assert_eq!(data[1], u8::from(DescriptorType::Hid));
+        assert_eq!(data[4], u8::from(HidCountryCode::German));
+    }
+
+    #[test]
+    fn report_handler_feature_reports() {
+        #[derive(Default)]
+        struct FeatureHandler {
+            feature: RefCell<Vec<u8>>,
+        }
+
+        impl ReportHandler for FeatureHandler {
+            fn get_report(
+                &self,
+                report_type: ReportType,
+                report_id: u8,
+                data: &mut [u8],
+            ) -> Option<usize> {
+                if report_type == ReportType::Feature && report_id == 0x2 {
+                    let feature = self.feature.borrow();
+                    data[..feature.len()].copy_from_slice(&feature);
+                    Some(feature.len())
+                } else {
+                    None
+                }
+            }
+
+            fn set_report(&self, report_type: ReportType, report_id: u8, data: &[u8]) -> bool {
+                if report_type == ReportType::Feature && report_id == 0x2 {
+                    self.feature.replace(data.to_vec());
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+
+        init_logging();
+
+        let handler = FeatureHandler::default();
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .report_handler(&handler)
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        // Set feature report
+        manager
+            .host_write_setup(
+                &UsbRequest {
+                    direction: UsbDirection::In != UsbDirection::In,
+                    request_type: RequestType::Class as u8,
+                    recipient: Recipient::Interface as u8,
+                    request: HidRequest::SetReport.into(),
+                    value: u16::from(u8::from(ReportType::Feature)) << 8 | 0x2,
+                    index: 0x0,
+                    length: 0x3,
+                }
+                .pack()
+                .unwrap(),
+            )
+            .unwrap();
+        assert!(usb_dev.poll(&mut [&mut hid]));
+
+        manager.host_write_out(&[0xA, 0xB, 0xC]).unwrap();
+        assert!(usb_dev.poll(&mut [&mut hid]));
+
+        assert_eq!(*handler.feature.borrow(), [0xA, 0xB, 0xC]);
+        // consumed by the handler, not buffered for read_report
+        assert_eq!(
+            hid.device::<Interface<_, InBytes64, OutBytes64, ReportSingle>, _>()
+                .read_report(&mut [0; 64]),
+            Err(UsbError::WouldBlock)
+        );
+        manager.host_read_in();
+
+        // Get feature report
+        manager
+            .host_write_setup(
+                &UsbRequest {
+                    direction: UsbDirection::In != UsbDirection::Out,
+                    request_type: RequestType::Class as u8,
+                    recipient: Recipient::Interface as u8,
+                    request: HidRequest::GetReport.into(),
+                    value: u16::from(u8::from(ReportType::Feature)) << 8 | 0x2,
+                    index: 0x0,
+                    length: 0x3,
+                }
+                .pack()
+                .unwrap(),
+            )
+            .unwrap();
+        assert!(usb_dev.poll(&mut [&mut hid]));
+
+        assert_eq!(manager.host_read_in(), [0xA, 0xB, 0xC]);
+    }
+
+    #[test]
+    fn get_input_report_from_cache() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, Reports8>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        for report in [[0x1, 0xA], [0x2, 0xB], [0x1, 0xC]] {
+            hid.device::<Interface<_, _, _, _>, _>()
+                .write_report(&report)
+                .unwrap();
+            manager.host_read_in();
+        }
+
+        // Each request is served the latest report for the ID, not just the first
+        for (report_id, expected) in [(0x1, [0x1, 0xC]), (0x1, [0x1, 0xC]), (0x2, [0x2, 0xB])] {
+            manager
+                .host_write_setup(
+                    &UsbRequest {
+                        direction: UsbDirection::In != UsbDirection::Out,
+                        request_type: RequestType::Class as u8,
+                        recipient: Recipient::Interface as u8,
+                        request: HidRequest::GetReport.into(),
+                        value: u16::from(u8::from(ReportType::Input)) << 8 | report_id,
+                        index: 0x0,
+                        length: 0x2,
+                    }
+                    .pack()
+                    .unwrap(),
+                )
+                .unwrap();
+            assert!(usb_dev.poll(&mut [&mut hid]));
+
+            assert_eq!(manager.host_read_in(), expected);
+        }
+    }
+
+    #[test]
+    fn get_protocol_default_to_report() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        // Get protocol
+        manager
+            .host_write_setup(
+                &UsbRequest {
+                    direction: UsbDirection::In != UsbDirection::Out,
+                    request_type: RequestType::Class as u8,
+                    recipient: Recipient::Interface as u8,
+                    request: HidRequest::GetProtocol.into(),
+                    value: 0x0,
+                    index: 0x0,
+                    length: 0x1,
+                }
+                .pack()
+                .unwrap(),
+            )
+            .unwrap();
+
+        assert!(usb_dev.poll(&mut [&mut hid]));
+
+        // read and validate hid_protocol report
+        let data = manager.host_read_in();
+        assert_eq!(
+            data,
+            [HidProtocol::Report.into()],
+            "Expected protocol to be Report by default"
+        );
+    }
+
+    #[test]
+    fn set_protocol() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        // Set protocol to boot
+        manager
+            .host_write_setup(
+                &UsbRequest {
+                    direction: UsbDirection::In != UsbDirection::In,
+                    request_type: RequestType::Class as u8,
+                    recipient: Recipient::Interface as u8,
+                    request: HidRequest::SetProtocol.into(),
+                    value: HidProtocol::Boot as u16,
+                    index: 0x0,
+                    length: 0x0,
+                }
+                .pack()
+                .unwrap(),
+            )
+            .unwrap();
+
+        assert!(usb_dev.poll(&mut [&mut hid]));
+
+        // Get protocol
+        manager
+            .host_write_setup(
+                &UsbRequest {
+                    direction: UsbDirection::In != UsbDirection::Out,
+                    request_type: RequestType::Class as u8,
+                    recipient: Recipient::Interface as u8,
+                    request: HidRequest::GetProtocol.into(),
+                    value: 0x0,
+                    index: 0x0,
+                    length: 0x1,
+                }
+                .pack()
+                .unwrap(),
+            )
+            .unwrap();
+
+        assert!(usb_dev.poll(&mut [&mut hid]));
+
+        // read and validate hid_protocol report
+        let data = &manager.host_read_in();
+        assert_eq!(
+            data,
             &[HidProtocol::Boot.into()],
             "Expected protocol to be Boot"
         );
     }
 
+    #[cfg(feature = "alloc")]
+    #[test]
+    fn dynamic_devices_set_protocol() {
+        use crate::device::dynamic::DynamicUsbHidClassBuilder;
+
+        init_logging();
+
+        let manager = UsbTestManager::default();
+
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = DynamicUsbHidClassBuilder::new(&usb_alloc)
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build();
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        // Set protocol to boot on the second interface
+        manager
+            .host_write_setup(
+                &UsbRequest {
+                    direction: UsbDirection::In != UsbDirection::In,
+                    request_type: RequestType::Class as u8,
+                    recipient: Recipient::Interface as u8,
+                    request: HidRequest::SetProtocol.into(),
+                    value: HidProtocol::Boot as u16,
+                    index: 0x1,
+                    length: 0x0,
+                }
+                .pack()
+                .unwrap(),
+            )
+            .unwrap();
+
+        assert!(usb_dev.poll(&mut [&mut hid]));
+
+        assert_eq!(
+            hid.dynamic_device(0).unwrap().interface().get_protocol(),
+            HidProtocol::Report
+        );
+        assert_eq!(
+            hid.dynamic_device(1).unwrap().interface().get_protocol(),
+            HidProtocol::Boot
+        );
+        assert!(hid.dynamic_device(2).is_none());
+    }
+
     #[test]
     fn get_protocol_default_post_reset() {
         init_logging();
@@ -1132,14 +1712,2324 @@ mod test {
             [u8::try_from(IDLE_DEFAULT.ticks()).unwrap() / 4],
             "Unexpected global idle value"
         );
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(device.get_idle_duration(REPORT_ID), Some(IDLE_NEW));
+        assert_eq!(device.report_idle(REPORT_ID), Some(IDLE_NEW));
+        assert_eq!(device.get_idle_duration(REPORT_ID + 1), Some(IDLE_DEFAULT));
+        assert_eq!(device.get_idle_duration(0), Some(IDLE_DEFAULT));
+
+        // Setting global idle to indefinite applies to all reports
+        manager.set_idle(&mut usb_dev, &mut [&mut hid], 0, 0, 0);
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(device.get_idle_duration(REPORT_ID), None);
+        assert_eq!(device.get_idle_duration(0), None);
     }
 
     #[test]
-    fn set_report_idle_no_reports() {
-        const IDLE_DEFAULT: MillisDurationU32 = MillisDurationU32::millis(40);
-        const IDLE_NEW: MillisDurationU32 = MillisDurationU32::millis(88);
-        const REPORT_ID: u16 = 0x4;
-
+    fn tick_clock_resends_idle_report() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        // Boot keyboards default to a 500ms idle period
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let now = Cell::new(1_000u32);
+        let mut clock = MillisClock(|| now.get());
+
+        hid.device().write_report([Keyboard::A]).unwrap();
+        assert_eq!(manager.host_read_in(), [0, 0, 4, 0, 0, 0, 0, 0]);
+
+        // First reading only sets the reference time
+        hid.tick_clock(&mut clock).unwrap();
+
+        now.set(1_500);
+        hid.tick_clock(&mut clock).unwrap();
+        assert!(manager.host_read_in().is_empty());
+
+        now.set(1_501);
+        hid.tick_clock(&mut clock).unwrap();
+        assert_eq!(manager.host_read_in(), [0, 0, 4, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn sourced_device_writes_polled_reports() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let buttons = Cell::new(0u8);
+        let source = Debounce::new(
+            || {
+                Some(BootMouseReport {
+                    buttons: buttons.get(),
+                    ..Default::default()
+                })
+            },
+            MillisDurationU32::millis(2),
+        );
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(SourcedConfig::new(BootMouseConfig::default(), source))
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        hid.tick().unwrap();
+        assert!(manager.host_read_in().is_empty());
+        hid.tick().unwrap();
+        assert_eq!(manager.host_read_in(), [0, 0, 0]);
+
+        buttons.set(0x1);
+        hid.tick().unwrap();
+        hid.tick().unwrap();
+        // Change is only written once stable for 2ms
+        assert_eq!(manager.host_read_in(), [1, 0, 0]);
+        hid.tick().unwrap();
+        assert!(manager.host_read_in().is_empty());
+    }
+
+    #[test]
+    fn suppress_duplicate_reports() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .suppress_duplicate_reports()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+
+        assert_eq!(device.write_report(&[1, 2, 3]).unwrap(), 3);
+        assert_eq!(manager.host_read_in(), [1, 2, 3]);
+
+        assert_eq!(device.write_report(&[1, 2, 3]).unwrap(), 3);
+        assert!(manager.host_read_in().is_empty());
+
+        assert_eq!(device.write_report(&[1, 2, 4]).unwrap(), 3);
+        assert_eq!(manager.host_read_in(), [1, 2, 4]);
+    }
+
+    #[test]
+    fn duplicate_reports_repeated_at_idle_rate() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .suppress_duplicate_reports()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        manager.enumerate(&mut usb_dev, &mut [&mut hid]);
+        //8ms
+        manager.set_idle(&mut usb_dev, &mut [&mut hid], 0, 0, 2);
+
+        let device = hid.device::<Interface<_, _, _, ReportSingle>, _>();
+        device.write_report(&[1, 2, 3]).unwrap();
+        assert_eq!(manager.host_read_in(), [1, 2, 3]);
+
+        for _ in 0..7 {
+            hid.tick().unwrap();
+            let device = hid.device::<Interface<_, _, _, ReportSingle>, _>();
+            device.write_report(&[1, 2, 3]).unwrap();
+            assert!(manager.host_read_in().is_empty());
+        }
+
+        //Once the idle period has passed the duplicate is retransmitted
+        hid.tick().unwrap();
+        let device = hid.device::<Interface<_, _, _, ReportSingle>, _>();
+        device.write_report(&[1, 2, 3]).unwrap();
+        assert_eq!(manager.host_read_in(), [1, 2, 3]);
+        device.write_report(&[1, 2, 3]).unwrap();
+        assert!(manager.host_read_in().is_empty());
+        assert_eq!(device.stats().duplicates_suppressed, 8);
+    }
+
+    #[test]
+    fn unchanged_reports_follow_idle() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .send_unchanged_reports()
+                    .build(),
+            )
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, Reports8>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        //While the idle rate is 0, unchanged reports aren't sent
+        let device = hid.device::<Interface<_, _, _, Reports8>, _>();
+        assert_eq!(device.write_report(&[1, 5]).unwrap(), 2);
+        assert_eq!(manager.host_read_in(), [1, 5]);
+        assert_eq!(device.write_report(&[1, 5]).unwrap(), 2);
+        assert!(manager.host_read_in().is_empty());
+        assert_eq!(device.stats().duplicates_suppressed, 1);
+
+        //Reports are compared with the last report with the same report ID
+        assert_eq!(device.write_report(&[2, 5]).unwrap(), 2);
+        assert_eq!(manager.host_read_in(), [2, 5]);
+
+        //Unchanged reports are sent while the report has an idle rate
+        manager.set_idle(&mut usb_dev, &mut [&mut hid], 0, 1, 25);
+        let device = hid.device::<Interface<_, _, _, Reports8>, _>();
+        assert_eq!(device.write_report(&[1, 5]).unwrap(), 2);
+        assert_eq!(manager.host_read_in(), [1, 5]);
+        assert_eq!(device.write_report(&[2, 5]).unwrap(), 2);
+        assert!(manager.host_read_in().is_empty());
+
+        //Unless suppression is disabled
+        let device = hid.device::<Interface<_, _, _, ReportSingle>, _>();
+        assert_eq!(device.write_report(&[7]).unwrap(), 1);
+        assert_eq!(manager.host_read_in(), [7]);
+        assert_eq!(device.write_report(&[7]).unwrap(), 1);
+        assert_eq!(manager.host_read_in(), [7]);
+    }
+
+    #[test]
+    fn min_report_interval() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .min_report_interval(MillisDurationU32::millis(3))
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(device.write_report(&[1]).unwrap(), 1);
+        assert_eq!(manager.host_read_in(), [1]);
+
+        for _ in 0..2 {
+            let device = hid.device::<Interface<_, _, _, _>, _>();
+            assert_eq!(device.write_report(&[2]), Err(UsbHidError::WouldBlock));
+            hid.tick().unwrap();
+        }
+        assert!(manager.host_read_in().is_empty());
+
+        hid.tick().unwrap();
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(device.write_report(&[2]).unwrap(), 1);
+        assert_eq!(manager.host_read_in(), [2]);
+    }
+
+    #[test]
+    fn test_util_host_requests() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        manager.enumerate(&mut usb_dev, &mut [&mut hid]);
+        assert_eq!(usb_dev.state(), UsbDeviceState::Configured);
+        assert_eq!(manager.device_address(), 1);
+
+        manager.set_idle(&mut usb_dev, &mut [&mut hid], 0, 0, 0x10);
+        assert_eq!(
+            manager.get_idle(&mut usb_dev, &mut [&mut hid], 0, 0),
+            Some(0x10)
+        );
+
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0,
+            &[0x1],
+        );
+        assert_eq!(
+            hid.device().read_report(),
+            Ok(KeyboardLedsReport {
+                num_lock: true,
+                ..Default::default()
+            })
+        );
+
+        // Unsupported requests stall the control endpoint
+        assert!(manager
+            .get_report(&mut usb_dev, &mut [&mut hid], 0, ReportType::Feature, 0, 8)
+            .is_empty());
+        assert!(manager.control_stalled());
+    }
+
+    #[test]
+    fn keyboard_leds_changed() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let caps_lock = KeyboardLedsReport {
+            caps_lock: true,
+            ..Default::default()
+        };
+
+        hid.tick().unwrap();
+        assert_eq!(hid.device().leds_changed(), None);
+
+        //Over the control pipe
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0,
+            &[0x2],
+        );
+        hid.tick().unwrap();
+        assert_eq!(hid.device().leds_changed(), Some(caps_lock));
+        assert_eq!(hid.device().leds_changed(), None);
+        assert_eq!(hid.device().leds(), caps_lock);
+        //Reports received by tick are still returned by read_report
+        assert_eq!(hid.device().read_report(), Ok(caps_lock));
+        assert_eq!(hid.device().read_report(), Err(UsbError::WouldBlock));
+
+        //Over the out endpoint, only changes are reported
+        manager.host_write_out(&[0x2]).unwrap();
+        hid.tick().unwrap();
+        assert_eq!(hid.device().leds_changed(), None);
+        manager.host_write_out(&[0x0]).unwrap();
+        hid.tick().unwrap();
+        assert_eq!(
+            hid.device().leds_changed(),
+            Some(KeyboardLedsReport::default())
+        );
+    }
+
+    #[test]
+    fn precision_touchpad_feature_reports() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(PrecisionTouchpadConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        //Device capabilities, five contacts on a clickpad
+        let caps = manager.get_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x02,
+            2,
+        );
+        assert_eq!(caps, [0x02, 0x05]);
+
+        //Unknown feature reports aren't supported
+        manager.get_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x10,
+            2,
+        );
+        assert!(manager.control_stalled());
+
+        assert_eq!(hid.device().input_mode(), InputMode::Mouse);
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x04,
+            &[0x04, 0x03],
+        );
+        hid.tick().unwrap();
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x05,
+            &[0x05, 0x01],
+        );
+        hid.tick().unwrap();
+        assert_eq!(hid.device().input_mode(), InputMode::Touchpad);
+        assert!(hid.device().surface_enabled());
+        assert!(!hid.device().button_enabled());
+
+        //The host reads back the feature reports it has set
+        let input_mode = manager.get_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x04,
+            2,
+        );
+        assert_eq!(input_mode, [0x04, 0x03]);
+    }
+
+    #[test]
+    fn wireless_radio_led_changed() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(WirelessRadioControlsConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        hid.tick().unwrap();
+        assert_eq!(hid.device().led_changed(), None);
+
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0,
+            &[0x1],
+        );
+        hid.tick().unwrap();
+        let lit = WirelessRadioLedReport { led: true };
+        assert_eq!(hid.device().led_changed(), Some(lit));
+        assert_eq!(hid.device().led_changed(), None);
+        assert_eq!(hid.device().led(), lit);
+
+        //Unchanged LED state isn't reported again
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0,
+            &[0x1],
+        );
+        hid.tick().unwrap();
+        assert_eq!(hid.device().led_changed(), None);
+
+        hid.device()
+            .write_report(&WirelessRadioReport { button: true })
+            .unwrap();
+        assert_eq!(manager.host_read_in(), [0x01]);
+    }
+
+    #[test]
+    fn barcode_scanner_reports() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BarcodeScannerConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        //Split across two reports
+        let code = [b'7'; 70];
+        hid.device().scan(*b"]E0", &code).unwrap();
+        assert!(hid.device().is_sending());
+        assert_eq!(
+            hid.device().scan(*b"]E0", &code),
+            Err(UsbHidError::WouldBlock)
+        );
+        let first = manager.host_read_in();
+        assert_eq!(first.len(), 61);
+        assert_eq!(first[..4], [0x01, b']', b'E', b'0']);
+        assert_eq!(first[4..60], [b'7'; 56]);
+        assert_eq!(first[60], 0x01);
+
+        hid.tick().unwrap();
+        assert!(!hid.device().is_sending());
+        let second = manager.host_read_in();
+        assert_eq!(second[4..18], [b'7'; 14]);
+        assert_eq!(second[18..60], [0; 42]);
+        assert_eq!(second[60], 0x00);
+
+        //Trigger written by the host
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x02,
+            &[0x02, 0x01],
+        );
+        hid.tick().unwrap();
+        let trigger = BarcodeTriggerReport {
+            initiate_read: true,
+            ..Default::default()
+        };
+        assert_eq!(hid.device().trigger_changed(), Some(trigger));
+        assert_eq!(hid.device().trigger_changed(), None);
+
+        //Status read by the host
+        hid.device()
+            .set_status(BarcodeStatusReport {
+                trigger_pulled: true,
+                bar_code_present: true,
+            })
+            .unwrap();
+        let status = manager.get_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x03,
+            2,
+        );
+        assert_eq!(status, [0x03, 0x03]);
+    }
+
+    #[test]
+    fn card_reader_splits_tracks() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(CardReaderConfig::default())
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let track_2 = [b'2'; 40];
+        let track_3 = [b'3'; 100];
+        hid.device().swipe([&[], &track_2, &track_3]).unwrap();
+
+        let report = manager.host_read_in();
+        assert_eq!(report[..2], [0x02, 40]);
+        assert_eq!(report[2..42], [b'2'; 40]);
+        assert_eq!(report[42..], [0; 22]);
+
+        hid.tick().unwrap();
+        let report = manager.host_read_in();
+        assert_eq!(report[..2], [0x03, 100]);
+        assert_eq!(report[2..], [b'3'; 62]);
+
+        hid.tick().unwrap();
+        let report = manager.host_read_in();
+        assert_eq!(report[..2], [0x03, 100]);
+        assert_eq!(report[2..40], [b'3'; 38]);
+        assert!(!hid.device().is_sending());
+    }
+
+    #[test]
+    fn write_complete_after_host_read() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert!(!device.is_write_pending());
+        device.write_report(&[1]).unwrap();
+        assert!(device.is_write_pending());
+
+        //Not complete until the host has read the report
+        usb_dev.poll(&mut [&mut hid]);
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert!(device.is_write_pending());
+        assert!(!device.take_write_complete());
+
+        assert_eq!(manager.host_read_in(), [1]);
+        usb_dev.poll(&mut [&mut hid]);
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert!(!device.is_write_pending());
+        assert!(device.take_write_complete());
+        assert!(!device.take_write_complete());
+        assert_eq!(device.stats().reports_completed, 1);
+    }
+
+    #[test]
+    fn queued_reports_keep_up_with_polling() {
+        //Reports produced in bursts of 2 every other frame, read by the host once per frame
+        fn stream(queue: bool) -> Vec<u8> {
+            let manager = UsbTestManager::default();
+            let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+            let builder = InterfaceBuilder::<InBytes64, OutNone, ReportSingle>::new(&[]).unwrap();
+            let builder = if queue {
+                builder.queue_reports()
+            } else {
+                builder
+            };
+            let mut hid = UsbHidClassBuilder::new()
+                .add_device(builder.build())
+                .build(&usb_alloc);
+
+            let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+                .device_class(USB_CLASS_HID)
+                .build();
+
+            let mut received = Vec::new();
+            let mut sequence = 0_u8;
+            for frame in 0..100 {
+                if frame % 2 == 0 {
+                    for _ in 0..2 {
+                        let mut report = [0; 64];
+                        report[0] = sequence;
+                        sequence += 1;
+                        let _ = hid
+                            .device::<Interface<_, InBytes64, OutNone, ReportSingle>, _>()
+                            .write_report(&report);
+                    }
+                }
+                let report = manager.host_read_in();
+                if let Some(&n) = report.first() {
+                    assert_eq!(report.len(), 64);
+                    received.push(n);
+                }
+                usb_dev.poll(&mut [&mut hid]);
+            }
+            received
+        }
+
+        init_logging();
+
+        assert_eq!(stream(false).len(), 50);
+        assert_eq!(stream(true), (0..100).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn full_report_queue() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes8, OutNone, ReportSingle>::new(&[])
+                    .unwrap()
+                    .queue_reports()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let interface = hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>();
+        for n in 0..=crate::interface::REPORT_QUEUE_LEN {
+            interface.write_report(&[u8::try_from(n).unwrap()]).unwrap();
+        }
+        // The endpoint is busy and the queue is full
+        assert_eq!(interface.write_report(&[0xFF]), Err(UsbHidError::QueueFull));
+        assert_eq!(
+            interface.stats().reports_written,
+            u32::try_from(crate::interface::REPORT_QUEUE_LEN).unwrap() + 1
+        );
+
+        // Reading a report makes space in the queue
+        assert_eq!(manager.host_read_in(), [0]);
+        usb_dev.poll(&mut [&mut hid]);
+        hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>()
+            .write_report(&[0xFF])
+            .unwrap();
+    }
+
+    #[test]
+    fn submitted_buffer_returned_on_completion() {
+        init_logging();
+
+        let mut first = [1, 2, 3];
+        let mut second = [4, 5, 6];
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        device.submit_report(&mut first).unwrap();
+        let (error, second) = device.submit_report(&mut second).unwrap_err();
+        assert_eq!(error, UsbError::WouldBlock);
+        assert!(device.take_completed_buffer().is_none());
+
+        assert_eq!(manager.host_read_in(), [1, 2, 3]);
+        usb_dev.poll(&mut [&mut hid]);
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        let first = device.take_completed_buffer().unwrap();
+        assert_eq!(first, [1, 2, 3]);
+
+        device.submit_report(second).unwrap();
+        assert_eq!(manager.host_read_in(), [4, 5, 6]);
+        assert!(device.take_completed_buffer().is_none());
+
+        //A reset returns the buffer without waiting for the host
+        UsbClass::reset(&mut hid);
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(device.take_completed_buffer(), Some(&mut [4, 5, 6][..]));
+    }
+
+    #[test]
+    fn blocking_write_retries_until_read() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootMouseConfig::default())
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let now = Cell::new(0);
+        let mut clock = MillisClock(|| now.get());
+
+        let report = BootMouseReport {
+            buttons: 1,
+            ..Default::default()
+        };
+        hid.device::<BootMouse<_>, _>()
+            .write_report(&report)
+            .unwrap();
+
+        let mut attempts = 0;
+        hid.write_report_blocking::<BootMouse<_>, _, _>(
+            &BootMouseReport::default(),
+            &mut clock,
+            MillisDurationU32::millis(10),
+            |_| {
+                attempts += 1;
+                if attempts == 3 {
+                    assert_eq!(manager.host_read_in(), [1, 0, 0]);
+                }
+            },
+        )
+        .unwrap();
+        assert_eq!(attempts, 3);
+        assert_eq!(manager.host_read_in(), [0, 0, 0]);
+
+        hid.device::<BootMouse<_>, _>()
+            .write_report(&report)
+            .unwrap();
+        let result = hid.write_report_blocking::<BootMouse<_>, _, _>(
+            &BootMouseReport::default(),
+            &mut clock,
+            MillisDurationU32::millis(10),
+            |_| now.set(now.get() + 1),
+        );
+        assert_eq!(result, Err(UsbHidError::WouldBlock));
+        assert_eq!(now.get(), 10);
+    }
+
+    #[test]
+    fn report_descriptor_from_source() {
+        //Descriptor stored as separate parts, as if read from external flash
+        struct Parts<'a>(&'a [&'a [u8]]);
+
+        impl ReportDescriptorSource for Parts<'_> {
+            fn length(&self) -> usize {
+                self.0.iter().map(|p| p.len()).sum()
+            }
+
+            fn read(&self, offset: usize, buf: &mut [u8]) -> usize {
+                let bytes = self.0.iter().flat_map(|p| p.iter()).skip(offset);
+                buf.iter_mut().zip(bytes).map(|(b, &v)| *b = v).count()
+            }
+        }
+
+        init_logging();
+
+        let source = Parts(&[
+            &BOOT_MOUSE_REPORT_DESCRIPTOR[..10],
+            &BOOT_MOUSE_REPORT_DESCRIPTOR[10..],
+        ]);
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::from_source(&source)
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        assert_eq!(
+            manager.get_report_descriptor(&mut usb_dev, &mut [&mut hid], 0),
+            BOOT_MOUSE_REPORT_DESCRIPTOR
+        );
+        let interface = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(
+            usize::from(interface.report_descriptor_length()),
+            BOOT_MOUSE_REPORT_DESCRIPTOR.len()
+        );
+        assert_eq!(interface.report_length(ReportType::Input, 0), None);
+    }
+
+    #[test]
+    fn large_report_descriptor_from_source() {
+        //Descriptor generated as it's read, longer than the 128 byte control buffer
+        struct Generated {
+            reads: RefCell<Vec<(usize, usize)>>,
+        }
+
+        impl ReportDescriptorSource for Generated {
+            fn length(&self) -> usize {
+                300
+            }
+
+            #[allow(clippy::cast_possible_truncation)]
+            fn read(&self, offset: usize, buf: &mut [u8]) -> usize {
+                self.reads.borrow_mut().push((offset, buf.len()));
+                let len = buf.len().min(self.length() - offset);
+                for (i, b) in buf[..len].iter_mut().enumerate() {
+                    *b = (offset + i) as u8;
+                }
+                len
+            }
+        }
+
+        init_logging();
+
+        let source = Generated {
+            reads: RefCell::new(Vec::new()),
+        };
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::from_source(&source)
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let get_report_descriptor = |length| UsbRequest {
+            request_type: RequestType::Standard as u8,
+            recipient: Recipient::Interface as u8,
+            request: Request::GET_DESCRIPTOR,
+            value: u16::from(u8::from(DescriptorType::Report)) << 8,
+            ..UsbRequest::standard(UsbDirection::In, 0, 0, length)
+        };
+
+        //Only the requested part of the descriptor is read, in chunks
+        let data = manager.control_in(&mut usb_dev, &mut [&mut hid], get_report_descriptor(100));
+        assert_eq!(data, (0..100).collect::<Vec<u8>>());
+        assert_eq!(*source.reads.borrow(), [(0, 64), (64, 36)]);
+        assert!(!manager.control_stalled());
+
+        //The requested part must fit in the control buffer
+        source.reads.borrow_mut().clear();
+        let data = manager.control_in(&mut usb_dev, &mut [&mut hid], get_report_descriptor(0xFFFF));
+        assert!(data.is_empty());
+        assert!(manager.control_stalled());
+        assert!(source.reads.borrow().is_empty());
+    }
+
+    #[test]
+    fn reconfigure_interface_personality() {
+        init_logging();
+
+        let raw_descriptor = [0x06, 0x00, 0xFF, 0x09, 0x01, 0xA1, 0x01, 0xC0];
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(
+                    BOOT_MOUSE_REPORT_DESCRIPTOR,
+                )
+                .unwrap()
+                .in_endpoint(MillisDurationU32::millis(10))
+                .unwrap()
+                .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let interface = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(
+            interface.reconfigure(
+                InterfaceBuilder::new(&raw_descriptor)
+                    .unwrap()
+                    .in_endpoint(MillisDurationU32::millis(1))
+                    .unwrap()
+                    .build()
+            ),
+            Err(UsbHidBuilderError::AllocationMismatch)
+        );
+        interface
+            .reconfigure(
+                InterfaceBuilder::new(&raw_descriptor)
+                    .unwrap()
+                    .in_endpoint(MillisDurationU32::millis(10))
+                    .unwrap()
+                    .build(),
+            )
+            .unwrap();
+        assert_eq!(interface.stats().resets, 1);
+
+        let mut detached = false;
+        reenumerate(&mut usb_dev, || detached = true).unwrap();
+        assert!(detached);
+
+        assert_eq!(
+            manager.get_report_descriptor(&mut usb_dev, &mut [&mut hid], 0),
+            raw_descriptor
+        );
+    }
+
+    #[test]
+    fn composite_with_other_class() {
+        //Vendor class sharing the device, allocating its own interface and string
+        struct VendorClass {
+            interface: InterfaceNumber,
+            string: StringIndex,
+        }
+
+        impl<B: UsbBus> UsbClass<B> for VendorClass {
+            fn get_configuration_descriptors(&self, writer: &mut DescriptorWriter) -> Result<()> {
+                writer.iad(self.interface, 1, 0xFF, 0, 0, Some(self.string))?;
+                writer.interface(self.interface, 0xFF, 0, 0)
+            }
+
+            fn get_string(&self, index: StringIndex, _lang_id: LangID) -> Option<&str> {
+                (index == self.string).then_some("Vendor")
+            }
+        }
+
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut vendor = VendorClass {
+            interface: usb_alloc.interface(),
+            string: usb_alloc.string(),
+        };
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .boot_device(InterfaceProtocol::Mouse)
+                    .description("HID")
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .composite_with_iads()
+            .build();
+
+        let config =
+            manager.get_configuration_descriptor(&mut usb_dev, &mut [&mut vendor, &mut hid]);
+        let vendor_iad = [8, 0x0B, 0, 1, 0xFF, 0, 0, 4];
+        let hid_iad = [8, 0x0B, 1, 1, 0x03, 0x01, 0x02, 5];
+        let hid_interface = [9, 0x04, 1, 0, 1, 0x03, 0x01, 0x02, 5];
+        let position = |d: &[u8]| config.windows(d.len()).position(|w| w == d).unwrap();
+        assert!(position(&vendor_iad) < position(&hid_iad));
+        assert_eq!(position(&hid_iad) + hid_iad.len(), position(&hid_interface));
+
+        let mut string_descriptor = |index| {
+            manager.get_descriptor(
+                &mut usb_dev,
+                &mut [&mut vendor, &mut hid],
+                usb_device::descriptor::descriptor_type::STRING,
+                index,
+            )
+        };
+        assert_eq!(
+            string_descriptor(4),
+            [14, 3, b'V', 0, b'e', 0, b'n', 0, b'd', 0, b'o', 0, b'r', 0]
+        );
+        assert_eq!(string_descriptor(5), [8, 3, b'H', 0, b'I', 0, b'D', 0]);
+    }
+
+    #[test]
+    fn boot_only_rejects_report_protocol() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .boot_device(InterfaceProtocol::Keyboard)
+                    .boot_only()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        assert_eq!(
+            manager.get_protocol(&mut usb_dev, &mut [&mut hid], 0),
+            Some(HidProtocol::Boot)
+        );
+
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], 0, HidProtocol::Report);
+        assert!(manager.control_stalled());
+        assert_eq!(
+            manager.get_protocol(&mut usb_dev, &mut [&mut hid], 0),
+            Some(HidProtocol::Boot)
+        );
+
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], 0, HidProtocol::Boot);
+        assert!(!manager.control_stalled());
+
+        UsbClass::reset(&mut hid);
+        let interface = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(interface.protocol(), HidProtocol::Boot);
+    }
+
+    #[test]
+    fn dual_mode_keyboard_follows_protocol() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(DualModeKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        //Report protocol, NKRO report
+        let keys = [Keyboard::LeftShift, Keyboard::A];
+        hid.device().write_report(keys).unwrap();
+        let mut expected = [0; 19];
+        expected[..2].copy_from_slice(&[2, 0x02]);
+        expected[2] = 1 << 4;
+        assert_eq!(manager.host_read_in(), expected);
+        assert!(matches!(
+            hid.device().write_report(keys),
+            Err(UsbHidError::Duplicate)
+        ));
+
+        //The last report is sent again as a boot report once the host selects the boot protocol
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], 0, HidProtocol::Boot);
+        hid.tick().unwrap();
+        assert_eq!(manager.host_read_in(), [0x02, 0, 0x04, 0, 0, 0, 0, 0]);
+        //Boot reports are requested with report ID 0
+        assert_eq!(
+            manager.get_report(&mut usb_dev, &mut [&mut hid], 0, ReportType::Input, 0, 8),
+            [0x02, 0, 0x04, 0, 0, 0, 0, 0]
+        );
+
+        //LED reports don't have a report ID in the boot protocol
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0,
+            &[0x02],
+        );
+        hid.tick().unwrap();
+        assert!(hid.device().leds().caps_lock);
+
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], 0, HidProtocol::Report);
+        hid.device().set_rollover(Rollover::SixKey);
+        hid.tick().unwrap();
+        assert_eq!(manager.host_read_in(), [1, 0x02, 0, 0x04, 0, 0, 0, 0, 0]);
+
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            1,
+            &[1, 0x01],
+        );
+        hid.tick().unwrap();
+        let leds = hid.device().leds();
+        assert!(leds.num_lock && !leds.caps_lock);
+    }
+
+    #[test]
+    #[should_panic(expected = "input reports are longer than the in endpoint")]
+    fn validate_rejects_reports_longer_than_endpoint() {
+        use crate::device::keyboard::NKRO_BOOT_KEYBOARD_REPORT_DESCRIPTOR;
+        use crate::interface::InBytes16;
+
+        let _ = InterfaceBuilder::<InBytes16, OutBytes64, ReportSingle>::new(
+            NKRO_BOOT_KEYBOARD_REPORT_DESCRIPTOR,
+        )
+        .unwrap()
+        .validate();
+    }
+
+    #[test]
+    fn hybrid_mouse_writes_both_pointers() {
+        use crate::device::mouse::{AbsoluteWheelMouseReport, HybridMouseConfig, WheelMouseReport};
+
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(HybridMouseConfig::default())
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        hid.device()
+            .write_absolute(&AbsoluteWheelMouseReport {
+                buttons: 0x1,
+                x: 0x1234,
+                y: 0x4000,
+                wheel: 0,
+            })
+            .unwrap();
+        assert_eq!(manager.host_read_in(), [2, 0x1, 0x34, 0x12, 0, 0x40, 0]);
+
+        hid.device()
+            .write_relative(&WheelMouseReport {
+                x: -2,
+                y: 3,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(manager.host_read_in(), [1, 0, 0xFE, 3, 0, 0]);
+    }
+
+    #[test]
+    fn haptic_controller_commands() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(HapticControllerConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        //Waveform list of click, buzz, rumble, press and release, followed by their durations
+        let list = manager.get_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x01,
+            21,
+        );
+        assert_eq!(
+            list,
+            [
+                0x01, 0x03, 0x10, 0x04, 0x10, 0x05, 0x10, 0x06, 0x10, 0x07, 0x10, 10, 0, 0, 0, 0,
+                0, 10, 0, 10, 0
+            ]
+        );
+
+        //Auto trigger the click waveform at full intensity from button 1
+        let auto_trigger = manager.get_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x02,
+            8,
+        );
+        assert_eq!(
+            auto_trigger,
+            [0x02, 0x03, 0x00, 0x01, 0x00, 0x09, 0x00, 100]
+        );
+
+        //Manual trigger of ordinal 5, rumble, at 50% repeated twice every 200ms
+        assert_eq!(hid.device().take_command(), None);
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0x03,
+            &[0x03, 0x05, 0x00, 50, 2, 200, 0],
+        );
+        hid.tick().unwrap();
+        assert_eq!(
+            hid.device().take_command(),
+            Some(HapticCommand {
+                waveform: Haptics::WaveformRumbleContinuous.id(),
+                intensity: 50,
+                repeat_count: 2,
+                retrigger_period: 200,
+            })
+        );
+        assert_eq!(hid.device().take_command(), None);
+
+        //Ordinal 2 stops the waveform, unknown ordinals are ignored
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0x03,
+            &[0x03, 0x09, 0x00, 50, 0, 0, 0],
+        );
+        hid.tick().unwrap();
+        assert_eq!(hid.device().take_command(), None);
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0x03,
+            &[0x03, 0x02, 0x00, 0, 0, 0, 0],
+        );
+        hid.tick().unwrap();
+        assert_eq!(
+            hid.device().take_command().map(|c| c.waveform),
+            Some(Haptics::WaveformStop.id())
+        );
+
+        //The host selects the press waveform at 30% for the auto trigger
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Feature,
+            0x02,
+            &[0x02, 0x06, 0x00, 0x01, 0x00, 0x09, 0x00, 30],
+        );
+        hid.tick().unwrap();
+        assert_eq!(hid.device().auto_trigger(), Haptics::WaveformPress.id());
+        assert_eq!(hid.device().auto_trigger_intensity(), 30);
+    }
+
+    #[test]
+    fn keyboard_get_report_serves_key_state() {
+        init_logging();
+
+        let state = RefCell::new(KeyboardState::new(Rollover::NKey));
+        let boot_reports = KeyStateReports::boot(&state);
+        let dual_mode_reports = KeyStateReports::dual_mode(&state);
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            //Devices are allocated in reverse order, the boot keyboard is interface 0
+            .add_device(DualModeKeyboardConfig::new(
+                InterfaceBuilder::new(DUAL_MODE_KEYBOARD_REPORT_DESCRIPTOR)
+                    .unwrap()
+                    .boot_device(InterfaceProtocol::Keyboard)
+                    .report_handler(&dual_mode_reports)
+                    .in_endpoint(MillisDurationU32::millis(10))
+                    .unwrap()
+                    .without_out_endpoint()
+                    .build(),
+            ))
+            .add_device(BootKeyboardConfig::new(ManagedIdleInterfaceConfig::new(
+                InterfaceBuilder::new(BOOT_KEYBOARD_REPORT_DESCRIPTOR)
+                    .unwrap()
+                    .boot_device(InterfaceProtocol::Keyboard)
+                    .report_handler(&boot_reports)
+                    .in_endpoint(MillisDurationU32::millis(10))
+                    .unwrap()
+                    .without_out_endpoint()
+                    .build(),
+            )))
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        //Keys held without any report being written
+        state.borrow_mut().press(Keyboard::LeftShift);
+        state.borrow_mut().press(Keyboard::A);
+        let boot = manager.get_report(&mut usb_dev, &mut [&mut hid], 0, ReportType::Input, 0, 8);
+        assert_eq!(boot, [0x02, 0, 0x04, 0, 0, 0, 0, 0]);
+
+        let six_key = manager.get_report(&mut usb_dev, &mut [&mut hid], 1, ReportType::Input, 1, 9);
+        assert_eq!(six_key, [0x01, 0x02, 0, 0x04, 0, 0, 0, 0, 0]);
+
+        state.borrow_mut().release(Keyboard::LeftShift);
+        let nkro = manager.get_report(&mut usb_dev, &mut [&mut hid], 1, ReportType::Input, 2, 19);
+        assert_eq!(nkro[..3], [0x02, 0x00, 0x10]);
+        assert_eq!(nkro.len(), 19);
+
+        //The last report written is sent while the state is being updated
+        hid.device::<BootKeyboard<_>, _>()
+            .write_report([Keyboard::B])
+            .unwrap();
+        let _guard = state.borrow_mut();
+        let written = manager.get_report(&mut usb_dev, &mut [&mut hid], 0, ReportType::Input, 0, 8);
+        assert_eq!(written, [0, 0, 0x05, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn vendor_control_requests() {
+        #[derive(Default)]
+        struct RemapHandler {
+            map: RefCell<Vec<u8>>,
+        }
+
+        impl ControlRequestHandler for RemapHandler {
+            fn control_in(&self, request: &Request, data: &mut [u8]) -> Option<usize> {
+                (request.request == 0x01).then(|| {
+                    let map = self.map.borrow();
+                    let n = map.len().min(data.len());
+                    data[..n].copy_from_slice(&map[..n]);
+                    n
+                })
+            }
+
+            fn control_out(&self, request: &Request, data: &[u8]) -> bool {
+                if request.request == 0x02 {
+                    self.map.replace(data.to_vec());
+                    true
+                } else {
+                    false
+                }
+            }
+        }
+
+        init_logging();
+
+        let handler = RemapHandler::default();
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes8, OutNone, ReportSingle>::new(&[])
+                    .unwrap()
+                    .control_handler(&handler)
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        manager.control_out(
+            &mut usb_dev,
+            &mut [&mut hid],
+            UsbRequest::vendor(UsbDirection::Out, 0x02, 0, 0, 0),
+            &[0x04, 0x05, 0x06],
+        );
+        assert!(!manager.control_stalled());
+        assert_eq!(*handler.map.borrow(), [0x04, 0x05, 0x06]);
+
+        let data = manager.control_in(
+            &mut usb_dev,
+            &mut [&mut hid],
+            UsbRequest::vendor(UsbDirection::In, 0x01, 0, 0, 8),
+        );
+        assert_eq!(data, [0x04, 0x05, 0x06]);
+
+        // Unhandled vendor and class requests are stalled
+        manager.control_in(
+            &mut usb_dev,
+            &mut [&mut hid],
+            UsbRequest::vendor(UsbDirection::In, 0x03, 0, 0, 8),
+        );
+        assert!(manager.control_stalled());
+        manager.control_in(
+            &mut usb_dev,
+            &mut [&mut hid],
+            UsbRequest {
+                request_type: RequestType::Class as u8,
+                ..UsbRequest::vendor(UsbDirection::In, 0x05, 0, 0, 8)
+            },
+        );
+        assert!(manager.control_stalled());
+        assert_eq!(
+            hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>()
+                .stats()
+                .control_requests,
+            4
+        );
+    }
+
+    #[test]
+    fn wake_on_input() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes8, OutNone, ReportSingle>::new(&[])
+                    .unwrap()
+                    .wake_on_input()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .supports_remote_wakeup(true)
+            .build();
+
+        manager.enumerate(&mut usb_dev, &mut [&mut hid]);
+        manager.enable_remote_wakeup(&mut usb_dev, &mut [&mut hid]);
+        assert!(usb_dev.remote_wakeup_enabled());
+
+        manager.host_suspend();
+        usb_dev.poll(&mut [&mut hid]);
+        assert_eq!(usb_dev.state(), UsbDeviceState::Suspend);
+        hid.update_suspend(&usb_dev, || panic!("unexpected remote wakeup"));
+
+        let interface = hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>();
+        assert!(interface.is_suspended());
+        interface.write_report(&[0x01]).unwrap();
+        interface.write_report(&[0x02]).unwrap();
+        assert!(manager.host_read_in().is_empty());
+
+        let mut wakeups = 0;
+        hid.update_suspend(&usb_dev, || wakeups += 1);
+        hid.update_suspend(&usb_dev, || wakeups += 1);
+        assert_eq!(wakeups, 1);
+
+        // The last report written while suspended is sent on resume
+        manager.host_resume();
+        usb_dev.poll(&mut [&mut hid]);
+        assert_eq!(usb_dev.state(), UsbDeviceState::Configured);
+        hid.update_suspend(&usb_dev, || panic!("unexpected remote wakeup"));
+        assert_eq!(manager.host_read_in(), [0x02]);
+    }
+
+    #[test]
+    fn busy_endpoint_would_block() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes8, OutNone, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .add_device(
+                InterfaceBuilder::<InBytes16, OutNone, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        manager.enumerate(&mut usb_dev, &mut [&mut hid]);
+
+        // The test bus shares one IN buffer between endpoints, so the second interface's first
+        // report finds its endpoint busy and must not be reported as sent
+        hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>()
+            .write_report(&[0x01])
+            .unwrap();
+        let interface = hid.device::<Interface<_, InBytes16, OutNone, ReportSingle>, _>();
+        assert_eq!(
+            interface.write_report(&[0x02]),
+            Err(UsbHidError::WouldBlock)
+        );
+        assert_eq!(interface.stats().reports_written, 0);
+
+        assert_eq!(manager.host_read_in(), [0x01]);
+        usb_dev.poll(&mut [&mut hid]);
+        hid.device::<Interface<_, InBytes16, OutNone, ReportSingle>, _>()
+            .write_report(&[0x02])
+            .unwrap();
+        assert_eq!(manager.host_read_in(), [0x02]);
+    }
+
+    #[test]
+    fn inactive_interface() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes8, OutNone, ReportSingle>::new(&[])
+                    .unwrap()
+                    .inactive()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        manager.enumerate(&mut usb_dev, &mut [&mut hid]);
+        assert!(!manager
+            .get_configuration_descriptor(&mut usb_dev, &mut [&mut hid])
+            .is_empty());
+
+        let interface = hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>();
+        assert!(!interface.is_active());
+        assert_eq!(
+            interface.write_report(&[0x01]),
+            Err(UsbHidError::WouldBlock)
+        );
+        assert!(manager.host_read_in().is_empty());
+
+        // Idle and protocol requests are still handled, report requests are stalled
+        manager.set_idle(&mut usb_dev, &mut [&mut hid], 0, 0, 25);
+        assert_eq!(
+            manager.get_idle(&mut usb_dev, &mut [&mut hid], 0, 0),
+            Some(25)
+        );
+        manager.get_report(&mut usb_dev, &mut [&mut hid], 0, ReportType::Input, 0, 1);
+        assert!(manager.control_stalled());
+
+        hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>()
+            .set_active(true);
+        UsbClass::reset(&mut hid);
+
+        let interface = hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>();
+        assert!(interface.is_active());
+        interface.write_report(&[0x02]).unwrap();
+        assert_eq!(manager.host_read_in(), [0x02]);
+        assert_eq!(
+            manager.get_report(&mut usb_dev, &mut [&mut hid], 0, ReportType::Input, 0, 1),
+            [0x02]
+        );
+    }
+
+    #[test]
+    fn endpoint_halt_events() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        manager.enumerate(&mut usb_dev, &mut [&mut hid]);
+        let in_ep = hid.device().interface().in_endpoint_info().address;
+        let out_ep = hid
+            .device()
+            .interface()
+            .out_endpoint_info()
+            .unwrap()
+            .address;
+
+        // Halted by the device
+        hid.device().interface().stall(UsbDirection::Out);
+        assert!(manager.is_stalled(out_ep));
+        hid.device().interface().unstall(UsbDirection::Out);
+        assert!(!manager.is_stalled(out_ep));
+        assert_eq!(
+            hid.take_event(),
+            Some((0, InterfaceEvent::EndpointHalted(out_ep)))
+        );
+        assert_eq!(
+            hid.take_event(),
+            Some((0, InterfaceEvent::EndpointHaltCleared(out_ep)))
+        );
+
+        // Halted by the host while a report is being written
+        let report = [0, 0, Keyboard::A.into(), 0, 0, 0, 0, 0];
+        hid.device().interface().write_report(&report).unwrap();
+        manager.set_endpoint_halt(&mut usb_dev, &mut [&mut hid], in_ep, true);
+        assert!(manager.is_stalled(in_ep));
+        assert!(hid.device().interface().is_stalled(UsbDirection::In));
+        assert_eq!(
+            hid.device().interface().write_report(&[0; 8]),
+            Err(UsbHidError::WouldBlock)
+        );
+
+        // Clearing the halt discards the report being written
+        manager.set_endpoint_halt(&mut usb_dev, &mut [&mut hid], in_ep, false);
+        assert!(!manager.is_stalled(in_ep));
+        assert!(!hid.device().interface().is_write_pending());
+        manager.host_read_in();
+        hid.device().interface().write_report(&[0; 8]).unwrap();
+        assert_eq!(manager.host_read_in(), [0; 8]);
+        assert_eq!(
+            hid.take_event(),
+            Some((0, InterfaceEvent::EndpointHalted(in_ep)))
+        );
+        assert_eq!(
+            hid.take_event(),
+            Some((0, InterfaceEvent::EndpointHaltCleared(in_ep)))
+        );
+        assert_eq!(hid.take_event(), None);
+    }
+
+    #[test]
+    fn malformed_set_report_stalled() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        // The LED report is a single byte
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0,
+            &[0x2, 0x0],
+        );
+        assert!(manager.control_stalled());
+        assert_eq!(
+            hid.take_event(),
+            Some((
+                0,
+                InterfaceEvent::ReportRejected {
+                    report_type: ReportType::Output,
+                    report_id: 0
+                }
+            ))
+        );
+        hid.tick().unwrap();
+        assert_eq!(hid.device().leds_changed(), None);
+
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0,
+            &[0x2],
+        );
+        assert!(!manager.control_stalled());
+        hid.tick().unwrap();
+        assert!(hid.device().leds_changed().is_some_and(|l| l.caps_lock));
+        assert_eq!(hid.device().interface().stats().reports_rejected, 1);
+    }
+
+    #[test]
+    fn protocol_and_idle_events() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::default())
+            .add_device(BootMouseConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let keyboard = u8::from(hid.device::<BootKeyboard<_>, _>().interface().id());
+        let mouse = u8::from(hid.device::<BootMouse<_>, _>().interface().id());
+
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], mouse, HidProtocol::Boot);
+        manager.set_idle(&mut usb_dev, &mut [&mut hid], keyboard, 0, 0x10);
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], mouse, HidProtocol::Boot);
+
+        assert_eq!(
+            hid.device::<BootKeyboard<_>, _>().interface().take_event(),
+            Some(InterfaceEvent::SetIdle {
+                report_id: 0,
+                idle: MillisDurationU32::millis(64)
+            })
+        );
+        assert_eq!(
+            hid.take_event(),
+            Some((mouse, InterfaceEvent::SetProtocol(HidProtocol::Boot)))
+        );
+        assert_eq!(
+            hid.take_event(),
+            Some((mouse, InterfaceEvent::SetProtocol(HidProtocol::Boot)))
+        );
+        assert_eq!(hid.take_event(), None);
+
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], mouse, HidProtocol::Report);
+        hid.reset();
+        assert_eq!(hid.take_event(), None);
+    }
+
+    #[test]
+    fn interface_stats() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .suppress_duplicate_reports()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        device.write_report(&[1]).unwrap();
+        device.write_report(&[1]).unwrap();
+        assert_eq!(device.write_report(&[2]), Err(UsbHidError::WouldBlock));
+        manager.host_read_in();
+
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], 0, HidProtocol::Boot);
+        manager.set_protocol(&mut usb_dev, &mut [&mut hid], 0, HidProtocol::Boot);
+        manager.get_idle(&mut usb_dev, &mut [&mut hid], 0, 0);
+        manager.set_report(
+            &mut usb_dev,
+            &mut [&mut hid],
+            0,
+            ReportType::Output,
+            0,
+            &[3],
+        );
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(device.read_report(&mut [0; 64]), Ok(1));
+        assert_eq!(
+            device.stats(),
+            InterfaceStats {
+                reports_written: 1,
+                reports_blocked: 1,
+                duplicates_suppressed: 1,
+                out_reports_read: 1,
+                set_report_requests: 1,
+                get_idle_requests: 1,
+                set_protocol_requests: 2,
+                protocol_changes: 1,
+                reports_completed: 1,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn bus_reset_counted_once() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .add_device(BootKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        UsbClass::reset(&mut hid);
+
+        let device = hid.device::<Interface<_, InBytes64, OutBytes64, ReportSingle>, _>();
+        assert_eq!(device.stats().resets, 1);
+        let keyboard = hid.device::<BootKeyboard<_>, _>();
+        assert_eq!(keyboard.interface().stats().resets, 1);
+    }
+
+    #[test]
+    fn alternate_settings() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .in_endpoint(MillisDurationU32::millis(10))
+                    .unwrap()
+                    .with_out_endpoint(MillisDurationU32::millis(10))
+                    .unwrap()
+                    .alternate_setting(MillisDurationU32::millis(1))
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        manager.enumerate(&mut usb_dev, &mut [&mut hid]);
+
+        let descriptor = manager.get_configuration_descriptor(&mut usb_dev, &mut [&mut hid]);
+        //Configuration, then interface, HID, in and out endpoint descriptors for each setting
+        assert_eq!(descriptor.len(), 9 + 2 * (9 + 9 + 7 + 7));
+        assert_eq!(&descriptor[9..14], [9, 4, 0, 0, 2]);
+        assert_eq!(&descriptor[41..46], [9, 4, 0, 1, 2]);
+        //bInterval of each setting's in endpoint
+        assert_eq!(descriptor[18 + 6 + 9], 10);
+        assert_eq!(descriptor[50 + 6 + 9], 1);
+        //Alternate setting endpoints are distinct
+        assert_ne!(descriptor[18 + 2 + 9], descriptor[50 + 2 + 9]);
+
+        assert_eq!(
+            manager.get_interface(&mut usb_dev, &mut [&mut hid], 0),
+            Some(0)
+        );
+        manager.set_interface(&mut usb_dev, &mut [&mut hid], 0, 1);
+        assert!(!manager.control_stalled());
+        assert_eq!(
+            manager.get_interface(&mut usb_dev, &mut [&mut hid], 0),
+            Some(1)
+        );
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(device.alt_setting(), 1);
+        assert_eq!(device.take_alt_setting_change(), Some(1));
+        assert_eq!(device.take_alt_setting_change(), None);
+        assert_eq!(device.stats().alt_setting_changes, 1);
+
+        manager.set_interface(&mut usb_dev, &mut [&mut hid], 0, 2);
+        assert!(manager.control_stalled());
+        assert_eq!(hid.device::<Interface<_, _, _, _>, _>().alt_setting(), 1);
+
+        hid.reset();
+        assert_eq!(hid.device::<Interface<_, _, _, _>, _>().alt_setting(), 0);
+    }
+
+    #[test]
+    fn interface_introspection() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let interface = hid.device().interface();
+        assert_eq!(
+            usize::from(interface.report_descriptor_length()),
+            BOOT_KEYBOARD_REPORT_DESCRIPTOR.len()
+        );
+        assert_eq!(interface.report_length(ReportType::Input, 0), Some(8));
+        assert_eq!(interface.report_length(ReportType::Output, 0), Some(1));
+        assert_eq!(
+            interface.in_endpoint_info(),
+            EndpointInfo {
+                address: EndpointAddress::from_parts(1, UsbDirection::In),
+                max_packet_size: 8,
+                interval: 10,
+            }
+        );
+        assert_eq!(
+            interface.out_endpoint_info(),
+            Some(EndpointInfo {
+                address: EndpointAddress::from_parts(2, UsbDirection::Out),
+                max_packet_size: 8,
+                interval: 100,
+            })
+        );
+    }
+
+    #[test]
+    fn high_speed_poll_intervals() {
+        use fugit::MicrosDurationU32;
+
+        init_logging();
+
+        let builder = || InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[]).unwrap();
+        for micros in [0, 100, 375, 5_000, 8_192_000] {
+            assert_eq!(
+                builder()
+                    .in_endpoint_high_speed(MicrosDurationU32::micros(micros))
+                    .err(),
+                Some(UsbHidBuilderError::ValueOverflow)
+            );
+        }
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                builder()
+                    .in_endpoint_high_speed(MicrosDurationU32::micros(125))
+                    .unwrap()
+                    .with_out_endpoint_high_speed(MicrosDurationU32::micros(4_096_000))
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let interface = hid.device::<Interface<_, InBytes64, OutBytes64, ReportSingle>, _>();
+        // Every microframe, and every 2^15 microframes
+        assert_eq!(interface.in_endpoint_info().interval, 1);
+        assert_eq!(interface.out_endpoint_info().unwrap().interval, 16);
+    }
+
+    #[test]
+    fn write_report_bytes() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes64, OutBytes64, ReportSingle>::new(&[])
+                    .unwrap()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let device = hid.device::<Interface<_, _, _, _>, _>();
+        assert_eq!(device.write_report_bytes(&[0xAA; 64]).unwrap(), 64);
+        assert_eq!(manager.host_read_in(), [0xAA; 64]);
+
+        ReportWriter::write_report(device, &[1, 2]).unwrap();
+        assert_eq!(manager.host_read_in(), [1, 2]);
+
+        device
+            .write_serialized::<PackedStructSerializer, _>(&BootMouseReport {
+                buttons: 0x1,
+                x: 2,
+                y: 3,
+            })
+            .unwrap();
+        assert_eq!(manager.host_read_in(), [0x1, 2, 3]);
+    }
+
+    #[test]
+    fn switch_gamepad_subcommands() {
+        struct Rumble(Option<[u8; 2]>);
+
+        impl SubcommandHandler for Rumble {
+            fn subcommand(&mut self, id: u8, payload: &[u8]) -> u8 {
+                match (id, payload) {
+                    (0x10, &[left, right, ..]) => {
+                        self.0 = Some([left, right]);
+                        0x90
+                    }
+                    _ => self.unknown_subcommand(id, payload),
+                }
+            }
+        }
+
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(SwitchGamepadConfig::default())
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x0F0D, 0x0092))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let mut rumble = Rumble(None);
+        let device = hid.device();
+        assert_eq!(device.dispatch_subcommand(&mut rumble), Ok(None));
+        device
+            .write_report(&SwitchGamepadReport::default())
+            .unwrap();
+        assert_eq!(
+            manager.host_read_in(),
+            [0, 0, HAT_CENTERED, 0x80, 0x80, 0x80, 0x80, 0]
+        );
+
+        manager
+            .host_write_out(&[0x10, 3, 4, 0, 0, 0, 0, 0])
+            .unwrap();
+        assert_eq!(device.dispatch_subcommand(&mut rumble), Ok(Some(0x10)));
+        assert_eq!(rumble.0, Some([3, 4]));
+        let pressed = SwitchGamepadReport {
+            buttons: 0x1,
+            ..Default::default()
+        };
+        device.write_report(&pressed).unwrap();
+        assert_eq!(
+            manager.host_read_in(),
+            [1, 0, HAT_CENTERED, 0x80, 0x80, 0x80, 0x80, 0x90]
+        );
+
+        //Unknown subcommands are acknowledged by echoing their ID
+        manager
+            .host_write_out(&[0x33, 0, 0, 0, 0, 0, 0, 0])
+            .unwrap();
+        assert_eq!(device.dispatch_subcommand(&mut rumble), Ok(Some(0x33)));
+        device.write_report(&pressed).unwrap();
+        assert_eq!(manager.host_read_in()[7], 0x33);
+
+        manager
+            .host_write_out(&[0x40, 0, 0, 0, 0, 0, 0, 0])
+            .unwrap();
+        assert_eq!(
+            device.dispatch_subcommand(&mut AcknowledgeSubcommands),
+            Ok(Some(0x40))
+        );
+        device.write_report(&pressed).unwrap();
+        assert_eq!(manager.host_read_in()[7], 0x40);
+        assert_eq!(rumble.0, Some([3, 4]));
+    }
+
+    #[test]
+    fn keyboard_type_str() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::default())
+            .build(&usb_alloc);
+
+        let _usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        hid.device().type_str(Layout::Us, "Hi!");
+
+        let mut reports = Vec::new();
+        while hid.device().is_typing() {
+            hid.tick().unwrap();
+            let report = manager.host_read_in();
+            if !report.is_empty() {
+                reports.push(report);
+            }
+        }
+
+        let shift = 0x02;
+        assert_eq!(
+            reports,
+            [
+                [shift, 0, Keyboard::H.into(), 0, 0, 0, 0, 0],
+                [0; 8],
+                [0, 0, Keyboard::I.into(), 0, 0, 0, 0, 0],
+                [0; 8],
+                [shift, 0, Keyboard::Keyboard1.into(), 0, 0, 0, 0, 0],
+                [0; 8],
+            ]
+        );
+    }
+
+    #[test]
+    fn keyboard_typing_paced() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(BootKeyboardConfig::new(ManagedIdleInterfaceConfig::new(
+                InterfaceBuilder::new(BOOT_KEYBOARD_REPORT_DESCRIPTOR)
+                    .unwrap()
+                    .pace_reports(MillisDurationU32::millis(5))
+                    .build(),
+            )))
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        hid.device().type_str(Layout::Us, "ab");
+
+        let mut reports = Vec::new();
+        for ms in 0..50 {
+            hid.tick().unwrap();
+            let report = manager.host_read_in();
+            if !report.is_empty() {
+                reports.push((ms, report));
+            }
+            usb_dev.poll(&mut [&mut hid]);
+        }
+
+        assert_eq!(
+            reports.iter().map(|(_, r)| r.clone()).collect::<Vec<_>>(),
+            [
+                [0, 0, Keyboard::A.into(), 0, 0, 0, 0, 0],
+                [0; 8],
+                [0, 0, Keyboard::B.into(), 0, 0, 0, 0, 0],
+                [0; 8],
+            ]
+        );
+        assert!(reports.windows(2).all(|w| w[1].0 - w[0].0 >= 5));
+    }
+
+    #[test]
+    fn encoder_steps_carried_over() {
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(crate::device::encoder::EncodersConfig::default())
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        hid.device().rotate(0, 300);
+        hid.device().rotate(3, -5);
+        hid.device().set_buttons(0b10);
+
+        let mut reports = Vec::new();
+        for _ in 0..5 {
+            hid.tick().unwrap();
+            let report = manager.host_read_in();
+            if !report.is_empty() {
+                reports.push(report);
+            }
+            usb_dev.poll(&mut [&mut hid]);
+        }
+
+        assert_eq!(
+            reports,
+            [
+                [0b10, 127, 0, 0, 0xFB],
+                [0b10, 127, 0, 0, 0],
+                [0b10, 46, 0, 0, 0],
+            ]
+        );
+        assert_eq!(hid.device().pending_steps(), [0; 4]);
+    }
+
+    #[test]
+    fn kvm_bridge_taps() {
+        use crate::device::kvm::KvmBridgeConfig;
+        use crate::page::Consumer;
+
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut kvm = KvmBridgeConfig::default().allocate(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        let keyboard = kvm.class().device::<BootKeyboard<'_, TestUsbBus>, _>();
+        assert_eq!(u8::from(keyboard.interface().id()), 0);
+
+        let mut read_reports = |kvm: &mut _| {
+            let mut reports = Vec::new();
+            for _ in 0..10 {
+                crate::device::kvm::KvmBridge::tick(kvm).unwrap();
+                let report = manager.host_read_in();
+                if !report.is_empty() {
+                    reports.push(report);
+                }
+                usb_dev.poll(&mut [kvm]);
+            }
+            reports
+        };
+
+        kvm.press_key(Keyboard::LeftShift);
+        kvm.send_key(Keyboard::A).unwrap();
+        assert_eq!(kvm.send_key(Keyboard::B), Err(UsbHidError::WouldBlock));
+        assert_eq!(
+            read_reports(&mut kvm),
+            [
+                [0x2, 0, Keyboard::A.into(), 0, 0, 0, 0, 0],
+                [0x2, 0, 0, 0, 0, 0, 0, 0],
+            ]
+        );
+
+        kvm.move_rel(200, 0);
+        kvm.click(0x1).unwrap();
+        assert_eq!(
+            read_reports(&mut kvm),
+            [[0x1, 127, 0, 0, 0], [0, 73, 0, 0, 0]]
+        );
+
+        kvm.media(Consumer::Mute).unwrap();
+        assert_eq!(
+            read_reports(&mut kvm),
+            [[0xE2, 0, 0, 0, 0, 0, 0, 0], [0; 8]]
+        );
+
+        // Moving the absolute pointer releases buttons held on the relative pointer
+        kvm.set_buttons(0x2);
+        kvm.move_abs(0x1234, 0xFFFF);
+        assert_eq!(
+            read_reports(&mut kvm),
+            [&[0, 0, 0, 0, 0][..], &[0x2, 0x34, 0x12, 0xFF, 0x7F, 0]]
+        );
+    }
+
+    #[test]
+    fn elapsed_ticks_wrap_and_limit() {
+        let mut elapsed = ElapsedTicks::default();
+
+        assert_eq!(elapsed.update(u32::MAX - 1), 0);
+        assert_eq!(elapsed.update(3), 5);
+        assert_eq!(elapsed.update(100_000), MAX_CATCH_UP_MS);
+    }
+
+    #[test]
+    fn set_report_idle_no_reports() {
+        const IDLE_DEFAULT: MillisDurationU32 = MillisDurationU32::millis(40);
+        const IDLE_NEW: MillisDurationU32 = MillisDurationU32::millis(88);
+        const REPORT_ID: u16 = 0x4;
+
         init_logging();
 
         let manager = UsbTestManager::default();
@@ -1309,4 +4199,44 @@ mod test {
             "Unexpected report idle value"
         );
     }
+
+    #[test]
+    fn usbd_hid_report() {
+        use usbd_hid::descriptor::{MouseReport, SerializedDescriptor};
+
+        init_logging();
+
+        let manager = UsbTestManager::default();
+        let usb_alloc = UsbBusAllocator::new(TestUsbBus::new(&manager));
+
+        let mut hid = UsbHidClassBuilder::new()
+            .add_device(
+                InterfaceBuilder::<InBytes8, OutNone, ReportSingle>::from_usbd_hid::<MouseReport>()
+                    .unwrap()
+                    .without_out_endpoint()
+                    .build(),
+            )
+            .build(&usb_alloc);
+
+        let mut usb_dev = UsbDeviceBuilder::new(&usb_alloc, UsbVidPid(0x1209, 0x0001))
+            .device_class(USB_CLASS_HID)
+            .build();
+
+        assert_eq!(
+            manager.get_report_descriptor(&mut usb_dev, &mut [&mut hid], 0),
+            MouseReport::desc()
+        );
+
+        let device = hid.device::<Interface<_, InBytes8, OutNone, ReportSingle>, _>();
+        device
+            .write_usbd_hid_report(&MouseReport {
+                buttons: 0x1,
+                x: 2,
+                y: -3,
+                wheel: 4,
+                pan: 0,
+            })
+            .unwrap();
+        assert_eq!(manager.host_read_in(), [0x1, 2, 0xFD, 4, 0]);
+    }
 }